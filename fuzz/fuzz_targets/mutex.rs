@@ -0,0 +1,93 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use async_lock::{Mutex, MutexGuard};
+use futures_lite::future;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    /// Starts a lock operation.
+    Lock,
+    /// Starts a fair lock operation.
+    LockFair,
+    TryLock,
+    /// Polls one of the pending lock operations once.
+    Poll(u8),
+    /// Drops the guard, or one of the pending lock operations.
+    Drop(u8),
+    UnlockFair,
+    Write(u8),
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    mutex: Mutex<u8>,
+    ops: Vec<Op>,
+}
+
+fuzz_target!(|input: Input| {
+    let Input { mutex, ops } = input;
+    let mut value = *mutex.try_lock().unwrap();
+    let mut guard: Option<MutexGuard<'_, u8>> = None;
+    let mut pending = Vec::new();
+
+    for op in ops {
+        match op {
+            Op::Lock => pending.push(Box::pin(mutex.lock())),
+            Op::LockFair => pending.push(Box::pin(mutex.lock_fair())),
+            Op::TryLock => {
+                if let Some(g) = mutex.try_lock() {
+                    assert!(guard.is_none());
+                    assert_eq!(*g, value);
+                    guard = Some(g);
+                }
+            }
+            Op::Poll(i) => {
+                if !pending.is_empty() {
+                    let i = i as usize % pending.len();
+                    if let Some(g) = future::block_on(future::poll_once(pending[i].as_mut())) {
+                        assert!(guard.is_none());
+                        assert_eq!(*g, value);
+                        guard = Some(g);
+                        drop(pending.remove(i));
+                    }
+                }
+            }
+            Op::Drop(i) => {
+                if guard.is_some() {
+                    guard = None;
+                } else if !pending.is_empty() {
+                    drop(pending.remove(i as usize % pending.len()));
+                }
+            }
+            Op::UnlockFair => {
+                if let Some(g) = guard.take() {
+                    MutexGuard::unlock_fair(g);
+                }
+            }
+            Op::Write(v) => {
+                if let Some(g) = guard.as_mut() {
+                    **g = v;
+                    value = v;
+                }
+            }
+        }
+    }
+
+    // Once the mutex is released, every pending lock operation gets it in turn. They are polled
+    // together, since a woken lock operation may have to take the mutex before the others can.
+    drop(guard);
+    while !pending.is_empty() {
+        pending.retain_mut(
+            |lock| match future::block_on(future::poll_once(lock.as_mut())) {
+                Some(g) => {
+                    assert_eq!(*g, value);
+                    false
+                }
+                None => true,
+            },
+        );
+    }
+    assert!(mutex.try_lock().is_some());
+});
@@ -1,13 +1,25 @@
+use core::fmt;
+
 use event_listener::Event;
 
+use crate::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use crate::Mutex;
 
 /// A counter to synchronize multiple tasks at the same time.
+///
+/// If a task that is part of the group fails, the barrier can be
+/// [marked as broken][`Barrier::mark_broken()`], so the other tasks stop waiting for it instead of
+/// waiting forever.
 #[derive(Debug)]
 pub struct Barrier {
     n: usize,
     state: Mutex<State>,
     event: Event,
+    broken: AtomicBool,
+
+    /// The generation ID of `state`, published so cancelled waiters can tell if they were
+    /// already released.
+    generation: AtomicUsize,
 }
 
 #[derive(Debug)]
@@ -39,6 +51,8 @@ impl Barrier {
                 generation_id: 0,
             }),
             event: Event::new(),
+            broken: AtomicBool::new(false),
+            generation: AtomicUsize::new(0),
         }
     }
 
@@ -49,6 +63,10 @@ impl Barrier {
     /// Returns a [`BarrierWaitResult`] indicating whether this task is the "leader", meaning the
     /// last task to call this method.
     ///
+    /// This ignores whether the barrier is [broken][`Barrier::mark_broken()`] and always waits
+    /// for the other tasks. Use [`wait_checked()`][`Barrier::wait_checked()`] to stop waiting once
+    /// the barrier is broken.
+    ///
     /// # Examples
     ///
     /// ```
@@ -73,28 +91,280 @@ impl Barrier {
     /// }
     /// ```
     pub async fn wait(&self) -> BarrierWaitResult {
+        match self.wait_inner(false).await {
+            Ok(result) => result,
+            Err(_) => unreachable!("unchecked waits ignore a broken barrier"),
+        }
+    }
+
+    /// Blocks the current task until all tasks reach this point, or until the barrier is broken.
+    ///
+    /// Returns an error if the barrier is broken, either before this call or while waiting. If
+    /// the returned future is dropped while waiting for the other tasks, the barrier is marked as
+    /// broken, because the other tasks can no longer be released. Dropping it after the barrier
+    /// has released it, but before it completed, leaves the barrier intact.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Barrier;
+    /// use futures_lite::future;
+    ///
+    /// let barrier = Barrier::new(3);
+    ///
+    /// // One of the tasks gives up while waiting.
+    /// let mut waiter = Box::pin(barrier.wait_checked());
+    /// assert!(future::poll_once(&mut waiter).await.is_none());
+    /// drop(waiter);
+    ///
+    /// assert!(barrier.is_broken());
+    /// assert!(barrier.wait_checked().await.is_err());
+    /// # });
+    /// ```
+    pub async fn wait_checked(&self) -> Result<BarrierWaitResult, BrokenBarrierError> {
+        self.wait_inner(true).await
+    }
+
+    async fn wait_inner(&self, checked: bool) -> Result<BarrierWaitResult, BrokenBarrierError> {
         let mut state = self.state.lock().await;
+        if checked && self.is_broken() {
+            return Err(BrokenBarrierError(()));
+        }
         let local_gen = state.generation_id;
         state.count += 1;
 
         if state.count < self.n {
+            let mut cancel = BreakOnDrop(if checked { Some(self) } else { None }, local_gen);
             while local_gen == state.generation_id && state.count < self.n {
                 let listener = self.event.listen();
+                if checked && self.is_broken() {
+                    // This task no longer takes part in the current generation.
+                    state.count -= 1;
+                    cancel.0 = None;
+                    return Err(BrokenBarrierError(()));
+                }
                 drop(state);
                 listener.await;
                 state = self.state.lock().await;
             }
-            BarrierWaitResult { is_leader: false }
+            cancel.0 = None;
+            Ok(BarrierWaitResult { is_leader: false })
         } else {
             state.count = 0;
             state.generation_id = state.generation_id.wrapping_add(1);
-            self.event.notify(std::usize::MAX);
-            BarrierWaitResult { is_leader: true }
+            self.generation
+                .store(state.generation_id as usize, Ordering::SeqCst);
+            self.event.notify(usize::MAX);
+            Ok(BarrierWaitResult { is_leader: true })
+        }
+    }
+
+    /// Marks the barrier as broken and wakes all waiting tasks.
+    ///
+    /// Call this when a task of the group fails and will never reach the barrier, for example
+    /// from a guard that is dropped when it panics. Tasks waiting in
+    /// [`wait_checked()`][`Barrier::wait_checked()`] then get an error, and so do all later calls.
+    /// Tasks in [`wait()`][`Barrier::wait()`] are not affected and keep waiting for the whole
+    /// group. A broken barrier stays broken.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Barrier;
+    ///
+    /// let barrier = Barrier::new(2);
+    /// barrier.mark_broken();
+    /// assert!(barrier.wait_checked().await.is_err());
+    /// # });
+    /// ```
+    pub fn mark_broken(&self) {
+        self.broken.store(true, Ordering::SeqCst);
+        self.event.notify(usize::MAX);
+    }
+
+    /// Returns `true` if the barrier was [marked as broken][`Barrier::mark_broken()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Barrier;
+    ///
+    /// let barrier = Barrier::new(2);
+    /// assert!(!barrier.is_broken());
+    /// ```
+    pub fn is_broken(&self) -> bool {
+        self.broken.load(Ordering::SeqCst)
+    }
+}
+
+/// Marks a barrier as broken when a task waiting in the given generation is cancelled.
+struct BreakOnDrop<'a>(Option<&'a Barrier>, u64);
+
+impl Drop for BreakOnDrop<'_> {
+    fn drop(&mut self) {
+        if let Some(barrier) = self.0 {
+            // A task the barrier already released does not hold up the others.
+            if barrier.generation.load(Ordering::SeqCst) == self.1 as usize {
+                barrier.mark_broken();
+            }
+        }
+    }
+}
+
+/// Returned by [`Barrier::wait_checked()`] when the barrier is broken.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::Barrier;
+///
+/// let barrier = Barrier::new(2);
+/// barrier.mark_broken();
+/// let err = barrier.wait_checked().await.unwrap_err();
+/// assert_eq!(err.to_string(), "barrier is broken");
+/// # });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenBarrierError(());
+
+impl fmt::Display for BrokenBarrierError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("barrier is broken")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BrokenBarrierError {}
+
+/// A barrier that synchronizes tasks once.
+///
+/// Unlike [`Barrier`], this barrier is not reusable: once `n` tasks have called [`wait()`], it
+/// stays tripped and later calls return immediately. In exchange, it is a single atomic counter
+/// and an [`Event`], and waiting never locks anything. This is what fork-join code usually needs.
+///
+/// [`wait()`]: `OneShotBarrier::wait()`
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::OneShotBarrier;
+/// use futures_lite::future;
+///
+/// let barrier = OneShotBarrier::new(2);
+/// let (a, b) = future::zip(barrier.wait(), barrier.wait()).await;
+/// assert!(a.is_leader() != b.is_leader());
+///
+/// // The barrier stays tripped.
+/// assert!(barrier.is_tripped());
+/// assert!(!barrier.wait().await.is_leader());
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct OneShotBarrier {
+    /// Number of tasks that still have to call `wait()`.
+    remaining: AtomicUsize,
+    event: Event,
+}
+
+impl OneShotBarrier {
+    /// Creates a barrier that trips once the given number of tasks are waiting on it.
+    ///
+    /// A barrier for zero tasks behaves like one for a single task: the first call to
+    /// [`wait()`][`OneShotBarrier::wait()`] trips it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::OneShotBarrier;
+    ///
+    /// let barrier = OneShotBarrier::new(5);
+    /// ```
+    pub const fn new(n: usize) -> OneShotBarrier {
+        OneShotBarrier {
+            remaining: AtomicUsize::new(if n == 0 { 1 } else { n }),
+            event: Event::new(),
         }
     }
+
+    /// Blocks the current task until `n` tasks have reached this point.
+    ///
+    /// Returns a [`BarrierWaitResult`] indicating whether this task is the "leader", meaning the
+    /// task that tripped the barrier. After the barrier has tripped, this returns immediately and
+    /// the result is never the leader.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::OneShotBarrier;
+    /// use futures_lite::future;
+    /// use std::sync::Arc;
+    /// use std::thread;
+    ///
+    /// let barrier = Arc::new(OneShotBarrier::new(5));
+    ///
+    /// for _ in 0..5 {
+    ///     let b = barrier.clone();
+    ///     thread::spawn(move || {
+    ///         future::block_on(async {
+    ///             println!("before wait");
+    ///             b.wait().await;
+    ///             println!("after wait");
+    ///         });
+    ///     });
+    /// }
+    /// ```
+    pub async fn wait(&self) -> BarrierWaitResult {
+        let mut remaining = self.remaining.load(Ordering::Acquire);
+        loop {
+            if remaining == 0 {
+                return BarrierWaitResult { is_leader: false };
+            }
+            match self.remaining.compare_exchange_weak(
+                remaining,
+                remaining - 1,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(r) => remaining = r,
+            }
+        }
+
+        if remaining == 1 {
+            self.event.notify(usize::MAX);
+            return BarrierWaitResult { is_leader: true };
+        }
+
+        while !self.is_tripped() {
+            let listener = self.event.listen();
+            if self.is_tripped() {
+                break;
+            }
+            listener.await;
+        }
+        BarrierWaitResult { is_leader: false }
+    }
+
+    /// Returns `true` if `n` tasks have called [`wait()`][`OneShotBarrier::wait()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::OneShotBarrier;
+    ///
+    /// let barrier = OneShotBarrier::new(1);
+    /// assert!(!barrier.is_tripped());
+    /// ```
+    pub fn is_tripped(&self) -> bool {
+        self.remaining.load(Ordering::Acquire) == 0
+    }
 }
 
-/// Returned by [`Barrier::wait()`] when all tasks have called it.
+/// Returned by [`Barrier::wait()`] and [`OneShotBarrier::wait()`] when all tasks have called it.
 ///
 /// # Examples
 ///
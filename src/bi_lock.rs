@@ -0,0 +1,322 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::future::Future;
+use core::ops::{Deref, DerefMut};
+use core::ptr::{self, NonNull};
+use core::task::{Context, Poll, Waker};
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+
+use crate::sync::atomic::{AtomicPtr, Ordering};
+
+/// The state of a locked [`BiLock`] whose other half is not waiting.
+///
+/// A dangling pointer never equals a boxed waker.
+const LOCKED: *mut Waker = NonNull::dangling().as_ptr();
+
+/// One of the two halves of a lock shared by exactly two owners.
+///
+/// Since only the other half can be waiting while one half holds the lock, the whole lock is a
+/// single atomic pointer: null when unlocked, and otherwise either a marker or the waker of the
+/// other half. This is cheaper than a [`Mutex`][`crate::Mutex`], which keeps a queue of any number
+/// of waiters, and is a good fit for splitting a value such as an I/O stream into a read half and
+/// a write half.
+///
+/// Locking takes the half by `&mut`, so each half is locked by at most one task at a time.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::BiLock;
+///
+/// let (mut reader, mut writer) = BiLock::new(Vec::new());
+///
+/// writer.lock().await.push(1);
+/// assert_eq!(reader.lock().await.pop(), Some(1));
+///
+/// assert_eq!(reader.reunite(writer).unwrap(), []);
+/// # })
+/// ```
+pub struct BiLock<T> {
+    inner: Arc<Inner<T>>,
+}
+
+/// The state shared by both halves.
+struct Inner<T> {
+    /// Null when unlocked, [`LOCKED`] when locked, or a boxed waker of the waiting half.
+    state: AtomicPtr<Waker>,
+
+    /// The value inside the lock.
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for Inner<T> {}
+unsafe impl<T: Send> Sync for Inner<T> {}
+
+impl<T> BiLock<T> {
+    /// Puts `value` behind a new lock and returns its two halves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::BiLock;
+    ///
+    /// let (a, b) = BiLock::new(0);
+    /// assert!(a.is_pair_of(&b));
+    /// ```
+    pub fn new(value: T) -> (BiLock<T>, BiLock<T>) {
+        let inner = Arc::new(Inner {
+            state: AtomicPtr::new(ptr::null_mut()),
+            value: UnsafeCell::new(value),
+        });
+        (
+            BiLock {
+                inner: inner.clone(),
+            },
+            BiLock { inner },
+        )
+    }
+
+    /// Acquires the lock.
+    ///
+    /// Returns a guard that releases the lock when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::BiLock;
+    ///
+    /// let (mut a, _b) = BiLock::new(10);
+    /// *a.lock().await += 1;
+    /// assert_eq!(*a.lock().await, 11);
+    /// # })
+    /// ```
+    pub fn lock(&mut self) -> impl Future<Output = BiLockGuard<'_, T>> {
+        let this = &*self;
+        core::future::poll_fn(move |cx| this.poll(cx))
+    }
+
+    /// Attempts to acquire the lock, registering the current task to be woken once the other half
+    /// releases it.
+    ///
+    /// This is useful in manual implementations of [`Future`] or I/O traits, which poll both
+    /// halves of a split stream.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::BiLock;
+    /// use std::task::{Context, Waker};
+    ///
+    /// let (mut a, mut b) = BiLock::new(0);
+    /// let cx = &mut Context::from_waker(Waker::noop());
+    ///
+    /// let guard = a.poll_lock(cx);
+    /// assert!(guard.is_ready());
+    /// assert!(b.poll_lock(cx).is_pending());
+    /// ```
+    pub fn poll_lock(&mut self, cx: &mut Context<'_>) -> Poll<BiLockGuard<'_, T>> {
+        (*self).poll(cx)
+    }
+
+    /// Attempts to acquire the lock without waiting.
+    ///
+    /// If the other half holds the lock, then [`None`] is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::BiLock;
+    ///
+    /// let (mut a, mut b) = BiLock::new(0);
+    ///
+    /// let guard = a.try_lock().unwrap();
+    /// assert!(b.try_lock().is_none());
+    /// drop(guard);
+    /// assert!(b.try_lock().is_some());
+    /// ```
+    pub fn try_lock(&mut self) -> Option<BiLockGuard<'_, T>> {
+        let this = &*self;
+        this.inner
+            .state
+            .compare_exchange(
+                ptr::null_mut(),
+                LOCKED,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .ok()
+            .map(move |_| BiLockGuard { lock: this })
+    }
+
+    /// Returns `true` if `self` and `other` are the two halves of the same lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::BiLock;
+    ///
+    /// let (a, b) = BiLock::new(0);
+    /// let (c, _d) = BiLock::new(0);
+    /// assert!(a.is_pair_of(&b));
+    /// assert!(!a.is_pair_of(&c));
+    /// ```
+    pub fn is_pair_of(&self, other: &BiLock<T>) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// Joins the two halves again, returning the value inside the lock.
+    ///
+    /// If `other` is not the other half of this lock, both halves are handed back in the error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::BiLock;
+    ///
+    /// let (a, b) = BiLock::new(1);
+    /// let (c, d) = BiLock::new(2);
+    ///
+    /// let err = a.reunite(c).unwrap_err();
+    /// let (a, c) = (err.0, err.1);
+    /// assert_eq!(a.reunite(b).unwrap(), 1);
+    /// assert_eq!(c.reunite(d).unwrap(), 2);
+    /// ```
+    pub fn reunite(self, other: BiLock<T>) -> Result<T, ReuniteError<T>> {
+        if !self.is_pair_of(&other) {
+            return Err(ReuniteError(self, other));
+        }
+        drop(other);
+
+        // This was the last reference, and neither half is locked, since locking borrows the halves
+        // that were just consumed.
+        match Arc::try_unwrap(self.inner) {
+            Ok(inner) => Ok(inner.value.into_inner()),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Attempts to acquire the lock, storing the waker if the other half holds it.
+    fn poll(&self, cx: &mut Context<'_>) -> Poll<BiLockGuard<'_, T>> {
+        let mut waker = None;
+
+        loop {
+            let prev = self.inner.state.swap(LOCKED, Ordering::Acquire);
+            if prev.is_null() {
+                // The lock was free.
+                return Poll::Ready(BiLockGuard { lock: self });
+            }
+            if prev != LOCKED {
+                // The other half holds the lock, and a waker of this half from an earlier poll is
+                // still registered.
+                //
+                // SAFETY: Wakers are only stored by boxing them, and the one swapped out is owned
+                // here.
+                drop(unsafe { Box::from_raw(prev) });
+            }
+
+            // Register the waker, unless the lock was released in the meantime.
+            let boxed = waker.take().unwrap_or_else(|| Box::new(cx.waker().clone()));
+            let boxed = Box::into_raw(boxed);
+            match self.inner.state.compare_exchange(
+                LOCKED,
+                boxed,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Poll::Pending,
+                Err(_) => {
+                    // SAFETY: The box was not stored, so it is still owned here.
+                    waker = Some(unsafe { Box::from_raw(boxed) });
+                }
+            }
+        }
+    }
+
+    /// Releases the lock and wakes the other half if it is waiting.
+    fn unlock(&self) {
+        let prev = self.inner.state.swap(ptr::null_mut(), Ordering::AcqRel);
+        debug_assert!(!prev.is_null(), "unlocked a `BiLock` that was not locked");
+
+        if !prev.is_null() && prev != LOCKED {
+            // SAFETY: Wakers are only stored by boxing them, and the one swapped out is owned
+            // here.
+            unsafe { Box::from_raw(prev) }.wake();
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for BiLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Printing the value would need a lock, which only `&mut self` can acquire.
+        f.debug_struct("BiLock")
+            .field(
+                "locked",
+                &!self.inner.state.load(Ordering::Relaxed).is_null(),
+            )
+            .finish()
+    }
+}
+
+/// The error returned by [`BiLock::reunite()`] when the halves belong to different locks.
+///
+/// It holds both halves, so they can still be used.
+pub struct ReuniteError<T>(pub BiLock<T>, pub BiLock<T>);
+
+impl<T> fmt::Debug for ReuniteError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ReuniteError").field(&"..").finish()
+    }
+}
+
+impl<T> fmt::Display for ReuniteError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("tried to reunite two halves of different `BiLock`s")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> std::error::Error for ReuniteError<T> {}
+
+/// A guard that releases a [`BiLock`] when dropped.
+pub struct BiLockGuard<'a, T> {
+    lock: &'a BiLock<T>,
+}
+
+unsafe impl<T: Send> Send for BiLockGuard<'_, T> {}
+unsafe impl<T: Sync> Sync for BiLockGuard<'_, T> {}
+
+impl<T> Drop for BiLockGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.unlock();
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for BiLockGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for BiLockGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T> Deref for BiLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.inner.value.get() }
+    }
+}
+
+impl<T> DerefMut for BiLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.inner.value.get() }
+    }
+}
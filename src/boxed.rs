@@ -0,0 +1,95 @@
+//! Type-erased lock operations for trait objects.
+
+use core::fmt;
+use core::future::Future;
+use core::ops::{Deref, DerefMut};
+use core::pin::Pin;
+
+use alloc::boxed::Box;
+
+use crate::{Mutex, RwLock};
+
+/// A boxed lock operation, as returned by [`Mutex::lock_boxed()`] and [`DynLock::lock_dyn()`].
+pub type BoxLockFuture<'a, G> = Pin<Box<dyn Future<Output = G> + Send + 'a>>;
+
+/// A guard with its lock type erased, as returned by [`DynLock`].
+///
+/// Releases the lock when dropped.
+pub struct DynGuard<'a, T: ?Sized>(Box<dyn DerefMut<Target = T> + Send + 'a>);
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for DynGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized> Deref for DynGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> DerefMut for DynGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// A lock that gives exclusive access to a `T`, usable as a trait object.
+///
+/// Implemented by [`Mutex`] and by the write half of [`RwLock`], so plugin interfaces, for example
+/// ones built with `async_trait`, can hand out `&dyn DynLock<T>` without making every signature
+/// generic over the lock type.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::{DynLock, Mutex, RwLock};
+///
+/// async fn bump(counter: &dyn DynLock<u32>) {
+///     *counter.lock_dyn().await += 1;
+/// }
+///
+/// let a = Mutex::new(0);
+/// let b = RwLock::new(0);
+/// bump(&a).await;
+/// bump(&b).await;
+/// assert_eq!(*a.lock().await + *b.read().await, 2);
+/// # })
+/// ```
+pub trait DynLock<T: ?Sized>: Send + Sync {
+    /// Acquires exclusive access to the data.
+    fn lock_dyn(&self) -> BoxLockFuture<'_, DynGuard<'_, T>>;
+
+    /// Attempts to acquire exclusive access to the data without waiting.
+    fn try_lock_dyn(&self) -> Option<DynGuard<'_, T>>;
+}
+
+impl<T: Send + ?Sized> DynLock<T> for Mutex<T> {
+    #[track_caller]
+    fn lock_dyn(&self) -> BoxLockFuture<'_, DynGuard<'_, T>> {
+        let lock = self.lock();
+        Box::pin(async move { DynGuard(Box::new(lock.await)) })
+    }
+
+    #[track_caller]
+    fn try_lock_dyn(&self) -> Option<DynGuard<'_, T>> {
+        self.try_lock().map(|guard| DynGuard(Box::new(guard)))
+    }
+}
+
+impl<T: Send + Sync + ?Sized> DynLock<T> for RwLock<T> {
+    #[track_caller]
+    fn lock_dyn(&self) -> BoxLockFuture<'_, DynGuard<'_, T>> {
+        let lock = self.write();
+        Box::pin(async move { DynGuard(Box::new(lock.await)) })
+    }
+
+    #[track_caller]
+    fn try_lock_dyn(&self) -> Option<DynGuard<'_, T>> {
+        self.try_write().map(|guard| DynGuard(Box::new(guard)))
+    }
+}
@@ -0,0 +1,129 @@
+//! The clock used to detect starving lock operations.
+//!
+//! A [`Mutex`][`crate::Mutex`] operation that has waited for longer than half a millisecond
+//! switches to a fairer locking strategy, so newer operations cannot keep taking the lock from it.
+//! By default, the time is measured with `std`'s `Instant`. Targets without `std`, or with a
+//! cheaper time source such as a cycle counter, can install their own [`Clock`] with
+//! [`set_clock()`]. Tests can install a mock clock to control when operations count as starved.
+//!
+//! Without `std` and without an installed clock, lock operations only switch to fair locking once
+//! another starved operation is already waiting.
+//!
+//! # Examples
+//!
+//! ```
+//! use async_lock::clock::{self, Clock};
+//! use std::sync::atomic::{AtomicU64, Ordering};
+//! use std::time::Duration;
+//!
+//! struct Ticks(AtomicU64);
+//!
+//! impl Clock for Ticks {
+//!     fn now(&self) -> Duration {
+//!         Duration::from_micros(self.0.fetch_add(1, Ordering::Relaxed))
+//!     }
+//! }
+//!
+//! static TICKS: Ticks = Ticks(AtomicU64::new(0));
+//! static CLOCK: &dyn Clock = &TICKS;
+//! clock::set_clock(&CLOCK);
+//! # clock::clear_clock();
+//! ```
+
+use core::time::Duration;
+
+use crate::sync::atomic::{AtomicPtr, Ordering};
+#[cfg(feature = "std")]
+use crate::time::Instant;
+
+/// A monotonic time source.
+pub trait Clock: Send + Sync {
+    /// Returns the time elapsed since an arbitrary fixed point.
+    ///
+    /// The returned value must never decrease.
+    fn now(&self) -> Duration;
+}
+
+/// The installed clock, or null if there is none.
+static CLOCK: AtomicPtr<&'static dyn Clock> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Installs a process-wide clock used instead of `Instant`.
+///
+/// The clock is passed as a reference to a `&dyn Clock` that lives forever, usually a `static`,
+/// so it can be swapped in atomically. Lock operations that are already waiting keep using the
+/// clock they started with.
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::clock::{self, Clock};
+/// use std::time::Duration;
+///
+/// struct Frozen;
+///
+/// impl Clock for Frozen {
+///     fn now(&self) -> Duration {
+///         Duration::ZERO
+///     }
+/// }
+///
+/// static FROZEN: &dyn Clock = &Frozen;
+/// clock::set_clock(&FROZEN);
+/// # clock::clear_clock();
+/// ```
+pub fn set_clock(clock: &'static &'static dyn Clock) {
+    CLOCK.store(clock as *const _ as *mut _, Ordering::Release);
+}
+
+/// Removes the clock installed with [`set_clock()`].
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::clock;
+///
+/// clock::clear_clock();
+/// ```
+pub fn clear_clock() {
+    CLOCK.store(core::ptr::null_mut(), Ordering::Release);
+}
+
+fn installed() -> Option<&'static dyn Clock> {
+    let ptr = CLOCK.load(Ordering::Acquire);
+
+    // SAFETY: Non-null values come from a `&'static` reference in `set_clock()`.
+    unsafe { ptr.as_ref().copied() }
+}
+
+/// Measures time with the installed clock, such as how long a lock operation has been waiting.
+pub(crate) enum Stopwatch {
+    Clock(&'static dyn Clock, Duration),
+    #[cfg(feature = "std")]
+    Instant(Instant),
+    #[cfg(not(feature = "std"))]
+    Unavailable,
+}
+
+impl Stopwatch {
+    /// Starts measuring with the installed clock, or `Instant` if there is none.
+    pub(crate) fn start() -> Stopwatch {
+        match installed() {
+            Some(clock) => Stopwatch::Clock(clock, clock.now()),
+            #[cfg(feature = "std")]
+            None => Stopwatch::Instant(Instant::now()),
+            #[cfg(not(feature = "std"))]
+            None => Stopwatch::Unavailable,
+        }
+    }
+
+    /// Returns the time since the stopwatch was started, if there is a clock to tell.
+    pub(crate) fn elapsed(&self) -> Option<Duration> {
+        match self {
+            Stopwatch::Clock(clock, start) => Some(clock.now().saturating_sub(*start)),
+            #[cfg(feature = "std")]
+            Stopwatch::Instant(start) => Some(start.elapsed()),
+            #[cfg(not(feature = "std"))]
+            Stopwatch::Unavailable => None,
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use core::fmt;
+use core::future::Future;
+use core::panic::Location;
+
+use crate::{timer, Flag, Mutex, MutexGuard};
+
+/// A mutex that can be closed during shutdown.
+///
+/// After [`close()`][`CloseableLock::close()`], lock operations that are waiting for the lock fail
+/// with a [`ClosedLockError`], and so do new ones. Tasks blocked on shared state can therefore
+/// stop cleanly instead of hanging until the process exits. Guards that are already held keep
+/// working and release the lock as usual.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::CloseableLock;
+/// use futures_lite::future;
+///
+/// let lock = CloseableLock::new(0);
+/// let guard = lock.lock().await.unwrap();
+///
+/// let mut waiter = Box::pin(lock.lock());
+/// assert!(future::poll_once(&mut waiter).await.is_none());
+///
+/// lock.close();
+/// assert!(waiter.await.is_err());
+/// # })
+/// ```
+pub struct CloseableLock<T: ?Sized> {
+    /// Set once the lock is closed.
+    closed: Flag,
+
+    mutex: Mutex<T>,
+}
+
+impl<T> CloseableLock<T> {
+    /// Creates a new lock that is open.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::CloseableLock;
+    ///
+    /// let lock = CloseableLock::new(0);
+    /// ```
+    pub const fn new(data: T) -> CloseableLock<T> {
+        CloseableLock {
+            closed: Flag::new(),
+            mutex: Mutex::new(data),
+        }
+    }
+
+    /// Consumes the lock, returning the underlying data, even if the lock is closed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::CloseableLock;
+    ///
+    /// let lock = CloseableLock::new(10);
+    /// lock.close();
+    /// assert_eq!(lock.into_inner(), 10);
+    /// ```
+    pub fn into_inner(self) -> T {
+        self.mutex.into_inner()
+    }
+}
+
+impl<T: ?Sized> CloseableLock<T> {
+    /// Acquires the lock.
+    ///
+    /// Returns a guard that releases the lock when dropped, or an error if the lock is closed
+    /// before it could be acquired.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::CloseableLock;
+    ///
+    /// let lock = CloseableLock::new(10);
+    /// assert_eq!(*lock.lock().await.unwrap(), 10);
+    ///
+    /// lock.close();
+    /// assert!(lock.lock().await.is_err());
+    /// # })
+    /// ```
+    #[track_caller]
+    pub fn lock(&self) -> impl Future<Output = Result<MutexGuard<'_, T>, ClosedLockError>> {
+        let location = Location::caller();
+
+        async move {
+            if self.is_closed() {
+                return Err(ClosedLockError(()));
+            }
+            timer::timeout(self.mutex.lock_at(location), self.closed.wait())
+                .await
+                .ok_or(ClosedLockError(()))
+        }
+    }
+
+    /// Attempts to acquire the lock.
+    ///
+    /// If the lock is closed or could not be acquired at this time, then [`None`] is returned.
+    /// Otherwise, a guard is returned that releases the lock when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::CloseableLock;
+    ///
+    /// let lock = CloseableLock::new(10);
+    /// assert!(lock.try_lock().is_some());
+    ///
+    /// lock.close();
+    /// assert!(lock.try_lock().is_none());
+    /// ```
+    #[track_caller]
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        if self.is_closed() {
+            return None;
+        }
+        self.mutex.try_lock()
+    }
+
+    /// Closes the lock.
+    ///
+    /// Lock operations that are waiting fail right away, and so do new ones. Guards that are
+    /// already held are not affected. Closing a closed lock has no effect.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::CloseableLock;
+    ///
+    /// let lock = CloseableLock::new(());
+    /// lock.close();
+    /// assert!(lock.is_closed());
+    /// ```
+    pub fn close(&self) {
+        self.closed.set();
+    }
+
+    /// Returns `true` if the lock is closed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::CloseableLock;
+    ///
+    /// let lock = CloseableLock::new(());
+    /// assert!(!lock.is_closed());
+    /// ```
+    pub fn is_closed(&self) -> bool {
+        self.closed.is_set()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the lock mutably, no actual locking takes place -- the mutable
+    /// borrow statically guarantees the lock is not held. This works even if the lock is closed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::CloseableLock;
+    ///
+    /// let mut lock = CloseableLock::new(0);
+    /// *lock.get_mut() = 10;
+    /// assert_eq!(*lock.try_lock().unwrap(), 10);
+    /// ```
+    pub fn get_mut(&mut self) -> &mut T {
+        self.mutex.get_mut()
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for CloseableLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CloseableLock")
+            .field("closed", &self.is_closed())
+            .field("mutex", &&self.mutex)
+            .finish()
+    }
+}
+
+impl<T: Default> Default for CloseableLock<T> {
+    fn default() -> CloseableLock<T> {
+        CloseableLock::new(T::default())
+    }
+}
+
+impl<T> From<T> for CloseableLock<T> {
+    fn from(data: T) -> CloseableLock<T> {
+        CloseableLock::new(data)
+    }
+}
+
+/// The error returned by [`CloseableLock::lock()`] once the lock is closed.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::CloseableLock;
+///
+/// let lock = CloseableLock::new(());
+/// lock.close();
+/// let err = lock.lock().await.unwrap_err();
+/// assert_eq!(err.to_string(), "lock is closed");
+/// # });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClosedLockError(());
+
+impl fmt::Display for ClosedLockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("lock is closed")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ClosedLockError {}
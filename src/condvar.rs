@@ -0,0 +1,266 @@
+use core::future::Future;
+use core::panic::Location;
+use core::time::Duration;
+
+use event_listener::{Event, IntoNotification};
+
+use crate::timer::{self, Timer};
+use crate::MutexGuard;
+
+/// An async condition variable.
+///
+/// A condition variable lets tasks wait for a condition on data protected by a [`Mutex`] to
+/// become true. [`wait()`] releases the mutex and waits for a notification in one step, so a
+/// notification sent by a task that changed the data while holding the mutex is never missed.
+///
+/// Waiting tasks can wake up spuriously, so they should check the condition in a loop, or use
+/// [`wait_while()`].
+///
+/// [`Mutex`]: `crate::Mutex`
+/// [`wait()`]: `Condvar::wait()`
+/// [`wait_while()`]: `Condvar::wait_while()`
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::{Condvar, Mutex};
+/// use futures_lite::future;
+/// use std::collections::VecDeque;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let queue = Arc::new((Mutex::new(VecDeque::new()), Condvar::new()));
+///
+/// let producer = queue.clone();
+/// thread::spawn(move || {
+///     let (items, not_empty) = &*producer;
+///     future::block_on(items.lock()).push_back(1);
+///     not_empty.notify_one();
+/// });
+///
+/// let (items, not_empty) = &*queue;
+/// let mut items = not_empty.wait_while(items.lock().await, |q| q.is_empty()).await;
+/// assert_eq!(items.pop_front(), Some(1));
+/// # })
+/// ```
+#[derive(Debug)]
+pub struct Condvar {
+    event: Event,
+}
+
+impl Condvar {
+    /// Creates a new condition variable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Condvar;
+    ///
+    /// let condvar = Condvar::new();
+    /// ```
+    pub const fn new() -> Condvar {
+        Condvar {
+            event: Event::new(),
+        }
+    }
+
+    /// Releases the mutex, waits for a notification, and locks the mutex again.
+    ///
+    /// The mutex is locked again before the returned future completes, also when the wakeup was
+    /// spurious. If the future is dropped while it waits for a notification, the notification
+    /// is passed on to another waiting task.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{Condvar, Mutex};
+    /// use futures_lite::future;
+    ///
+    /// let ready = Mutex::new(false);
+    /// let condvar = Condvar::new();
+    ///
+    /// let mut waiter = Box::pin(condvar.wait(ready.lock().await));
+    /// assert!(future::poll_once(&mut waiter).await.is_none());
+    ///
+    /// *ready.lock().await = true;
+    /// condvar.notify_one();
+    /// assert!(*waiter.await);
+    /// # })
+    /// ```
+    #[track_caller]
+    pub fn wait<'a, T: ?Sized>(
+        &self,
+        guard: MutexGuard<'a, T>,
+    ) -> impl Future<Output = MutexGuard<'a, T>> + 'a {
+        let location = Location::caller();
+
+        // Listening before the mutex is released makes sure no notification is missed.
+        let mutex = MutexGuard::source(&guard);
+        let listener = self.event.listen();
+        drop(guard);
+
+        async move {
+            listener.await;
+            mutex.lock_at(location).await
+        }
+    }
+
+    /// Releases the mutex, waits for a notification for at most `timeout`, and locks the mutex
+    /// again.
+    ///
+    /// Returns the guard together with whether the wait timed out. The mutex is locked again in
+    /// either case, which may take longer than `timeout`. Like [`wait()`][`Condvar::wait()`],
+    /// this can wake up spuriously.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{AsyncIoTimer, Condvar, Mutex};
+    /// use std::time::Duration;
+    ///
+    /// let mutex = Mutex::new(());
+    /// let condvar = Condvar::new();
+    ///
+    /// let timeout = Duration::from_millis(10);
+    /// let (guard, result) = condvar.wait_timeout(mutex.lock().await, AsyncIoTimer, timeout).await;
+    /// assert!(result.timed_out());
+    /// # })
+    /// ```
+    #[track_caller]
+    pub fn wait_timeout<'a, T: ?Sized>(
+        &self,
+        guard: MutexGuard<'a, T>,
+        timer: impl Timer + 'a,
+        timeout: Duration,
+    ) -> impl Future<Output = (MutexGuard<'a, T>, WaitTimeoutResult)> + 'a {
+        let location = Location::caller();
+
+        let mutex = MutexGuard::source(&guard);
+        let listener = self.event.listen();
+        drop(guard);
+
+        async move {
+            let notified = timer::timeout(listener, timer.sleep(timeout)).await;
+            let guard = mutex.lock_at(location).await;
+            (guard, WaitTimeoutResult(notified.is_none()))
+        }
+    }
+
+    /// Waits for notifications until `condition` returns `false`.
+    ///
+    /// The condition is checked with the mutex locked, first right away and then after every
+    /// wakeup. Returns the guard once the condition is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{Condvar, Mutex};
+    ///
+    /// let pending = Mutex::new(0);
+    /// let condvar = Condvar::new();
+    ///
+    /// let guard = condvar.wait_while(pending.lock().await, |n| *n > 0).await;
+    /// assert_eq!(*guard, 0);
+    /// # })
+    /// ```
+    #[track_caller]
+    pub fn wait_while<'a, T: ?Sized, F: FnMut(&mut T) -> bool + 'a>(
+        &'a self,
+        guard: MutexGuard<'a, T>,
+        mut condition: F,
+    ) -> impl Future<Output = MutexGuard<'a, T>> + 'a {
+        let location = Location::caller();
+
+        async move {
+            let mut guard = guard;
+            while condition(&mut *guard) {
+                let mutex = MutexGuard::source(&guard);
+                let listener = self.event.listen();
+                drop(guard);
+
+                listener.await;
+                guard = mutex.lock_at(location).await;
+            }
+            guard
+        }
+    }
+
+    /// Wakes up one task waiting on this condition variable.
+    ///
+    /// Every call wakes another task, even if the tasks woken by earlier calls have not run yet.
+    /// If no task is waiting, the notification is lost.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Condvar;
+    ///
+    /// let condvar = Condvar::new();
+    /// condvar.notify_one();
+    /// ```
+    pub fn notify_one(&self) {
+        self.event.notify(1.additional());
+    }
+
+    /// Wakes up all tasks waiting on this condition variable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Condvar;
+    ///
+    /// let condvar = Condvar::new();
+    /// condvar.notify_all();
+    /// ```
+    pub fn notify_all(&self) {
+        self.event.notify(usize::MAX);
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Condvar {
+        Condvar::new()
+    }
+}
+
+/// Tells whether [`Condvar::wait_timeout()`] timed out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitTimeoutResult(bool);
+
+impl WaitTimeoutResult {
+    /// Returns `true` if the wait ended because the timeout elapsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{Condvar, Mutex, Timer};
+    /// use std::future::{ready, Ready};
+    /// use std::time::Duration;
+    ///
+    /// struct Expired;
+    ///
+    /// impl Timer for Expired {
+    ///     type Sleep = Ready<()>;
+    ///
+    ///     fn sleep(&self, _: Duration) -> Ready<()> {
+    ///         ready(())
+    ///     }
+    /// }
+    ///
+    /// let mutex = Mutex::new(());
+    /// let condvar = Condvar::new();
+    ///
+    /// let (_, result) = condvar.wait_timeout(mutex.lock().await, Expired, Duration::ZERO).await;
+    /// assert!(result.timed_out());
+    /// # })
+    /// ```
+    pub fn timed_out(&self) -> bool {
+        self.0
+    }
+}
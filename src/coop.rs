@@ -0,0 +1,246 @@
+//! Cooperative scheduling budget.
+//!
+//! When a lock is uncontended, acquiring it completes immediately without ever returning
+//! [`Poll::Pending`]. A task that keeps re-acquiring a free lock in a loop therefore never yields,
+//! and can starve every other task on its executor thread.
+//!
+//! Executors usually solve this with a per-task budget that resources consume as they make
+//! progress. Installing a budget hook with [`set_budget()`] makes every lock acquisition in this
+//! crate consume one unit of it, yielding back to the executor when the budget is exhausted.
+//!
+//! # Examples
+//!
+//! ```
+//! use async_lock::coop::{self, BudgetHook};
+//! use std::task::{Context, Poll};
+//!
+//! fn always_proceed(_: &mut Context<'_>) -> Poll<()> {
+//!     Poll::Ready(())
+//! }
+//!
+//! static BUDGET: BudgetHook = always_proceed;
+//! coop::set_budget(&BUDGET);
+//! # coop::clear_budget();
+//! ```
+//!
+//! # Yielding after repeated acquisitions
+//!
+//! Without an executor budget, [`set_yield_after()`] offers a cruder safeguard: once a task has
+//! acquired the same lock a given number of times in a row without yielding, the next acquisition
+//! yields back to the executor once before proceeding. This keeps a tight producer loop from
+//! starving consumers that share its executor thread.
+
+#[cfg(feature = "std")]
+use core::cell::Cell;
+use core::future::Future;
+use core::mem;
+use core::pin::Pin;
+#[cfg(feature = "std")]
+use core::task::Waker;
+use core::task::{Context, Poll};
+
+#[cfg(feature = "std")]
+use crate::sync::atomic::AtomicUsize;
+use crate::sync::atomic::{AtomicPtr, Ordering};
+
+/// A hook that consumes one unit of the current task's budget.
+///
+/// It returns [`Poll::Ready`] if the task may proceed. If the budget is exhausted, it arranges for
+/// the task to be woken and returns [`Poll::Pending`].
+pub type BudgetHook = fn(&mut Context<'_>) -> Poll<()>;
+
+/// The installed hook, or null if there is none.
+static HOOK: AtomicPtr<BudgetHook> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Installs a process-wide budget hook consulted by every lock acquisition.
+///
+/// The hook is passed as a reference that lives forever, usually a `static`, so it can be swapped
+/// in atomically.
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::coop::{self, BudgetHook};
+/// use std::task::{Context, Poll};
+///
+/// static BUDGET: BudgetHook = |_: &mut Context<'_>| Poll::Ready(());
+/// coop::set_budget(&BUDGET);
+/// # coop::clear_budget();
+/// ```
+pub fn set_budget(hook: &'static BudgetHook) {
+    HOOK.store(hook as *const _ as *mut _, Ordering::Release);
+}
+
+/// Removes the budget hook installed with [`set_budget()`].
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::coop;
+///
+/// coop::clear_budget();
+/// ```
+pub fn clear_budget() {
+    HOOK.store(core::ptr::null_mut(), Ordering::Release);
+}
+
+/// A budget hook backed by tokio's cooperative scheduling budget.
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::coop::{self, BudgetHook};
+///
+/// static BUDGET: BudgetHook = coop::tokio_budget;
+/// coop::set_budget(&BUDGET);
+/// # coop::clear_budget();
+/// ```
+#[cfg(feature = "tokio")]
+pub fn tokio_budget(cx: &mut Context<'_>) -> Poll<()> {
+    tokio::task::coop::poll_proceed(cx).map(|restore| restore.made_progress())
+}
+
+/// The number of consecutive acquisitions after which to yield, or 0 if disabled.
+#[cfg(feature = "std")]
+static YIELD_AFTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Consecutive acquisitions of the same lock by the same task.
+#[cfg(feature = "std")]
+#[derive(Default)]
+struct Streak {
+    /// Identifies the task by its waker.
+    task: Option<Waker>,
+
+    /// The address of the lock.
+    lock: usize,
+
+    /// The number of acquisitions in a row.
+    count: usize,
+}
+
+#[cfg(feature = "std")]
+impl Streak {
+    const NONE: Streak = Streak {
+        task: None,
+        lock: 0,
+        count: 0,
+    };
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    /// The streak of the task polled most recently on this thread.
+    static STREAK: Cell<Streak> = const { Cell::new(Streak::NONE) };
+}
+
+/// Makes a lock acquisition yield once after the same task acquired the same lock `n` times in a
+/// row without yielding.
+///
+/// Passing [`None`] disables this, which is the default. The count starts over whenever one of the
+/// task's lock operations has to wait, the [budget hook][`set_budget()`] makes it yield, or
+/// another task acquires a lock on the same thread. Awaiting something other than a lock of this
+/// crate is not noticed, so a task that does so between acquisitions of an uncontended lock may
+/// still be made to yield.
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::coop;
+///
+/// coop::set_yield_after(Some(64));
+/// # coop::set_yield_after(None);
+/// ```
+#[cfg(feature = "std")]
+pub fn set_yield_after(n: Option<usize>) {
+    YIELD_AFTER.store(n.unwrap_or(0), Ordering::Relaxed);
+}
+
+/// Returns `true` if the task of `waker` acquiring `lock` should yield first.
+#[cfg(feature = "std")]
+fn should_yield(lock: usize, waker: &Waker) -> bool {
+    let limit = YIELD_AFTER.load(Ordering::Relaxed);
+    if limit == 0 {
+        return false;
+    }
+
+    STREAK
+        .try_with(|cell| {
+            // The streak is moved out, so dropping a waker cannot observe it half-updated.
+            let mut streak = cell.take();
+            let same_task = streak.task.as_ref().is_some_and(|t| t.will_wake(waker));
+            if same_task && streak.lock == lock {
+                streak.count += 1;
+            } else {
+                streak = Streak {
+                    task: Some(waker.clone()),
+                    lock,
+                    count: 1,
+                };
+            }
+
+            let yield_now = streak.count > limit;
+            if yield_now {
+                streak.count = 0;
+            }
+            cell.set(streak);
+            yield_now
+        })
+        .unwrap_or(false)
+}
+
+/// Starts the count of [`set_yield_after()`] over, because the current task is yielding while
+/// it waits for a lock.
+#[inline]
+pub(crate) fn yielded() {
+    #[cfg(feature = "std")]
+    let _ = STREAK.try_with(|streak| drop(streak.take()));
+}
+
+/// Consumes one unit of budget for an acquisition of `lock`, if a hook is installed.
+///
+/// This also yields if `lock` was acquired too many times in a row, see [`set_yield_after()`].
+#[inline]
+#[allow(unused_variables)]
+pub(crate) fn consume_budget<T: ?Sized>(lock: &T) -> ConsumeBudget {
+    ConsumeBudget {
+        #[cfg(feature = "std")]
+        lock: lock as *const T as *const () as usize,
+        started: false,
+    }
+}
+
+/// Future for [`consume_budget()`].
+pub(crate) struct ConsumeBudget {
+    /// The address of the lock being acquired.
+    #[cfg(feature = "std")]
+    lock: usize,
+
+    /// Whether the future was polled before.
+    started: bool,
+}
+
+impl Future for ConsumeBudget {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if !mem::replace(&mut self.started, true) {
+            #[cfg(feature = "std")]
+            if should_yield(self.lock, cx.waker()) {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+        }
+
+        // SAFETY: Non-null values come from a `&'static` reference in `set_budget()`.
+        let hook = match unsafe { HOOK.load(Ordering::Acquire).as_ref() } {
+            Some(hook) => hook,
+            None => return Poll::Ready(()),
+        };
+
+        let poll = hook(cx);
+        if poll.is_pending() {
+            yielded();
+        }
+        poll
+    }
+}
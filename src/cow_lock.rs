@@ -0,0 +1,340 @@
+use core::fmt;
+use core::future::Future;
+use core::mem::{self, ManuallyDrop};
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+use alloc::sync::Arc;
+use std::thread;
+
+use crate::{Mutex, MutexGuard};
+
+/// A copy-on-write lock with non-blocking reads.
+///
+/// Readers call [`load()`][`CowLock::load()`] to get an [`Arc`] snapshot of the current value;
+/// this never waits for writers or other readers. Writers lock, receive a private copy of the
+/// value, mutate it, and publish it as the new value when the write guard is dropped. Snapshots
+/// taken earlier keep seeing the old value.
+///
+/// Every write clones the whole value, so this suits medium-sized configuration or state objects
+/// that are read far more often than they are written.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::CowLock;
+///
+/// let config = CowLock::new(vec!["a"]);
+/// let before = config.load();
+///
+/// config.write().await.push("b");
+///
+/// assert_eq!(*before, ["a"]);
+/// assert_eq!(*config.load(), ["a", "b"]);
+/// # })
+/// ```
+pub struct CowLock<T> {
+    /// The current value, from `Arc::into_raw()`.
+    ptr: AtomicPtr<T>,
+
+    /// Incremented by every write after it publishes a new value.
+    epoch: AtomicUsize,
+
+    /// Number of readers in the middle of `load()`, indexed by the parity of the epoch they saw.
+    readers: [AtomicUsize; 2],
+
+    /// Serializes writers.
+    writer: Mutex<()>,
+}
+
+unsafe impl<T: Send + Sync> Send for CowLock<T> {}
+unsafe impl<T: Send + Sync> Sync for CowLock<T> {}
+
+impl<T> CowLock<T> {
+    /// Creates a new copy-on-write lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::CowLock;
+    ///
+    /// let lock = CowLock::new(0);
+    /// ```
+    pub fn new(value: T) -> CowLock<T> {
+        CowLock::from_arc(Arc::new(value))
+    }
+
+    /// Creates a new copy-on-write lock whose first snapshot is `value`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::CowLock;
+    /// use std::sync::Arc;
+    ///
+    /// let value = Arc::new(0);
+    /// let lock = CowLock::from_arc(value.clone());
+    /// assert!(Arc::ptr_eq(&lock.load(), &value));
+    /// ```
+    pub fn from_arc(value: Arc<T>) -> CowLock<T> {
+        CowLock {
+            ptr: AtomicPtr::new(Arc::into_raw(value) as *mut T),
+            epoch: AtomicUsize::new(0),
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// Returns a snapshot of the current value.
+    ///
+    /// This never waits: writers that are busy copying or mutating the value do not block it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::CowLock;
+    ///
+    /// let lock = CowLock::new(10);
+    /// assert_eq!(*lock.load(), 10);
+    /// ```
+    pub fn load(&self) -> Arc<T> {
+        // Announce this reader, so writers do not free the value while it is being cloned.
+        let readers = loop {
+            let epoch = self.epoch.load(Ordering::SeqCst);
+            let readers = &self.readers[epoch % 2];
+            readers.fetch_add(1, Ordering::SeqCst);
+            if self.epoch.load(Ordering::SeqCst) == epoch {
+                break readers;
+            }
+            readers.fetch_sub(1, Ordering::SeqCst);
+        };
+
+        let ptr = self.ptr.load(Ordering::SeqCst);
+        let value = unsafe {
+            Arc::increment_strong_count(ptr);
+            Arc::from_raw(ptr)
+        };
+
+        readers.fetch_sub(1, Ordering::SeqCst);
+        value
+    }
+
+    /// Replaces the current value, waiting for writers that are in progress.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::CowLock;
+    ///
+    /// let lock = CowLock::new(1);
+    /// lock.store(2).await;
+    /// assert_eq!(*lock.load(), 2);
+    /// # })
+    /// ```
+    pub async fn store(&self, value: T) {
+        let _writer = self.writer.lock().await;
+        self.publish(Arc::new(value));
+    }
+
+    /// Returns a mutable reference to the current value, if no snapshots of it exist anymore.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::CowLock;
+    ///
+    /// let mut lock = CowLock::new(1);
+    /// *lock.get_mut().unwrap() += 1;
+    ///
+    /// let snapshot = lock.load();
+    /// assert!(lock.get_mut().is_none());
+    /// ```
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        let mut value = ManuallyDrop::new(unsafe { Arc::from_raw(*self.ptr.get_mut()) });
+        let value = Arc::get_mut(&mut value)? as *mut T;
+        Some(unsafe { &mut *value })
+    }
+
+    /// Consumes the lock, returning the current value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::CowLock;
+    ///
+    /// let lock = CowLock::new(5);
+    /// assert_eq!(*lock.into_arc(), 5);
+    /// ```
+    pub fn into_arc(mut self) -> Arc<T> {
+        let ptr = mem::replace(self.ptr.get_mut(), ptr::null_mut());
+        unsafe { Arc::from_raw(ptr) }
+    }
+
+    /// Makes `value` the current value and frees the previous one once no reader is copying it.
+    fn publish(&self, value: Arc<T>) {
+        let old = self
+            .ptr
+            .swap(Arc::into_raw(value) as *mut T, Ordering::SeqCst);
+
+        // Readers that saw the old epoch may still be cloning the old value. New readers see the
+        // new epoch and the new value, so this waits for a bounded number of readers.
+        let epoch = self.epoch.fetch_add(1, Ordering::SeqCst);
+        let mut spins = 0u32;
+        while self.readers[epoch % 2].load(Ordering::SeqCst) != 0 {
+            if spins < 100 {
+                core::hint::spin_loop();
+                spins += 1;
+            } else {
+                thread::yield_now();
+            }
+        }
+
+        drop(unsafe { Arc::from_raw(old) });
+    }
+}
+
+impl<T: Clone> CowLock<T> {
+    /// Locks out other writers and returns a guard holding a private copy of the current value.
+    ///
+    /// Changes made through the guard become visible to readers when the guard is dropped. Use
+    /// [`CowLockWriteGuard::discard()`] to drop the changes instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::CowLock;
+    ///
+    /// let lock = CowLock::new(String::from("hello"));
+    ///
+    /// let mut guard = lock.write().await;
+    /// guard.push_str(" world");
+    /// assert_eq!(*lock.load(), "hello");
+    ///
+    /// drop(guard);
+    /// assert_eq!(*lock.load(), "hello world");
+    /// # })
+    /// ```
+    #[track_caller]
+    pub fn write(&self) -> impl Future<Output = CowLockWriteGuard<'_, T>> {
+        let lock = self.writer.lock();
+        async move { CowLockWriteGuard::new(self, lock.await) }
+    }
+
+    /// Attempts to lock out other writers without waiting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::CowLock;
+    ///
+    /// let lock = CowLock::new(1);
+    /// let guard = lock.try_write().unwrap();
+    /// assert!(lock.try_write().is_none());
+    /// ```
+    #[track_caller]
+    pub fn try_write(&self) -> Option<CowLockWriteGuard<'_, T>> {
+        let writer = self.writer.try_lock()?;
+        Some(CowLockWriteGuard::new(self, writer))
+    }
+}
+
+impl<T> Drop for CowLock<T> {
+    fn drop(&mut self) {
+        let ptr = *self.ptr.get_mut();
+        if !ptr.is_null() {
+            drop(unsafe { Arc::from_raw(ptr) });
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CowLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CowLock")
+            .field("value", &self.load())
+            .finish()
+    }
+}
+
+impl<T> From<T> for CowLock<T> {
+    fn from(value: T) -> CowLock<T> {
+        CowLock::new(value)
+    }
+}
+
+impl<T: Default> Default for CowLock<T> {
+    fn default() -> CowLock<T> {
+        CowLock::new(Default::default())
+    }
+}
+
+/// A guard that publishes its copy of the value of a [`CowLock`] when dropped.
+pub struct CowLockWriteGuard<'a, T> {
+    lock: &'a CowLock<T>,
+    _writer: MutexGuard<'a, ()>,
+
+    /// The private copy, or `None` once it was discarded.
+    value: Option<T>,
+}
+
+impl<'a, T: Clone> CowLockWriteGuard<'a, T> {
+    fn new(lock: &'a CowLock<T>, writer: MutexGuard<'a, ()>) -> CowLockWriteGuard<'a, T> {
+        CowLockWriteGuard {
+            lock,
+            _writer: writer,
+            value: Some((*lock.load()).clone()),
+        }
+    }
+}
+
+impl<T> CowLockWriteGuard<'_, T> {
+    /// Releases the lock without publishing the changes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{CowLock, CowLockWriteGuard};
+    ///
+    /// let lock = CowLock::new(1);
+    /// let mut guard = lock.write().await;
+    /// *guard = 2;
+    /// CowLockWriteGuard::discard(guard);
+    /// assert_eq!(*lock.load(), 1);
+    /// # })
+    /// ```
+    pub fn discard(mut guard: Self) {
+        guard.value = None;
+    }
+}
+
+impl<T> Drop for CowLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take() {
+            self.lock.publish(Arc::new(value));
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CowLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> Deref for CowLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().unwrap()
+    }
+}
+
+impl<T> DerefMut for CowLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_mut().unwrap()
+    }
+}
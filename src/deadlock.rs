@@ -0,0 +1,197 @@
+//! A wait-for graph between labeled tasks, used to detect deadlocks in debug builds.
+//!
+//! Every [`Mutex`] and [`RwLock`] operation that runs inside a [`label()`] future records which
+//! locks its task holds and which lock it is waiting for. When a task starts waiting, the graph is
+//! searched for a cycle leading back to it. Holders outside a labeled future are unknown, so a lock
+//! with such a holder is never reported as part of a deadlock.
+//!
+//! [`Mutex`]: crate::Mutex
+//! [`RwLock`]: crate::RwLock
+//! [`label()`]: crate::diagnostics::label()
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::panic::Location;
+use std::sync::{Arc, Mutex};
+
+use crate::trace::LockName;
+
+/// The locks held and awaited by labeled tasks.
+static GRAPH: Mutex<Graph> = Mutex::new(Graph {
+    holders: BTreeMap::new(),
+    waiting: BTreeMap::new(),
+});
+
+struct Graph {
+    /// The holders of each lock, by the address of the lock.
+    holders: BTreeMap<usize, Vec<Holder>>,
+
+    /// The lock each task is waiting for, by task ID.
+    waiting: BTreeMap<u64, Waiting>,
+}
+
+struct Holder {
+    /// The ID and label of the holding task, or `None` outside a labeled future.
+    task: Option<(u64, Arc<str>)>,
+    location: &'static Location<'static>,
+}
+
+struct Waiting {
+    label: Arc<str>,
+    kind: &'static str,
+    mode: &'static str,
+    lock: LockName,
+    location: &'static Location<'static>,
+}
+
+/// Returns `true` if locks of this kind take part in deadlock detection.
+///
+/// Semaphore permits can be added, forgotten, and released by any task, so a task holding one is
+/// not necessarily what a waiter is waiting for.
+fn is_tracked(kind: &str) -> bool {
+    cfg!(debug_assertions) && kind != "Semaphore"
+}
+
+fn graph() -> std::sync::MutexGuard<'static, Graph> {
+    GRAPH.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Records that the current task acquired the lock at `lock`, returning the task ID.
+pub(crate) fn acquired(
+    kind: &'static str,
+    lock: usize,
+    location: &'static Location<'static>,
+) -> Option<u64> {
+    if !is_tracked(kind) {
+        return None;
+    }
+
+    let task = crate::diagnostics::current_task();
+    let id = task.as_ref().map(|(id, _)| *id);
+    graph()
+        .holders
+        .entry(lock)
+        .or_default()
+        .push(Holder { task, location });
+    id
+}
+
+/// Records that the task `task` released the lock at `lock`.
+pub(crate) fn released(lock: usize, task: Option<u64>) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+
+    let mut graph = graph();
+    if let Some(holders) = graph.holders.get_mut(&lock) {
+        // Guards can move between tasks, so fall back to any holder.
+        let i = holders
+            .iter()
+            .position(|h| h.task.as_ref().map(|(id, _)| *id) == task)
+            .unwrap_or(0);
+        if i < holders.len() {
+            holders.remove(i);
+        }
+        if holders.is_empty() {
+            graph.holders.remove(&lock);
+        }
+    }
+}
+
+/// Records that the current task started waiting for `lock`, returning the task ID.
+///
+/// # Panics
+///
+/// Panics with a description of the cycle if this completes a deadlock.
+pub(crate) fn wait_started(
+    kind: &'static str,
+    mode: &'static str,
+    lock: LockName,
+    location: &'static Location<'static>,
+) -> Option<u64> {
+    if !is_tracked(kind) {
+        return None;
+    }
+    let (task, label) = crate::diagnostics::current_task()?;
+
+    let waiting = Waiting {
+        label,
+        kind,
+        mode,
+        lock,
+        location,
+    };
+    let mut graph = graph();
+    if graph.is_deadlocked(task, lock.id, &mut BTreeSet::new()) {
+        let mut report = String::from("deadlock detected:");
+        graph.describe(task, &waiting, &mut BTreeSet::new(), &mut report);
+        drop(graph);
+        panic!("{}", report);
+    }
+    graph.waiting.insert(task, waiting);
+    Some(task)
+}
+
+/// Records that the task `task` stopped waiting.
+pub(crate) fn wait_ended(task: Option<u64>) {
+    if let Some(task) = task {
+        graph().waiting.remove(&task);
+    }
+}
+
+impl Graph {
+    /// Returns `true` if every holder of `lock` waits, directly or through other locks, for
+    /// `task`, which is about to wait for `lock`.
+    fn is_deadlocked(&self, task: u64, lock: usize, visited: &mut BTreeSet<usize>) -> bool {
+        if !visited.insert(lock) {
+            // A cycle that does not involve `task` was already reported when it formed.
+            return false;
+        }
+
+        let holders = match self.holders.get(&lock) {
+            Some(holders) => holders,
+            None => return false,
+        };
+        holders.iter().all(|holder| match &holder.task {
+            Some((id, _)) if *id == task => true,
+            Some((id, _)) => match self.waiting.get(id) {
+                Some(waiting) => self.is_deadlocked(task, waiting.lock.id, visited),
+                None => false,
+            },
+            None => false,
+        })
+    }
+
+    /// Writes the waits and holders that make up a deadlock, starting from `task`.
+    fn describe(
+        &self,
+        task: u64,
+        waiting: &Waiting,
+        visited: &mut BTreeSet<usize>,
+        report: &mut String,
+    ) {
+        let _ = write!(
+            report,
+            "\n  task \"{}\" waits for {} {} ({}) at {}",
+            waiting.label, waiting.kind, waiting.lock, waiting.mode, waiting.location
+        );
+        if !visited.insert(waiting.lock.id) {
+            return;
+        }
+
+        for holder in self.holders.get(&waiting.lock.id).into_iter().flatten() {
+            if let Some((id, label)) = &holder.task {
+                let _ = write!(
+                    report,
+                    "\n    held by task \"{}\", acquired at {}",
+                    label, holder.location
+                );
+                if *id != task {
+                    if let Some(next) = self.waiting.get(id) {
+                        self.describe(task, next, visited, report);
+                    }
+                }
+            }
+        }
+    }
+}
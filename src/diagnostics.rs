@@ -0,0 +1,1188 @@
+//! Diagnostics for detecting lock contention.
+//!
+//! # Slow acquisitions
+//!
+//! With the `log` feature, once a threshold is set with [`set_slow_threshold()`], every lock operation that has to wait
+//! longer than the threshold emits a warning through the [`log`](https://docs.rs/log) crate when
+//! it finally acquires the lock. The warning names the kind of lock, the access mode, the address
+//! of the lock, and the source location of the call that acquired it.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "log")]
+//! # {
+//! use async_lock::diagnostics;
+//! use std::time::Duration;
+//!
+//! diagnostics::set_slow_threshold(Some(Duration::from_millis(100)));
+//! # diagnostics::set_slow_threshold(None);
+//! # }
+//! ```
+//!
+//! # Metrics
+//!
+//! With the `hooks` feature, every [`Mutex`], [`RwLock`], and [`Semaphore`] reports contention,
+//! wait times, and hold times to a [`LockMetrics`] implementation. Hooks can be installed for the
+//! whole process with [`set_metrics()`], or for a single lock with constructors such as
+//! [`Mutex::with_metrics()`], which take precedence over the global hooks.
+//!
+//! [`LockHistograms`] is a built-in implementation that collects wait and hold times into
+//! fixed-bucket histograms, without any dependencies and without sampling, so even short contention
+//! spikes show up.
+//!
+//! With the `metrics` feature, [`MetricsRecorder`] forwards these measurements to the
+//! [`metrics`](https://docs.rs/metrics) facade, so any exporter such as Prometheus picks them up.
+//!
+//! # Lock registry
+//!
+//! With the `registry` feature, every lock is recorded in a process-wide registry the first time it
+//! is used, and [`dump()`] reports the state of all live locks. This is meant for crash and
+//! `SIGQUIT` handlers that need to explain why a process is stuck.
+//!
+//! Each lock can also list the operations waiting for it, with methods such as
+//! [`Mutex::waiters()`]. Every waiter carries an opaque ID, the source location of the call, the
+//! time it started waiting, and the label of the enclosing [`label()`] future, if any. Methods such
+//! as [`Mutex::holder_info()`] report the same about the most recent acquisition of a held lock,
+//! and the `Debug` output of a locked [`Mutex`] or [`RwLock`] names where it was acquired.
+//!
+//! [`stuck_waiters()`] finds the operations that have waited longer than a budget across all
+//! locks, and [`watchdog()`] runs that scan periodically and reports its findings to a callback.
+//!
+//! # Deadlock detection
+//!
+//! With the `deadlock-detection` feature, debug builds track which [`Mutex`]es and [`RwLock`]s each
+//! [`label()`] future holds and which lock it is waiting for. When a labeled task starts waiting
+//! for a lock whose holders are all labeled tasks waiting, directly or through other locks, for the
+//! first task, the lock operation panics with a description of the cycle: every task in it, the
+//! lock it waits for, and where the locks were acquired. Semaphores are not tracked.
+//!
+//! Each [`label()`] future counts as one task, so code that runs several futures concurrently
+//! should label each of them. Holders outside a labeled future are never considered deadlocked.
+//! Release builds skip the bookkeeping entirely.
+//!
+//! ```should_panic
+//! # #[cfg(all(feature = "deadlock-detection", debug_assertions))]
+//! # futures_lite::future::block_on(async {
+//! use async_lock::{diagnostics, Mutex};
+//!
+//! let mutex = Mutex::new(());
+//! diagnostics::label("worker", async {
+//!     let _guard = mutex.lock().await;
+//!     // Panics: "deadlock detected: task "worker" waits for Mutex ...".
+//!     let _again = mutex.lock().await;
+//! })
+//! .await;
+//! # });
+//! # #[cfg(not(all(feature = "deadlock-detection", debug_assertions)))]
+//! # panic!();
+//! ```
+//!
+//! # Lock events
+//!
+//! With the `events` feature, methods such as [`Mutex::events()`] subscribe to the acquisitions
+//! and releases of a single lock. Every [`LockEvent`] carries a timestamp and the label of the
+//! enclosing [`label()`] future, so test harnesses and monitors can observe lock activity without
+//! changing the code that uses the lock.
+//!
+//! [`Mutex`]: crate::Mutex
+//! [`RwLock`]: crate::RwLock
+//! [`Semaphore`]: crate::Semaphore
+//! [`Mutex::with_metrics()`]: crate::Mutex::with_metrics()
+//! [`Mutex::waiters()`]: crate::Mutex::waiters()
+//! [`Mutex::holder_info()`]: crate::Mutex::holder_info()
+//! [`Mutex::events()`]: crate::Mutex::events()
+
+#[cfg(any(feature = "registry", feature = "events"))]
+use core::cell::RefCell;
+#[cfg(any(feature = "log", feature = "hooks"))]
+use core::convert::TryFrom;
+#[cfg(any(feature = "registry", feature = "events"))]
+use core::fmt;
+#[cfg(any(feature = "registry", feature = "events"))]
+use core::future::Future;
+#[cfg(any(feature = "registry", feature = "events"))]
+use core::panic::Location;
+#[cfg(any(feature = "registry", feature = "events"))]
+use core::pin::Pin;
+#[cfg(feature = "hooks")]
+use core::sync::atomic::AtomicPtr;
+#[cfg(any(feature = "log", feature = "hooks"))]
+use core::sync::atomic::AtomicU64;
+#[cfg(any(feature = "log", feature = "hooks"))]
+use core::sync::atomic::Ordering;
+#[cfg(any(feature = "registry", feature = "events"))]
+use core::task::{Context, Poll};
+#[cfg(any(feature = "log", feature = "hooks", feature = "registry"))]
+use core::time::Duration;
+
+#[cfg(any(feature = "metrics", feature = "registry", feature = "events"))]
+use alloc::sync::Arc;
+#[cfg(any(feature = "metrics", feature = "registry"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "log")]
+/// The threshold in microseconds, or `u64::MAX` if slow acquisitions are not logged.
+static THRESHOLD: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// Sets how long a lock operation may wait before it is logged as slow.
+///
+/// Passing [`None`] disables logging, which is the default.
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::diagnostics;
+/// use std::time::Duration;
+///
+/// diagnostics::set_slow_threshold(Some(Duration::from_millis(50)));
+/// assert_eq!(diagnostics::slow_threshold(), Some(Duration::from_millis(50)));
+///
+/// diagnostics::set_slow_threshold(None);
+/// assert_eq!(diagnostics::slow_threshold(), None);
+/// ```
+#[cfg(feature = "log")]
+pub fn set_slow_threshold(threshold: Option<Duration>) {
+    let micros = match threshold {
+        Some(t) => u64::try_from(t.as_micros()).unwrap_or(u64::MAX - 1),
+        None => u64::MAX,
+    };
+    THRESHOLD.store(micros, Ordering::Relaxed);
+}
+
+/// Returns the threshold set with [`set_slow_threshold()`].
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::diagnostics;
+///
+/// assert_eq!(diagnostics::slow_threshold(), None);
+/// ```
+#[cfg(feature = "log")]
+pub fn slow_threshold() -> Option<Duration> {
+    match THRESHOLD.load(Ordering::Relaxed) {
+        u64::MAX => None,
+        micros => Some(Duration::from_micros(micros)),
+    }
+}
+
+/// Returns `true` if a lock operation that waited for `wait` should be logged.
+#[cfg(feature = "log")]
+pub(crate) fn is_slow(wait: Duration) -> bool {
+    let threshold = THRESHOLD.load(Ordering::Relaxed);
+    threshold != u64::MAX && wait.as_micros() > u128::from(threshold)
+}
+
+/// Receives measurements from lock operations.
+///
+/// All methods have empty default implementations, so implementors only need to override the
+/// events they care about. Hooks are called synchronously from inside lock operations and should
+/// be cheap.
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::diagnostics::{LockInfo, LockMetrics};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// #[derive(Default)]
+/// struct Contention(AtomicUsize);
+///
+/// impl LockMetrics for Contention {
+///     fn on_contended(&self, _: &LockInfo) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+/// ```
+#[cfg(feature = "hooks")]
+pub trait LockMetrics: Send + Sync {
+    /// Called when a lock operation cannot complete immediately and starts waiting.
+    fn on_contended(&self, lock: &LockInfo) {
+        let _ = lock;
+    }
+
+    /// Called when a lock operation acquires the lock, after waiting for `wait`.
+    fn on_acquired(&self, lock: &LockInfo, wait: Duration) {
+        let _ = (lock, wait);
+    }
+
+    /// Called when a guard is dropped, after holding the lock for `hold`.
+    fn on_released(&self, lock: &LockInfo, hold: Duration) {
+        let _ = (lock, hold);
+    }
+}
+
+/// Describes the lock an event comes from.
+#[cfg(feature = "hooks")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockInfo {
+    pub(crate) kind: &'static str,
+    pub(crate) mode: &'static str,
+    pub(crate) id: usize,
+    pub(crate) name: Option<&'static str>,
+}
+
+#[cfg(feature = "hooks")]
+impl LockInfo {
+    /// Returns the kind of primitive, such as `"Mutex"` or `"RwLock"`.
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    /// Returns the access mode, such as `"lock"`, `"read"`, or `"write"`.
+    pub fn mode(&self) -> &'static str {
+        self.mode
+    }
+
+    /// Returns an identifier that is unique among live locks.
+    ///
+    /// This is the address of the lock.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns the name the lock was created with, if any.
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+}
+
+/// The global hooks, or null if there are none.
+#[cfg(feature = "hooks")]
+static METRICS: AtomicPtr<&'static dyn LockMetrics> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Installs process-wide metrics hooks for locks that have none of their own.
+///
+/// The hooks are passed as a reference to a `&dyn LockMetrics` that lives forever, usually a
+/// `static`, so they can be swapped in atomically.
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::diagnostics::{self, LockMetrics};
+///
+/// struct Ignore;
+/// impl LockMetrics for Ignore {}
+///
+/// static IGNORE: &dyn LockMetrics = &Ignore;
+/// diagnostics::set_metrics(&IGNORE);
+/// # diagnostics::clear_metrics();
+/// ```
+#[cfg(feature = "hooks")]
+pub fn set_metrics(metrics: &'static &'static dyn LockMetrics) {
+    METRICS.store(metrics as *const _ as *mut _, Ordering::Release);
+}
+
+/// Removes the hooks installed with [`set_metrics()`].
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::diagnostics;
+///
+/// diagnostics::clear_metrics();
+/// ```
+#[cfg(feature = "hooks")]
+pub fn clear_metrics() {
+    METRICS.store(core::ptr::null_mut(), Ordering::Release);
+}
+
+/// Returns the hooks installed with [`set_metrics()`].
+#[cfg(feature = "hooks")]
+pub(crate) fn global_metrics() -> Option<&'static dyn LockMetrics> {
+    let ptr = METRICS.load(Ordering::Acquire);
+
+    // SAFETY: Non-null values come from a `&'static` reference in `set_metrics()`.
+    unsafe { ptr.as_ref().copied() }
+}
+
+/// Forwards lock measurements to the [`metrics`](https://docs.rs/metrics) facade.
+///
+/// The following metrics are recorded, labeled with the `kind` and `mode` of the lock operation,
+/// and with `lock` if the recorder was created with [`MetricsRecorder::named()`]:
+///
+/// * `async_lock_acquisitions_total` - a counter of successful lock operations.
+/// * `async_lock_contentions_total` - a counter of lock operations that had to wait.
+/// * `async_lock_wait_seconds` - a histogram of how long lock operations waited.
+/// * `async_lock_hold_seconds` - a histogram of how long guards were held.
+///
+/// # Examples
+///
+/// Report every lock in the process:
+///
+/// ```
+/// use async_lock::diagnostics::{self, LockMetrics, MetricsRecorder};
+///
+/// static METRICS: &dyn LockMetrics = &MetricsRecorder::new();
+/// diagnostics::set_metrics(&METRICS);
+/// # diagnostics::clear_metrics();
+/// ```
+///
+/// Report a single lock under its own name:
+///
+/// ```
+/// use async_lock::diagnostics::MetricsRecorder;
+/// use async_lock::Mutex;
+/// use std::sync::Arc;
+///
+/// let state = Mutex::with_metrics(0, Arc::new(MetricsRecorder::named("scheduler-state")));
+/// ```
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Default)]
+pub struct MetricsRecorder {
+    name: Option<Arc<str>>,
+}
+
+#[cfg(feature = "metrics")]
+impl MetricsRecorder {
+    /// Creates a recorder that labels metrics only by lock kind and mode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::diagnostics::MetricsRecorder;
+    ///
+    /// let recorder = MetricsRecorder::new();
+    /// ```
+    pub const fn new() -> MetricsRecorder {
+        MetricsRecorder { name: None }
+    }
+
+    /// Creates a recorder that also labels metrics with `lock = name`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::diagnostics::MetricsRecorder;
+    ///
+    /// let recorder = MetricsRecorder::named("connections");
+    /// ```
+    pub fn named(name: &str) -> MetricsRecorder {
+        MetricsRecorder {
+            name: Some(name.into()),
+        }
+    }
+
+    /// Returns the labels for an event from `lock`.
+    fn labels(&self, lock: &LockInfo) -> Vec<metrics::Label> {
+        let mut labels = Vec::with_capacity(3);
+        labels.push(metrics::Label::new("kind", lock.kind()));
+        labels.push(metrics::Label::new("mode", lock.mode()));
+        if let Some(name) = &self.name {
+            labels.push(metrics::Label::new(
+                "lock",
+                metrics::SharedString::from_shared(name.clone()),
+            ));
+        }
+        labels
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl LockMetrics for MetricsRecorder {
+    fn on_contended(&self, lock: &LockInfo) {
+        metrics::counter!("async_lock_contentions_total", self.labels(lock)).increment(1);
+    }
+
+    fn on_acquired(&self, lock: &LockInfo, wait: Duration) {
+        let labels = self.labels(lock);
+        metrics::counter!("async_lock_acquisitions_total", labels.iter()).increment(1);
+        metrics::histogram!("async_lock_wait_seconds", labels).record(wait.as_secs_f64());
+    }
+
+    fn on_released(&self, lock: &LockInfo, hold: Duration) {
+        metrics::histogram!("async_lock_hold_seconds", self.labels(lock))
+            .record(hold.as_secs_f64());
+    }
+}
+
+/// Number of buckets in a [`Histogram`].
+#[cfg(feature = "hooks")]
+const BUCKETS: usize = 32;
+
+/// Collects wait and hold times of the locks it is installed on into histograms.
+///
+/// Durations are sorted into [`Histogram`] buckets whose bounds double from 1µs up to about 18
+/// minutes. Recording a duration is a couple of relaxed atomic increments.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::diagnostics::LockHistograms;
+/// use async_lock::Mutex;
+/// use std::sync::Arc;
+///
+/// let histograms = Arc::new(LockHistograms::new());
+/// let mutex = Mutex::with_metrics(0, histograms.clone());
+///
+/// *mutex.lock().await += 1;
+/// assert_eq!(histograms.wait().count(), 1);
+/// assert_eq!(histograms.hold().count(), 1);
+/// # })
+/// ```
+#[cfg(feature = "hooks")]
+#[derive(Debug, Default)]
+pub struct LockHistograms {
+    contentions: AtomicU64,
+    wait: AtomicHistogram,
+    hold: AtomicHistogram,
+}
+
+#[cfg(feature = "hooks")]
+impl LockHistograms {
+    /// Creates empty histograms.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::diagnostics::{self, LockHistograms, LockMetrics};
+    ///
+    /// static HISTOGRAMS: LockHistograms = LockHistograms::new();
+    /// static METRICS: &dyn LockMetrics = &HISTOGRAMS;
+    /// diagnostics::set_metrics(&METRICS);
+    /// # diagnostics::clear_metrics();
+    /// ```
+    pub const fn new() -> LockHistograms {
+        LockHistograms {
+            contentions: AtomicU64::new(0),
+            wait: AtomicHistogram::new(),
+            hold: AtomicHistogram::new(),
+        }
+    }
+
+    /// Returns the number of lock operations that had to wait.
+    pub fn contentions(&self) -> u64 {
+        self.contentions.load(Ordering::Relaxed)
+    }
+
+    /// Returns how long lock operations waited before acquiring the lock.
+    ///
+    /// Lock operations that completed immediately are recorded as waiting for zero time.
+    pub fn wait(&self) -> Histogram {
+        self.wait.snapshot()
+    }
+
+    /// Returns how long guards held the lock.
+    pub fn hold(&self) -> Histogram {
+        self.hold.snapshot()
+    }
+
+    /// Clears all histograms and counters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::diagnostics::LockHistograms;
+    /// use async_lock::Mutex;
+    /// use std::sync::Arc;
+    ///
+    /// let histograms = Arc::new(LockHistograms::new());
+    /// let mutex = Mutex::with_metrics(0, histograms.clone());
+    ///
+    /// drop(mutex.try_lock());
+    /// histograms.reset();
+    /// assert_eq!(histograms.wait().count(), 0);
+    /// ```
+    pub fn reset(&self) {
+        self.contentions.store(0, Ordering::Relaxed);
+        self.wait.reset();
+        self.hold.reset();
+    }
+}
+
+#[cfg(feature = "hooks")]
+impl LockMetrics for LockHistograms {
+    fn on_contended(&self, _: &LockInfo) {
+        self.contentions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_acquired(&self, _: &LockInfo, wait: Duration) {
+        self.wait.record(wait);
+    }
+
+    fn on_released(&self, _: &LockInfo, hold: Duration) {
+        self.hold.record(hold);
+    }
+}
+
+/// The live counters behind a [`Histogram`].
+#[cfg(feature = "hooks")]
+#[derive(Debug)]
+struct AtomicHistogram {
+    /// Total of all recorded durations, in nanoseconds.
+    sum: AtomicU64,
+    counts: [AtomicU64; BUCKETS],
+}
+
+#[cfg(feature = "hooks")]
+impl AtomicHistogram {
+    const fn new() -> AtomicHistogram {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const ZERO: AtomicU64 = AtomicU64::new(0);
+
+        AtomicHistogram {
+            sum: AtomicU64::new(0),
+            counts: [ZERO; BUCKETS],
+        }
+    }
+
+    fn record(&self, duration: Duration) {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.sum.fetch_add(nanos, Ordering::Relaxed);
+        self.counts[bucket(duration)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn reset(&self) {
+        self.sum.store(0, Ordering::Relaxed);
+        for count in &self.counts {
+            count.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn snapshot(&self) -> Histogram {
+        let mut counts = [0; BUCKETS];
+        for (count, atomic) in counts.iter_mut().zip(&self.counts) {
+            *count = atomic.load(Ordering::Relaxed);
+        }
+
+        Histogram {
+            sum: Duration::from_nanos(self.sum.load(Ordering::Relaxed)),
+            counts,
+        }
+    }
+}
+
+#[cfg(feature = "hooks")]
+impl Default for AtomicHistogram {
+    fn default() -> AtomicHistogram {
+        AtomicHistogram::new()
+    }
+}
+
+/// Returns the index of the bucket `duration` falls into.
+#[cfg(feature = "hooks")]
+fn bucket(duration: Duration) -> usize {
+    let micros = duration.as_micros();
+    if micros == 0 {
+        return 0;
+    }
+
+    // Bucket `i` holds durations below `2^i` microseconds.
+    let i = (128 - micros.leading_zeros()) as usize;
+    i.min(BUCKETS - 1)
+}
+
+/// Returns the exclusive upper bound of bucket `i`, or `None` for the last bucket.
+#[cfg(feature = "hooks")]
+fn upper_bound(i: usize) -> Option<Duration> {
+    if i == BUCKETS - 1 {
+        None
+    } else {
+        Some(Duration::from_micros(1 << i))
+    }
+}
+
+/// A snapshot of a histogram of durations, returned by [`LockHistograms`].
+///
+/// Bucket `0` counts durations below 1µs, bucket `i` counts durations from `2^(i-1)`µs up to
+/// `2^i`µs, and the last bucket counts everything longer.
+#[cfg(feature = "hooks")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    sum: Duration,
+    counts: [u64; BUCKETS],
+}
+
+#[cfg(feature = "hooks")]
+impl Histogram {
+    /// Returns the number of recorded durations.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the total of all recorded durations.
+    pub fn sum(&self) -> Duration {
+        self.sum
+    }
+
+    /// Returns the average recorded duration, or `None` if nothing was recorded.
+    pub fn mean(&self) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let nanos = self.sum.as_nanos() / u128::from(count);
+        Some(Duration::from_nanos(nanos as u64))
+    }
+
+    /// Returns the buckets as pairs of an exclusive upper bound and a count.
+    ///
+    /// The last bucket has no upper bound.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::diagnostics::LockHistograms;
+    ///
+    /// let histograms = LockHistograms::new();
+    /// for (bound, count) in histograms.hold().buckets() {
+    ///     match bound {
+    ///         Some(bound) => println!("< {:?}: {}", bound, count),
+    ///         None => println!("longer: {}", count),
+    ///     }
+    /// }
+    /// ```
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| (upper_bound(i), count))
+    }
+
+    /// Returns an upper bound for the duration below which a fraction `q` of recordings fall.
+    ///
+    /// For example, `quantile(0.99)` bounds the 99th percentile. Returns `None` if nothing was
+    /// recorded, or if the quantile falls into the last bucket, which has no upper bound.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` is not between `0.0` and `1.0`.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        assert!((0.0..=1.0).contains(&q), "quantile must be between 0 and 1");
+
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+
+        let rank = ((count as f64) * q).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (i, &n) in self.counts.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return upper_bound(i);
+            }
+        }
+        None
+    }
+}
+
+/// The state of a lock at the time of a [`dump()`].
+#[cfg(feature = "registry")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockSnapshot {
+    pub(crate) kind: &'static str,
+    pub(crate) id: usize,
+    pub(crate) name: Option<&'static str>,
+    pub(crate) holders: usize,
+    pub(crate) holder: Option<&'static Location<'static>>,
+    pub(crate) waiters: usize,
+}
+
+#[cfg(feature = "registry")]
+impl LockSnapshot {
+    /// Returns the kind of primitive, such as `"Mutex"` or `"RwLock"`.
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    /// Returns the address of the lock when it was first used.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns the name the lock was created with, if any.
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    /// Returns `true` if the lock was held.
+    pub fn is_held(&self) -> bool {
+        self.holders > 0
+    }
+
+    /// Returns the number of guards holding the lock.
+    ///
+    /// This can be more than one for read locks and semaphore permits.
+    pub fn holders(&self) -> usize {
+        self.holders
+    }
+
+    /// Returns where the lock was most recently acquired, if it was held.
+    pub fn holder(&self) -> Option<&'static Location<'static>> {
+        self.holder
+    }
+
+    /// Returns the number of lock operations waiting for the lock.
+    pub fn waiters(&self) -> usize {
+        self.waiters
+    }
+}
+
+#[cfg(feature = "registry")]
+impl fmt::Display for LockSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lock = crate::trace::LockName {
+            name: self.name,
+            id: self.id,
+        };
+        write!(f, "{} {}: ", self.kind, lock)?;
+        match self.holder {
+            Some(holder) if self.holders > 1 => {
+                write!(f, "held {} times, last at {}", self.holders, holder)?
+            }
+            Some(holder) => write!(f, "held at {}", holder)?,
+            None => f.write_str("free")?,
+        }
+        write!(f, ", {} waiting", self.waiters)
+    }
+}
+
+/// Reports the state of every live lock that has been used at least once.
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::{diagnostics, Mutex};
+///
+/// let m = Mutex::new(());
+/// let guard = m.try_lock().unwrap();
+///
+/// for lock in diagnostics::dump() {
+///     eprintln!("{}", lock);
+/// }
+/// ```
+#[cfg(feature = "registry")]
+pub fn dump() -> Vec<LockSnapshot> {
+    crate::registry::dump()
+}
+
+/// A lock operation waiting for a lock, as reported by methods such as [`Mutex::waiters()`].
+///
+/// [`Mutex::waiters()`]: crate::Mutex::waiters()
+#[cfg(feature = "registry")]
+#[derive(Debug, Clone)]
+pub struct WaiterInfo {
+    pub(crate) id: u64,
+    pub(crate) label: Option<Arc<str>>,
+    pub(crate) location: &'static Location<'static>,
+    pub(crate) since: crate::time::Instant,
+}
+
+#[cfg(feature = "registry")]
+impl WaiterInfo {
+    /// Returns an ID that is unique to this lock operation within the process.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the label of the [`label()`] future the lock operation ran in, if any.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Returns where the lock operation was called.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    /// Returns when the lock operation started waiting.
+    pub fn since(&self) -> crate::time::Instant {
+        self.since
+    }
+
+    /// Returns how long the lock operation has been waiting so far.
+    pub fn waited(&self) -> Duration {
+        self.since.elapsed()
+    }
+}
+
+#[cfg(feature = "registry")]
+impl fmt::Display for WaiterInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.id)?;
+        if let Some(label) = &self.label {
+            write!(f, " ({})", label)?;
+        }
+        write!(f, " at {}, waiting for {:?}", self.location, self.waited())
+    }
+}
+
+/// The most recent acquisition of a lock, as reported by methods such as [`Mutex::holder_info()`].
+///
+/// [`Mutex::holder_info()`]: crate::Mutex::holder_info()
+#[cfg(feature = "registry")]
+#[derive(Debug, Clone)]
+pub struct HolderInfo {
+    pub(crate) label: Option<Arc<str>>,
+    pub(crate) location: &'static Location<'static>,
+    pub(crate) since: crate::time::Instant,
+}
+
+#[cfg(feature = "registry")]
+impl HolderInfo {
+    /// Returns the label of the [`label()`] future that acquired the lock, if any.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Returns where the lock was acquired.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    /// Returns when the lock was acquired.
+    pub fn since(&self) -> crate::time::Instant {
+        self.since
+    }
+
+    /// Returns how long the lock has been held so far.
+    pub fn held(&self) -> Duration {
+        self.since.elapsed()
+    }
+}
+
+#[cfg(feature = "registry")]
+impl fmt::Display for HolderInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(label) = &self.label {
+            write!(f, "({}) ", label)?;
+        }
+        write!(f, "at {}, held for {:?}", self.location, self.held())
+    }
+}
+
+/// A lock operation that has been waiting for longer than a budget, as reported by
+/// [`stuck_waiters()`] and [`watchdog()`].
+#[cfg(feature = "registry")]
+#[derive(Debug, Clone)]
+pub struct StuckWaiter {
+    lock: LockSnapshot,
+    waiter: WaiterInfo,
+}
+
+#[cfg(feature = "registry")]
+impl StuckWaiter {
+    /// Returns the state of the lock the operation is waiting for, including its holder.
+    pub fn lock(&self) -> &LockSnapshot {
+        &self.lock
+    }
+
+    /// Returns the waiting lock operation.
+    pub fn waiter(&self) -> &WaiterInfo {
+        &self.waiter
+    }
+}
+
+#[cfg(feature = "registry")]
+impl fmt::Display for StuckWaiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} on {}", self.waiter, self.lock)
+    }
+}
+
+/// Returns every lock operation that has been waiting for at least `budget`.
+///
+/// Waiters are grouped by lock, in the order they started waiting.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::{diagnostics, Mutex};
+/// use futures_lite::future;
+/// use std::time::Duration;
+///
+/// let m = Mutex::new(());
+/// let _guard = m.try_lock().unwrap();
+///
+/// let mut waiter = Box::pin(m.lock());
+/// assert!(future::poll_once(&mut waiter).await.is_none());
+///
+/// let stuck = diagnostics::stuck_waiters(Duration::ZERO);
+/// assert!(stuck.iter().any(|s| s.lock().id() == &m as *const _ as usize));
+/// assert!(diagnostics::stuck_waiters(Duration::from_secs(60)).is_empty());
+/// # })
+/// ```
+#[cfg(feature = "registry")]
+pub fn stuck_waiters(budget: Duration) -> Vec<StuckWaiter> {
+    crate::registry::stuck(budget)
+        .into_iter()
+        .map(|(lock, waiter)| StuckWaiter { lock, waiter })
+        .collect()
+}
+
+/// Scans all locks every `interval` and reports lock operations waiting for longer than `budget`.
+///
+/// `report` is called with the stuck waiters after every scan that found any, so hangs can raise
+/// an alert instead of going unnoticed. The returned future never completes; spawn it on an
+/// executor and drop it to stop the watchdog.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
+/// # {
+/// use async_lock::{diagnostics, AsyncIoTimer};
+/// use std::time::Duration;
+///
+/// let watchdog = diagnostics::watchdog(
+///     AsyncIoTimer,
+///     Duration::from_secs(5),
+///     Duration::from_secs(1),
+///     |stuck| {
+///         for waiter in stuck {
+///             eprintln!("stuck: {}", waiter);
+///         }
+///     },
+/// );
+/// # drop(watchdog);
+/// # }
+/// ```
+#[cfg(feature = "registry")]
+pub async fn watchdog<T: crate::Timer>(
+    timer: T,
+    budget: Duration,
+    interval: Duration,
+    mut report: impl FnMut(&[StuckWaiter]),
+) {
+    loop {
+        timer.sleep(interval).await;
+
+        let stuck = stuck_waiters(budget);
+        if !stuck.is_empty() {
+            report(&stuck);
+        }
+    }
+}
+
+#[cfg(any(feature = "registry", feature = "events"))]
+std::thread_local! {
+    /// The label of the [`Labeled`] future being polled on this thread.
+    static LABEL: RefCell<Option<Label>> = const { RefCell::new(None) };
+}
+
+/// The label of a [`Labeled`] future.
+#[cfg(any(feature = "registry", feature = "events"))]
+#[derive(Debug, Clone)]
+struct Label {
+    name: Arc<str>,
+
+    /// Identifies the labeled future as a task in deadlock reports.
+    #[cfg(feature = "deadlock-detection")]
+    task: u64,
+}
+
+/// The ID of the next labeled future.
+#[cfg(feature = "deadlock-detection")]
+static NEXT_TASK: core::sync::atomic::AtomicU64 = core::sync::atomic::AtomicU64::new(1);
+
+/// Labels every lock operation in `future`.
+///
+/// Lock operations that run while `future` is being polled are reported with this label by
+/// methods such as `Mutex::waiters()` and in [`LockEvent`]s, which helps tell apart tasks that
+/// use a lock at the same location.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::{diagnostics, Mutex};
+///
+/// let session = Mutex::new(());
+///
+/// diagnostics::label("request 42", async {
+///     let _guard = session.lock().await;
+/// })
+/// .await;
+/// # })
+/// ```
+#[cfg(any(feature = "registry", feature = "events"))]
+pub fn label<F: Future>(label: impl Into<Arc<str>>, future: F) -> Labeled<F> {
+    Labeled {
+        future,
+        label: Label {
+            name: label.into(),
+            #[cfg(feature = "deadlock-detection")]
+            task: NEXT_TASK.fetch_add(1, core::sync::atomic::Ordering::Relaxed),
+        },
+    }
+}
+
+#[cfg(any(feature = "registry", feature = "events"))]
+pin_project_lite::pin_project! {
+    /// Future for [`label()`].
+    #[derive(Debug)]
+    #[must_use = "futures do nothing unless you `.await` or poll them"]
+    pub struct Labeled<F> {
+        #[pin]
+        future: F,
+        label: Label,
+    }
+}
+
+#[cfg(any(feature = "registry", feature = "events"))]
+impl<F: Future> Future for Labeled<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        /// Restores the previous label, even if the inner future panics.
+        struct Restore(Option<Label>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                let prev = self.0.take();
+                let _ = LABEL.try_with(|label| *label.borrow_mut() = prev);
+            }
+        }
+
+        let this = self.project();
+        let prev = LABEL.with(|label| label.replace(Some(this.label.clone())));
+        let _restore = Restore(prev);
+        this.future.poll(cx)
+    }
+}
+
+/// Returns the label of the [`Labeled`] future being polled on this thread, if any.
+#[cfg(any(feature = "registry", feature = "events"))]
+pub(crate) fn current_label() -> Option<Arc<str>> {
+    LABEL
+        .try_with(|label| label.borrow().as_ref().map(|label| label.name.clone()))
+        .ok()
+        .flatten()
+}
+
+/// Returns the ID and label of the [`Labeled`] future being polled on this thread, if any.
+#[cfg(feature = "deadlock-detection")]
+pub(crate) fn current_task() -> Option<(u64, Arc<str>)> {
+    LABEL
+        .try_with(|label| {
+            let label = label.borrow();
+            label.as_ref().map(|label| (label.task, label.name.clone()))
+        })
+        .ok()
+        .flatten()
+}
+
+/// Whether a [`LockEvent`] reports an acquisition or a release.
+#[cfg(feature = "events")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LockAction {
+    /// The lock was acquired.
+    Acquired,
+    /// The lock was released.
+    Released,
+}
+
+/// An acquisition or release of a lock, as reported by [`LockEvents`].
+#[cfg(feature = "events")]
+#[derive(Debug, Clone)]
+pub struct LockEvent {
+    pub(crate) action: LockAction,
+    pub(crate) kind: &'static str,
+    pub(crate) mode: &'static str,
+    pub(crate) id: usize,
+    pub(crate) at: crate::time::Instant,
+    pub(crate) location: Option<&'static Location<'static>>,
+    pub(crate) label: Option<Arc<str>>,
+}
+
+#[cfg(feature = "events")]
+impl LockEvent {
+    /// Returns whether the lock was acquired or released.
+    pub fn action(&self) -> LockAction {
+        self.action
+    }
+
+    /// Returns the kind of primitive, such as `"Mutex"` or `"RwLock"`.
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    /// Returns the access mode, such as `"lock"`, `"read"`, or `"acquire"`.
+    pub fn mode(&self) -> &'static str {
+        self.mode
+    }
+
+    /// Returns the address of the lock.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns when the lock was acquired or released.
+    pub fn at(&self) -> crate::time::Instant {
+        self.at
+    }
+
+    /// Returns where the lock was acquired, for acquisitions.
+    pub fn location(&self) -> Option<&'static Location<'static>> {
+        self.location
+    }
+
+    /// Returns the label of the [`label()`] future the lock was acquired or released in, if any.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+}
+
+#[cfg(feature = "events")]
+impl fmt::Display for LockEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self.action {
+            LockAction::Acquired => "acquired",
+            LockAction::Released => "released",
+        };
+        write!(f, "{} {:#x} {} {}", self.kind, self.id, self.mode, action)?;
+        if let Some(location) = self.location {
+            write!(f, " at {}", location)?;
+        }
+        if let Some(label) = &self.label {
+            write!(f, " ({})", label)?;
+        }
+        Ok(())
+    }
+}
+
+/// A stream of the [`LockEvent`]s of a single lock, as returned by methods such as
+/// [`Mutex::events()`].
+///
+/// Events are queued until they are read. If more than 1024 events are waiting to be read, new
+/// events are dropped and counted by [`missed()`][`LockEvents::missed()`]. The stream ends once the
+/// lock is dropped and all queued events have been read.
+///
+/// [`Mutex::events()`]: crate::Mutex::events()
+#[cfg(feature = "events")]
+pub struct LockEvents {
+    pub(crate) channel: Arc<crate::events::Channel>,
+}
+
+#[cfg(feature = "events")]
+impl LockEvents {
+    /// Returns the next queued event without waiting.
+    pub fn try_next(&self) -> Option<LockEvent> {
+        self.channel.try_recv()
+    }
+
+    /// Returns the number of events dropped because the stream was not read fast enough.
+    pub fn missed(&self) -> u64 {
+        self.channel.missed()
+    }
+}
+
+#[cfg(feature = "events")]
+impl fmt::Debug for LockEvents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LockEvents").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "events")]
+impl futures_core::Stream for LockEvents {
+    type Item = LockEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<LockEvent>> {
+        self.channel.poll_recv(cx)
+    }
+}
@@ -0,0 +1,88 @@
+use core::fmt;
+
+use crate::Mutex;
+
+/// A raw mutex for `embassy-sync`.
+///
+/// This implements [`embassy_sync::blocking_mutex::raw::RawMutex`], so drivers written against
+/// `embassy-sync` types can be instantiated with it. Each raw mutex is a lock of its own, separate
+/// from the locks its users create with this crate.
+///
+/// With the `critical-section` feature, the closure passed to
+/// [`lock()`][`embassy_sync::blocking_mutex::raw::RawMutex::lock()`] runs inside
+/// `critical_section::with()`, so the raw mutex can be shared with interrupt handlers. Without it,
+/// locking spins until no other thread holds the raw mutex, which is not usable from interrupt
+/// handlers: an interrupt that preempts the holder on the same core spins forever.
+///
+/// Either way, the closure should be short, and it must not lock the same raw mutex again.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::EmbassyRawMutex;
+/// use embassy_sync::mutex::Mutex;
+///
+/// static STATE: Mutex<EmbassyRawMutex, u32> = Mutex::new(0);
+///
+/// *STATE.lock().await += 1;
+/// assert_eq!(*STATE.lock().await, 1);
+/// # })
+/// ```
+pub struct EmbassyRawMutex(Mutex<()>);
+
+impl EmbassyRawMutex {
+    /// Creates a new raw mutex.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::EmbassyRawMutex;
+    ///
+    /// let raw = EmbassyRawMutex::new();
+    /// ```
+    pub const fn new() -> EmbassyRawMutex {
+        EmbassyRawMutex(Mutex::new(()))
+    }
+}
+
+impl Default for EmbassyRawMutex {
+    fn default() -> EmbassyRawMutex {
+        EmbassyRawMutex::new()
+    }
+}
+
+impl fmt::Debug for EmbassyRawMutex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmbassyRawMutex").finish()
+    }
+}
+
+unsafe impl embassy_sync::blocking_mutex::raw::RawMutex for EmbassyRawMutex {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const INIT: EmbassyRawMutex = EmbassyRawMutex::new();
+
+    #[cfg(feature = "critical-section")]
+    fn lock<R>(&self, f: impl FnOnce() -> R) -> R {
+        critical_section::with(|_| {
+            // Every holder runs inside a critical section, so the mutex is only taken here when
+            // the closure locks it again.
+            let _guard = self
+                .0
+                .try_lock()
+                .expect("`EmbassyRawMutex` locked recursively");
+            f()
+        })
+    }
+
+    #[cfg(not(feature = "critical-section"))]
+    fn lock<R>(&self, f: impl FnOnce() -> R) -> R {
+        let _guard = loop {
+            if let Some(guard) = self.0.try_lock() {
+                break guard;
+            }
+            core::hint::spin_loop();
+        };
+        f()
+    }
+}
@@ -0,0 +1,139 @@
+//! Per-lock queues of acquire and release events.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::task::{Context, Poll, Waker};
+
+use crate::diagnostics::{LockEvent, LockEvents};
+
+/// Events a subscriber may fall behind by before further events are dropped.
+const CAPACITY: usize = 1024;
+
+/// The subscribers of a single lock.
+pub(crate) struct Subscribers {
+    /// Number of entries in `list`, so locks nobody observes skip taking the mutex.
+    count: AtomicUsize,
+
+    /// Subscribers, pruned of dropped ones whenever an event is published.
+    list: Mutex<Vec<Weak<Channel>>>,
+}
+
+impl Subscribers {
+    pub(crate) const fn new() -> Subscribers {
+        Subscribers {
+            count: AtomicUsize::new(0),
+            list: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn subscribe(&self) -> LockEvents {
+        let channel = Arc::new(Channel::default());
+        let mut list = self.lock_list();
+        list.push(Arc::downgrade(&channel));
+        self.count.store(list.len(), Ordering::Relaxed);
+        LockEvents { channel }
+    }
+
+    /// Sends the event built by `event` to every subscriber.
+    #[inline]
+    pub(crate) fn publish(&self, event: impl FnOnce() -> LockEvent) {
+        if self.count.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+
+        let event = event();
+        let mut list = self.lock_list();
+        list.retain(|channel| match channel.upgrade() {
+            Some(channel) => {
+                channel.send(event.clone());
+                true
+            }
+            None => false,
+        });
+        self.count.store(list.len(), Ordering::Relaxed);
+    }
+
+    fn lock_list(&self) -> MutexGuard<'_, Vec<Weak<Channel>>> {
+        self.list.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for Subscribers {
+    fn drop(&mut self) {
+        // The lock is gone, so end every stream.
+        for channel in self.lock_list().drain(..) {
+            if let Some(channel) = channel.upgrade() {
+                channel.close();
+            }
+        }
+    }
+}
+
+/// The queue of a single subscriber.
+#[derive(Default)]
+pub(crate) struct Channel {
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    events: VecDeque<LockEvent>,
+    waker: Option<Waker>,
+    missed: u64,
+    closed: bool,
+}
+
+impl Channel {
+    fn send(&self, event: LockEvent) {
+        let waker = {
+            let mut state = self.lock_state();
+            if state.events.len() < CAPACITY {
+                state.events.push_back(event);
+            } else {
+                state.missed += 1;
+            }
+            state.waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    fn close(&self) {
+        let waker = {
+            let mut state = self.lock_state();
+            state.closed = true;
+            state.waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    pub(crate) fn try_recv(&self) -> Option<LockEvent> {
+        self.lock_state().events.pop_front()
+    }
+
+    pub(crate) fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Option<LockEvent>> {
+        let mut state = self.lock_state();
+        match state.events.pop_front() {
+            Some(event) => Poll::Ready(Some(event)),
+            None if state.closed => Poll::Ready(None),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    pub(crate) fn missed(&self) -> u64 {
+        self.lock_state().missed
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,255 @@
+//! A C API for sharing mutexes with foreign code.
+//!
+//! Foreign code refers to a mutex through an [`AsyncLockMutex`] handle. Handles are created either
+//! from C with [`async_lock_mutex_new()`], or from Rust with [`mutex_handle()`], which shares a
+//! [`Mutex`] that Rust tasks keep using. Either way, C and Rust contend on the same lock.
+//!
+//! The C declarations are:
+//!
+//! ```c
+//! typedef struct AsyncLockMutex AsyncLockMutex;
+//!
+//! AsyncLockMutex *async_lock_mutex_new(void);
+//! AsyncLockMutex *async_lock_mutex_clone(const AsyncLockMutex *mutex);
+//! void async_lock_mutex_destroy(AsyncLockMutex *mutex);
+//! bool async_lock_mutex_try_lock(const AsyncLockMutex *mutex);
+//! void async_lock_mutex_lock(const AsyncLockMutex *mutex, void (*callback)(void *), void *data);
+//! void async_lock_mutex_unlock(const AsyncLockMutex *mutex);
+//! ```
+//!
+//! Locking from C never blocks the calling thread. [`async_lock_mutex_lock()`] invokes its callback
+//! once the mutex is acquired: right away if it is free, or otherwise on a background thread that
+//! this module starts on first use. The mutex then stays locked until [`async_lock_mutex_unlock()`]
+//! is called.
+//!
+//! # Examples
+//!
+//! ```
+//! use async_lock::{ffi, Mutex};
+//! use std::sync::Arc;
+//!
+//! let mutex = Arc::new(Mutex::new(0));
+//! let handle = ffi::mutex_handle(mutex.clone());
+//!
+//! unsafe {
+//!     assert!(ffi::async_lock_mutex_try_lock(handle));
+//!     assert!(mutex.try_lock().is_none());
+//!     ffi::async_lock_mutex_unlock(handle);
+//!     ffi::async_lock_mutex_destroy(handle);
+//! }
+//! assert!(mutex.try_lock().is_some());
+//! ```
+
+use core::ffi::c_void;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Context;
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use std::sync::{mpsc, Mutex as StdMutex, OnceLock};
+use std::task::Wake;
+use std::thread;
+
+use crate::{Mutex, MutexGuard};
+
+/// A handle to a mutex, as seen from C.
+///
+/// Each handle owns a reference to the mutex and must be freed with
+/// [`async_lock_mutex_destroy()`].
+pub struct AsyncLockMutex(Arc<dyn RawLock>);
+
+impl core::fmt::Debug for AsyncLockMutex {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AsyncLockMutex").finish_non_exhaustive()
+    }
+}
+
+/// A mutex with its data type erased.
+trait RawLock: Send + Sync {
+    fn try_lock(&self) -> bool;
+    fn lock(self: Arc<Self>) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+    unsafe fn unlock(&self);
+}
+
+impl<T: Send + 'static> RawLock for Mutex<T> {
+    fn try_lock(&self) -> bool {
+        match Mutex::try_lock(self) {
+            Some(guard) => {
+                MutexGuard::into_raw(guard);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn lock(self: Arc<Self>) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(async move {
+            MutexGuard::into_raw(Mutex::lock(&self).await);
+        })
+    }
+
+    unsafe fn unlock(&self) {
+        drop(MutexGuard::from_raw(self));
+    }
+}
+
+/// Creates a handle that shares `mutex` with foreign code.
+///
+/// Foreign code cannot access the data inside the mutex, only lock and unlock it.
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::{ffi, Mutex};
+/// use std::sync::Arc;
+///
+/// let handle = ffi::mutex_handle(Arc::new(Mutex::new(())));
+/// unsafe { ffi::async_lock_mutex_destroy(handle) };
+/// ```
+pub fn mutex_handle<T: Send + 'static>(mutex: Arc<Mutex<T>>) -> *mut AsyncLockMutex {
+    Box::into_raw(Box::new(AsyncLockMutex(mutex)))
+}
+
+/// Creates a new, unlocked mutex and returns a handle to it.
+#[no_mangle]
+pub extern "C" fn async_lock_mutex_new() -> *mut AsyncLockMutex {
+    mutex_handle(Arc::new(Mutex::new(())))
+}
+
+/// Creates another handle to the same mutex.
+///
+/// # Safety
+///
+/// `mutex` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn async_lock_mutex_clone(
+    mutex: *const AsyncLockMutex,
+) -> *mut AsyncLockMutex {
+    Box::into_raw(Box::new(AsyncLockMutex((*mutex).0.clone())))
+}
+
+/// Frees a handle.
+///
+/// The mutex itself is freed once no handles or Rust references to it remain.
+///
+/// # Safety
+///
+/// `mutex` must be a live handle, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn async_lock_mutex_destroy(mutex: *mut AsyncLockMutex) {
+    drop(Box::from_raw(mutex));
+}
+
+/// Attempts to lock the mutex, returning `true` if it was acquired.
+///
+/// # Safety
+///
+/// `mutex` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn async_lock_mutex_try_lock(mutex: *const AsyncLockMutex) -> bool {
+    (*mutex).0.try_lock()
+}
+
+/// Locks the mutex and then calls `callback(data)`.
+///
+/// If the mutex is free, `callback` runs before this function returns. Otherwise, this function
+/// returns immediately and `callback` runs later on a background thread shared by all handles.
+/// The callback should be short, for example scheduling the rest of the critical section on the
+/// caller's own event loop.
+///
+/// # Safety
+///
+/// `mutex` must be a live handle. `callback` must be safe to call with `data` from any thread.
+#[no_mangle]
+pub unsafe extern "C" fn async_lock_mutex_lock(
+    mutex: *const AsyncLockMutex,
+    callback: extern "C" fn(*mut c_void),
+    data: *mut c_void,
+) {
+    let lock = (*mutex).0.clone();
+    let data = SendPtr(data);
+    Task::spawn(async move {
+        lock.lock().await;
+        callback(data.0);
+    });
+}
+
+/// Unlocks the mutex.
+///
+/// # Safety
+///
+/// `mutex` must be a live handle, and the mutex must have been locked through
+/// [`async_lock_mutex_try_lock()`] or [`async_lock_mutex_lock()`] and not yet unlocked.
+#[no_mangle]
+pub unsafe extern "C" fn async_lock_mutex_unlock(mutex: *const AsyncLockMutex) {
+    (*mutex).0.unlock();
+}
+
+/// A pointer that the caller promised may be used from any thread.
+struct SendPtr(*mut c_void);
+
+unsafe impl Send for SendPtr {}
+
+/// A future driven by the background thread, so foreign code needs no executor.
+struct Task {
+    /// Set while the task is waiting in the queue.
+    scheduled: AtomicBool,
+
+    /// The future, or `None` once it has completed.
+    future: StdMutex<Option<Pin<Box<dyn Future<Output = ()> + Send>>>>,
+}
+
+impl Task {
+    /// Polls `future` once on the current thread, and after that on the background thread.
+    fn spawn(future: impl Future<Output = ()> + Send + 'static) {
+        let task = Arc::new(Task {
+            scheduled: AtomicBool::new(false),
+            future: StdMutex::new(Some(Box::pin(future))),
+        });
+        task.poll();
+    }
+
+    fn poll(self: &Arc<Self>) {
+        let mut slot = self.future.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(future) = slot.as_mut() {
+            let waker = self.clone().into();
+            if future
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_ready()
+            {
+                *slot = None;
+            }
+        }
+    }
+}
+
+impl Wake for Task {
+    fn wake(self: Arc<Self>) {
+        if !self.scheduled.swap(true, Ordering::AcqRel) {
+            // Wakers run inside other primitives, so the task is polled elsewhere.
+            let _ = queue().send(self);
+        }
+    }
+}
+
+/// Returns the queue of the background thread, starting it if necessary.
+fn queue() -> &'static mpsc::Sender<Arc<Task>> {
+    static QUEUE: OnceLock<mpsc::Sender<Arc<Task>>> = OnceLock::new();
+
+    QUEUE.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<Arc<Task>>();
+        thread::Builder::new()
+            .name("async-lock-ffi".into())
+            .spawn(move || {
+                for task in receiver {
+                    task.scheduled.store(false, Ordering::Release);
+                    task.poll();
+                }
+            })
+            .expect("failed to spawn the async-lock FFI thread");
+        sender
+    })
+}
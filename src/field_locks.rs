@@ -0,0 +1,105 @@
+/// Declares a struct together with a companion type that puts each field behind its own lock.
+///
+/// The macro takes a struct whose field types are written as `Mutex<T>` or `RwLock<T>`, followed
+/// by the declaration of the companion type. It defines the struct with plain `T` fields, and the
+/// companion type with each field wrapped in a [`Mutex`][`crate::Mutex`] or
+/// [`RwLock`][`crate::RwLock`] from this crate. Attributes such as `#[derive]` apply to the type
+/// they are written on.
+///
+/// The companion type gets:
+///
+/// * `new(state)` and a [`From`] impl, which move every field into its lock.
+/// * `into_inner(self)`, which takes every field back out of its lock.
+/// * An accessor named after each field that returns the lock around it, with the visibility of
+///   the field.
+///
+/// This makes it cheap to split a large state struct into fine-grained locks, so tasks that touch
+/// different fields no longer contend.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// async_lock::field_locks! {
+///     #[derive(Debug, Default)]
+///     pub struct State {
+///         pub users: RwLock<Vec<String>>,
+///         pub jobs: Mutex<u32>,
+///     }
+///
+///     pub struct SharedState;
+/// }
+///
+/// let shared = SharedState::new(State::default());
+/// shared.users().write().await.push("ferris".to_string());
+///
+/// // Holding one field's lock does not block the others.
+/// let users = shared.users().read().await;
+/// *shared.jobs().lock().await += 1;
+/// drop(users);
+///
+/// let state = shared.into_inner();
+/// assert_eq!(state.users, ["ferris"]);
+/// assert_eq!(state.jobs, 1);
+/// # })
+/// ```
+#[macro_export]
+macro_rules! field_locks {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_attr:meta])*
+                $field_vis:vis $field:ident : $lock:ident < $ty:ty >
+            ),* $(,)?
+        }
+
+        $(#[$locks_attr:meta])*
+        $locks_vis:vis struct $locks:ident;
+    ) => {
+        $(#[$attr])*
+        $vis struct $name {
+            $(
+                $(#[$field_attr])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        $(#[$locks_attr])*
+        $locks_vis struct $locks {
+            $($field: $crate::$lock<$ty>,)*
+        }
+
+        impl $locks {
+            /// Moves every field of `state` into its own lock.
+            #[allow(dead_code)]
+            $locks_vis fn new(state: $name) -> $locks {
+                $locks {
+                    $($field: $crate::$lock::new(state.$field),)*
+                }
+            }
+
+            /// Consumes the locks, returning the fields.
+            #[allow(dead_code)]
+            $locks_vis fn into_inner(self) -> $name {
+                $name {
+                    $($field: self.$field.into_inner(),)*
+                }
+            }
+
+            $(
+                #[doc = concat!("Returns the lock around `", stringify!($field), "`.")]
+                #[allow(dead_code)]
+                $field_vis fn $field(&self) -> &$crate::$lock<$ty> {
+                    &self.$field
+                }
+            )*
+        }
+
+        impl ::core::convert::From<$name> for $locks {
+            fn from(state: $name) -> $locks {
+                $locks::new(state)
+            }
+        }
+    };
+}
@@ -0,0 +1,333 @@
+// `File::lock()` and friends need Rust 1.89, which is why this module has its own feature.
+#![allow(clippy::incompatible_msrv)]
+
+use core::fmt;
+use core::ops::Deref;
+
+use alloc::sync::Arc;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io;
+use std::path::Path;
+use std::sync::Mutex as StdMutex;
+use std::thread;
+
+use event_listener::Event;
+
+use crate::{Mutex, MutexGuardArc};
+
+/// An advisory lock on a file, for coordinating with other processes.
+///
+/// The lock is taken with the operating system's advisory file locks (`flock` on Unix,
+/// `LockFileEx` on Windows), so it excludes other processes that lock the same file, whichever
+/// language they are written in. Tasks of the same process that share a `FileLock` also exclude
+/// each other, and wait for each other like on a [`Mutex`].
+///
+/// Advisory locks only exclude other operations that also lock the file; they do not prevent
+/// reading or writing it.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::FileLock;
+/// use std::io::Write;
+///
+/// # let path = std::env::temp_dir().join(format!("async-lock-doc-{}.lock", std::process::id()));
+/// let lock = FileLock::open(&path)?;
+///
+/// let mut guard = lock.lock().await?;
+/// writeln!(&*guard, "owned by {}", std::process::id())?;
+/// drop(guard);
+/// # std::fs::remove_file(&path)?;
+/// # std::io::Result::Ok(())
+/// # }).unwrap();
+/// ```
+pub struct FileLock {
+    file: Arc<File>,
+
+    /// Serializes the tasks of this process, which the operating system would not tell apart.
+    ///
+    /// A cancelled lock operation hands this to its helper thread, which holds it until the file
+    /// lock is given back.
+    inner: Arc<Mutex<()>>,
+}
+
+impl FileLock {
+    /// Uses an open file for locking.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::FileLock;
+    /// use std::fs::File;
+    ///
+    /// # let path = std::env::temp_dir().join(format!("async-lock-doc-new-{}.lock", std::process::id()));
+    /// let lock = FileLock::new(File::create(&path)?);
+    /// # std::fs::remove_file(&path)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn new(file: File) -> FileLock {
+        FileLock {
+            file: Arc::new(file),
+            inner: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Opens a file for locking, creating it if it does not exist.
+    ///
+    /// The file is opened for reading and writing, and is not truncated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::FileLock;
+    ///
+    /// # let path = std::env::temp_dir().join(format!("async-lock-doc-open-{}.lock", std::process::id()));
+    /// let lock = FileLock::open(&path)?;
+    /// # std::fs::remove_file(&path)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn open(path: impl AsRef<Path>) -> io::Result<FileLock> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        Ok(FileLock::new(file))
+    }
+
+    /// Attempts to acquire the lock without waiting.
+    ///
+    /// Returns [`None`] if the file is locked by another task or process.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::FileLock;
+    ///
+    /// # let path = std::env::temp_dir().join(format!("async-lock-doc-try-{}.lock", std::process::id()));
+    /// let lock = FileLock::open(&path)?;
+    /// let guard = lock.try_lock()?.unwrap();
+    /// assert!(lock.try_lock()?.is_none());
+    /// # drop(guard);
+    /// # std::fs::remove_file(&path)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn try_lock(&self) -> io::Result<Option<FileLockGuard<'_>>> {
+        let inner = match self.inner.try_lock_arc() {
+            Some(inner) => inner,
+            None => return Ok(None),
+        };
+
+        match self.file.try_lock() {
+            Ok(()) => Ok(Some(FileLockGuard {
+                lock: self,
+                _inner: inner,
+            })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(err)) => Err(err),
+        }
+    }
+
+    /// Acquires the lock.
+    ///
+    /// Waits for other tasks of this process first, and then for other processes. Since operating
+    /// systems only offer blocking calls for the latter, a helper thread waits for the file lock
+    /// while another process holds it. If the returned future is dropped in the meantime, the
+    /// helper thread releases the file lock again as soon as it gets it. Until then, other tasks
+    /// of this process keep waiting, since they share the helper thread's file handle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::FileLock;
+    ///
+    /// # let path = std::env::temp_dir().join(format!("async-lock-doc-lock-{}.lock", std::process::id()));
+    /// let lock = FileLock::open(&path)?;
+    /// let guard = lock.lock().await?;
+    /// # drop(guard);
+    /// # std::fs::remove_file(&path)?;
+    /// # std::io::Result::Ok(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn lock(&self) -> io::Result<FileLockGuard<'_>> {
+        let inner = self.inner.lock_arc().await;
+
+        match self.file.try_lock() {
+            Ok(()) => {
+                return Ok(FileLockGuard {
+                    lock: self,
+                    _inner: inner,
+                })
+            }
+            Err(TryLockError::WouldBlock) => {}
+            Err(TryLockError::Error(err)) => return Err(err),
+        }
+
+        let request = Arc::new(Request {
+            state: StdMutex::new(RequestState::Waiting),
+            done: Event::new(),
+        });
+        let mut cancel = CancelOnDrop {
+            request: &request,
+            file: &self.file,
+            inner: Some(inner),
+        };
+
+        let file = self.file.clone();
+        let helper = request.clone();
+        thread::Builder::new()
+            .name("async-lock-file".into())
+            .spawn(move || {
+                let result = file.lock();
+                let mut state = helper.state();
+                match core::mem::replace(&mut *state, RequestState::Taken) {
+                    RequestState::Cancelled(inner) => {
+                        if result.is_ok() {
+                            let _ = file.unlock();
+                        }
+                        // Only now may the next task of this process lock the file.
+                        drop(inner);
+                    }
+                    _ => {
+                        *state = RequestState::Done(result);
+                        drop(state);
+                        helper.done.notify(usize::MAX);
+                    }
+                }
+            })?;
+
+        loop {
+            let listener = request.done.listen();
+            if let RequestState::Done(_) = *request.state() {
+                break;
+            }
+            listener.await;
+        }
+
+        // The result is taken, so dropping the request from now on does not unlock the file.
+        let result = core::mem::replace(&mut *request.state(), RequestState::Taken);
+        let inner = cancel.inner.take().unwrap();
+        drop(cancel);
+        match result {
+            RequestState::Done(result) => result.map(|()| FileLockGuard {
+                lock: self,
+                _inner: inner,
+            }),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the underlying file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::FileLock;
+    ///
+    /// # let path = std::env::temp_dir().join(format!("async-lock-doc-file-{}.lock", std::process::id()));
+    /// let lock = FileLock::open(&path)?;
+    /// assert!(lock.file().metadata()?.is_file());
+    /// # std::fs::remove_file(&path)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+}
+
+impl fmt::Debug for FileLock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileLock")
+            .field("file", &self.file)
+            .finish()
+    }
+}
+
+/// A blocking lock call that runs on a helper thread.
+struct Request {
+    state: StdMutex<RequestState>,
+    done: Event,
+}
+
+enum RequestState {
+    /// The helper thread is still waiting for the lock.
+    Waiting,
+
+    /// The helper thread is done.
+    Done(io::Result<()>),
+
+    /// The result was taken by the lock operation.
+    Taken,
+
+    /// The lock operation was dropped before the helper thread was done, and left the helper
+    /// thread the lock of this process.
+    Cancelled(MutexGuardArc<()>),
+}
+
+impl Request {
+    fn state(&self) -> std::sync::MutexGuard<'_, RequestState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Releases the file lock when the lock operation is dropped, now or once the helper thread
+/// gets it.
+struct CancelOnDrop<'a> {
+    request: &'a Request,
+    file: &'a File,
+
+    /// The lock of this process, until the lock operation takes it.
+    inner: Option<MutexGuardArc<()>>,
+}
+
+impl Drop for CancelOnDrop<'_> {
+    fn drop(&mut self) {
+        let mut state = self.request.state();
+        match *state {
+            RequestState::Waiting => {
+                if let Some(inner) = self.inner.take() {
+                    *state = RequestState::Cancelled(inner);
+                }
+            }
+            RequestState::Done(Ok(())) => {
+                // Released before `inner`, like in `FileLockGuard`.
+                let _ = self.file.unlock();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A guard that releases a [`FileLock`] when dropped.
+///
+/// Dereferences to the locked file.
+pub struct FileLockGuard<'a> {
+    lock: &'a FileLock,
+    _inner: MutexGuardArc<()>,
+}
+
+impl Drop for FileLockGuard<'_> {
+    fn drop(&mut self) {
+        // Released before `_inner`, so the next task of this process finds the file unlocked.
+        let _ = self.lock.file.unlock();
+    }
+}
+
+impl fmt::Debug for FileLockGuard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileLockGuard")
+            .field("file", &self.lock.file)
+            .finish()
+    }
+}
+
+impl Deref for FileLockGuard<'_> {
+    type Target = File;
+
+    fn deref(&self) -> &File {
+        &self.lock.file
+    }
+}
@@ -0,0 +1,143 @@
+use event_listener::Event;
+
+use crate::sync::atomic::{AtomicBool, Ordering};
+
+/// A flag that tasks can wait on until it is set.
+///
+/// Setting the flag wakes every waiting task, and [`wait()`][`Flag::wait()`] returns right away for
+/// as long as the flag stays set. [`reset()`][`Flag::reset()`] clears it again, so later calls to
+/// `wait()` wait for the next [`set()`][`Flag::set()`]. This is also known as a manual-reset event.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::Flag;
+/// use futures_lite::future;
+///
+/// let ready = Flag::new();
+///
+/// let mut wait = Box::pin(ready.wait());
+/// assert!(future::poll_once(&mut wait).await.is_none());
+///
+/// ready.set();
+/// wait.await;
+///
+/// // The flag stays set.
+/// ready.wait().await;
+/// # })
+/// ```
+#[derive(Debug)]
+pub struct Flag {
+    /// Whether the flag is set.
+    set: AtomicBool,
+
+    /// Notified when the flag is set.
+    event: Event,
+}
+
+impl Flag {
+    /// Creates a flag that is not set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Flag;
+    ///
+    /// let flag = Flag::new();
+    /// assert!(!flag.is_set());
+    /// ```
+    pub const fn new() -> Flag {
+        Flag {
+            set: AtomicBool::new(false),
+            event: Event::new(),
+        }
+    }
+
+    /// Sets the flag and wakes every waiting task.
+    ///
+    /// Setting a flag that is already set has no effect.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Flag;
+    ///
+    /// let flag = Flag::new();
+    /// flag.set();
+    /// assert!(flag.is_set());
+    /// ```
+    pub fn set(&self) {
+        if !self.set.swap(true, Ordering::AcqRel) {
+            self.event.notify(usize::MAX);
+        }
+    }
+
+    /// Clears the flag.
+    ///
+    /// Tasks that were already woken by [`set()`][`Flag::set()`] still complete.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Flag;
+    ///
+    /// let flag = Flag::new();
+    /// flag.set();
+    /// flag.reset();
+    /// assert!(!flag.is_set());
+    /// ```
+    pub fn reset(&self) {
+        self.set.store(false, Ordering::Release);
+    }
+
+    /// Returns `true` if the flag is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Flag;
+    ///
+    /// let flag = Flag::new();
+    /// assert!(!flag.is_set());
+    /// ```
+    pub fn is_set(&self) -> bool {
+        self.set.load(Ordering::Acquire)
+    }
+
+    /// Waits until the flag is set.
+    ///
+    /// Returns right away if the flag is already set. Once [`set()`][`Flag::set()`] wakes the
+    /// task, this completes even if the flag was reset in the meantime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Flag;
+    ///
+    /// let flag = Flag::new();
+    /// flag.set();
+    /// flag.wait().await;
+    /// # })
+    /// ```
+    pub async fn wait(&self) {
+        if self.is_set() {
+            return;
+        }
+        let listener = self.event.listen();
+        if self.is_set() {
+            return;
+        }
+
+        // The event is only notified by `set()`, so the flag was set after this task started
+        // waiting, even if it has been reset since.
+        listener.await;
+    }
+}
+
+impl Default for Flag {
+    fn default() -> Flag {
+        Flag::new()
+    }
+}
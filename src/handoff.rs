@@ -0,0 +1,137 @@
+use core::fmt;
+use core::future::Future;
+
+use event_listener::{Event, IntoNotification};
+
+/// A notification that hands a value to a waiting task.
+///
+/// Each value passed to [`notify_one()`][`Handoff::notify_one()`] is delivered directly to exactly
+/// one pending [`wait()`][`Handoff::wait()`], in the order the waits were started. This covers
+/// wake-with-work-item patterns without a channel: values are never buffered, so notifying when
+/// nobody is waiting gives the value back.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::Handoff;
+///
+/// let jobs = Handoff::new();
+///
+/// let worker = jobs.wait();
+/// jobs.notify_one("job").unwrap();
+/// assert_eq!(worker.await, "job");
+///
+/// // Nobody is waiting anymore.
+/// assert_eq!(jobs.notify_one("job"), Err("job"));
+/// # })
+/// ```
+pub struct Handoff<T> {
+    event: Event<T>,
+}
+
+impl<T> Handoff<T> {
+    /// Creates a handoff with no waiters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Handoff;
+    ///
+    /// let handoff = Handoff::<u32>::new();
+    /// ```
+    pub const fn new() -> Handoff<T> {
+        Handoff {
+            event: Event::with_tag(),
+        }
+    }
+
+    /// Waits for a value.
+    ///
+    /// The wait starts when this method is called, not when the future is first polled, so a
+    /// value sent right after calling `wait()` is not missed.
+    ///
+    /// If the future is dropped after a value was handed to it but before it completed, the value
+    /// is passed on to the next waiter, or dropped if there is none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Handoff;
+    ///
+    /// let handoff = Handoff::new();
+    /// let first = handoff.wait();
+    /// let second = handoff.wait();
+    ///
+    /// handoff.notify_one(1).unwrap();
+    /// handoff.notify_one(2).unwrap();
+    /// assert_eq!(first.await, 1);
+    /// assert_eq!(second.await, 2);
+    /// # })
+    /// ```
+    pub fn wait(&self) -> impl Future<Output = T> {
+        self.event.listen()
+    }
+
+    /// Hands `value` to the longest-waiting task that has not received a value yet.
+    ///
+    /// Returns the value back if no task is waiting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Handoff;
+    ///
+    /// let handoff = Handoff::new();
+    /// assert_eq!(handoff.notify_one(10), Err(10));
+    /// ```
+    pub fn notify_one(&self, value: T) -> Result<(), T> {
+        let mut value = Some(value);
+        self.event
+            .notify(1.additional().tag_with(|| value.take().unwrap()));
+
+        match value {
+            None => Ok(()),
+            Some(value) => Err(value),
+        }
+    }
+
+    /// Hands a clone of `value` to every task that is waiting and has not received a value yet.
+    ///
+    /// Returns the number of tasks that received a value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Handoff;
+    ///
+    /// let handoff = Handoff::new();
+    /// let a = handoff.wait();
+    /// let b = handoff.wait();
+    ///
+    /// assert_eq!(handoff.notify_all("shutdown"), 2);
+    /// assert_eq!(a.await, "shutdown");
+    /// assert_eq!(b.await, "shutdown");
+    /// # })
+    /// ```
+    pub fn notify_all(&self, value: T) -> usize
+    where
+        T: Clone,
+    {
+        self.event.notify(usize::MAX.additional().tag(value))
+    }
+}
+
+impl<T> fmt::Debug for Handoff<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handoff").finish_non_exhaustive()
+    }
+}
+
+impl<T> Default for Handoff<T> {
+    fn default() -> Handoff<T> {
+        Handoff::new()
+    }
+}
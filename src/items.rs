@@ -0,0 +1,96 @@
+//! Streams over locked collections that only hold the lock while copying out items.
+
+use core::future::Future;
+use core::ops::Deref;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use alloc::collections::VecDeque;
+
+use futures_core::Stream;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Stream for methods such as [`Mutex::stream_items()`][`crate::Mutex::stream_items()`].
+    pub(crate) struct Items<L, F, T> {
+        // Starts acquiring the lock.
+        lock: L,
+
+        // The lock operation in progress.
+        #[pin]
+        acquire: Option<F>,
+
+        // Maximum number of items to copy out per acquisition.
+        chunk_size: usize,
+
+        // Index of the next item to copy out.
+        next: usize,
+
+        // Items copied out but not yielded yet.
+        buffer: VecDeque<T>,
+
+        // Set once the end of the collection was reached.
+        done: bool,
+    }
+}
+
+impl<L, F, G, C, T> Items<L, F, T>
+where
+    L: Fn() -> F,
+    F: Future<Output = G>,
+    G: Deref<Target = C>,
+    C: AsRef<[T]> + ?Sized,
+    T: Clone,
+{
+    pub(crate) fn new(lock: L, chunk_size: usize) -> Items<L, F, T> {
+        Items {
+            lock,
+            acquire: None,
+            chunk_size: chunk_size.max(1),
+            next: 0,
+            buffer: VecDeque::new(),
+            done: false,
+        }
+    }
+}
+
+impl<L, F, G, C, T> Stream for Items<L, F, T>
+where
+    L: Fn() -> F,
+    F: Future<Output = G>,
+    G: Deref<Target = C>,
+    C: AsRef<[T]> + ?Sized,
+    T: Clone,
+{
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut this = self.project();
+
+        loop {
+            if let Some(item) = this.buffer.pop_front() {
+                return Poll::Ready(Some(item));
+            }
+            if *this.done {
+                return Poll::Ready(None);
+            }
+
+            if this.acquire.is_none() {
+                this.acquire.set(Some((this.lock)()));
+            }
+            let guard = match this.acquire.as_mut().as_pin_mut().unwrap().poll(cx) {
+                Poll::Ready(guard) => guard,
+                Poll::Pending => return Poll::Pending,
+            };
+            this.acquire.set(None);
+
+            // The lock is released again at the end of this iteration.
+            let items = (*guard).as_ref();
+            let start = (*this.next).min(items.len());
+            let end = items.len().min(start + *this.chunk_size);
+            this.buffer.extend(items[start..end].iter().cloned());
+            *this.next = end;
+            *this.done = end == items.len();
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use core::fmt;
+use core::hash::Hash;
+
+use alloc::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Mutex as StdMutex, MutexGuard as StdMutexGuard};
+
+use crate::{RwLock, RwLockReadGuard, RwLockWriteGuard, Semaphore, SemaphoreGuardArc};
+
+/// A counter for limiting the number of concurrent operations per key.
+///
+/// Every key gets its own [`Semaphore`] with the same limit, created when the first operation for
+/// the key arrives and removed when the last one holding or waiting for a permit is gone. Keys
+/// that are not in use take no memory, so this suits keys such as host names, of which a
+/// long-running program may see any number.
+///
+/// With a limit of one, this is a lock per key: operations on the same key run one at a time,
+/// while operations on different keys run in parallel.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::KeyedSemaphore;
+///
+/// // At most two requests per host.
+/// let limits = KeyedSemaphore::new(2);
+///
+/// let a1 = limits.acquire("a.example").await;
+/// let a2 = limits.acquire("a.example").await;
+/// assert!(limits.try_acquire("a.example").is_none());
+///
+/// // Other hosts have their own limit.
+/// let b1 = limits.acquire("b.example").await;
+/// # })
+/// ```
+///
+/// Serializing writes to each file:
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::KeyedSemaphore;
+///
+/// let files = KeyedSemaphore::new(1);
+///
+/// let guard = files.acquire("a.txt").await;
+/// assert_eq!(*guard.key(), "a.txt");
+/// assert!(files.try_acquire("a.txt").is_none());
+/// assert!(files.try_acquire("b.txt").is_some());
+/// # })
+/// ```
+///
+/// Bulk maintenance that needs every key at once can escalate to
+/// [`acquire_exclusive()`][`KeyedSemaphore::acquire_exclusive()`] instead of acquiring the keys
+/// one by one.
+pub struct KeyedSemaphore<K> {
+    /// The limit of every key.
+    limit: usize,
+
+    /// The semaphore of every key in use, and how many operations use it.
+    keys: StdMutex<HashMap<K, (Arc<Semaphore>, usize)>>,
+
+    /// Read by every per-key operation, and written by exclusive operations.
+    shared: RwLock<()>,
+}
+
+impl<K: Eq + Hash + Clone> KeyedSemaphore<K> {
+    /// Creates a new semaphore with a limit of `n` concurrent operations per key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::KeyedSemaphore;
+    ///
+    /// let s = KeyedSemaphore::<String>::new(2);
+    /// ```
+    pub fn new(n: usize) -> KeyedSemaphore<K> {
+        KeyedSemaphore {
+            limit: n,
+            keys: StdMutex::new(HashMap::new()),
+            shared: RwLock::new(()),
+        }
+    }
+
+    /// Attempts to get a permit for an operation on `key`.
+    ///
+    /// If the permit could not be acquired at this time, then [`None`] is returned. Otherwise, a
+    /// guard is returned that releases the permit when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::KeyedSemaphore;
+    ///
+    /// let s = KeyedSemaphore::new(1);
+    /// let guard = s.try_acquire(1).unwrap();
+    /// assert!(s.try_acquire(1).is_none());
+    /// assert!(s.try_acquire(2).is_some());
+    /// ```
+    pub fn try_acquire(&self, key: K) -> Option<KeyedSemaphoreGuard<'_, K>> {
+        let shared = self.shared.try_read()?;
+        let (user, semaphore) = self.register(key);
+        let permit = semaphore.try_acquire_arc()?;
+        Some(KeyedSemaphoreGuard {
+            _permit: permit,
+            user,
+            _shared: shared,
+        })
+    }
+
+    /// Waits for a permit for an operation on `key`.
+    ///
+    /// Returns a guard that releases the permit when dropped. While an exclusive operation holds
+    /// or waits for the whole semaphore, this waits for it first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::KeyedSemaphore;
+    ///
+    /// let s = KeyedSemaphore::new(1);
+    /// let guard = s.acquire("key").await;
+    /// # })
+    /// ```
+    pub async fn acquire(&self, key: K) -> KeyedSemaphoreGuard<'_, K> {
+        let shared = self.shared.read().await;
+        // Registered before waiting, so the key's semaphore stays while this operation waits.
+        let (user, semaphore) = self.register(key);
+        let permit = semaphore.acquire_arc().await;
+        KeyedSemaphoreGuard {
+            _permit: permit,
+            user,
+            _shared: shared,
+        }
+    }
+
+    /// Attempts to get exclusive access to all keys at once.
+    ///
+    /// If any operation holds or waits for a permit, then [`None`] is returned. Otherwise, a guard
+    /// is returned that keeps out all other operations until it is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::KeyedSemaphore;
+    ///
+    /// let s = KeyedSemaphore::new(1);
+    /// let guard = s.try_acquire("key").unwrap();
+    /// assert!(s.try_acquire_exclusive().is_none());
+    ///
+    /// drop(guard);
+    /// let exclusive = s.try_acquire_exclusive().unwrap();
+    /// assert!(s.try_acquire("other").is_none());
+    /// ```
+    pub fn try_acquire_exclusive(&self) -> Option<KeyedSemaphoreExclusiveGuard<'_>> {
+        let shared = self.shared.try_write()?;
+        Some(KeyedSemaphoreExclusiveGuard { _shared: shared })
+    }
+
+    /// Waits for exclusive access to all keys at once.
+    ///
+    /// From the moment this is called, new operations on any key wait until the returned guard is
+    /// dropped. This then waits for the operations that already hold or wait for a permit to
+    /// finish. Bulk maintenance that would otherwise need a permit for many keys can use this
+    /// instead.
+    ///
+    /// A task that holds a permit of this semaphore must not wait for another one while an
+    /// exclusive operation might be waiting, or the two wait for each other forever.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::KeyedSemaphore;
+    /// use futures_lite::future;
+    ///
+    /// let s = KeyedSemaphore::new(1);
+    /// let guard = s.acquire("a").await;
+    ///
+    /// let mut exclusive = Box::pin(s.acquire_exclusive());
+    /// assert!(future::poll_once(&mut exclusive).await.is_none());
+    ///
+    /// // New operations wait for the exclusive one.
+    /// assert!(s.try_acquire("b").is_none());
+    ///
+    /// drop(guard);
+    /// let exclusive = exclusive.await;
+    /// # })
+    /// ```
+    pub async fn acquire_exclusive(&self) -> KeyedSemaphoreExclusiveGuard<'_> {
+        KeyedSemaphoreExclusiveGuard {
+            _shared: self.shared.write().await,
+        }
+    }
+
+    /// Returns the number of keys with operations holding or waiting for a permit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::KeyedSemaphore;
+    ///
+    /// let s = KeyedSemaphore::new(1);
+    /// let guard = s.try_acquire("key").unwrap();
+    /// assert_eq!(s.len(), 1);
+    ///
+    /// drop(guard);
+    /// assert_eq!(s.len(), 0);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.keys().len()
+    }
+
+    /// Returns `true` if no operation holds or waits for a permit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::KeyedSemaphore;
+    ///
+    /// let s = KeyedSemaphore::<u32>::new(1);
+    /// assert!(s.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.keys().is_empty()
+    }
+
+    /// Returns the number of keys the semaphore can hold without allocating more memory.
+    ///
+    /// Entries of keys that are no longer in use are removed, but the memory for them is kept
+    /// until [`shrink()`][`KeyedSemaphore::shrink()`] is called, even though it is not all
+    /// counted in the capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::KeyedSemaphore;
+    ///
+    /// let s = KeyedSemaphore::new(1);
+    /// let guards = (0..100).map(|i| s.try_acquire(i).unwrap()).collect::<Vec<_>>();
+    /// assert!(s.capacity() >= 100);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.keys().capacity()
+    }
+
+    /// Frees the memory of keys that are no longer in use.
+    ///
+    /// After a burst of many keys, this lets a long-running program give the memory back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::KeyedSemaphore;
+    ///
+    /// let s = KeyedSemaphore::new(1);
+    /// let guards = (0..100).map(|i| s.try_acquire(i).unwrap()).collect::<Vec<_>>();
+    /// drop(guards);
+    ///
+    /// s.shrink();
+    /// assert_eq!(s.capacity(), 0);
+    /// ```
+    pub fn shrink(&self) {
+        self.keys().shrink_to_fit();
+    }
+
+    /// Counts a new operation on `key`, creating the key's semaphore if needed.
+    fn register(&self, key: K) -> (User<'_, K>, Arc<Semaphore>) {
+        let mut keys = self.keys();
+        let entry = keys
+            .entry(key.clone())
+            .or_insert_with(|| (Arc::new(Semaphore::new(self.limit)), 0));
+        entry.1 += 1;
+        let semaphore = entry.0.clone();
+        drop(keys);
+
+        (User { owner: self, key }, semaphore)
+    }
+
+    fn keys(&self) -> StdMutexGuard<'_, HashMap<K, (Arc<Semaphore>, usize)>> {
+        self.keys.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<K> fmt::Debug for KeyedSemaphore<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        f.debug_struct("KeyedSemaphore")
+            .field("limit", &self.limit)
+            .field("keys", &keys.len())
+            .field("capacity", &keys.capacity())
+            .finish()
+    }
+}
+
+/// An operation counted in a key's entry, which is removed with the last operation.
+struct User<'a, K: Eq + Hash + Clone> {
+    owner: &'a KeyedSemaphore<K>,
+    key: K,
+}
+
+impl<K: Eq + Hash + Clone> Drop for User<'_, K> {
+    fn drop(&mut self) {
+        let mut keys = self.owner.keys();
+        if let Some(entry) = keys.get_mut(&self.key) {
+            entry.1 -= 1;
+            if entry.1 == 0 {
+                keys.remove(&self.key);
+            }
+        }
+    }
+}
+
+/// A guard that releases a permit of a [`KeyedSemaphore`].
+pub struct KeyedSemaphoreGuard<'a, K: Eq + Hash + Clone> {
+    // Dropped first, so the permit is returned before the key's entry may be removed.
+    _permit: SemaphoreGuardArc,
+    user: User<'a, K>,
+
+    // Dropped last, so exclusive operations find the key's entry removed.
+    _shared: RwLockReadGuard<'a, ()>,
+}
+
+impl<K: Eq + Hash + Clone> KeyedSemaphoreGuard<'_, K> {
+    /// Returns the key the permit was acquired for.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::KeyedSemaphore;
+    ///
+    /// let s = KeyedSemaphore::new(1);
+    /// let guard = s.try_acquire(7).unwrap();
+    /// assert_eq!(*guard.key(), 7);
+    /// ```
+    pub fn key(&self) -> &K {
+        &self.user.key
+    }
+}
+
+impl<K: Eq + Hash + Clone + fmt::Debug> fmt::Debug for KeyedSemaphoreGuard<'_, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyedSemaphoreGuard")
+            .field("key", &self.user.key)
+            .finish()
+    }
+}
+
+/// A guard that gives exclusive access to all keys of a [`KeyedSemaphore`].
+///
+/// Created by [`KeyedSemaphore::acquire_exclusive()`]. Other operations on the semaphore wait
+/// until it is dropped.
+pub struct KeyedSemaphoreExclusiveGuard<'a> {
+    _shared: RwLockWriteGuard<'a, ()>,
+}
+
+impl fmt::Debug for KeyedSemaphoreExclusiveGuard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyedSemaphoreExclusiveGuard").finish()
+    }
+}
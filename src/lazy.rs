@@ -0,0 +1,140 @@
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+
+use alloc::boxed::Box;
+
+use crate::OnceCell;
+
+/// A value that is initialized by an async operation the first time it is used.
+///
+/// The initialization function is given when the value is created, so a `Lazy` can be a
+/// `static` and simply be awaited wherever the value is needed. Like with a [`OnceCell`], the
+/// first task to call [`get()`] runs the initialization future and later callers wait for it.
+///
+/// The function may be called more than once when an initialization is cancelled, so it is an
+/// [`Fn`] rather than an [`FnOnce`]. In a `static`, it has to be a function pointer, which can
+/// return a boxed future.
+///
+/// [`get()`]: `Lazy::get()`
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::Lazy;
+/// use futures_lite::future::Boxed;
+///
+/// static GREETING: Lazy<String, fn() -> Boxed<String>> = Lazy::new(|| {
+///     Box::pin(async { "hello".to_string() })
+/// });
+///
+/// assert_eq!(&*GREETING.get().await, "hello");
+/// # })
+/// ```
+pub struct Lazy<T, F> {
+    cell: OnceCell<T>,
+    init: F,
+}
+
+impl<T, F: Fn() -> Fut, Fut: Future<Output = T>> Lazy<T, F> {
+    /// Creates a value that is initialized with the future returned by `init`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Lazy;
+    ///
+    /// let lazy = Lazy::new(|| async { 1 + 1 });
+    /// ```
+    pub const fn new(init: F) -> Lazy<T, F> {
+        Lazy {
+            cell: OnceCell::new(),
+            init,
+        }
+    }
+
+    /// Returns the value, initializing it first if that has not happened yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Lazy;
+    ///
+    /// let lazy = Lazy::new(|| async { vec![1, 2, 3] });
+    /// assert_eq!(lazy.get().await.len(), 3);
+    /// # })
+    /// ```
+    pub async fn get(&self) -> &T {
+        self.cell.get_or_init(&self.init).await
+    }
+
+    /// Starts initializing the value in the background, so the first caller of
+    /// [`get()`][`Lazy::get()`] does not have to wait as long.
+    ///
+    /// `spawn` is called with a future that initializes the value, and should run it on an
+    /// executor. It is not called if the value is already initialized. Callers of `get()` wait
+    /// for the spawned initialization instead of starting their own, and take over if it is
+    /// cancelled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Lazy;
+    /// use futures_lite::future::{self, Boxed};
+    ///
+    /// static POOL: Lazy<Vec<u32>, fn() -> Boxed<Vec<u32>>> = Lazy::new(|| {
+    ///     Box::pin(async { vec![1, 2, 3] })
+    /// });
+    ///
+    /// POOL.prime(|init| {
+    ///     std::thread::spawn(move || future::block_on(init));
+    /// });
+    /// assert_eq!(POOL.get().await.len(), 3);
+    /// # })
+    /// ```
+    // Eliding `'a` would make the future's lifetime higher-ranked, so it could not be spawned.
+    #[allow(clippy::needless_lifetimes)]
+    pub fn prime<'a, S>(&'a self, spawn: S)
+    where
+        S: FnOnce(Pin<Box<dyn Future<Output = ()> + Send + 'a>>),
+        T: Send + Sync,
+        F: Sync,
+        Fut: Send,
+    {
+        if self.try_get().is_none() {
+            spawn(Box::pin(async move {
+                self.get().await;
+            }));
+        }
+    }
+}
+
+impl<T, F> Lazy<T, F> {
+    /// Returns the value if it is initialized, without initializing it or waiting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Lazy;
+    ///
+    /// let lazy = Lazy::new(|| async { 5 });
+    /// assert_eq!(lazy.try_get(), None);
+    ///
+    /// lazy.get().await;
+    /// assert_eq!(lazy.try_get(), Some(&5));
+    /// # })
+    /// ```
+    pub fn try_get(&self) -> Option<&T> {
+        self.cell.get()
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for Lazy<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lazy").field("cell", &self.cell).finish()
+    }
+}
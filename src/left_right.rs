@@ -0,0 +1,301 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use event_listener::Event;
+use std::thread;
+
+use crate::Mutex;
+
+/// A reader-writer primitive whose readers never wait and never retry.
+///
+/// The value is stored twice. Readers always use the active copy, while the single writer changes
+/// the inactive copy, makes it the active one, waits until no reader uses the old copy anymore,
+/// and then applies the same change to it. Reading costs two atomic increments and one decrement,
+/// which suits hot lookups such as routing tables, where even an uncontended
+/// [`RwLock`][`crate::RwLock`] read is too slow.
+///
+/// The data is stored twice and every change is applied twice, so changes must be deterministic.
+/// A writer has to wait for all readers of the old copy, so read guards should not be held for
+/// long.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::LeftRight;
+/// use std::collections::HashMap;
+///
+/// let routes = LeftRight::new(HashMap::new());
+/// routes.write(|r| { r.insert("/", "index"); }).await;
+///
+/// assert_eq!(routes.read().get("/"), Some(&"index"));
+/// # })
+/// ```
+pub struct LeftRight<T> {
+    /// The two copies of the value.
+    copies: [UnsafeCell<T>; 2],
+
+    /// The copy that new readers use.
+    active: AtomicUsize,
+
+    /// The read indicator that new readers register in.
+    version: AtomicUsize,
+
+    /// Number of readers registered in each read indicator.
+    readers: [AtomicUsize; 2],
+
+    /// Notified when a read indicator drops to zero.
+    drained: Event,
+
+    /// Serializes writers.
+    writer: Mutex<()>,
+}
+
+unsafe impl<T: Send> Send for LeftRight<T> {}
+unsafe impl<T: Send + Sync> Sync for LeftRight<T> {}
+
+impl<T: Clone> LeftRight<T> {
+    /// Creates a new left-right primitive holding two copies of `value`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::LeftRight;
+    ///
+    /// let lock = LeftRight::new(vec![1, 2, 3]);
+    /// ```
+    pub fn new(value: T) -> LeftRight<T> {
+        LeftRight {
+            copies: [UnsafeCell::new(value.clone()), UnsafeCell::new(value)],
+            active: AtomicUsize::new(0),
+            version: AtomicUsize::new(0),
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            drained: Event::new(),
+            writer: Mutex::new(()),
+        }
+    }
+}
+
+impl<T> LeftRight<T> {
+    /// Returns a guard for reading the active copy.
+    ///
+    /// This never waits, not even for writers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::LeftRight;
+    ///
+    /// let lock = LeftRight::new(5);
+    /// assert_eq!(*lock.read(), 5);
+    /// ```
+    pub fn read(&self) -> LeftRightReadGuard<'_, T> {
+        let indicator = self.version.load(Ordering::SeqCst);
+        self.readers[indicator].fetch_add(1, Ordering::SeqCst);
+        let active = self.active.load(Ordering::SeqCst);
+
+        LeftRightReadGuard {
+            lock: self,
+            indicator,
+            // SAFETY: The writer does not change this copy until this reader has deregistered.
+            value: unsafe { &*self.copies[active].get() },
+        }
+    }
+
+    /// Applies a change to both copies.
+    ///
+    /// `f` is called exactly twice, once for each copy, and must make the same change both times.
+    /// Readers see the change as soon as the first copy has been changed. This waits for other
+    /// writers and for readers of the old copy.
+    ///
+    /// If the returned future is dropped after the first copy was changed, the change is still
+    /// completed, blocking the thread until the readers of the old copy are done.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::LeftRight;
+    ///
+    /// let lock = LeftRight::new(vec![1]);
+    /// lock.write(|v| v.push(2)).await;
+    /// assert_eq!(*lock.read(), [1, 2]);
+    /// # })
+    /// ```
+    pub async fn write<F: FnMut(&mut T)>(&self, mut f: F) {
+        let _writer = self.writer.lock().await;
+
+        // Readers only ever use the active copy, so the inactive one can be changed right away.
+        let stale = self.active.load(Ordering::SeqCst);
+        f(unsafe { &mut *self.copies[1 - stale].get() });
+        self.active.store(1 - stale, Ordering::SeqCst);
+
+        let mut write = Write {
+            lock: self,
+            f,
+            stale,
+            phase: Phase::Swapped,
+        };
+
+        // Move new readers to the other read indicator, then wait until all readers that may have
+        // seen the old copy are gone.
+        let version = self.version.load(Ordering::SeqCst);
+        self.drain(1 - version).await;
+        self.version.store(1 - version, Ordering::SeqCst);
+        write.phase = Phase::Moved;
+        self.drain(version).await;
+
+        write.finish();
+    }
+
+    /// Consumes the primitive, returning the active copy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::LeftRight;
+    ///
+    /// let lock = LeftRight::new(10);
+    /// assert_eq!(lock.into_inner(), 10);
+    /// ```
+    pub fn into_inner(self) -> T {
+        let [left, right] = self.copies;
+        match self.active.into_inner() {
+            0 => left.into_inner(),
+            _ => right.into_inner(),
+        }
+    }
+
+    /// Waits until no reader is registered in the given read indicator.
+    async fn drain(&self, indicator: usize) {
+        let readers = &self.readers[indicator];
+        while readers.load(Ordering::SeqCst) != 0 {
+            let listener = self.drained.listen();
+            if readers.load(Ordering::SeqCst) == 0 {
+                break;
+            }
+            listener.await;
+        }
+    }
+
+    /// Blocks the current thread until no reader is registered in the given read indicator.
+    fn drain_blocking(&self, indicator: usize) {
+        let mut spins = 0u32;
+        while self.readers[indicator].load(Ordering::SeqCst) != 0 {
+            if spins < 100 {
+                core::hint::spin_loop();
+                spins += 1;
+            } else {
+                thread::yield_now();
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LeftRight<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LeftRight")
+            .field("value", &&*self.read())
+            .finish()
+    }
+}
+
+impl<T: Clone> From<T> for LeftRight<T> {
+    fn from(value: T) -> LeftRight<T> {
+        LeftRight::new(value)
+    }
+}
+
+impl<T: Clone + Default> Default for LeftRight<T> {
+    fn default() -> LeftRight<T> {
+        LeftRight::new(Default::default())
+    }
+}
+
+/// How far a write has come after changing the first copy.
+enum Phase {
+    /// The changed copy is active, and new readers still use the old read indicator.
+    Swapped,
+
+    /// New readers use the other read indicator.
+    Moved,
+
+    /// Both copies were changed.
+    Done,
+}
+
+/// A write that changed one copy, and changes the other one when finished or dropped.
+struct Write<'a, T, F: FnMut(&mut T)> {
+    lock: &'a LeftRight<T>,
+    f: F,
+
+    /// The copy that has not been changed yet.
+    stale: usize,
+
+    phase: Phase,
+}
+
+impl<T, F: FnMut(&mut T)> Write<'_, T, F> {
+    /// Changes the stale copy, once no reader uses it anymore.
+    fn finish(&mut self) {
+        (self.f)(unsafe { &mut *self.lock.copies[self.stale].get() });
+        self.phase = Phase::Done;
+    }
+}
+
+impl<T, F: FnMut(&mut T)> Drop for Write<'_, T, F> {
+    fn drop(&mut self) {
+        let lock = self.lock;
+        let version = lock.version.load(Ordering::SeqCst);
+        match self.phase {
+            Phase::Done => return,
+            Phase::Swapped => {
+                lock.drain_blocking(1 - version);
+                lock.version.store(1 - version, Ordering::SeqCst);
+                lock.drain_blocking(version);
+            }
+            Phase::Moved => lock.drain_blocking(1 - version),
+        }
+        self.finish();
+    }
+}
+
+/// A guard that lets a reader use the active copy of a [`LeftRight`].
+pub struct LeftRightReadGuard<'a, T> {
+    lock: &'a LeftRight<T>,
+    indicator: usize,
+    value: &'a T,
+}
+
+unsafe impl<T: Sync> Send for LeftRightReadGuard<'_, T> {}
+unsafe impl<T: Sync> Sync for LeftRightReadGuard<'_, T> {}
+
+impl<T> Drop for LeftRightReadGuard<'_, T> {
+    fn drop(&mut self) {
+        if self.lock.readers[self.indicator].fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.lock.drained.notify(usize::MAX);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LeftRightReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.value, f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for LeftRightReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.value, f)
+    }
+}
+
+impl<T> Deref for LeftRightReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
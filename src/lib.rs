@@ -3,18 +3,300 @@
 //! This crate provides the following primitives:
 //!
 //! * [`Barrier`] - enables tasks to synchronize all together at the same time.
+//! * [`BiLock`] - a lock shared by exactly two owners, such as the halves of a split stream.
+//! * [`CloseableLock`] - a mutex whose waiting lock operations fail once it is closed.
+//! * [`Condvar`] - lets tasks wait for a condition on data protected by a [`Mutex`].
+//! * [`CowLock`] - a copy-on-write lock whose readers never wait.
+//! * [`Flag`] - a flag that tasks can wait on until it is set.
+//! * [`Handoff`] - hands values directly to waiting tasks.
+//! * [`KeyedSemaphore`] - limits the number of concurrent operations per key.
+//! * [`Lazy`] - a value that is initialized by an async operation the first time it is used.
+//! * [`LeftRight`] - keeps two copies of a value, so readers never wait.
+//! * [`LocalLock`] - a mutual exclusion lock without atomics, for tasks on a single thread.
 //! * [`Mutex`] - a mutual exclusion lock.
+//! * [`Notify`] - wakes waiting tasks, or stores a notification until a task waits.
+//! * [`Once`] - runs an async operation once.
+//! * [`OnceCell`] - a cell that is initialized once, by an async operation.
+//! * [`OneShotBarrier`] - a cheaper [`Barrier`] for tasks that only synchronize once.
+//! * [`PoisonLock`] - a mutex that is poisoned when a task panics while holding it.
+//! * [`PollLock`] - lets several tasks take turns polling one future or stream.
+//! * [`Pool`] - a pool of reusable objects, such as connections.
+//! * [`PriorityMutex`] - a mutex that serves higher-priority waiters first.
+//! * [`PrioritySemaphore`] - a semaphore that serves higher-priority waiters first.
+//! * [`RateLimiter`] - limits how often operations run, with a token bucket.
+//! * [`ReentrantLock`] - a lock that the task holding it can acquire again.
 //! * [`RwLock`] - a reader-writer lock, allowing any number of readers or a single writer.
 //! * [`Semaphore`] - limits the number of concurrent operations.
+//! * [`ShardedLock`] - a reader-writer lock whose readers on different threads do not contend.
+//! * [`Shutdown`] - turns away new operations and waits for in-flight ones to finish.
+//! * [`StaticLock`] - a mutual exclusion lock with a fixed number of inline waiter slots.
+//! * [`WaitGroup`] - waits for a group of operations to finish.
+//! * [`Watch`] - a value that tasks can watch for changes.
+//!
+//! The [`field_locks!`] macro splits a struct into separately locked fields. [`lock_all()`] and
+//! [`OrderedLockSet`] acquire several locks at once without risking a deadlock.
+//!
+//! # Features
+//!
+//! The `std` feature is enabled by default. Disabling it makes the crate `no_std`, relying only
+//! on `alloc` for the reference-counted guards. Without `std`, the mutex can no longer measure how
+//! long a lock operation has been starved, unless a [`clock`] is installed, so it only switches to
+//! fair locking once another starved operation is already waiting.
+//!
+//! The `critical-section` feature protects the internal waiter queues with the
+//! [`critical-section`](https://docs.rs/critical-section) crate instead of a spinlock. On
+//! microcontrollers this makes it safe to call `try_lock()` and to drop guards from interrupt
+//! handlers: the interrupt only updates the lock state and wakes waiters, which then run in task
+//! context once the executor polls them.
+//!
+//! The `portable-atomic` feature takes the atomics from
+//! [`portable-atomic`](https://docs.rs/portable-atomic), so the crate builds on targets without
+//! native compare-and-swap, such as `thumbv6m-none-eabi`. Enable `critical-section` as well, or
+//! one of `portable-atomic`'s own options, to provide it. The owned guards, such as
+//! [`MutexGuardArc`], need [`alloc::sync::Arc`] and are not available on these targets.
+//!
+//! The `embassy-sync` feature adds [`EmbassyRawMutex`], a raw mutex that `embassy-sync` primitives
+//! such as its `Mutex` and channels can be instantiated with. Combined with `critical-section`, it
+//! locks with a critical section and can be used from interrupt handlers.
+//!
+//! The crate builds with Rust 1.70 and later. Two features need a newer compiler and are
+//! therefore opt-in: `file-lock` adds [`FileLock`], an advisory file lock for coordinating with
+//! other processes, which needs Rust 1.89, and `async-closure` adds
+//! [`Mutex::with_lock_async()`], which takes an async closure and needs Rust 1.85.
+//!
+//! The `stream` feature adds methods such as [`Mutex::stream_items()`], which stream the items of
+//! a locked collection while only holding the lock for one chunk of items at a time. It also
+//! implements `FusedFuture` for [`LockFuture`].
+//!
+//! The `ffi` feature adds a C API in [`ffi`], so foreign code can lock the same mutexes as Rust
+//! tasks.
+//!
+//! The `futures-io` feature implements `AsyncRead`, `AsyncWrite`, and `AsyncSeek` for
+//! [`PollLock`], so tasks can share one I/O object without locking it at every call site.
+//!
+//! The `serde` feature implements `Serialize` and `Deserialize` for [`Mutex`] and [`RwLock`].
+//! Serializing does not wait for the lock; it fails if the data cannot be read right away.
+//!
+//! Timed operations take a [`Timer`]. The `async-io` and `tokio` features provide implementations
+//! backed by those runtimes. The `async-io` timer is not available on `wasm32`.
+//!
+//! Lock acquisitions can also consume an executor's cooperative scheduling budget, see [`coop`].
+//! With the `priority-ceiling` feature, locks can also raise their holders to a priority ceiling
+//! through executor hooks, see [`priority`].
+//!
+//! The `tracing` feature emits [`tracing`](https://docs.rs/tracing) events at the `TRACE` level when
+//! a lock operation starts waiting, when it acquires the lock (including how long it waited), when
+//! the lock is released, and when [`MutexGuard::unlock_fair()`] hands it to a waiting operation.
+//! Every event carries the kind of lock, the access mode, the address of the lock as its ID, and
+//! the name of the lock, if it was created with a constructor such as [`Mutex::new_named()`].
+//!
+//! The `console` feature additionally describes every [`Mutex`], [`RwLock`], and [`Semaphore`] as a
+//! resource to [`tokio-console`](https://github.com/tokio-rs/console), so tasks waiting on a lock
+//! show up there together with the lock's current state.
+//!
+//! The `log` feature warns through [`log`](https://docs.rs/log) whenever a lock operation waits
+//! longer than a configurable threshold, naming the lock and the code that tried to acquire it.
+//! See [`diagnostics::set_slow_threshold()`].
+//!
+//! The `hooks` feature reports contention, wait times, and hold times to a
+//! [`diagnostics::LockMetrics`] implementation, installed globally or per lock. The built-in
+//! [`diagnostics::LockHistograms`] collects them into histograms. The `metrics` feature
+//! additionally provides [`diagnostics::MetricsRecorder`], which exports them through the
+//! [`metrics`](https://docs.rs/metrics) facade.
+//!
+//! The `registry` feature keeps track of every live lock, so [`diagnostics::dump()`] can report
+//! which locks are held, where they were acquired, and how many operations are waiting on them.
+//! Each lock can also list its waiters, labeled with [`diagnostics::label()`], and report where it
+//! was acquired, with methods such as [`Mutex::holder_info()`].
+//!
+//! The `deadlock-detection` feature builds on the registry: in debug builds, a labeled task that
+//! starts waiting for a lock held by tasks that are, directly or indirectly, waiting for it panics
+//! with a description of the cycle, instead of hanging forever.
+//!
+//! The `events` feature lets test harnesses and monitors subscribe to the acquisitions and
+//! releases of a lock as a stream of [`diagnostics::LockEvent`]s, with methods such as
+//! [`Mutex::events()`].
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs, missing_debug_implementations, rust_2018_idioms)]
 
+extern crate alloc;
+
 mod barrier;
+#[cfg(target_has_atomic = "ptr")]
+mod bi_lock;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod blocking;
+mod boxed;
+pub mod clock;
+mod closeable_lock;
+mod condvar;
+pub mod coop;
+#[cfg(feature = "std")]
+mod cow_lock;
+#[cfg(feature = "deadlock-detection")]
+mod deadlock;
+#[cfg(any(
+    feature = "log",
+    feature = "hooks",
+    feature = "registry",
+    feature = "events"
+))]
+pub mod diagnostics;
+#[cfg(feature = "embassy-sync")]
+mod embassy;
+#[cfg(feature = "events")]
+mod events;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod field_locks;
+#[cfg(all(feature = "file-lock", not(target_arch = "wasm32")))]
+mod file_lock;
+mod flag;
+#[cfg(feature = "std")]
+mod handoff;
+#[cfg(feature = "stream")]
+mod items;
+#[cfg(feature = "std")]
+mod keyed_semaphore;
+mod lazy;
+#[cfg(feature = "std")]
+mod left_right;
+mod local_lock;
+mod lock_all;
 mod mutex;
+#[cfg(feature = "std")]
+mod notify;
+mod once;
+mod once_cell;
+mod ordered_lock_set;
+#[cfg(feature = "std")]
+mod poison_lock;
+#[cfg(feature = "std")]
+mod poll_lock;
+#[cfg(feature = "std")]
+mod pool;
+#[cfg(feature = "priority-ceiling")]
+pub mod priority;
+#[cfg(feature = "std")]
+mod priority_mutex;
+#[cfg(feature = "std")]
+mod priority_semaphore;
+#[cfg(feature = "std")]
+mod rate_limiter;
+mod reentrant_lock;
+#[cfg(feature = "registry")]
+mod registry;
 mod rwlock;
 mod semaphore;
+#[cfg(feature = "std")]
+mod sharded_lock;
+mod shutdown;
+mod static_lock;
+mod sub_lock;
+mod sync;
+
+#[cfg(feature = "std")]
+mod time;
+mod timer;
+mod trace;
+mod wait_group;
+mod watch;
+
+pub use barrier::{Barrier, BarrierWaitResult, BrokenBarrierError, OneShotBarrier};
+#[cfg(target_has_atomic = "ptr")]
+pub use bi_lock::{BiLock, BiLockGuard, ReuniteError};
+pub use boxed::{BoxLockFuture, DynGuard, DynLock};
+pub use closeable_lock::{CloseableLock, ClosedLockError};
+pub use condvar::{Condvar, WaitTimeoutResult};
+#[cfg(feature = "std")]
+pub use cow_lock::{CowLock, CowLockWriteGuard};
+#[cfg(feature = "embassy-sync")]
+pub use embassy::EmbassyRawMutex;
+#[cfg(all(feature = "file-lock", not(target_arch = "wasm32")))]
+pub use file_lock::{FileLock, FileLockGuard};
+pub use flag::Flag;
+#[cfg(feature = "std")]
+pub use handoff::Handoff;
+#[cfg(feature = "std")]
+pub use keyed_semaphore::{KeyedSemaphore, KeyedSemaphoreExclusiveGuard, KeyedSemaphoreGuard};
+pub use lazy::Lazy;
+#[cfg(feature = "std")]
+pub use left_right::{LeftRight, LeftRightReadGuard};
+pub use local_lock::{LocalLock, LocalLockFuture, LocalLockGuard, LocalLockGuardRc};
+pub use lock_all::{lock_all, try_lock_all, LockSet, Lockable};
+pub use mutex::{
+    FairnessPolicy, LockCancelledError, LockFuture, MappedMutexGuard, Mutex, MutexGuard,
+};
+#[cfg(target_has_atomic = "ptr")]
+pub use mutex::{MappedMutexGuardArc, MutexGuardArc};
+#[cfg(feature = "std")]
+pub use notify::{Notified, Notify};
+pub use once::Once;
+pub use once_cell::OnceCell;
+pub use ordered_lock_set::{OrderedGuards, OrderedLockSet};
+#[cfg(feature = "std")]
+pub use poison_lock::{LockResult, PoisonError, PoisonLock, PoisonLockGuard};
+#[cfg(feature = "std")]
+pub use poll_lock::{PollLock, PollLockGuard};
+#[cfg(feature = "std")]
+pub use pool::{Pool, PoolGuard, PoolManager};
+#[cfg(feature = "std")]
+pub use priority_mutex::{PriorityMutex, PriorityMutexGuard};
+#[cfg(feature = "std")]
+pub use priority_semaphore::{PrioritySemaphore, PrioritySemaphoreGuard};
+#[cfg(feature = "std")]
+pub use rate_limiter::RateLimiter;
+pub use reentrant_lock::{LockOwner, ReentrantLock, ReentrantLockGuard};
+pub use rwlock::{
+    MappedRwLockReadGuard, MappedRwLockWriteGuard, RwLock, RwLockReadGuard,
+    RwLockUpgradableReadGuard, RwLockWriteGuard,
+};
+#[cfg(target_has_atomic = "ptr")]
+pub use rwlock::{RwLockReadGuardArc, RwLockWriteGuardArc};
+#[cfg(target_has_atomic = "ptr")]
+pub use semaphore::SemaphoreGuardArc;
+pub use semaphore::{ClosedSemaphoreError, Semaphore, SemaphoreGuard};
+#[cfg(feature = "std")]
+pub use sharded_lock::{ShardedLock, ShardedLockReadGuard, ShardedLockWriteGuard};
+#[cfg(target_has_atomic = "ptr")]
+pub use shutdown::ShutdownGuardArc;
+pub use shutdown::{Shutdown, ShutdownGuard};
+pub use static_lock::{StaticLock, StaticLockGuard};
+pub use sub_lock::{SubLock, SubLockGuard};
+#[cfg(feature = "std")]
+pub use time::Instant;
+pub use timer::Timer;
+#[cfg(target_has_atomic = "ptr")]
+pub use wait_group::WaitGroupGuardArc;
+pub use wait_group::{WaitGroup, WaitGroupGuard};
+pub use watch::{Watch, Watcher};
+
+#[cfg(feature = "tokio")]
+pub use timer::TokioTimer;
+#[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
+pub use timer::{AsyncIoSleep, AsyncIoTimer};
+
+/// Aborts the process, used when a counter is about to overflow.
+#[cold]
+fn abort() -> ! {
+    #[cfg(feature = "std")]
+    {
+        std::process::abort()
+    }
+
+    #[cfg(not(feature = "std"))]
+    {
+        // Panicking while panicking aborts the process.
+        struct Bomb;
+
+        impl Drop for Bomb {
+            fn drop(&mut self) {
+                panic!("panic while panicking to abort");
+            }
+        }
 
-pub use barrier::{Barrier, BarrierWaitResult};
-pub use mutex::{Mutex, MutexGuard, MutexGuardArc};
-pub use rwlock::{RwLock, RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard};
-pub use semaphore::{Semaphore, SemaphoreGuard, SemaphoreGuardArc};
+        let _bomb = Bomb;
+        panic!("panic while panicking to abort");
+    }
+}
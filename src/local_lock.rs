@@ -0,0 +1,435 @@
+use core::cell::{Cell, RefCell, UnsafeCell};
+use core::fmt;
+use core::future::Future;
+use core::ops::{Deref, DerefMut};
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+
+/// A mutual exclusion lock for tasks that all run on one thread.
+///
+/// This works like [`Mutex`][`crate::Mutex`], but keeps its state in [`Cell`]s instead of
+/// atomics, and its owned guards hold an [`Rc`] instead of an [`Arc`][`alloc::sync::Arc`]. On
+/// single-threaded executors, such as a per-core runtime or a `LocalExecutor`, this saves the cost
+/// of the atomic operations. The lock cannot be shared with other threads, and its futures and
+/// guards cannot be sent to them.
+///
+/// Waiting lock operations are woken in the order they started waiting, but a lock operation that
+/// finds the lock free takes it right away.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::LocalLock;
+/// use futures_lite::future;
+/// use std::rc::Rc;
+///
+/// let lock = Rc::new(LocalLock::new(0));
+/// let guard = lock.lock().await;
+///
+/// let mut task = Box::pin({
+///     let lock = lock.clone();
+///     async move { *lock.lock().await += 1 }
+/// });
+/// assert!(future::poll_once(&mut task).await.is_none());
+///
+/// drop(guard);
+/// task.await;
+/// assert_eq!(*lock.lock().await, 1);
+/// # })
+/// ```
+pub struct LocalLock<T: ?Sized> {
+    /// Set while a guard exists.
+    locked: Cell<bool>,
+
+    /// Lock operations waiting for the lock.
+    waiters: RefCell<Waiters>,
+
+    /// The value inside the lock.
+    data: UnsafeCell<T>,
+}
+
+/// Lock operations waiting for a [`LocalLock`], in the order they started waiting.
+struct Waiters {
+    /// The ID of the next lock operation to start waiting.
+    next_id: u64,
+
+    queue: VecDeque<(u64, Waker)>,
+}
+
+impl<T> LocalLock<T> {
+    /// Creates a new local lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::LocalLock;
+    ///
+    /// let lock = LocalLock::new(0);
+    /// ```
+    pub const fn new(data: T) -> LocalLock<T> {
+        LocalLock {
+            locked: Cell::new(false),
+            waiters: RefCell::new(Waiters {
+                next_id: 0,
+                queue: VecDeque::new(),
+            }),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Consumes the lock, returning the underlying data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::LocalLock;
+    ///
+    /// let lock = LocalLock::new(10);
+    /// assert_eq!(lock.into_inner(), 10);
+    /// ```
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized> LocalLock<T> {
+    /// Acquires the lock.
+    ///
+    /// Returns a guard that releases the lock when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::LocalLock;
+    ///
+    /// let lock = LocalLock::new(10);
+    /// let guard = lock.lock().await;
+    /// assert_eq!(*guard, 10);
+    /// # })
+    /// ```
+    pub fn lock(&self) -> LocalLockFuture<'_, T> {
+        LocalLockFuture {
+            lock: self,
+            id: None,
+        }
+    }
+
+    /// Attempts to acquire the lock.
+    ///
+    /// If the lock could not be acquired at this time, then [`None`] is returned. Otherwise, a
+    /// guard is returned that releases the lock when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::LocalLock;
+    ///
+    /// let lock = LocalLock::new(10);
+    /// if let Some(guard) = lock.try_lock() {
+    ///     assert_eq!(*guard, 10);
+    /// }
+    /// # ;
+    /// ```
+    pub fn try_lock(&self) -> Option<LocalLockGuard<'_, T>> {
+        if self.locked.replace(true) {
+            None
+        } else {
+            Some(LocalLockGuard(self))
+        }
+    }
+
+    /// Returns `true` if the lock is held.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::LocalLock;
+    ///
+    /// let lock = LocalLock::new(());
+    /// let guard = lock.try_lock().unwrap();
+    /// assert!(lock.is_locked());
+    /// ```
+    pub fn is_locked(&self) -> bool {
+        self.locked.get()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the lock mutably, no actual locking takes place -- the mutable
+    /// borrow statically guarantees the lock is not held.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::LocalLock;
+    ///
+    /// let mut lock = LocalLock::new(0);
+    /// *lock.get_mut() = 10;
+    /// assert_eq!(*lock.try_lock().unwrap(), 10);
+    /// ```
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    /// Acquires the lock and clones a reference to it.
+    ///
+    /// Returns an owned guard that releases the lock when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::LocalLock;
+    /// use std::rc::Rc;
+    ///
+    /// let lock = Rc::new(LocalLock::new(10));
+    /// let guard = lock.lock_rc().await;
+    /// assert_eq!(*guard, 10);
+    /// # })
+    /// ```
+    pub fn lock_rc(self: &Rc<Self>) -> impl Future<Output = LocalLockGuardRc<T>> {
+        let lock = self.clone();
+        async move {
+            let guard = lock.lock().await;
+            core::mem::forget(guard);
+            LocalLockGuardRc(lock)
+        }
+    }
+
+    /// Attempts to acquire the lock and clone a reference to it.
+    ///
+    /// If the lock could not be acquired at this time, then [`None`] is returned. Otherwise, an
+    /// owned guard is returned that releases the lock when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::LocalLock;
+    /// use std::rc::Rc;
+    ///
+    /// let lock = Rc::new(LocalLock::new(10));
+    /// if let Some(guard) = lock.try_lock_rc() {
+    ///     assert_eq!(*guard, 10);
+    /// }
+    /// # ;
+    /// ```
+    pub fn try_lock_rc(self: &Rc<Self>) -> Option<LocalLockGuardRc<T>> {
+        let guard = self.try_lock()?;
+        core::mem::forget(guard);
+        Some(LocalLockGuardRc(self.clone()))
+    }
+
+    /// Releases the lock and wakes the first waiting lock operation.
+    fn unlock(&self) {
+        self.locked.set(false);
+        self.wake_first();
+    }
+
+    /// Wakes the first waiting lock operation, if any.
+    fn wake_first(&self) {
+        // Release the queue first, in case waking runs code that uses the lock.
+        let waker = self.waiters.borrow().queue.front().map(|(_, w)| w.clone());
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for LocalLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Locked;
+        impl fmt::Debug for Locked {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("<locked>")
+            }
+        }
+
+        match self.try_lock() {
+            None => f.debug_struct("LocalLock").field("data", &Locked).finish(),
+            Some(guard) => f.debug_struct("LocalLock").field("data", &&*guard).finish(),
+        }
+    }
+}
+
+impl<T> From<T> for LocalLock<T> {
+    fn from(val: T) -> LocalLock<T> {
+        LocalLock::new(val)
+    }
+}
+
+impl<T: Default> Default for LocalLock<T> {
+    fn default() -> LocalLock<T> {
+        LocalLock::new(Default::default())
+    }
+}
+
+/// The future returned by [`LocalLock::lock()`].
+pub struct LocalLockFuture<'a, T: ?Sized> {
+    lock: &'a LocalLock<T>,
+
+    /// The ID of this lock operation in the queue of waiters, once it is waiting.
+    id: Option<u64>,
+}
+
+impl<T: ?Sized> Unpin for LocalLockFuture<'_, T> {}
+
+impl<'a, T: ?Sized> Future for LocalLockFuture<'a, T> {
+    type Output = LocalLockGuard<'a, T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<LocalLockGuard<'a, T>> {
+        let lock = self.lock;
+        let mut waiters = lock.waiters.borrow_mut();
+
+        match self.id {
+            None => {
+                if !lock.locked.replace(true) {
+                    return Poll::Ready(LocalLockGuard(lock));
+                }
+
+                let id = waiters.next_id;
+                waiters.next_id += 1;
+                waiters.queue.push_back((id, cx.waker().clone()));
+                self.id = Some(id);
+            }
+            Some(id) => {
+                let i = waiters
+                    .queue
+                    .iter()
+                    .position(|(waiter, _)| *waiter == id)
+                    .unwrap();
+                if !lock.locked.replace(true) {
+                    waiters.queue.remove(i);
+                    self.id = None;
+                    return Poll::Ready(LocalLockGuard(lock));
+                }
+
+                let waker = &mut waiters.queue[i].1;
+                if !waker.will_wake(cx.waker()) {
+                    *waker = cx.waker().clone();
+                }
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl<T: ?Sized> Drop for LocalLockFuture<'_, T> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            let mut waiters = self.lock.waiters.borrow_mut();
+            let i = waiters
+                .queue
+                .iter()
+                .position(|(waiter, _)| *waiter == id)
+                .unwrap();
+            waiters.queue.remove(i);
+            drop(waiters);
+
+            // This operation may have been woken to take the lock, so pass the wakeup on.
+            if i == 0 && !self.lock.locked.get() {
+                self.lock.wake_first();
+            }
+        }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for LocalLockFuture<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalLockFuture")
+            .field("waiting", &self.id.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A guard that releases the lock when dropped.
+pub struct LocalLockGuard<'a, T: ?Sized>(&'a LocalLock<T>);
+
+impl<T: ?Sized> Drop for LocalLockGuard<'_, T> {
+    fn drop(&mut self) {
+        self.0.unlock();
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for LocalLockGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display + ?Sized> fmt::Display for LocalLockGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized> Deref for LocalLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.0.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for LocalLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.0.data.get() }
+    }
+}
+
+/// An owned guard that releases the lock when dropped.
+pub struct LocalLockGuardRc<T: ?Sized>(Rc<LocalLock<T>>);
+
+impl<T: ?Sized> LocalLockGuardRc<T> {
+    /// Returns a reference to the lock a guard came from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::{LocalLock, LocalLockGuardRc};
+    /// use std::rc::Rc;
+    ///
+    /// let lock = Rc::new(LocalLock::new(10));
+    /// let guard = lock.try_lock_rc().unwrap();
+    /// assert!(Rc::ptr_eq(LocalLockGuardRc::source(&guard), &lock));
+    /// ```
+    pub fn source(guard: &LocalLockGuardRc<T>) -> &Rc<LocalLock<T>> {
+        &guard.0
+    }
+}
+
+impl<T: ?Sized> Drop for LocalLockGuardRc<T> {
+    fn drop(&mut self) {
+        self.0.unlock();
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for LocalLockGuardRc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display + ?Sized> fmt::Display for LocalLockGuardRc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized> Deref for LocalLockGuardRc<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.0.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for LocalLockGuardRc<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.0.data.get() }
+    }
+}
@@ -0,0 +1,245 @@
+use core::future::Future;
+
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
+use crate::{Mutex, MutexGuard, RwLock, RwLockWriteGuard, Semaphore, SemaphoreGuard};
+#[cfg(target_has_atomic = "ptr")]
+use crate::{MutexGuardArc, SemaphoreGuardArc};
+
+/// A lock that can be acquired as part of [`lock_all()`].
+///
+/// This is implemented for references to [`Mutex`], [`RwLock`] (for write access), and
+/// [`Semaphore`] (for a single permit), for `Arc<Mutex<T>>` and `Arc<Semaphore>` with owned
+/// guards, and can be implemented for other locks.
+pub trait Lockable {
+    /// The guard that releases the lock when dropped.
+    type Guard;
+
+    /// Returns an address that identifies the lock, used to order acquisitions.
+    fn id(&self) -> usize;
+
+    /// Attempts to acquire the lock without waiting.
+    fn try_lock(&self) -> Option<Self::Guard>;
+
+    /// Acquires the lock.
+    fn lock(&self) -> impl Future<Output = Self::Guard>;
+}
+
+impl<'a, T: ?Sized> Lockable for &'a Mutex<T> {
+    type Guard = MutexGuard<'a, T>;
+
+    fn id(&self) -> usize {
+        *self as *const Mutex<T> as *const () as usize
+    }
+
+    fn try_lock(&self) -> Option<MutexGuard<'a, T>> {
+        Mutex::try_lock(self)
+    }
+
+    fn lock(&self) -> impl Future<Output = MutexGuard<'a, T>> {
+        Mutex::lock(self)
+    }
+}
+
+impl<'a, T: ?Sized> Lockable for &'a RwLock<T> {
+    type Guard = RwLockWriteGuard<'a, T>;
+
+    fn id(&self) -> usize {
+        *self as *const RwLock<T> as *const () as usize
+    }
+
+    fn try_lock(&self) -> Option<RwLockWriteGuard<'a, T>> {
+        RwLock::try_write(self)
+    }
+
+    fn lock(&self) -> impl Future<Output = RwLockWriteGuard<'a, T>> {
+        RwLock::write(self)
+    }
+}
+
+impl<'a> Lockable for &'a Semaphore {
+    type Guard = SemaphoreGuard<'a>;
+
+    fn id(&self) -> usize {
+        *self as *const Semaphore as usize
+    }
+
+    fn try_lock(&self) -> Option<SemaphoreGuard<'a>> {
+        Semaphore::try_acquire(self)
+    }
+
+    fn lock(&self) -> impl Future<Output = SemaphoreGuard<'a>> {
+        Semaphore::acquire(self)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized> Lockable for Arc<Mutex<T>> {
+    type Guard = MutexGuardArc<T>;
+
+    fn id(&self) -> usize {
+        Arc::as_ptr(self) as *const () as usize
+    }
+
+    fn try_lock(&self) -> Option<MutexGuardArc<T>> {
+        Mutex::try_lock_arc(self)
+    }
+
+    fn lock(&self) -> impl Future<Output = MutexGuardArc<T>> {
+        Mutex::lock_arc(self)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl Lockable for Arc<Semaphore> {
+    type Guard = SemaphoreGuardArc;
+
+    fn id(&self) -> usize {
+        Arc::as_ptr(self) as usize
+    }
+
+    fn try_lock(&self) -> Option<SemaphoreGuardArc> {
+        Semaphore::try_acquire_arc(self)
+    }
+
+    fn lock(&self) -> impl Future<Output = SemaphoreGuardArc> {
+        Semaphore::acquire_arc(self)
+    }
+}
+
+/// A tuple of [`Lockable`] locks, acquired together by [`lock_all()`] or [`try_lock_all()`].
+///
+/// This is implemented for tuples of up to eight locks.
+pub trait LockSet {
+    /// The guards of every lock, in the same order as the locks.
+    type Guards;
+
+    /// Acquires every lock. See [`lock_all()`].
+    fn lock_all(self) -> impl Future<Output = Self::Guards>;
+
+    /// Attempts to acquire every lock without waiting. See [`try_lock_all()`].
+    fn try_lock_all(self) -> Option<Self::Guards>;
+}
+
+/// Acquires several locks at once without risking a deadlock.
+///
+/// Returns the guards in the same order as the locks. The locks may be of different kinds, see
+/// [`Lockable`].
+///
+/// This waits for one lock at a time and then tries to acquire the others, in the order of their
+/// addresses, without waiting. If one of them is taken, all acquired locks are released again, and
+/// the next attempt starts by waiting for the lock that was taken. No task ever waits for a lock
+/// while holding another one of the set, so tasks that lock overlapping sets in different orders,
+/// or that also lock some of them individually, cannot deadlock.
+///
+/// # Panics
+///
+/// Panics if the same lock appears twice.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::{lock_all, Mutex, RwLock};
+///
+/// let accounts = Mutex::new(vec![10, 20]);
+/// let log = RwLock::new(Vec::new());
+///
+/// let (mut accounts, mut log) = lock_all((&accounts, &log)).await;
+/// accounts[0] -= 5;
+/// accounts[1] += 5;
+/// log.push("transfer");
+/// # })
+/// ```
+pub fn lock_all<S: LockSet>(locks: S) -> impl Future<Output = S::Guards> {
+    locks.lock_all()
+}
+
+/// Attempts to acquire several locks at once without waiting.
+///
+/// Returns the guards in the same order as the locks if every lock could be acquired. Otherwise,
+/// the locks acquired so far are released again and [`None`] is returned, so no lock stays held.
+/// This is a cheap first attempt before falling back to [`lock_all()`].
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::{try_lock_all, Mutex, Semaphore};
+///
+/// let m = Mutex::new(0);
+/// let s = Semaphore::new(1);
+///
+/// let permit = s.try_acquire().unwrap();
+/// assert!(try_lock_all((&m, &s)).is_none());
+/// assert!(m.try_lock().is_some());
+///
+/// drop(permit);
+/// let (mut n, _permit) = try_lock_all((&m, &s)).unwrap();
+/// *n += 1;
+/// ```
+pub fn try_lock_all<S: LockSet>(locks: S) -> Option<S::Guards> {
+    locks.try_lock_all()
+}
+
+macro_rules! impl_lock_set {
+    ($($idx:tt $lock:ident),+) => {
+        impl<$($lock: Lockable),+> LockSet for ($($lock,)+) {
+            type Guards = ($($lock::Guard,)+);
+
+            fn lock_all(self) -> impl Future<Output = Self::Guards> {
+                async move {
+                    let ids = [$(self.$idx.id()),+];
+                    let mut order = [$($idx),+];
+                    order.sort_by_key(|&i| ids[i]);
+                    assert!(
+                        order.windows(2).all(|w| ids[w[0]] != ids[w[1]]),
+                        "the same lock was passed to `lock_all()` twice"
+                    );
+
+                    let mut first = order[0];
+                    loop {
+                        let mut guards = ($(None::<$lock::Guard>,)+);
+                        match first {
+                            $($idx => guards.$idx = Some(self.$idx.lock().await),)+
+                            _ => unreachable!(),
+                        }
+
+                        let taken = order.iter().copied().filter(|&i| i != first).find(|&i| {
+                            match i {
+                                $($idx => {
+                                    guards.$idx = self.$idx.try_lock();
+                                    guards.$idx.is_none()
+                                })+
+                                _ => unreachable!(),
+                            }
+                        });
+
+                        match taken {
+                            None => return ($(guards.$idx.unwrap(),)+),
+                            Some(i) => {
+                                // Release everything before waiting for the lock that was taken.
+                                drop(guards);
+                                first = i;
+                            }
+                        }
+                    }
+                }
+            }
+
+            fn try_lock_all(self) -> Option<Self::Guards> {
+                // Guards acquired before a failure are dropped on return.
+                Some(($(self.$idx.try_lock()?,)+))
+            }
+        }
+    };
+}
+
+impl_lock_set!(0 A);
+impl_lock_set!(0 A, 1 B);
+impl_lock_set!(0 A, 1 B, 2 C);
+impl_lock_set!(0 A, 1 B, 2 C, 3 D);
+impl_lock_set!(0 A, 1 B, 2 C, 3 D, 4 E);
+impl_lock_set!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F);
+impl_lock_set!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G);
+impl_lock_set!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H);
@@ -1,22 +1,47 @@
-use std::cell::UnsafeCell;
-use std::fmt;
-use std::ops::{Deref, DerefMut};
-use std::process;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::future::Future;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::panic::Location;
+use core::pin::Pin;
+use core::ptr;
+use core::task::{ready, Context, Poll};
 
-#[cfg(not(target_arch = "wasm32"))]
-use std::time::{Duration, Instant};
+use alloc::boxed::Box;
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+#[cfg(feature = "registry")]
+use alloc::vec::Vec;
 
-use std::usize;
+use core::time::Duration;
 
-use event_listener::Event;
+use crate::clock::Stopwatch;
+#[cfg(feature = "events")]
+use crate::diagnostics::LockEvents;
+#[cfg(feature = "hooks")]
+use crate::diagnostics::LockMetrics;
+#[cfg(feature = "registry")]
+use crate::diagnostics::{HolderInfo, WaiterInfo};
+use crate::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(feature = "std")]
+use crate::time::Instant;
+use crate::timer::{self, Timer};
+use crate::trace;
+use crate::SubLock;
+
+use event_listener::{Event, EventListener, Listener};
+#[cfg(feature = "stream")]
+use futures_core::future::FusedFuture;
+#[cfg(feature = "stream")]
+use futures_core::Stream;
 
 /// An async mutex.
 ///
 /// The locking mechanism uses eventual fairness to ensure locking will be fair on average without
 /// sacrificing performance. This is done by forcing a fair lock whenever a lock operation is
-/// starved for longer than 0.5 milliseconds.
+/// starved for longer than 0.5 milliseconds. Use [`Mutex::with_fairness()`] to choose a different
+/// [`FairnessPolicy`].
 ///
 /// # Examples
 ///
@@ -34,15 +59,32 @@ use event_listener::Event;
 /// assert_eq!(*m.try_lock().unwrap(), 2);
 /// # })
 /// ```
+///
+/// The value may be unsized, such as a slice or a trait object. Create the mutex with a sized
+/// value and coerce a reference or [`Arc`] to it:
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::Mutex;
+/// use std::fmt::Debug;
+/// use std::sync::Arc;
+///
+/// let m: Arc<Mutex<dyn Debug + Send>> = Arc::new(Mutex::new(1));
+/// assert_eq!(format!("{:?}", &*m.lock().await), "1");
+///
+/// let buf: &Mutex<[u8]> = &Mutex::new([0; 4]);
+/// buf.lock().await[0] = 1;
+/// # })
+/// ```
 pub struct Mutex<T: ?Sized> {
-    /// Current state of the mutex.
-    ///
-    /// The least significant bit is set to 1 if the mutex is locked.
-    /// The other bits hold the number of starved lock operations.
-    state: AtomicUsize,
+    /// The locking mechanism.
+    raw: RawMutex,
 
-    /// Lock operations waiting for the mutex to be released.
-    lock_ops: Event,
+    /// Instrumentation settings.
+    hooks: trace::Hooks,
+
+    /// When lock operations switch to fair locking.
+    fairness: FairnessPolicy,
 
     /// The value inside the mutex.
     data: UnsafeCell<T>,
@@ -63,8 +105,104 @@ impl<T> Mutex<T> {
     /// ```
     pub const fn new(data: T) -> Mutex<T> {
         Mutex {
-            state: AtomicUsize::new(0),
-            lock_ops: Event::new(),
+            raw: RawMutex::new(),
+            hooks: trace::Hooks::new(),
+            fairness: FairnessPolicy::DEFAULT,
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Creates a new async mutex with a name.
+    ///
+    /// The name identifies the mutex in its [`Debug`][`fmt::Debug`] output, in `tracing` events,
+    /// in slow-acquisition warnings, in the lock registry, and in deadlock reports, instead of
+    /// its address.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Mutex;
+    ///
+    /// let mutex = Mutex::new_named("scheduler-state", 0);
+    /// assert_eq!(mutex.name(), Some("scheduler-state"));
+    /// assert_eq!(format!("{:?}", mutex), r#"Mutex { name: "scheduler-state", data: 0 }"#);
+    /// ```
+    pub const fn new_named(name: &'static str, data: T) -> Mutex<T> {
+        Mutex {
+            raw: RawMutex::new(),
+            hooks: trace::Hooks::named(name),
+            fairness: FairnessPolicy::DEFAULT,
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Creates a new async mutex with the given fairness policy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::{FairnessPolicy, Mutex};
+    /// use std::time::Duration;
+    ///
+    /// let fifo = Mutex::with_fairness(0, FairnessPolicy::Fifo);
+    /// let patient = Mutex::with_fairness(0, FairnessPolicy::StarvationThreshold(Duration::from_millis(5)));
+    /// ```
+    pub const fn with_fairness(data: T, policy: FairnessPolicy) -> Mutex<T> {
+        Mutex {
+            raw: RawMutex::new(),
+            hooks: trace::Hooks::new(),
+            fairness: policy,
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Creates a new async mutex that reports to its own metrics hooks.
+    ///
+    /// The hooks are used instead of the ones installed with
+    /// [`set_metrics()`][`crate::diagnostics::set_metrics()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::diagnostics::LockMetrics;
+    /// use async_lock::Mutex;
+    /// use std::sync::Arc;
+    ///
+    /// struct Ignore;
+    /// impl LockMetrics for Ignore {}
+    ///
+    /// let mutex = Mutex::with_metrics(0, Arc::new(Ignore));
+    /// ```
+    #[cfg(feature = "hooks")]
+    pub fn with_metrics(data: T, metrics: Arc<dyn LockMetrics>) -> Mutex<T> {
+        Mutex {
+            raw: RawMutex::new(),
+            hooks: trace::Hooks::with_metrics(metrics),
+            fairness: FairnessPolicy::DEFAULT,
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Creates a new async mutex with a priority ceiling.
+    ///
+    /// Acquiring the mutex raises the acquiring task to `ceiling` through the hooks installed with
+    /// [`priority::set_hooks()`][`crate::priority::set_hooks()`], and releasing it restores the
+    /// previous priority.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Mutex;
+    ///
+    /// let mutex = Mutex::with_priority_ceiling(0, 5);
+    /// assert_eq!(mutex.priority_ceiling(), Some(5));
+    /// ```
+    #[cfg(feature = "priority-ceiling")]
+    pub const fn with_priority_ceiling(data: T, ceiling: u32) -> Mutex<T> {
+        Mutex {
+            raw: RawMutex::new(),
+            hooks: trace::Hooks::with_ceiling(ceiling),
+            fairness: FairnessPolicy::DEFAULT,
             data: UnsafeCell::new(data),
         }
     }
@@ -101,150 +239,53 @@ impl<T: ?Sized> Mutex<T> {
     /// # })
     /// ```
     #[inline]
-    pub async fn lock(&self) -> MutexGuard<'_, T> {
-        if let Some(guard) = self.try_lock() {
-            return guard;
-        }
-        self.acquire_slow().await;
-        MutexGuard(self)
+    #[track_caller]
+    pub fn lock(&self) -> LockFuture<'_, T> {
+        self.lock_at(Location::caller())
     }
 
-    /// Slow path for acquiring the mutex.
-    #[cold]
-    async fn acquire_slow(&self) {
-        // Get the current time.
-        #[cfg(not(target_arch = "wasm32"))]
-        let start = Instant::now();
-
-        loop {
-            // Start listening for events.
-            let listener = self.lock_ops.listen();
-
-            // Try locking if nobody is being starved.
-            match self
-                .state
-                .compare_exchange(0, 1, Ordering::Acquire, Ordering::Acquire)
-                .unwrap_or_else(|x| x)
-            {
-                // Lock acquired!
-                0 => return,
-
-                // Lock is held and nobody is starved.
-                1 => {}
-
-                // Somebody is starved.
-                _ => break,
-            }
-
-            // Wait for a notification.
-            listener.await;
-
-            // Try locking if nobody is being starved.
-            match self
-                .state
-                .compare_exchange(0, 1, Ordering::Acquire, Ordering::Acquire)
-                .unwrap_or_else(|x| x)
-            {
-                // Lock acquired!
-                0 => return,
-
-                // Lock is held and nobody is starved.
-                1 => {}
-
-                // Somebody is starved.
-                _ => {
-                    // Notify the first listener in line because we probably received a
-                    // notification that was meant for a starved task.
-                    self.lock_ops.notify(1);
-                    break;
-                }
-            }
-
-            // If waiting for too long, fall back to a fairer locking strategy that will prevent
-            // newer lock operations from starving us forever.
-            // #[cfg(not(target_arch = "wasm32"))]
-            if start.elapsed() > Duration::from_micros(500) {
-                break;
-            }
-        }
-
-        // Increment the number of starved lock operations.
-        if self.state.fetch_add(2, Ordering::Release) > usize::MAX / 2 {
-            // In case of potential overflow, abort.
-            process::abort();
-        }
-
-        // Decrement the counter when exiting this function.
-        let _call = CallOnDrop(|| {
-            self.state.fetch_sub(2, Ordering::Release);
-        });
-
-        loop {
-            // Start listening for events.
-            let listener = self.lock_ops.listen();
-
-            // Try locking if nobody else is being starved.
-            match self
-                .state
-                .compare_exchange(2, 2 | 1, Ordering::Acquire, Ordering::Acquire)
-                .unwrap_or_else(|x| x)
-            {
-                // Lock acquired!
-                2 => return,
-
-                // Lock is held by someone.
-                s if s % 2 == 1 => {}
-
-                // Lock is available.
-                _ => {
-                    // Be fair: notify the first listener and then go wait in line.
-                    self.lock_ops.notify(1);
-                }
-            }
-
-            // Wait for a notification.
-            listener.await;
-
-            // Try acquiring the lock without waiting for others.
-            if self.state.fetch_or(1, Ordering::Acquire) % 2 == 0 {
-                return;
-            }
-        }
+    /// Acquires the mutex on behalf of the code at `location`.
+    #[inline]
+    pub(crate) fn lock_at(&self, location: &'static Location<'static>) -> LockFuture<'_, T> {
+        self.lock_with(location, self.fairness, 0)
     }
 
-    /// Attempts to acquire the mutex.
+    /// Acquires the mutex, spinning up to `max_spins` times before waiting.
     ///
-    /// If the mutex could not be acquired at this time, then [`None`] is returned. Otherwise, a
-    /// guard is returned that releases the mutex when dropped.
+    /// This suits very short critical sections on multi-core machines: if the mutex is released
+    /// while spinning, the lock operation takes it without registering a waker and waiting to be
+    /// woken. Like [`try_lock_spin()`][`Mutex::try_lock_spin()`], spinning stops early if other
+    /// lock operations have been starving for the mutex. A spin count of zero makes this the same
+    /// as [`lock()`][`Mutex::lock()`].
     ///
     /// # Examples
     ///
     /// ```
+    /// # futures_lite::future::block_on(async {
     /// use async_lock::Mutex;
     ///
     /// let mutex = Mutex::new(10);
-    /// if let Some(guard) = mutex.try_lock() {
-    ///     assert_eq!(*guard, 10);
-    /// }
-    /// # ;
+    /// let guard = mutex.lock_spin(100).await;
+    /// assert_eq!(*guard, 10);
+    /// # })
     /// ```
     #[inline]
-    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
-        if self
-            .state
-            .compare_exchange(0, 1, Ordering::Acquire, Ordering::Acquire)
-            .is_ok()
-        {
-            Some(MutexGuard(self))
-        } else {
-            None
-        }
+    #[track_caller]
+    pub fn lock_spin(&self, max_spins: u32) -> LockFuture<'_, T> {
+        self.lock_with(Location::caller(), self.fairness, max_spins)
     }
 
-    /// Returns a mutable reference to the underlying data.
+    /// Acquires the mutex, waiting in line with the starved lock operations.
     ///
-    /// Since this call borrows the mutex mutably, no actual locking takes place -- the mutable
-    /// borrow statically guarantees the mutex is not already acquired.
+    /// Unlike [`lock()`][`Mutex::lock()`], this operation counts as starved as soon as it has to
+    /// wait, whatever the [`FairnessPolicy`] of the mutex, so it is never overtaken by newer lock
+    /// operations. Together with [`MutexGuard::unlock_fair()`], this keeps tasks that lock the
+    /// mutex in a loop from starving each other.
+    ///
+    /// It only waits behind lock operations that are starved too, though. Lock operations that
+    /// are waiting but not starved yet, such as ones that started with [`lock()`][`Mutex::lock()`]
+    /// shortly before, may be overtaken by it, including when it finds the mutex unlocked and
+    /// takes it right away.
     ///
     /// # Examples
     ///
@@ -252,206 +293,1722 @@ impl<T: ?Sized> Mutex<T> {
     /// # futures_lite::future::block_on(async {
     /// use async_lock::Mutex;
     ///
-    /// let mut mutex = Mutex::new(0);
-    /// *mutex.get_mut() = 10;
-    /// assert_eq!(*mutex.lock().await, 10);
+    /// let mutex = Mutex::new(10);
+    /// let guard = mutex.lock_fair().await;
+    /// assert_eq!(*guard, 10);
     /// # })
     /// ```
-    pub fn get_mut(&mut self) -> &mut T {
-        unsafe { &mut *self.data.get() }
+    #[inline]
+    #[track_caller]
+    pub fn lock_fair(&self) -> LockFuture<'_, T> {
+        self.lock_with(Location::caller(), FairnessPolicy::Fifo, 0)
     }
-}
 
-impl<T: ?Sized> Mutex<T> {
-    /// Acquires the mutex and clones a reference to it.
+    /// Acquires the mutex with a fairness policy on behalf of the code at `location`, spinning up
+    /// to `spins` times before waiting.
+    fn lock_with(
+        &self,
+        location: &'static Location<'static>,
+        fairness: FairnessPolicy,
+        spins: u32,
+    ) -> LockFuture<'_, T> {
+        let lock = Lock {
+            mutex: self,
+            location,
+            fairness,
+            spins,
+            state: LockState::Budget(crate::coop::consume_budget(self)),
+        };
+        LockFuture {
+            inner: trace::instrument(self.target(), "Mutex::lock", lock),
+            terminated: false,
+        }
+    }
+
+    /// Acquires the mutex, giving up after `timeout`.
     ///
-    /// Returns an owned guard that releases the mutex when dropped.
+    /// Returns a guard that releases the mutex when dropped, or [`None`] if the mutex did not
+    /// become available in time. A lock operation that gives up leaves the queue of waiters right
+    /// away, so it never holds up the tasks behind it.
     ///
     /// # Examples
     ///
     /// ```
+    /// # #[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
     /// # futures_lite::future::block_on(async {
-    /// use async_lock::Mutex;
-    /// use std::sync::Arc;
+    /// use async_lock::{AsyncIoTimer, Mutex};
+    /// use std::time::Duration;
     ///
-    /// let mutex = Arc::new(Mutex::new(10));
-    /// let guard = mutex.lock_arc().await;
-    /// assert_eq!(*guard, 10);
-    /// # })
+    /// let mutex = Mutex::new(10);
+    /// let guard = mutex.lock().await;
+    /// assert!(mutex.lock_timeout(AsyncIoTimer, Duration::from_millis(10)).await.is_none());
+    ///
+    /// drop(guard);
+    /// assert!(mutex.lock_timeout(AsyncIoTimer, Duration::from_millis(10)).await.is_some());
+    /// # });
     /// ```
-    #[inline]
-    pub async fn lock_arc(self: &Arc<Self>) -> MutexGuardArc<T> {
-        if let Some(guard) = self.try_lock_arc() {
-            return guard;
+    #[track_caller]
+    pub fn lock_timeout<'a>(
+        &'a self,
+        timer: impl Timer + 'a,
+        timeout: Duration,
+    ) -> impl Future<Output = Option<MutexGuard<'a, T>>> + 'a {
+        let location = Location::caller();
+
+        async move {
+            if let Some(guard) = self.try_lock_at(location) {
+                return Some(guard);
+            }
+            timer::timeout(self.lock_at(location), timer.sleep(timeout)).await
         }
-        self.acquire_slow().await;
-        MutexGuardArc(self.clone())
     }
 
-    /// Attempts to acquire the mutex and clone a reference to it.
+    /// Acquires the mutex, giving up at `deadline`.
     ///
-    /// If the mutex could not be acquired at this time, then [`None`] is returned. Otherwise, an
-    /// owned guard is returned that releases the mutex when dropped.
+    /// This works like [`lock_timeout()`][`Mutex::lock_timeout()`], but takes an absolute point in
+    /// time, so one deadline can be shared by several lock operations. The time left is measured
+    /// when the returned future is first polled. A deadline in the past still locks the mutex if
+    /// it is available.
     ///
     /// # Examples
     ///
     /// ```
-    /// use async_lock::Mutex;
-    /// use std::sync::Arc;
+    /// # #[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{AsyncIoTimer, Instant, Mutex};
+    /// use std::time::Duration;
     ///
-    /// let mutex = Arc::new(Mutex::new(10));
-    /// if let Some(guard) = mutex.try_lock() {
-    ///     assert_eq!(*guard, 10);
-    /// }
-    /// # ;
+    /// let a = Mutex::new(1);
+    /// let b = Mutex::new(2);
+    /// let deadline = Instant::now() + Duration::from_millis(10);
+    ///
+    /// let a = a.lock_deadline(AsyncIoTimer, deadline).await.unwrap();
+    /// let b = b.lock_deadline(AsyncIoTimer, deadline).await.unwrap();
+    /// assert_eq!(*a + *b, 3);
+    /// # });
     /// ```
-    #[inline]
-    pub fn try_lock_arc(self: &Arc<Self>) -> Option<MutexGuardArc<T>> {
-        if self
-            .state
-            .compare_exchange(0, 1, Ordering::Acquire, Ordering::Acquire)
-            .is_ok()
-        {
-            Some(MutexGuardArc(self.clone()))
-        } else {
-            None
-        }
-    }
-}
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn lock_deadline<'a>(
+        &'a self,
+        timer: impl Timer + 'a,
+        deadline: Instant,
+    ) -> impl Future<Output = Option<MutexGuard<'a, T>>> + 'a {
+        let location = Location::caller();
 
-impl<T: fmt::Debug + ?Sized> fmt::Debug for Mutex<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        struct Locked;
-        impl fmt::Debug for Locked {
-            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                f.write_str("<locked>")
+        async move {
+            if let Some(guard) = self.try_lock_at(location) {
+                return Some(guard);
             }
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            timer::timeout(self.lock_at(location), timer.sleep(timeout)).await
         }
-
-        match self.try_lock() {
-            None => f.debug_struct("Mutex").field("data", &Locked).finish(),
-            Some(guard) => f.debug_struct("Mutex").field("data", &&*guard).finish(),
-        }
-    }
-}
-
-impl<T> From<T> for Mutex<T> {
-    fn from(val: T) -> Mutex<T> {
-        Mutex::new(val)
-    }
-}
-
-impl<T: Default + ?Sized> Default for Mutex<T> {
-    fn default() -> Mutex<T> {
-        Mutex::new(Default::default())
     }
-}
-
-/// A guard that releases the mutex when dropped.
-pub struct MutexGuard<'a, T: ?Sized>(&'a Mutex<T>);
-
-unsafe impl<T: Send + ?Sized> Send for MutexGuard<'_, T> {}
-unsafe impl<T: Sync + ?Sized> Sync for MutexGuard<'_, T> {}
 
-impl<'a, T: ?Sized> MutexGuard<'a, T> {
-    /// Returns a reference to the mutex a guard came from.
+    /// Acquires the mutex, giving up when `cancel` completes.
+    ///
+    /// Returns a guard that releases the mutex when dropped, or an error if `cancel` completed
+    /// first. Passing a shutdown signal, such as a stop token or a channel closing, lets tasks
+    /// waiting for the mutex exit cleanly. If the mutex is available, it is locked even if
+    /// `cancel` is already complete.
     ///
     /// # Examples
     ///
     /// ```
     /// # futures_lite::future::block_on(async {
-    /// use async_lock::{Mutex, MutexGuard};
+    /// use async_lock::Mutex;
+    ///
+    /// let mutex = Mutex::new(10);
+    /// let (stop, stopped) = async_channel::bounded::<()>(1);
     ///
-    /// let mutex = Mutex::new(10i32);
     /// let guard = mutex.lock().await;
-    /// dbg!(MutexGuard::source(&guard));
+    /// drop(stop);
+    /// assert!(mutex.lock_cancellable(stopped.recv()).await.is_err());
+    ///
+    /// drop(guard);
+    /// assert!(mutex.lock_cancellable(stopped.recv()).await.is_ok());
     /// # })
     /// ```
-    pub fn source(guard: &MutexGuard<'a, T>) -> &'a Mutex<T> {
-        guard.0
-    }
-}
-
-impl<T: ?Sized> Drop for MutexGuard<'_, T> {
-    fn drop(&mut self) {
-        // Remove the last bit and notify a waiting lock operation.
-        self.0.state.fetch_sub(1, Ordering::Release);
-        self.0.lock_ops.notify(1);
-    }
-}
-
-impl<T: fmt::Debug + ?Sized> fmt::Debug for MutexGuard<'_, T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Debug::fmt(&**self, f)
-    }
-}
-
-impl<T: fmt::Display + ?Sized> fmt::Display for MutexGuard<'_, T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        (**self).fmt(f)
-    }
-}
-
-impl<T: ?Sized> Deref for MutexGuard<'_, T> {
-    type Target = T;
-
-    fn deref(&self) -> &T {
-        unsafe { &*self.0.data.get() }
-    }
-}
+    #[track_caller]
+    pub fn lock_cancellable<'a>(
+        &'a self,
+        cancel: impl Future + 'a,
+    ) -> impl Future<Output = Result<MutexGuard<'a, T>, LockCancelledError>> + 'a {
+        let location = Location::caller();
 
-impl<T: ?Sized> DerefMut for MutexGuard<'_, T> {
-    fn deref_mut(&mut self) -> &mut T {
-        unsafe { &mut *self.0.data.get() }
+        async move {
+            let cancel = async {
+                cancel.await;
+            };
+            timer::timeout(self.lock_at(location), cancel)
+                .await
+                .ok_or(LockCancelledError(()))
+        }
     }
-}
-
-/// An owned guard that releases the mutex when dropped.
-pub struct MutexGuardArc<T: ?Sized>(Arc<Mutex<T>>);
-
-unsafe impl<T: Send + ?Sized> Send for MutexGuardArc<T> {}
-unsafe impl<T: Sync + ?Sized> Sync for MutexGuardArc<T> {}
 
-impl<T: ?Sized> MutexGuardArc<T> {
-    /// Returns a reference to the mutex a guard came from.
+    /// Acquires the mutex, blocking the current thread until it is available.
+    ///
+    /// Blocked threads wait in the same queue as tasks awaiting [`lock()`][`Mutex::lock()`] and take
+    /// turns with them fairly. This method must not be called from async code.
     ///
     /// # Examples
     ///
     /// ```
-    /// # futures_lite::future::block_on(async {
-    /// use async_lock::{Mutex, MutexGuardArc};
-    /// use std::sync::Arc;
+    /// use async_lock::Mutex;
     ///
-    /// let mutex = Arc::new(Mutex::new(10i32));
-    /// let guard = mutex.lock_arc().await;
-    /// dbg!(MutexGuardArc::source(&guard));
-    /// # })
+    /// let mutex = Mutex::new(10);
+    /// let guard = mutex.lock_blocking();
+    /// assert_eq!(*guard, 10);
     /// ```
-    pub fn source(guard: &MutexGuardArc<T>) -> &Arc<Mutex<T>> {
-        &guard.0
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    #[track_caller]
+    pub fn lock_blocking(&self) -> MutexGuard<'_, T> {
+        crate::blocking::block_on(self.lock())
     }
-}
 
+    /// Acquires the mutex, blocking the current thread for at most `timeout`.
+    ///
+    /// Returns a guard that releases the mutex when dropped, or [`None`] if the mutex did not
+    /// become available in time. Like [`lock_blocking()`][`Mutex::lock_blocking()`], the thread
+    /// waits in the same queue as async tasks. This method must not be called from async code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Mutex;
+    /// use std::time::Duration;
+    ///
+    /// let mutex = Mutex::new(10);
+    /// let guard = mutex.lock_blocking();
+    /// assert!(mutex.try_lock_for(Duration::from_millis(10)).is_none());
+    ///
+    /// drop(guard);
+    /// assert_eq!(*mutex.try_lock_for(Duration::from_millis(10)).unwrap(), 10);
+    /// ```
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    #[track_caller]
+    pub fn try_lock_for(&self, timeout: Duration) -> Option<MutexGuard<'_, T>> {
+        crate::blocking::block_on_timeout(self.lock(), timeout)
+    }
+
+    /// Acquires the mutex, returning the lock operation as a boxed future.
+    ///
+    /// This is useful in trait objects and `async_trait` interfaces, which cannot return
+    /// `impl Future`. See also [`DynLock`][`crate::DynLock`], which erases the lock type as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{BoxLockFuture, Mutex, MutexGuard};
+    ///
+    /// trait Store {
+    ///     fn state(&self) -> BoxLockFuture<'_, MutexGuard<'_, u32>>;
+    /// }
+    ///
+    /// struct Memory(Mutex<u32>);
+    ///
+    /// impl Store for Memory {
+    ///     fn state(&self) -> BoxLockFuture<'_, MutexGuard<'_, u32>> {
+    ///         self.0.lock_boxed()
+    ///     }
+    /// }
+    ///
+    /// let store: Box<dyn Store> = Box::new(Memory(Mutex::new(1)));
+    /// assert_eq!(*store.state().await, 1);
+    /// # })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn lock_boxed(&self) -> crate::BoxLockFuture<'_, MutexGuard<'_, T>>
+    where
+        T: Send,
+    {
+        Box::pin(self.lock())
+    }
+
+    /// Acquires the mutex, runs `f` on the data, and releases the mutex again.
+    ///
+    /// This keeps short critical sections to a single expression, and the guard can never be held
+    /// longer than `f` runs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Mutex;
+    ///
+    /// let mutex = Mutex::new(vec![1, 2]);
+    /// let len = mutex.with_lock(|v| {
+    ///     v.push(3);
+    ///     v.len()
+    /// })
+    /// .await;
+    /// assert_eq!(len, 3);
+    /// # })
+    /// ```
+    ///
+    /// Inside a stream pipeline:
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Mutex;
+    /// use futures_lite::stream::{self, StreamExt};
+    ///
+    /// let total = Mutex::new(0);
+    /// let running: Vec<i32> = stream::iter(1..=3)
+    ///     .then(|n| total.with_lock(move |t| {
+    ///         *t += n;
+    ///         *t
+    ///     }))
+    ///     .collect()
+    ///     .await;
+    /// assert_eq!(running, [1, 3, 6]);
+    /// # })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn with_lock<'a, R>(
+        &'a self,
+        f: impl FnOnce(&mut T) -> R + 'a,
+    ) -> impl Future<Output = R> + 'a {
+        let lock = self.lock();
+        async move { f(&mut *lock.await) }
+    }
+
+    /// Acquires the mutex, runs the async closure `f` on the data, and releases the mutex again.
+    ///
+    /// The mutex stays locked until the future returned by `f` completes. Like
+    /// [`with_lock()`][`Mutex::with_lock()`], this ties the critical section to `f`, so the guard
+    /// cannot be held past it by accident.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Mutex;
+    /// use futures_lite::future;
+    ///
+    /// let log = Mutex::new(Vec::new());
+    /// let len = log
+    ///     .with_lock_async(async |log| {
+    ///         log.push("start");
+    ///         future::yield_now().await;
+    ///         log.push("end");
+    ///         log.len()
+    ///     })
+    ///     .await;
+    /// assert_eq!(len, 2);
+    /// # })
+    /// ```
+    #[cfg(feature = "async-closure")]
+    #[inline]
+    #[track_caller]
+    pub fn with_lock_async<'a, R>(
+        &'a self,
+        f: impl AsyncFnOnce(&mut T) -> R + 'a,
+    ) -> impl Future<Output = R> + 'a {
+        let lock = self.lock();
+        async move {
+            let mut guard = lock.await;
+            f(&mut guard).await
+        }
+    }
+
+    /// Acquires the mutex, replaces the data with `value`, and returns the old data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Mutex;
+    ///
+    /// let mutex = Mutex::new(1);
+    /// assert_eq!(mutex.replace(2).await, 1);
+    /// assert_eq!(*mutex.lock().await, 2);
+    /// # })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn replace(&self, value: T) -> impl Future<Output = T> + '_
+    where
+        T: Sized,
+    {
+        self.with_lock(|data| mem::replace(data, value))
+    }
+
+    /// Acquires the mutex, takes the data, and leaves [`Default::default()`] in its place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Mutex;
+    ///
+    /// let batch = Mutex::new(vec![1, 2]);
+    /// assert_eq!(batch.take().await, [1, 2]);
+    /// assert!(batch.lock().await.is_empty());
+    /// # })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn take(&self) -> impl Future<Output = T> + '_
+    where
+        T: Default,
+    {
+        self.with_lock(mem::take)
+    }
+
+    /// Acquires both mutexes and swaps their data.
+    ///
+    /// The mutexes are acquired with [`lock_all()`][`crate::lock_all()`], so two tasks swapping
+    /// the same mutexes in opposite orders cannot deadlock. Swapping a mutex with itself does
+    /// nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Mutex;
+    ///
+    /// let (a, b) = (Mutex::new(1), Mutex::new(2));
+    /// a.swap(&b).await;
+    /// assert_eq!((*a.lock().await, *b.lock().await), (2, 1));
+    /// # })
+    /// ```
+    pub async fn swap(&self, other: &Mutex<T>)
+    where
+        T: Sized,
+    {
+        if ptr::eq(self, other) {
+            return;
+        }
+        let (mut a, mut b) = crate::lock_all((self, other)).await;
+        mem::swap(&mut *a, &mut *b);
+    }
+
+    /// Streams clones of the items in a locked collection, holding the lock only while copying
+    /// them out.
+    ///
+    /// The mutex is acquired once per chunk of up to `chunk_size` items, so long scans do not
+    /// block other tasks for the whole iteration. Items are read by position: if the collection is
+    /// modified between two chunks, the stream continues at the same index, so items may be
+    /// skipped or seen twice.
+    ///
+    /// The stream is not [`Unpin`], so it has to be pinned before calling methods such as
+    /// `StreamExt::next()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Mutex;
+    /// use futures_lite::StreamExt;
+    /// use std::pin::pin;
+    ///
+    /// let list = Mutex::new(vec![1, 2, 3]);
+    /// let mut items = pin!(list.stream_items(2));
+    ///
+    /// assert_eq!(items.next().await, Some(1));
+    /// // The lock is free between chunks.
+    /// list.lock().await.push(4);
+    /// assert_eq!(items.collect::<Vec<_>>().await, [2, 3, 4]);
+    /// # })
+    /// ```
+    #[cfg(feature = "stream")]
+    #[track_caller]
+    pub fn stream_items<'a, E: Clone + 'a>(
+        &'a self,
+        chunk_size: usize,
+    ) -> impl Stream<Item = E> + 'a
+    where
+        T: AsRef<[E]>,
+    {
+        let location = Location::caller();
+        crate::items::Items::new(move || self.lock_at(location), chunk_size)
+    }
+
+    /// Attempts to acquire the mutex.
+    ///
+    /// If the mutex could not be acquired at this time, then [`None`] is returned. Otherwise, a
+    /// guard is returned that releases the mutex when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Mutex;
+    ///
+    /// let mutex = Mutex::new(10);
+    /// if let Some(guard) = mutex.try_lock() {
+    ///     assert_eq!(*guard, 10);
+    /// }
+    /// # ;
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.try_lock_at(Location::caller())
+    }
+
+    /// Attempts to acquire the mutex on behalf of the code at `location`.
+    #[inline]
+    fn try_lock_at(&self, location: &'static Location<'static>) -> Option<MutexGuard<'_, T>> {
+        if self.raw.try_lock() {
+            Some(MutexGuard(self, trace::acquired(self.target(), location)))
+        } else {
+            None
+        }
+    }
+
+    /// Runs `f` on the data if the mutex can be acquired without waiting.
+    ///
+    /// Returns [`None`] without calling `f` if the mutex is locked. The mutex is released as soon
+    /// as `f` returns, so the guard cannot be held by accident.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Mutex;
+    ///
+    /// let hits = Mutex::new(0);
+    /// assert_eq!(hits.try_with(|n| { *n += 1; *n }), Some(1));
+    ///
+    /// let _guard = hits.try_lock().unwrap();
+    /// assert_eq!(hits.try_with(|n| *n += 1), None);
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn try_with<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        let mut guard = self.try_lock_at(Location::caller())?;
+        Some(f(&mut guard))
+    }
+
+    /// Attempts to acquire the mutex, retrying up to `max_spins` times before giving up.
+    ///
+    /// This is a middle ground between [`try_lock()`][`Mutex::try_lock()`] and
+    /// [`lock()`][`Mutex::lock()`] for latency-critical polling loops: if the mutex is held only
+    /// briefly, spinning often acquires it sooner than waiting would. It never yields to the
+    /// executor, and gives up early if other lock operations have been starving for the mutex.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Mutex;
+    ///
+    /// let mutex = Mutex::new(10);
+    /// assert_eq!(*mutex.try_lock_spin(100).unwrap(), 10);
+    ///
+    /// let guard = mutex.try_lock().unwrap();
+    /// assert!(mutex.try_lock_spin(100).is_none());
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn try_lock_spin(&self, max_spins: u32) -> Option<MutexGuard<'_, T>> {
+        if self.raw.try_lock_spin(max_spins) {
+            Some(MutexGuard(
+                self,
+                trace::acquired(self.target(), Location::caller()),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the mutex mutably, no actual locking takes place -- the mutable
+    /// borrow statically guarantees the mutex is not already acquired.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Mutex;
+    ///
+    /// let mut mutex = Mutex::new(0);
+    /// *mutex.get_mut() = 10;
+    /// assert_eq!(*mutex.lock().await, 10);
+    /// # })
+    /// ```
+    pub fn get_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.data.get() }
+    }
+
+    /// Returns a raw pointer to the underlying data.
+    ///
+    /// This is useful for low-level integrations such as FFI or heap-profiling tools that need the
+    /// address of the data without locking the mutex. The pointer is valid for as long as the
+    /// mutex is, but dereferencing it is unsafe: it may only be read or written through while the
+    /// calling code holds the lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Mutex;
+    ///
+    /// let mutex = Mutex::new(10);
+    /// let guard = mutex.try_lock().unwrap();
+    /// assert_eq!(mutex.data_ptr() as *const i32, &*guard as *const i32);
+    /// ```
+    pub fn data_ptr(&self) -> *mut T {
+        self.data.get()
+    }
+
+    /// Returns the name of this mutex, if it was created with [`Mutex::new_named()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Mutex;
+    ///
+    /// let mutex = Mutex::new(0);
+    /// assert_eq!(mutex.name(), None);
+    /// ```
+    pub fn name(&self) -> Option<&'static str> {
+        self.hooks.name()
+    }
+
+    /// Returns the priority ceiling of this mutex, if it has one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Mutex;
+    ///
+    /// let mutex = Mutex::new(0);
+    /// assert_eq!(mutex.priority_ceiling(), None);
+    /// ```
+    #[cfg(feature = "priority-ceiling")]
+    pub fn priority_ceiling(&self) -> Option<u32> {
+        self.hooks.ceiling()
+    }
+
+    /// Returns `true` if the mutex is locked.
+    ///
+    /// Unlike [`try_lock()`][`Mutex::try_lock()`], this never takes the mutex, so it does not
+    /// disturb the tasks using it. By the time it returns, the mutex may already have been locked
+    /// or released by another task.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Mutex;
+    ///
+    /// let mutex = Mutex::new(());
+    /// assert!(!mutex.is_locked());
+    ///
+    /// let guard = mutex.try_lock().unwrap();
+    /// assert!(mutex.is_locked());
+    /// ```
+    pub fn is_locked(&self) -> bool {
+        self.raw.is_locked()
+    }
+
+    /// Releases the mutex without a guard.
+    ///
+    /// This is for FFI code that locks and unlocks the mutex in separate calls, such as a C
+    /// callback that cannot carry the guard, after the guard was consumed with
+    /// [`MutexGuard::into_raw()`] or [`mem::forget()`]. It is the same as rebuilding the guard
+    /// with [`MutexGuard::from_raw()`] and dropping it.
+    ///
+    /// Under a priority ceiling, this restores the priority only if the guard was consumed with
+    /// [`MutexGuard::into_raw()`]. A guard passed to [`mem::forget()`] keeps its holder at the
+    /// ceiling.
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked by a guard that was consumed without being dropped, and no guard
+    /// may still reach the data, such as a reference returned by [`MutexGuard::leak()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Mutex;
+    ///
+    /// let mutex = Mutex::new(());
+    /// std::mem::forget(mutex.try_lock().unwrap());
+    /// assert!(mutex.is_locked());
+    ///
+    /// unsafe { mutex.force_unlock() };
+    /// assert!(!mutex.is_locked());
+    /// ```
+    pub unsafe fn force_unlock(&self) {
+        drop(MutexGuard::from_raw(self));
+    }
+
+    /// Returns the approximate number of lock operations waiting for the mutex.
+    ///
+    /// A lock operation that was just woken up may briefly not be counted while it tries to take
+    /// the mutex, so the count is only suited for health checks and dashboards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Mutex;
+    /// use futures_lite::future;
+    ///
+    /// let mutex = Mutex::new(());
+    /// let guard = mutex.lock().await;
+    /// assert_eq!(mutex.waiter_count(), 0);
+    ///
+    /// let mut waiter = mutex.lock();
+    /// assert!(future::poll_once(&mut waiter).await.is_none());
+    /// assert_eq!(mutex.waiter_count(), 1);
+    /// # })
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn waiter_count(&self) -> usize {
+        self.raw.waiter_count()
+    }
+
+    /// Returns a [`SubLock`] that locks this mutex but only gives access to part of the data.
+    ///
+    /// `project` picks the part, typically a field. It is called every time the sub-lock is
+    /// locked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Mutex;
+    ///
+    /// let state = Mutex::new((String::new(), 0));
+    /// let name = state.project(|s| &mut s.0);
+    ///
+    /// name.lock().await.push_str("ferris");
+    /// assert!(state.try_lock().is_some());
+    /// assert_eq!(state.lock().await.0, "ferris");
+    /// # })
+    /// ```
+    pub fn project<U: ?Sized>(&self, project: fn(&mut T) -> &mut U) -> SubLock<'_, T, U> {
+        SubLock::new(self, project)
+    }
+
+    /// Returns the lock operations currently waiting for this mutex.
+    ///
+    /// Waiters are listed in the order they started waiting. Each one is identified by an opaque
+    /// ID and carries the location of the call, when it started waiting, and its
+    /// [label][`crate::diagnostics::label()`], if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{diagnostics, Mutex};
+    /// use futures_lite::future;
+    ///
+    /// let m = Mutex::new(());
+    /// let _guard = m.try_lock().unwrap();
+    ///
+    /// let mut waiter = Box::pin(diagnostics::label("worker", m.lock()));
+    /// assert!(future::poll_once(&mut waiter).await.is_none());
+    ///
+    /// let waiters = m.waiters();
+    /// assert_eq!(waiters.len(), 1);
+    /// assert_eq!(waiters[0].label(), Some("worker"));
+    /// # })
+    /// ```
+    #[cfg(feature = "registry")]
+    pub fn waiters(&self) -> Vec<WaiterInfo> {
+        self.target().waiters()
+    }
+
+    /// Returns where this mutex was acquired, if it is locked.
+    ///
+    /// The [`HolderInfo`] carries the location of the call that acquired the mutex, when it was
+    /// acquired, and its [label][`crate::diagnostics::label()`], if any. The [`Debug`][`fmt::Debug`]
+    /// output of a locked mutex also includes the location.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Mutex;
+    ///
+    /// let m = Mutex::new(());
+    /// assert!(m.holder_info().is_none());
+    ///
+    /// let guard = m.try_lock().unwrap();
+    /// let holder = m.holder_info().unwrap();
+    /// assert_eq!(holder.location().line(), line!() - 2);
+    /// ```
+    #[cfg(feature = "registry")]
+    pub fn holder_info(&self) -> Option<HolderInfo> {
+        self.target().holder()
+    }
+
+    /// Subscribes to the acquisitions and releases of this mutex.
+    ///
+    /// The returned stream yields a [`LockEvent`][`crate::diagnostics::LockEvent`] every time
+    /// the mutex is acquired or released from now on, and ends once the mutex is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::diagnostics::LockAction;
+    /// use async_lock::Mutex;
+    /// use futures_lite::StreamExt;
+    ///
+    /// let m = Mutex::new(0);
+    /// let mut events = m.events();
+    ///
+    /// drop(m.lock().await);
+    /// assert_eq!(events.next().await.unwrap().action(), LockAction::Acquired);
+    /// assert_eq!(events.next().await.unwrap().action(), LockAction::Released);
+    /// # })
+    /// ```
+    #[cfg(feature = "events")]
+    pub fn events(&self) -> LockEvents {
+        self.target().events()
+    }
+
+    /// Identifies this mutex in instrumentation.
+    fn target(&self) -> trace::Target<'_> {
+        trace::Target::new("Mutex", "lock", self, &self.hooks)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized> Mutex<T> {
+    /// Acquires the mutex and clones a reference to it.
+    ///
+    /// Returns an owned guard that releases the mutex when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Mutex;
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(Mutex::new(10));
+    /// let guard = mutex.lock_arc().await;
+    /// assert_eq!(*guard, 10);
+    /// # })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn lock_arc(self: &Arc<Self>) -> impl Future<Output = MutexGuardArc<T>> + '_ {
+        self.lock_arc_at(Location::caller())
+    }
+
+    /// Acquires the mutex on behalf of a caller at `location`.
+    #[inline]
+    fn lock_arc_at(
+        self: &Arc<Self>,
+        location: &'static Location<'static>,
+    ) -> impl Future<Output = MutexGuardArc<T>> + '_ {
+        trace::instrument(self.target(), "Mutex::lock_arc", async move {
+            crate::coop::consume_budget(&**self).await;
+            if let Some(guard) = self.try_lock_arc_at(location) {
+                return guard;
+            }
+            let wait = trace::wait(self.target(), location);
+            self.raw.acquire_slow_with(self.fairness).await;
+            MutexGuardArc(self.clone(), wait.acquired())
+        })
+    }
+
+    /// Acquires the mutex and clones a reference to it, blocking the current thread until it is
+    /// available.
+    ///
+    /// This is the blocking version of [`lock_arc()`][`Mutex::lock_arc()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Mutex;
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(Mutex::new(10));
+    /// let guard = mutex.lock_arc_blocking();
+    /// assert_eq!(*guard, 10);
+    /// ```
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    #[track_caller]
+    pub fn lock_arc_blocking(self: &Arc<Self>) -> MutexGuardArc<T> {
+        crate::blocking::block_on(self.lock_arc())
+    }
+
+    /// Acquires the mutex and clones a reference to it, returning the lock operation as a boxed
+    /// future.
+    ///
+    /// The returned future owns a reference to the mutex, so it is `'static` if `T` is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{BoxLockFuture, Mutex, MutexGuardArc};
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(Mutex::new(10));
+    /// let lock: BoxLockFuture<'static, MutexGuardArc<i32>> = mutex.lock_arc_boxed();
+    /// assert_eq!(*lock.await, 10);
+    /// # })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn lock_arc_boxed<'a>(self: &Arc<Self>) -> crate::BoxLockFuture<'a, MutexGuardArc<T>>
+    where
+        T: Send + 'a,
+    {
+        let location = Location::caller();
+        let mutex = self.clone();
+        Box::pin(async move { mutex.lock_arc_at(location).await })
+    }
+
+    /// Acquires the mutex, runs `f` on the data, and releases the mutex again, with a future that
+    /// owns a reference to the mutex.
+    ///
+    /// Unlike [`with_lock()`][`Mutex::with_lock()`], the returned future does not borrow the
+    /// mutex, so it can be spawned or returned from a `'static` stream combinator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Mutex;
+    /// use futures_lite::stream::{self, Stream, StreamExt};
+    /// use std::sync::Arc;
+    ///
+    /// fn log_lines(log: &Arc<Mutex<Vec<String>>>) -> impl Stream<Item = usize> + 'static {
+    ///     let log = log.clone();
+    ///     stream::iter(["a", "b"]).then(move |line| {
+    ///         log.with_lock_arc(move |log| {
+    ///             log.push(line.to_string());
+    ///             log.len()
+    ///         })
+    ///     })
+    /// }
+    ///
+    /// let log = Arc::new(Mutex::new(Vec::new()));
+    /// assert_eq!(log_lines(&log).collect::<Vec<_>>().await, [1, 2]);
+    /// # })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn with_lock_arc<R>(
+        self: &Arc<Self>,
+        f: impl FnOnce(&mut T) -> R,
+    ) -> impl Future<Output = R> {
+        let location = Location::caller();
+        let mutex = self.clone();
+        async move { f(&mut *mutex.lock_arc_at(location).await) }
+    }
+
+    /// Attempts to acquire the mutex and clone a reference to it.
+    ///
+    /// If the mutex could not be acquired at this time, then [`None`] is returned. Otherwise, an
+    /// owned guard is returned that releases the mutex when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Mutex;
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(Mutex::new(10));
+    /// if let Some(guard) = mutex.try_lock() {
+    ///     assert_eq!(*guard, 10);
+    /// }
+    /// # ;
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn try_lock_arc(self: &Arc<Self>) -> Option<MutexGuardArc<T>> {
+        self.try_lock_arc_at(Location::caller())
+    }
+
+    /// Attempts to acquire the mutex on behalf of the code at `location`.
+    #[inline]
+    fn try_lock_arc_at(
+        self: &Arc<Self>,
+        location: &'static Location<'static>,
+    ) -> Option<MutexGuardArc<T>> {
+        if self.raw.try_lock() {
+            let held = trace::acquired(self.target(), location);
+            Some(MutexGuardArc(self.clone(), held))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for Mutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /// Where the lock was acquired, if known.
+        struct Locked(Option<&'static Location<'static>>);
+        impl fmt::Debug for Locked {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.0 {
+                    Some(location) => write!(f, "<locked at {}>", location),
+                    None => f.write_str("<locked>"),
+                }
+            }
+        }
+
+        #[cfg(feature = "registry")]
+        let holder = self.holder_info().map(|holder| holder.location());
+        #[cfg(not(feature = "registry"))]
+        let holder = None;
+
+        let mut d = f.debug_struct("Mutex");
+        if let Some(name) = self.name() {
+            d.field("name", &name);
+        }
+        match self.try_lock() {
+            None => d.field("data", &Locked(holder)).finish(),
+            Some(guard) => d.field("data", &&*guard).finish(),
+        }
+    }
+}
+
+impl<T> From<T> for Mutex<T> {
+    fn from(val: T) -> Mutex<T> {
+        Mutex::new(val)
+    }
+}
+
+impl<T: Default> Default for Mutex<T> {
+    fn default() -> Mutex<T> {
+        Mutex::new(Default::default())
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, T: arbitrary::Arbitrary<'a>> arbitrary::Arbitrary<'a> for Mutex<T> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        T::arbitrary(u).map(Mutex::new)
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        T::size_hint(depth)
+    }
+}
+
+/// Serializes the data, failing if the mutex is locked.
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize + ?Sized> serde::Serialize for Mutex<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.try_lock() {
+            Some(guard) => guard.serialize(serializer),
+            None => Err(serde::ser::Error::custom("the mutex is locked")),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Mutex<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Mutex::new)
+    }
+}
+
+/// The future returned by [`Mutex::lock()`].
+///
+/// Unlike an `async fn` future, this type can be named, so it can be stored in a struct and polled
+/// by hand. It is [`Unpin`], and with the `stream` feature it implements `FusedFuture`, so it can
+/// be used in `select!` loops.
+///
+/// Dropping it before it completes cancels the lock operation, which makes [`Mutex::lock()`] safe
+/// to use in `select!`. If the operation was already woken up to take the mutex, the wakeup is
+/// passed on to the next waiting lock operation, so no task is left waiting for a mutex that is
+/// free.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::{LockFuture, Mutex};
+/// use futures_lite::future;
+///
+/// struct Waiting<'a> {
+///     lock: LockFuture<'a, i32>,
+/// }
+///
+/// let mutex = Mutex::new(1);
+/// let guard = mutex.lock().await;
+///
+/// let mut waiting = Waiting { lock: mutex.lock() };
+/// assert!(future::poll_once(&mut waiting.lock).await.is_none());
+///
+/// drop(guard);
+/// assert_eq!(*waiting.lock.await, 1);
+/// # })
+/// ```
+pub struct LockFuture<'a, T: ?Sized> {
+    inner: trace::Instrumented<Lock<'a, T>>,
+
+    /// Whether the future has completed.
+    terminated: bool,
+}
+
+impl<'a, T: ?Sized> Future for LockFuture<'a, T> {
+    type Output = MutexGuard<'a, T>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<MutexGuard<'a, T>> {
+        let guard = ready!(Pin::new(&mut self.inner).poll(cx));
+        self.terminated = true;
+        Poll::Ready(guard)
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T: ?Sized> FusedFuture for LockFuture<'_, T> {
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for LockFuture<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LockFuture")
+            .field("terminated", &self.terminated)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The lock operation inside a [`LockFuture`].
+struct Lock<'a, T: ?Sized> {
+    mutex: &'a Mutex<T>,
+    location: &'static Location<'static>,
+    fairness: FairnessPolicy,
+    spins: u32,
+    state: LockState<'a>,
+}
+
+enum LockState<'a> {
+    /// Consuming cooperative scheduling budget before trying to lock.
+    Budget(crate::coop::ConsumeBudget),
+
+    /// Waiting for the mutex to be released.
+    Acquire(AcquireSlow<'a>, trace::Wait<'a>),
+
+    /// The mutex was locked.
+    Done,
+}
+
+impl<'a, T: ?Sized> Future for Lock<'a, T> {
+    type Output = MutexGuard<'a, T>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<MutexGuard<'a, T>> {
+        let this = &mut *self;
+        let mutex = this.mutex;
+
+        loop {
+            match &mut this.state {
+                LockState::Budget(budget) => {
+                    ready!(Pin::new(budget).poll(cx));
+                    if mutex.raw.try_lock_spin(this.spins) {
+                        this.state = LockState::Done;
+                        let held = trace::acquired(mutex.target(), this.location);
+                        return Poll::Ready(MutexGuard(mutex, held));
+                    }
+                    let wait = trace::wait(mutex.target(), this.location);
+                    this.state =
+                        LockState::Acquire(mutex.raw.acquire_slow_with(this.fairness), wait);
+                }
+                LockState::Acquire(acquire, _) => {
+                    ready!(Pin::new(acquire).poll(cx));
+                    match mem::replace(&mut this.state, LockState::Done) {
+                        LockState::Acquire(_, wait) => {
+                            return Poll::Ready(MutexGuard(mutex, wait.acquired()))
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+                LockState::Done => panic!("`LockFuture` polled after completion"),
+            }
+        }
+    }
+}
+
+/// When the lock operations of a [`Mutex`] switch to fair locking.
+///
+/// While a lock operation is waiting normally, newer lock operations may take the mutex before it,
+/// which keeps throughput high. Once it is starved, it is queued in order and newer lock
+/// operations, including [`Mutex::try_lock()`], have to wait behind it.
+///
+/// None of the policies changes how the mutex is released: dropping a guard unlocks the mutex and
+/// wakes the next lock operation, which then has to take it. Only [`MutexGuard::unlock_fair()`]
+/// hands the mutex over directly, without unlocking it in between.
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::FairnessPolicy;
+/// use std::time::Duration;
+///
+/// assert_eq!(
+///     FairnessPolicy::default(),
+///     FairnessPolicy::StarvationThreshold(Duration::from_micros(500)),
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FairnessPolicy {
+    /// Lock operations become starved after losing the mutex to newer lock operations for longer
+    /// than this duration.
+    ///
+    /// The time is measured from the first time a woken lock operation finds the mutex taken
+    /// again, so lock operations that get the mutex on their first wakeup never read the clock.
+    ///
+    /// This is the default, with a threshold of 0.5 milliseconds. Without the `std` feature, the
+    /// waiting time can only be measured if a [`clock`][`crate::clock`] is installed.
+    StarvationThreshold(Duration),
+
+    /// Lock operations are starved as soon as they have to wait, so newer lock operations never
+    /// take the mutex before them.
+    Fifo,
+
+    /// Lock operations are never starved by waiting, so newer lock operations can always take
+    /// the mutex first.
+    Barging,
+}
+
+impl FairnessPolicy {
+    /// The default policy, usable in constant contexts.
+    const DEFAULT: FairnessPolicy = FairnessPolicy::StarvationThreshold(Duration::from_micros(500));
+}
+
+impl Default for FairnessPolicy {
+    fn default() -> FairnessPolicy {
+        FairnessPolicy::DEFAULT
+    }
+}
+
+/// An error returned when a lock operation of a [`Mutex`] was cancelled.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::Mutex;
+///
+/// let mutex = Mutex::new(());
+/// let _guard = mutex.lock().await;
+/// let err = mutex.lock_cancellable(async {}).await.unwrap_err();
+/// assert_eq!(err.to_string(), "lock operation was cancelled");
+/// # });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockCancelledError(());
+
+impl fmt::Display for LockCancelledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("lock operation was cancelled")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LockCancelledError {}
+
+/// A guard that releases the mutex when dropped.
+pub struct MutexGuard<'a, T: ?Sized>(&'a Mutex<T>, trace::Held);
+
+unsafe impl<T: Send + ?Sized> Send for MutexGuard<'_, T> {}
+unsafe impl<T: Sync + ?Sized> Sync for MutexGuard<'_, T> {}
+
+impl<'a, T: ?Sized> MutexGuard<'a, T> {
+    /// Returns a reference to the mutex a guard came from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{Mutex, MutexGuard};
+    ///
+    /// let mutex = Mutex::new(10i32);
+    /// let guard = mutex.lock().await;
+    /// dbg!(MutexGuard::source(&guard));
+    /// # })
+    /// ```
+    pub fn source(guard: &MutexGuard<'a, T>) -> &'a Mutex<T> {
+        guard.0
+    }
+
+    /// Consumes the guard without releasing the mutex, returning the mutex it came from.
+    ///
+    /// The mutex stays locked until the guard is rebuilt with [`MutexGuard::from_raw()`] and
+    /// dropped. This lets a lock be held across an FFI boundary, such as a C callback that
+    /// finishes the critical section later.
+    ///
+    /// On a mutex with a priority ceiling, the priority to restore is kept in the mutex meanwhile,
+    /// and dropping the rebuilt guard restores it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::{Mutex, MutexGuard};
+    ///
+    /// let mutex = Mutex::new(10i32);
+    /// let raw = MutexGuard::into_raw(mutex.try_lock().unwrap());
+    /// assert!(mutex.try_lock().is_none());
+    ///
+    /// let guard = unsafe { MutexGuard::from_raw(raw) };
+    /// assert_eq!(*guard, 10);
+    /// drop(guard);
+    /// assert!(mutex.try_lock().is_some());
+    /// ```
+    pub fn into_raw(guard: MutexGuard<'a, T>) -> &'a Mutex<T> {
+        let mutex = guard.0;
+        trace::parked(mutex.target(), guard.1);
+        mem::forget(guard);
+        mutex
+    }
+
+    /// Rebuilds a guard from a mutex that was locked by a guard passed to
+    /// [`MutexGuard::into_raw()`].
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked by a guard that was consumed with [`MutexGuard::into_raw()`] or
+    /// [`MutexGuardArc::into_raw()`], and each such guard may be rebuilt at most once.
+    pub unsafe fn from_raw(mutex: &'a Mutex<T>) -> MutexGuard<'a, T> {
+        MutexGuard(mutex, trace::Held::unparked(mutex.target()))
+    }
+
+    /// Keeps the mutex locked forever, returning a reference to the data that lives as long as the
+    /// mutex.
+    ///
+    /// This seals the data after an initialization phase: the returned reference is the only way
+    /// to reach it from now on, and every later lock operation waits forever.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::{Mutex, MutexGuard};
+    ///
+    /// static CONFIG: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+    ///
+    /// let mut config = CONFIG.try_lock().unwrap();
+    /// config.push("verbose");
+    ///
+    /// let config: &'static mut Vec<&str> = MutexGuard::leak(config);
+    /// assert_eq!(config, &["verbose"]);
+    /// assert!(CONFIG.try_lock().is_none());
+    /// ```
+    pub fn leak(guard: MutexGuard<'a, T>) -> &'a mut T {
+        let mutex = MutexGuard::into_raw(guard);
+
+        // SAFETY: The mutex is never unlocked again, so no other guard can reach the data.
+        unsafe { &mut *mutex.data.get() }
+    }
+
+    /// Makes a guard for a part of the locked data.
+    ///
+    /// The mutex stays locked until the returned guard is dropped. This lets an API hand out a
+    /// guard for one field without exposing the whole locked type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{Mutex, MutexGuard};
+    ///
+    /// let mutex = Mutex::new((1, String::new()));
+    ///
+    /// let mut name = MutexGuard::map(mutex.lock().await, |pair| &mut pair.1);
+    /// name.push_str("one");
+    /// assert!(mutex.try_lock().is_none());
+    /// drop(name);
+    ///
+    /// assert_eq!(mutex.lock().await.1, "one");
+    /// # })
+    /// ```
+    pub fn map<U: ?Sized>(
+        mut guard: Self,
+        f: impl FnOnce(&mut T) -> &mut U,
+    ) -> MappedMutexGuard<'a, T, U> {
+        // The data stays in place inside the mutex while the guard is alive.
+        let value = f(&mut *guard) as *mut U;
+        MappedMutexGuard {
+            _guard: guard,
+            value,
+        }
+    }
+
+    /// Makes a guard for a part of the locked data, if there is one.
+    ///
+    /// Returns the original guard if `f` returns [`None`], so the caller can fall back to the
+    /// whole data, for example when an enum holds a different variant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{Mutex, MutexGuard};
+    ///
+    /// let mutex = Mutex::new(Some(1));
+    ///
+    /// let mut n = MutexGuard::try_map(mutex.lock().await, |o| o.as_mut()).unwrap();
+    /// *n += 1;
+    /// drop(n);
+    ///
+    /// *mutex.lock().await = None;
+    /// let guard = MutexGuard::try_map(mutex.lock().await, |o| o.as_mut()).unwrap_err();
+    /// assert_eq!(*guard, None);
+    /// # })
+    /// ```
+    pub fn try_map<U: ?Sized>(
+        mut guard: Self,
+        f: impl FnOnce(&mut T) -> Option<&mut U>,
+    ) -> Result<MappedMutexGuard<'a, T, U>, Self> {
+        match f(&mut *guard) {
+            Some(value) => {
+                let value = value as *mut U;
+                Ok(MappedMutexGuard {
+                    _guard: guard,
+                    value,
+                })
+            }
+            None => Err(guard),
+        }
+    }
+}
+
+impl<'a, T: ?Sized> MutexGuard<'a, T> {
+    /// Releases the mutex by handing it directly to the longest-waiting lock operation.
+    ///
+    /// Dropping a guard makes the mutex available to whichever task takes it first, which is
+    /// usually the releasing task if it locks again right away. This instead keeps the mutex
+    /// locked and passes it on, so the waiting task is guaranteed to get it. If no lock operation
+    /// is waiting, the mutex is simply released.
+    ///
+    /// The mutex is also simply released if every waiting lock operation was already woken by an
+    /// earlier release and has not retaken the mutex yet. Such a lock operation can still lose the
+    /// mutex to a newer one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{Mutex, MutexGuard};
+    /// use futures_lite::future;
+    ///
+    /// let mutex = Mutex::new(0);
+    /// let guard = mutex.lock().await;
+    ///
+    /// let mut waiter = mutex.lock();
+    /// assert!(future::poll_once(&mut waiter).await.is_none());
+    ///
+    /// MutexGuard::unlock_fair(guard);
+    /// assert!(mutex.try_lock().is_none());
+    /// assert_eq!(*waiter.await, 0);
+    /// # })
+    /// ```
+    pub fn unlock_fair(guard: MutexGuard<'a, T>) {
+        let guard = mem::ManuallyDrop::new(guard);
+        trace::released(guard.0.target(), guard.1);
+        if guard.0.raw.unlock_fair() {
+            trace::handed_over(guard.0.target());
+        }
+    }
+
+    /// Releases the mutex while `fut` runs, and acquires it again before returning.
+    ///
+    /// This lets a long critical section await I/O halfway through without holding up other
+    /// tasks, and without the error-prone dance of dropping the guard and locking again by hand.
+    /// Other tasks may change the data in the meantime, so anything read before must be checked
+    /// again afterwards.
+    ///
+    /// The guard is passed by value and handed back together with the output of `fut`. If the
+    /// returned future is dropped before it completes, the mutex is simply left unlocked, as with
+    /// an unlocked guard that was dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{Mutex, MutexGuard};
+    ///
+    /// let mutex = Mutex::new(Vec::new());
+    /// let mut guard = mutex.lock().await;
+    /// guard.push(1);
+    ///
+    /// let (mut guard, n) = MutexGuard::unlocked(guard, async {
+    ///     // Other tasks can take the mutex while waiting for I/O here.
+    ///     assert!(mutex.try_lock().is_some());
+    ///     2
+    /// })
+    /// .await;
+    /// guard.push(n);
+    /// assert_eq!(*guard, [1, 2]);
+    /// # })
+    /// ```
+    #[track_caller]
+    pub fn unlocked<F: Future>(
+        guard: MutexGuard<'a, T>,
+        fut: F,
+    ) -> impl Future<Output = (MutexGuard<'a, T>, F::Output)> {
+        let location = Location::caller();
+        async move {
+            let mutex = guard.0;
+            drop(guard);
+            let output = fut.await;
+            (mutex.lock_at(location).await, output)
+        }
+    }
+}
+
+impl<T: ?Sized> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        trace::released(self.0.target(), self.1);
+        self.0.raw.unlock();
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for MutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display + ?Sized> fmt::Display for MutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.0.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.0.data.get() }
+    }
+}
+
+/// A guard for a part of the data behind a mutex, created by [`MutexGuard::map()`].
+pub struct MappedMutexGuard<'a, T: ?Sized, U: ?Sized> {
+    _guard: MutexGuard<'a, T>,
+    value: *mut U,
+}
+
+unsafe impl<T: Send + ?Sized, U: Send + ?Sized> Send for MappedMutexGuard<'_, T, U> {}
+unsafe impl<T: Sync + ?Sized, U: Sync + ?Sized> Sync for MappedMutexGuard<'_, T, U> {}
+
+impl<'a, T: ?Sized, U: ?Sized> MappedMutexGuard<'a, T, U> {
+    /// Makes a guard for a smaller part of the locked data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{MappedMutexGuard, Mutex, MutexGuard};
+    ///
+    /// let mutex = Mutex::new(((1, 2), 3));
+    /// let inner = MutexGuard::map(mutex.lock().await, |t| &mut t.0);
+    /// let mut second = MappedMutexGuard::map(inner, |t| &mut t.1);
+    /// *second = 20;
+    /// drop(second);
+    ///
+    /// assert_eq!(*mutex.lock().await, ((1, 20), 3));
+    /// # })
+    /// ```
+    pub fn map<V: ?Sized>(
+        guard: Self,
+        f: impl FnOnce(&mut U) -> &mut V,
+    ) -> MappedMutexGuard<'a, T, V> {
+        let value = f(unsafe { &mut *guard.value }) as *mut V;
+        MappedMutexGuard {
+            _guard: guard._guard,
+            value,
+        }
+    }
+}
+
+impl<T: ?Sized, U: fmt::Debug + ?Sized> fmt::Debug for MappedMutexGuard<'_, T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized, U: fmt::Display + ?Sized> fmt::Display for MappedMutexGuard<'_, T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized, U: ?Sized> Deref for MappedMutexGuard<'_, T, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        unsafe { &*self.value }
+    }
+}
+
+impl<T: ?Sized, U: ?Sized> DerefMut for MappedMutexGuard<'_, T, U> {
+    fn deref_mut(&mut self) -> &mut U {
+        unsafe { &mut *self.value }
+    }
+}
+
+/// An owned guard that releases the mutex when dropped.
+#[cfg(target_has_atomic = "ptr")]
+pub struct MutexGuardArc<T: ?Sized>(Arc<Mutex<T>>, trace::Held);
+
+#[cfg(target_has_atomic = "ptr")]
+unsafe impl<T: Send + ?Sized> Send for MutexGuardArc<T> {}
+#[cfg(target_has_atomic = "ptr")]
+unsafe impl<T: Sync + ?Sized> Sync for MutexGuardArc<T> {}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized> MutexGuardArc<T> {
+    /// Returns a reference to the mutex a guard came from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{Mutex, MutexGuardArc};
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(Mutex::new(10i32));
+    /// let guard = mutex.lock_arc().await;
+    /// dbg!(MutexGuardArc::source(&guard));
+    /// # })
+    /// ```
+    pub fn source(guard: &MutexGuardArc<T>) -> &Arc<Mutex<T>> {
+        &guard.0
+    }
+
+    /// Consumes the guard without releasing the mutex, returning a raw pointer to it.
+    ///
+    /// The pointer owns the guard's reference count, as with [`Arc::into_raw()`]. The mutex stays
+    /// locked until the guard is rebuilt with [`MutexGuardArc::from_raw()`] and dropped, so the
+    /// pointer can be passed through C code that releases the lock later.
+    ///
+    /// As with [`MutexGuard::into_raw()`], the priority to restore under a priority ceiling is
+    /// kept in the mutex until the guard is rebuilt.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{Mutex, MutexGuardArc};
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(Mutex::new(10i32));
+    /// let raw = MutexGuardArc::into_raw(mutex.lock_arc().await);
+    /// assert!(mutex.try_lock().is_none());
+    ///
+    /// let guard = unsafe { MutexGuardArc::from_raw(raw) };
+    /// assert_eq!(*guard, 10);
+    /// drop(guard);
+    /// assert!(mutex.try_lock().is_some());
+    /// # })
+    /// ```
+    pub fn into_raw(guard: MutexGuardArc<T>) -> *const Mutex<T> {
+        let guard = mem::ManuallyDrop::new(guard);
+        // SAFETY: The guard is never used again, so its `Arc` is moved out exactly once.
+        let mutex = unsafe { ptr::read(&guard.0) };
+        trace::parked(mutex.target(), guard.1);
+        Arc::into_raw(mutex)
+    }
+
+    /// Rebuilds a guard from a pointer returned by [`MutexGuardArc::into_raw()`].
+    ///
+    /// # Safety
+    ///
+    /// The pointer must come from [`MutexGuardArc::into_raw()`] and may be rebuilt at most once.
+    pub unsafe fn from_raw(ptr: *const Mutex<T>) -> MutexGuardArc<T> {
+        let mutex = Arc::from_raw(ptr);
+        let held = trace::Held::unparked(mutex.target());
+        MutexGuardArc(mutex, held)
+    }
+
+    /// Releases the mutex by handing it directly to the longest-waiting lock operation.
+    ///
+    /// This is the owned version of [`MutexGuard::unlock_fair()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{Mutex, MutexGuardArc};
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(Mutex::new(10i32));
+    /// MutexGuardArc::unlock_fair(mutex.lock_arc().await);
+    /// assert!(mutex.try_lock().is_some());
+    /// # })
+    /// ```
+    pub fn unlock_fair(guard: MutexGuardArc<T>) {
+        let guard = mem::ManuallyDrop::new(guard);
+        // SAFETY: The guard is never used again, so its `Arc` is moved out exactly once.
+        let mutex = unsafe { ptr::read(&guard.0) };
+        trace::released(mutex.target(), guard.1);
+        if mutex.raw.unlock_fair() {
+            trace::handed_over(mutex.target());
+        }
+    }
+
+    /// Makes an owned guard for a part of the locked data.
+    ///
+    /// This is the owned version of [`MutexGuard::map()`]. The returned guard keeps the mutex
+    /// alive and locked, so a spawned task can own access to just one part of shared state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{MappedMutexGuardArc, Mutex, MutexGuardArc};
+    /// use std::sync::Arc;
+    ///
+    /// let state = Arc::new(Mutex::new((0, String::new())));
+    ///
+    /// let mut name: MappedMutexGuardArc<_, String> =
+    ///     MutexGuardArc::map(state.lock_arc().await, |state| &mut state.1);
+    /// std::thread::spawn(move || name.push_str("one")).join().unwrap();
+    ///
+    /// assert_eq!(state.lock().await.1, "one");
+    /// # })
+    /// ```
+    pub fn map<U: ?Sized>(
+        mut guard: MutexGuardArc<T>,
+        f: impl FnOnce(&mut T) -> &mut U,
+    ) -> MappedMutexGuardArc<T, U> {
+        // The data stays in place inside the mutex while the guard is alive.
+        let value = f(&mut *guard) as *mut U;
+        MappedMutexGuardArc {
+            _guard: guard,
+            value,
+        }
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
 impl<T: ?Sized> Drop for MutexGuardArc<T> {
     fn drop(&mut self) {
-        // Remove the last bit and notify a waiting lock operation.
-        self.0.state.fetch_sub(1, Ordering::Release);
-        self.0.lock_ops.notify(1);
+        trace::released(self.0.target(), self.1);
+        self.0.raw.unlock();
     }
 }
 
+#[cfg(target_has_atomic = "ptr")]
 impl<T: fmt::Debug + ?Sized> fmt::Debug for MutexGuardArc<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Debug::fmt(&**self, f)
     }
 }
 
+#[cfg(target_has_atomic = "ptr")]
 impl<T: fmt::Display + ?Sized> fmt::Display for MutexGuardArc<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         (**self).fmt(f)
     }
 }
 
+#[cfg(target_has_atomic = "ptr")]
 impl<T: ?Sized> Deref for MutexGuardArc<T> {
     type Target = T;
 
@@ -460,17 +2017,427 @@ impl<T: ?Sized> Deref for MutexGuardArc<T> {
     }
 }
 
+#[cfg(target_has_atomic = "ptr")]
 impl<T: ?Sized> DerefMut for MutexGuardArc<T> {
     fn deref_mut(&mut self) -> &mut T {
         unsafe { &mut *self.0.data.get() }
     }
 }
 
-/// Calls a function when dropped.
-struct CallOnDrop<F: Fn()>(F);
+/// An owned guard for a part of the data behind a mutex, created by [`MutexGuardArc::map()`].
+#[cfg(target_has_atomic = "ptr")]
+pub struct MappedMutexGuardArc<T: ?Sized, U: ?Sized> {
+    _guard: MutexGuardArc<T>,
+    value: *mut U,
+}
+
+#[cfg(target_has_atomic = "ptr")]
+unsafe impl<T: Send + ?Sized, U: Send + ?Sized> Send for MappedMutexGuardArc<T, U> {}
+#[cfg(target_has_atomic = "ptr")]
+unsafe impl<T: Sync + ?Sized, U: Sync + ?Sized> Sync for MappedMutexGuardArc<T, U> {}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized, U: ?Sized> MappedMutexGuardArc<T, U> {
+    /// Makes an owned guard for a smaller part of the locked data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{MappedMutexGuardArc, Mutex, MutexGuardArc};
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(Mutex::new(((1, 2), 3)));
+    /// let inner = MutexGuardArc::map(mutex.lock_arc().await, |t| &mut t.0);
+    /// let mut second = MappedMutexGuardArc::map(inner, |t| &mut t.1);
+    /// *second = 20;
+    /// drop(second);
+    ///
+    /// assert_eq!(*mutex.lock().await, ((1, 20), 3));
+    /// # })
+    /// ```
+    pub fn map<V: ?Sized>(
+        guard: MappedMutexGuardArc<T, U>,
+        f: impl FnOnce(&mut U) -> &mut V,
+    ) -> MappedMutexGuardArc<T, V> {
+        let value = f(unsafe { &mut *guard.value }) as *mut V;
+        MappedMutexGuardArc {
+            _guard: guard._guard,
+            value,
+        }
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized, U: fmt::Debug + ?Sized> fmt::Debug for MappedMutexGuardArc<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized, U: fmt::Display + ?Sized> fmt::Display for MappedMutexGuardArc<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized, U: ?Sized> Deref for MappedMutexGuardArc<T, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        unsafe { &*self.value }
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized, U: ?Sized> DerefMut for MappedMutexGuardArc<T, U> {
+    fn deref_mut(&mut self) -> &mut U {
+        unsafe { &mut *self.value }
+    }
+}
+
+/// The locking mechanism behind [`Mutex`].
+///
+/// This is also used by other primitives that need a mutex without data or instrumentation.
+#[derive(Debug)]
+pub(crate) struct RawMutex {
+    /// Current state of the mutex.
+    ///
+    /// The least significant bit is set to 1 if the mutex is locked.
+    /// The other bits hold the number of starved lock operations.
+    state: AtomicUsize,
+
+    /// Lock operations waiting for the mutex to be released.
+    lock_ops: Event,
+
+    /// Set while the mutex is being handed over to a waiting lock operation, which then holds it
+    /// without having to lock it.
+    handover: AtomicBool,
+}
+
+impl RawMutex {
+    /// Creates a new, unlocked mutex.
+    pub(crate) const fn new() -> RawMutex {
+        RawMutex {
+            state: AtomicUsize::new(0),
+            lock_ops: Event::new(),
+            handover: AtomicBool::new(false),
+        }
+    }
+
+    /// Attempts to acquire the mutex, returning `true` on success.
+    #[inline]
+    pub(crate) fn try_lock(&self) -> bool {
+        self.state
+            .compare_exchange(0, 1, Ordering::Acquire, Ordering::Acquire)
+            .is_ok()
+    }
+
+    /// Attempts to acquire the mutex, spinning up to `max_spins` times while it is locked.
+    pub(crate) fn try_lock_spin(&self, max_spins: u32) -> bool {
+        for _ in 0..max_spins {
+            if self.try_lock() {
+                return true;
+            }
+
+            // Starving lock operations get the mutex before anyone spinning.
+            if self.state.load(Ordering::Relaxed) > 1 {
+                return false;
+            }
+            core::hint::spin_loop();
+        }
+        self.try_lock()
+    }
+
+    /// Acquires the mutex.
+    #[inline]
+    pub(crate) async fn lock(&self) {
+        if !self.try_lock() {
+            self.acquire_slow().await;
+        }
+    }
+
+    /// Slow path for acquiring the mutex.
+    #[cold]
+    pub(crate) fn acquire_slow(&self) -> AcquireSlow<'_> {
+        self.acquire_slow_with(FairnessPolicy::DEFAULT)
+    }
+
+    /// Slow path for acquiring the mutex with a fairness policy.
+    #[cold]
+    pub(crate) fn acquire_slow_with(&self, fairness: FairnessPolicy) -> AcquireSlow<'_> {
+        AcquireSlow {
+            mutex: self,
+            fairness,
+            start: None,
+            listener: None,
+            starved: false,
+        }
+    }
+
+    /// Returns `true` if the mutex is locked.
+    pub(crate) fn is_locked(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & 1 == 1
+    }
+
+    /// Returns the approximate number of lock operations waiting for the mutex.
+    #[cfg(feature = "std")]
+    pub(crate) fn waiter_count(&self) -> usize {
+        self.lock_ops.total_listeners()
+    }
+
+    /// Releases the mutex.
+    ///
+    /// The mutex must be locked by the caller.
+    #[inline]
+    pub(crate) fn unlock(&self) {
+        // Remove the last bit and notify a waiting lock operation.
+        self.state.fetch_sub(1, Ordering::Release);
+        self.lock_ops.notify(1);
+    }
+
+    /// Releases the mutex by handing it over to the longest-waiting lock operation.
+    ///
+    /// Returns `true` if the mutex was handed over rather than simply released. The mutex must be
+    /// locked by the caller.
+    pub(crate) fn unlock_fair(&self) -> bool {
+        // The mutex stays locked until a notified lock operation takes it over. If nobody could be
+        // notified, take it back unless somebody took it over in the meantime.
+        self.handover.store(true, Ordering::Release);
+        if self.lock_ops.notify(1) == 0 && self.take_handover() {
+            self.unlock();
+            return false;
+        }
+        true
+    }
+
+    /// Takes over the mutex if it is being handed over, returning `true` on success.
+    fn take_handover(&self) -> bool {
+        self.handover.load(Ordering::Relaxed) && self.handover.swap(false, Ordering::Acquire)
+    }
+}
+
+/// Future for [`RawMutex::acquire_slow()`].
+pub(crate) struct AcquireSlow<'a> {
+    mutex: &'a RawMutex,
+
+    /// When this lock operation becomes starved.
+    fairness: FairnessPolicy,
+
+    /// When a newer lock operation first took the mutex from this one after it was woken.
+    start: Option<Stopwatch>,
+
+    /// Listens for the mutex to be released.
+    listener: Option<EventListener>,
+
+    /// Whether this lock operation is counted as starved in the mutex state.
+    starved: bool,
+}
+
+impl AcquireSlow<'_> {
+    /// Switches to the fairer locking strategy that keeps newer lock operations from starving
+    /// this one forever.
+    ///
+    /// The lock operation must not be listening.
+    fn starve(&mut self) {
+        self.starved = true;
+
+        // Increment the number of starved lock operations.
+        if self.mutex.state.fetch_add(2, Ordering::Release) > usize::MAX / 2 {
+            // In case of potential overflow, abort.
+            crate::abort();
+        }
+    }
+
+    /// Stops listening for the mutex to be released.
+    ///
+    /// Returns `true` if the mutex was handed over to this lock operation in the meantime.
+    fn stop_listening(&mut self) -> bool {
+        let notified = match self.listener.take() {
+            Some(listener) => listener.discard(),
+            None => false,
+        };
+        if !notified {
+            return false;
+        }
+        if self.mutex.take_handover() {
+            return true;
+        }
+
+        // Pass on a notification that was probably meant for someone else.
+        self.mutex.lock_ops.notify(1);
+        false
+    }
+
+    /// Completes the lock operation, which now holds the mutex.
+    fn acquired(&mut self) -> Poll<()> {
+        self.listener = None;
+        if mem::replace(&mut self.starved, false) {
+            self.mutex.state.fetch_sub(2, Ordering::Release);
+        }
+        Poll::Ready(())
+    }
+}
+
+impl Future for AcquireSlow<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let poll = self.poll_acquire(cx);
+        if poll.is_pending() {
+            crate::coop::yielded();
+        }
+        poll
+    }
+}
+
+impl AcquireSlow<'_> {
+    /// Polls for the mutex.
+    fn poll_acquire(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self;
+        let mutex = this.mutex;
+        // Always-fair lock operations wait in line from the start.
+        if this.fairness == FairnessPolicy::Fifo && !this.starved {
+            this.starve();
+        }
+
+        while !this.starved {
+            match this.listener.as_mut() {
+                None => {
+                    // Start listening for events.
+                    this.listener = Some(mutex.lock_ops.listen());
+
+                    // Try locking if nobody is being starved.
+                    match mutex
+                        .state
+                        .compare_exchange(0, 1, Ordering::Acquire, Ordering::Acquire)
+                        .unwrap_or_else(|x| x)
+                    {
+                        // Lock acquired!
+                        0 => return this.acquired(),
+
+                        // Lock is held and nobody is starved.
+                        1 => {}
+
+                        // Somebody is starved.
+                        _ => {
+                            if this.stop_listening() {
+                                return this.acquired();
+                            }
+                            this.starve();
+                        }
+                    }
+                }
+                Some(listener) => {
+                    // Wait for a notification.
+                    ready!(Pin::new(listener).poll(cx));
+                    this.listener = None;
+                    if mutex.take_handover() {
+                        return this.acquired();
+                    }
+
+                    // Try locking if nobody is being starved.
+                    match mutex
+                        .state
+                        .compare_exchange(0, 1, Ordering::Acquire, Ordering::Acquire)
+                        .unwrap_or_else(|x| x)
+                    {
+                        // Lock acquired!
+                        0 => return this.acquired(),
+
+                        // Lock is held and nobody is starved.
+                        1 => {}
+
+                        // Somebody is starved.
+                        _ => {
+                            // Notify the first listener in line because we probably received a
+                            // notification that was meant for a starved task.
+                            mutex.lock_ops.notify(1);
+                            this.starve();
+                            break;
+                        }
+                    }
+
+                    // If losing for too long, fall back to a fairer locking strategy that will
+                    // prevent newer lock operations from starving us forever. The clock is only
+                    // read once the mutex was lost, so lock operations that get it on their first
+                    // wakeup never read it.
+                    if let FairnessPolicy::StarvationThreshold(threshold) = this.fairness {
+                        match &this.start {
+                            None => this.start = Some(Stopwatch::start()),
+                            Some(start) => {
+                                if start.elapsed().is_some_and(|e| e > threshold) {
+                                    this.starve();
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        loop {
+            match this.listener.as_mut() {
+                None => {
+                    // Start listening for events.
+                    this.listener = Some(mutex.lock_ops.listen());
+
+                    // Try locking if nobody else is being starved.
+                    match mutex
+                        .state
+                        .compare_exchange(2, 2 | 1, Ordering::Acquire, Ordering::Acquire)
+                        .unwrap_or_else(|x| x)
+                    {
+                        // Lock acquired!
+                        2 => return this.acquired(),
+
+                        // Lock is held by someone.
+                        s if s % 2 == 1 => {}
+
+                        // Lock is available.
+                        _ => {
+                            // Be fair: notify the first listener and then go wait in line.
+                            mutex.lock_ops.notify(1);
+                        }
+                    }
+                }
+                Some(listener) => {
+                    // Wait for a notification.
+                    ready!(Pin::new(listener).poll(cx));
+                    this.listener = None;
+
+                    // Try acquiring the lock without waiting for others.
+                    if mutex.take_handover() || mutex.state.fetch_or(1, Ordering::Acquire) & 1 == 0
+                    {
+                        return this.acquired();
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for AcquireSlow<'_> {
+    fn drop(&mut self) {
+        // Decrement the counter if the lock operation is cancelled while starved.
+        if self.starved {
+            self.mutex.state.fetch_sub(2, Ordering::Release);
+        }
+
+        // Pass the mutex on if it was handed over to this lock operation.
+        if self.stop_listening() {
+            self.mutex.unlock_fair();
+        }
+    }
+}
+
+/// A guard that releases a [`RawMutex`] when dropped.
+pub(crate) struct RawMutexGuard<'a>(pub(crate) &'a RawMutex);
 
-impl<F: Fn()> Drop for CallOnDrop<F> {
+impl Drop for RawMutexGuard<'_> {
     fn drop(&mut self) {
-        (self.0)();
+        self.0.unlock();
     }
 }
@@ -0,0 +1,313 @@
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use alloc::vec::Vec;
+use std::sync::{Mutex as StdMutex, MutexGuard as StdMutexGuard};
+
+/// Wakes tasks waiting for a notification.
+///
+/// [`notify_one()`][`Notify::notify_one()`] wakes the task that has been waiting the longest. If
+/// no task is waiting, it stores a permit instead, so the next call to
+/// [`notified()`][`Notify::notified()`] completes right away and the notification is not lost.
+/// At most one permit is stored. [`notify_all()`][`Notify::notify_all()`] wakes every task that is
+/// currently waiting and stores no permit.
+///
+/// This is a building block for custom queues and channels: consumers wait for a notification
+/// whenever the queue is empty, and producers notify them after pushing an item.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::Notify;
+/// use futures_lite::future;
+///
+/// let notify = Notify::new();
+///
+/// // The notification is stored until a task waits for it.
+/// notify.notify_one();
+/// notify.notified().await;
+///
+/// let mut notified = Box::pin(notify.notified());
+/// assert!(future::poll_once(&mut notified).await.is_none());
+///
+/// notify.notify_one();
+/// notified.await;
+/// # })
+/// ```
+pub struct Notify {
+    state: StdMutex<State>,
+}
+
+/// The waiters and the stored permit of a [`Notify`].
+struct State {
+    /// Set by [`Notify::notify_one()`] when no task was waiting.
+    permit: bool,
+
+    /// Incremented by every [`Notify::notify_all()`].
+    generation: u64,
+
+    /// The ID of the next waiter.
+    next_id: u64,
+
+    /// The registered waiters, in the order they started waiting.
+    ///
+    /// [`Notify::notify_all()`] removes every waiter it releases.
+    waiters: Vec<Waiter>,
+}
+
+/// A task waiting in [`Notified`].
+struct Waiter {
+    id: u64,
+    waker: Waker,
+
+    /// Set once [`Notify::notify_one()`] picked this waiter.
+    notified: bool,
+}
+
+impl State {
+    /// Wakes the waiter that has been waiting the longest, or stores a permit.
+    fn notify_one(&mut self) {
+        match self.waiters.iter_mut().find(|w| !w.notified) {
+            Some(waiter) => {
+                waiter.notified = true;
+                waiter.waker.wake_by_ref();
+            }
+            None => self.permit = true,
+        }
+    }
+
+    /// Removes the waiter with ID `id`, returning it if it was still registered.
+    fn remove(&mut self, id: u64) -> Option<Waiter> {
+        let index = self.waiters.iter().position(|w| w.id == id)?;
+        Some(self.waiters.remove(index))
+    }
+}
+
+impl Notify {
+    /// Creates a notifier without a stored permit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Notify;
+    ///
+    /// let notify = Notify::new();
+    /// ```
+    pub const fn new() -> Notify {
+        Notify {
+            state: StdMutex::new(State {
+                permit: false,
+                generation: 0,
+                next_id: 0,
+                waiters: Vec::new(),
+            }),
+        }
+    }
+
+    /// Waits for a notification.
+    ///
+    /// This completes once [`notify_one()`][`Notify::notify_one()`] picks this task, or right away
+    /// if a permit is stored. It also completes after a call to
+    /// [`notify_all()`][`Notify::notify_all()`] that happens after this method was called, even if
+    /// the returned future was not polled before that. Checking a condition between creating the
+    /// future and awaiting it therefore cannot miss a `notify_all()`.
+    ///
+    /// If the future is dropped after `notify_one()` picked it, but before it completed, the
+    /// notification is passed on to the next waiting task, or stored as a permit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Notify;
+    ///
+    /// let notify = Notify::new();
+    ///
+    /// let notified = notify.notified();
+    /// notify.notify_all();
+    /// notified.await;
+    /// # })
+    /// ```
+    pub fn notified(&self) -> Notified<'_> {
+        Notified {
+            notify: self,
+            generation: self.state().generation,
+            id: None,
+            done: false,
+        }
+    }
+
+    /// Wakes the task that has been waiting the longest, or stores a permit if none is waiting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Notify;
+    ///
+    /// let notify = Notify::new();
+    /// notify.notify_one();
+    /// notify.notify_one();
+    ///
+    /// // Only one permit is stored.
+    /// notify.notified().await;
+    /// # })
+    /// ```
+    pub fn notify_one(&self) {
+        self.state().notify_one();
+    }
+
+    /// Wakes every waiting task.
+    ///
+    /// Unlike [`notify_one()`][`Notify::notify_one()`], this does not store a permit, so it has no
+    /// effect on tasks that call [`notified()`][`Notify::notified()`] afterwards. A notification
+    /// that `notify_one()` sent to a task released here is not lost, though: it is stored as a
+    /// permit instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Notify;
+    /// use futures_lite::future;
+    ///
+    /// let notify = Notify::new();
+    /// let (a, b) = (notify.notified(), notify.notified());
+    ///
+    /// notify.notify_all();
+    /// a.await;
+    /// b.await;
+    ///
+    /// assert!(future::poll_once(notify.notified()).await.is_none());
+    /// # })
+    /// ```
+    pub fn notify_all(&self) {
+        let mut state = self.state();
+        state.generation = state.generation.wrapping_add(1);
+
+        // Released waiters leave the queue, so later calls to `notify_one()` cannot pick them.
+        let mut forwarded = false;
+        for waiter in state.waiters.drain(..) {
+            forwarded |= waiter.notified;
+            waiter.waker.wake();
+        }
+
+        // A waiter picked by `notify_one()` is released anyway, so its notification is kept.
+        if forwarded {
+            state.permit = true;
+        }
+    }
+
+    fn state(&self) -> StdMutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for Notify {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state();
+        f.debug_struct("Notify")
+            .field("permit", &state.permit)
+            .field("waiters", &state.waiters.len())
+            .finish()
+    }
+}
+
+impl Default for Notify {
+    fn default() -> Notify {
+        Notify::new()
+    }
+}
+
+/// The future returned by [`Notify::notified()`].
+pub struct Notified<'a> {
+    notify: &'a Notify,
+
+    /// The generation of the notifier when this future was created.
+    generation: u64,
+
+    /// The ID of this waiter, once it is registered.
+    id: Option<u64>,
+
+    /// Set once this future has completed.
+    done: bool,
+}
+
+impl Future for Notified<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = &mut *self;
+        if this.done {
+            return Poll::Ready(());
+        }
+        let mut state = this.notify.state();
+
+        let ready = if state.generation != this.generation {
+            // Released by `notify_all()`, which already removed the waiter, if it was registered.
+            this.id = None;
+            true
+        } else if let Some(id) = this.id {
+            let waiter = state
+                .waiters
+                .iter_mut()
+                .find(|w| w.id == id)
+                .expect("a registered waiter is only removed by its own future");
+            if waiter.notified {
+                state.remove(id);
+                this.id = None;
+                true
+            } else {
+                if !waiter.waker.will_wake(cx.waker()) {
+                    waiter.waker = cx.waker().clone();
+                }
+                false
+            }
+        } else if state.permit {
+            state.permit = false;
+            true
+        } else {
+            let id = state.next_id;
+            state.next_id += 1;
+            state.waiters.push(Waiter {
+                id,
+                waker: cx.waker().clone(),
+                notified: false,
+            });
+            this.id = Some(id);
+            false
+        };
+
+        if ready {
+            this.done = true;
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for Notified<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            let mut state = self.notify.state();
+            if let Some(waiter) = state.remove(id) {
+                if waiter.notified {
+                    // Pass on the notification this future did not use.
+                    state.notify_one();
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Notified<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Notified")
+            .field("waiting", &self.id.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,121 @@
+use core::future::Future;
+
+use crate::OnceCell;
+
+/// Runs an async operation once.
+///
+/// This is a [`OnceCell`] without a value, for one-time side effects such as running database
+/// migrations or setting up logging. The first task calling [`call_once()`] runs its future,
+/// and every other task calling it in the meantime waits until that future completes.
+///
+/// If the running future is cancelled, one of the waiting tasks runs its own future instead.
+///
+/// [`call_once()`]: `Once::call_once()`
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::Once;
+///
+/// static SETUP: Once = Once::new();
+///
+/// SETUP.call_once(|| async { /* Run migrations... */ }).await;
+/// assert!(SETUP.is_completed());
+///
+/// // Later calls do nothing.
+/// SETUP.call_once(|| async { unreachable!() }).await;
+/// # })
+/// ```
+#[derive(Debug, Default)]
+pub struct Once {
+    cell: OnceCell<()>,
+}
+
+impl Once {
+    /// Creates a new `Once` whose operation has not run yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Once;
+    ///
+    /// let once = Once::new();
+    /// assert!(!once.is_completed());
+    /// ```
+    pub const fn new() -> Once {
+        Once {
+            cell: OnceCell::new(),
+        }
+    }
+
+    /// Returns `true` if an operation has completed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Once;
+    ///
+    /// let once = Once::new();
+    /// once.call_once(|| async {}).await;
+    /// assert!(once.is_completed());
+    /// # })
+    /// ```
+    pub fn is_completed(&self) -> bool {
+        self.cell.is_initialized()
+    }
+
+    /// Runs `f` if no operation has completed yet, and waits until one has.
+    ///
+    /// If another task is already running its operation, this waits for it instead of calling
+    /// `f`. So `f` is called at most once as long as no operation is cancelled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Once;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// let once = Once::new();
+    /// let calls = AtomicUsize::new(0);
+    ///
+    /// for _ in 0..3 {
+    ///     once.call_once(|| async { calls.fetch_add(1, Ordering::SeqCst); }).await;
+    /// }
+    /// assert_eq!(calls.load(Ordering::SeqCst), 1);
+    /// # })
+    /// ```
+    pub async fn call_once<F, Fut>(&self, f: F)
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        self.cell.get_or_init(f).await;
+    }
+
+    /// Waits until another task has completed an operation, without running one.
+    ///
+    /// This waits forever if no task calls [`call_once()`][`Once::call_once()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Once;
+    /// use futures_lite::future;
+    ///
+    /// let once = Once::new();
+    ///
+    /// let mut waiter = Box::pin(once.wait());
+    /// assert!(future::poll_once(&mut waiter).await.is_none());
+    ///
+    /// once.call_once(|| async {}).await;
+    /// waiter.await;
+    /// # })
+    /// ```
+    pub async fn wait(&self) {
+        self.cell.wait().await;
+    }
+}
@@ -0,0 +1,389 @@
+use core::cell::UnsafeCell;
+use core::convert::Infallible;
+use core::fmt;
+use core::future::Future;
+use core::mem::MaybeUninit;
+
+use event_listener::Event;
+
+use crate::sync::atomic::{AtomicUsize, Ordering};
+
+/// The cell is empty and nobody is initializing it.
+const EMPTY: usize = 0;
+
+/// A task is running the initialization future.
+const INITIALIZING: usize = 1;
+
+/// The cell holds a value.
+const INITIALIZED: usize = 2;
+
+/// A cell that is initialized once, by an async operation.
+///
+/// The first task that calls [`get_or_init()`] runs the initialization future, and every other
+/// task calling it in the meantime waits for that future instead of starting its own. The value
+/// can then be read without waiting. This suits values such as database pools or configuration
+/// that need async code to set up and are shared by the whole program.
+///
+/// If the initializing task is cancelled, or its initialization fails in
+/// [`get_or_try_init()`], another waiting task takes over and runs its own initialization future.
+///
+/// [`get_or_init()`]: `OnceCell::get_or_init()`
+/// [`get_or_try_init()`]: `OnceCell::get_or_try_init()`
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::OnceCell;
+///
+/// static CONFIG: OnceCell<String> = OnceCell::new();
+///
+/// let config = CONFIG.get_or_init(|| async { "loaded".to_string() }).await;
+/// assert_eq!(config, "loaded");
+///
+/// // Later calls reuse the value.
+/// let config = CONFIG.get_or_init(|| async { unreachable!() }).await;
+/// assert_eq!(config, "loaded");
+/// # })
+/// ```
+pub struct OnceCell<T> {
+    /// `EMPTY`, `INITIALIZING`, or `INITIALIZED`.
+    state: AtomicUsize,
+
+    /// Notified when an initialization future completes or gives up.
+    initialized: Event,
+
+    /// Notified when the cell is initialized, for tasks that do not initialize it themselves.
+    populated: Event,
+
+    /// The value, once `state` is `INITIALIZED`.
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+unsafe impl<T: Send> Send for OnceCell<T> {}
+unsafe impl<T: Send + Sync> Sync for OnceCell<T> {}
+
+impl<T> OnceCell<T> {
+    /// Creates an empty cell.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::OnceCell;
+    ///
+    /// let cell = OnceCell::<i32>::new();
+    /// assert!(cell.get().is_none());
+    /// ```
+    pub const fn new() -> OnceCell<T> {
+        OnceCell {
+            state: AtomicUsize::new(EMPTY),
+            initialized: Event::new(),
+            populated: Event::new(),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Returns `true` if the cell holds a value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::OnceCell;
+    ///
+    /// let cell = OnceCell::new();
+    /// assert!(!cell.is_initialized());
+    ///
+    /// cell.get_or_init(|| async { 1 }).await;
+    /// assert!(cell.is_initialized());
+    /// # })
+    /// ```
+    pub fn is_initialized(&self) -> bool {
+        self.state.load(Ordering::Acquire) == INITIALIZED
+    }
+
+    /// Returns the value, or [`None`] if the cell is not initialized yet.
+    ///
+    /// This never waits, not even while another task is initializing the cell.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::OnceCell;
+    ///
+    /// let cell = OnceCell::new();
+    /// assert_eq!(cell.get(), None);
+    ///
+    /// cell.get_or_init(|| async { 1 }).await;
+    /// assert_eq!(cell.get(), Some(&1));
+    /// # })
+    /// ```
+    pub fn get(&self) -> Option<&T> {
+        if self.is_initialized() {
+            // SAFETY: The value is initialized and never changed again through a shared reference.
+            Some(unsafe { (*self.value.get()).assume_init_ref() })
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the value, or [`None`] if the cell is not initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::OnceCell;
+    ///
+    /// let mut cell = OnceCell::new();
+    /// cell.get_or_init(|| async { 1 }).await;
+    ///
+    /// *cell.get_mut().unwrap() += 1;
+    /// assert_eq!(cell.get(), Some(&2));
+    /// # })
+    /// ```
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if *self.state.get_mut() == INITIALIZED {
+            // SAFETY: The value is initialized, and the cell is borrowed mutably.
+            Some(unsafe { (*self.value.get()).assume_init_mut() })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the value, initializing the cell with `f` if it is empty.
+    ///
+    /// If another task is already initializing the cell, this waits for it instead of calling
+    /// `f`. If that task is cancelled, one of the waiting tasks calls its own `f` instead. So `f`
+    /// is called at most once as long as no initialization is cancelled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::OnceCell;
+    ///
+    /// let cell = OnceCell::new();
+    /// let value = cell.get_or_init(|| async { 42 }).await;
+    /// assert_eq!(*value, 42);
+    /// # })
+    /// ```
+    pub async fn get_or_init<F, Fut>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let result = self
+            .initialize(|| async move { Ok::<T, Infallible>(f().await) })
+            .await;
+        match result {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Returns the value, initializing the cell with `f` if it is empty, blocking the current
+    /// thread.
+    ///
+    /// This is the blocking version of [`get_or_init()`][`OnceCell::get_or_init()`], for sync
+    /// code that shares a cell with async tasks. If a task is already initializing the cell, the
+    /// thread is parked until it is done. This method must not be called from async code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::OnceCell;
+    ///
+    /// let cell = OnceCell::new();
+    /// assert_eq!(*cell.get_or_init_blocking(|| 42), 42);
+    /// assert_eq!(*cell.get_or_init_blocking(|| unreachable!()), 42);
+    /// ```
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn get_or_init_blocking(&self, f: impl FnOnce() -> T) -> &T {
+        if let Some(value) = self.get() {
+            return value;
+        }
+        crate::blocking::block_on(self.get_or_init(|| async move { f() }))
+    }
+
+    /// Returns the value, initializing the cell with the fallible `f` if it is empty.
+    ///
+    /// This works like [`get_or_init()`][`OnceCell::get_or_init()`], except that `f` may fail.
+    /// An error is returned to the caller whose `f` failed, and the cell stays empty, so one of
+    /// the tasks waiting for it takes over and calls its own `f`. Errors are never cached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::OnceCell;
+    ///
+    /// let cell = OnceCell::new();
+    ///
+    /// let result = cell.get_or_try_init(|| async { Err("connection refused") }).await;
+    /// assert_eq!(result, Err("connection refused"));
+    /// assert!(!cell.is_initialized());
+    ///
+    /// let result = cell.get_or_try_init(|| async { Ok::<_, &str>(5) }).await;
+    /// assert_eq!(result, Ok(&5));
+    /// # })
+    /// ```
+    pub async fn get_or_try_init<F, Fut, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        self.initialize(f).await
+    }
+
+    /// Waits until another task has initialized the cell, and returns the value.
+    ///
+    /// This never initializes the cell itself, so it waits forever if nobody else does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::OnceCell;
+    /// use futures_lite::future;
+    ///
+    /// let cell = OnceCell::new();
+    ///
+    /// let mut waiter = Box::pin(cell.wait());
+    /// assert!(future::poll_once(&mut waiter).await.is_none());
+    ///
+    /// cell.get_or_init(|| async { 3 }).await;
+    /// assert_eq!(*waiter.await, 3);
+    /// # })
+    /// ```
+    pub async fn wait(&self) -> &T {
+        loop {
+            if let Some(value) = self.get() {
+                return value;
+            }
+
+            let listener = self.populated.listen();
+            if let Some(value) = self.get() {
+                return value;
+            }
+            listener.await;
+        }
+    }
+
+    /// Consumes the cell, returning the value if it was initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::OnceCell;
+    ///
+    /// let cell = OnceCell::new();
+    /// cell.get_or_init(|| async { "value" }).await;
+    /// assert_eq!(cell.into_inner(), Some("value"));
+    /// # })
+    /// ```
+    pub fn into_inner(mut self) -> Option<T> {
+        if core::mem::replace(self.state.get_mut(), EMPTY) == INITIALIZED {
+            // SAFETY: The value is initialized, and resetting the state keeps `drop()` from
+            // dropping it again.
+            Some(unsafe { (*self.value.get()).assume_init_read() })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the value, running `f` to initialize the cell if no other task does.
+    ///
+    /// If `f` fails, the cell stays empty and another waiting task gets to try.
+    async fn initialize<F, Fut, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        loop {
+            match self.state.compare_exchange(
+                EMPTY,
+                INITIALIZING,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(INITIALIZED) => return Ok(self.get().unwrap()),
+                Err(_) => {
+                    // Another task is initializing the cell, so wait for it to finish or give up.
+                    let listener = self.initialized.listen();
+                    if self.state.load(Ordering::Acquire) == INITIALIZING {
+                        listener.await;
+                    }
+                }
+            }
+        }
+
+        // Hands the initialization over to another task if `f` fails or is cancelled.
+        let abandon = Abandon(self);
+        let value = f().await?;
+        core::mem::forget(abandon);
+
+        // SAFETY: Only the task that moved the state to `INITIALIZING` writes the value.
+        unsafe { (*self.value.get()).write(value) };
+        self.state.store(INITIALIZED, Ordering::Release);
+        self.initialized.notify(usize::MAX);
+        self.populated.notify(usize::MAX);
+
+        Ok(self.get().unwrap())
+    }
+}
+
+impl<T> Drop for OnceCell<T> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == INITIALIZED {
+            // SAFETY: The value is initialized and dropped only once.
+            unsafe { (*self.value.get()).assume_init_drop() }
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for OnceCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Uninit;
+        impl fmt::Debug for Uninit {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("<uninit>")
+            }
+        }
+
+        match self.get() {
+            None => f.debug_struct("OnceCell").field("value", &Uninit).finish(),
+            Some(value) => f.debug_struct("OnceCell").field("value", value).finish(),
+        }
+    }
+}
+
+impl<T> From<T> for OnceCell<T> {
+    fn from(value: T) -> OnceCell<T> {
+        OnceCell {
+            state: AtomicUsize::new(INITIALIZED),
+            initialized: Event::new(),
+            populated: Event::new(),
+            value: UnsafeCell::new(MaybeUninit::new(value)),
+        }
+    }
+}
+
+impl<T> Default for OnceCell<T> {
+    fn default() -> OnceCell<T> {
+        OnceCell::new()
+    }
+}
+
+/// Empties a cell whose initialization failed or was cancelled, and wakes a task to retry.
+struct Abandon<'a, T>(&'a OnceCell<T>);
+
+impl<T> Drop for Abandon<'_, T> {
+    fn drop(&mut self) {
+        self.0.state.store(EMPTY, Ordering::Release);
+        self.0.initialized.notify(1);
+    }
+}
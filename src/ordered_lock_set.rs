@@ -0,0 +1,250 @@
+use core::fmt;
+use core::iter::FromIterator;
+use core::ops::{Deref, DerefMut};
+
+use alloc::vec::Vec;
+
+use crate::Lockable;
+
+/// A dynamic collection of locks that are always acquired in address order.
+///
+/// Tasks that only lock overlapping sets through [`lock_all()`][`OrderedLockSet::lock_all()`]
+/// never deadlock, because every task acquires the locks they share in the same order. This suits
+/// transaction-style code whose set of locks is only known at runtime. Unlike the free function
+/// [`lock_all()`][`crate::lock_all()`], all locks in a set are of the same kind, and a task holds
+/// the locks it already acquired while it waits for the next one.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::{Mutex, OrderedLockSet};
+///
+/// let accounts = [Mutex::new(10), Mutex::new(20), Mutex::new(30)];
+///
+/// let mut set = OrderedLockSet::new();
+/// set.insert(&accounts[2]);
+/// set.insert(&accounts[0]);
+///
+/// let mut guards = set.lock_all().await;
+/// for balance in guards.iter_mut() {
+///     **balance -= 1;
+/// }
+/// guards.unlock_all();
+///
+/// assert_eq!(*accounts[0].lock().await, 9);
+/// assert_eq!(*accounts[2].lock().await, 29);
+/// # })
+/// ```
+pub struct OrderedLockSet<L> {
+    /// Sorted by address, without duplicates.
+    locks: Vec<L>,
+}
+
+impl<L: Lockable> OrderedLockSet<L> {
+    /// Creates an empty set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::{Mutex, OrderedLockSet};
+    ///
+    /// let set = OrderedLockSet::<&Mutex<i32>>::new();
+    /// assert!(set.is_empty());
+    /// ```
+    pub fn new() -> OrderedLockSet<L> {
+        OrderedLockSet { locks: Vec::new() }
+    }
+
+    /// Adds a lock to the set.
+    ///
+    /// Returns `false` if the lock was already in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::{Mutex, OrderedLockSet};
+    ///
+    /// let m = Mutex::new(());
+    /// let mut set = OrderedLockSet::new();
+    /// assert!(set.insert(&m));
+    /// assert!(!set.insert(&m));
+    /// ```
+    pub fn insert(&mut self, lock: L) -> bool {
+        match self.position(lock.id()) {
+            Ok(_) => false,
+            Err(i) => {
+                self.locks.insert(i, lock);
+                true
+            }
+        }
+    }
+
+    /// Removes a lock from the set, returning it if it was in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::{Mutex, OrderedLockSet};
+    ///
+    /// let m = Mutex::new(());
+    /// let mut set = OrderedLockSet::new();
+    /// set.insert(&m);
+    /// assert!(set.remove(&&m).is_some());
+    /// assert!(set.is_empty());
+    /// ```
+    pub fn remove(&mut self, lock: &L) -> Option<L> {
+        match self.position(lock.id()) {
+            Ok(i) => Some(self.locks.remove(i)),
+            Err(_) => None,
+        }
+    }
+
+    /// Returns `true` if the lock is in the set.
+    pub fn contains(&self, lock: &L) -> bool {
+        self.position(lock.id()).is_ok()
+    }
+
+    /// Returns the number of locks in the set.
+    pub fn len(&self) -> usize {
+        self.locks.len()
+    }
+
+    /// Returns `true` if the set contains no locks.
+    pub fn is_empty(&self) -> bool {
+        self.locks.is_empty()
+    }
+
+    /// Iterates over the locks in the order they are acquired.
+    pub fn iter(&self) -> core::slice::Iter<'_, L> {
+        self.locks.iter()
+    }
+
+    /// Acquires every lock in the set, in address order.
+    ///
+    /// The guards are returned in the same order as [`iter()`][`OrderedLockSet::iter()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{Mutex, OrderedLockSet};
+    ///
+    /// let a = Mutex::new(1);
+    /// let b = Mutex::new(2);
+    /// let set: OrderedLockSet<_> = vec![&a, &b].into_iter().collect();
+    ///
+    /// let guards = set.lock_all().await;
+    /// assert_eq!(guards.iter().map(|g| **g).sum::<i32>(), 3);
+    /// assert!(a.try_lock().is_none());
+    /// # })
+    /// ```
+    pub async fn lock_all(&self) -> OrderedGuards<L::Guard> {
+        let mut guards = Vec::with_capacity(self.locks.len());
+        for lock in &self.locks {
+            guards.push(lock.lock().await);
+        }
+        OrderedGuards { guards }
+    }
+
+    /// Attempts to acquire every lock in the set without waiting.
+    ///
+    /// If any lock is taken, the locks acquired so far are released again and [`None`] is
+    /// returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::{Mutex, OrderedLockSet};
+    ///
+    /// let a = Mutex::new(1);
+    /// let b = Mutex::new(2);
+    /// let set: OrderedLockSet<_> = vec![&a, &b].into_iter().collect();
+    ///
+    /// let guard = b.try_lock().unwrap();
+    /// assert!(set.try_lock_all().is_none());
+    /// assert!(a.try_lock().is_some());
+    /// drop(guard);
+    /// assert!(set.try_lock_all().is_some());
+    /// ```
+    pub fn try_lock_all(&self) -> Option<OrderedGuards<L::Guard>> {
+        let guards = self
+            .locks
+            .iter()
+            .map(|lock| lock.try_lock())
+            .collect::<Option<Vec<_>>>()?;
+        Some(OrderedGuards { guards })
+    }
+
+    fn position(&self, id: usize) -> Result<usize, usize> {
+        self.locks.binary_search_by_key(&id, |lock| lock.id())
+    }
+}
+
+impl<L: Lockable> Default for OrderedLockSet<L> {
+    fn default() -> OrderedLockSet<L> {
+        OrderedLockSet::new()
+    }
+}
+
+impl<L: Lockable> FromIterator<L> for OrderedLockSet<L> {
+    fn from_iter<I: IntoIterator<Item = L>>(iter: I) -> OrderedLockSet<L> {
+        let mut set = OrderedLockSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<L: Lockable> Extend<L> for OrderedLockSet<L> {
+    fn extend<I: IntoIterator<Item = L>>(&mut self, iter: I) {
+        for lock in iter {
+            self.insert(lock);
+        }
+    }
+}
+
+impl<L: fmt::Debug> fmt::Debug for OrderedLockSet<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(&self.locks).finish()
+    }
+}
+
+/// The guards of every lock in an [`OrderedLockSet`].
+///
+/// Dereferences to a slice of guards in address order of their locks. Dropping it releases the
+/// locks in the same order.
+pub struct OrderedGuards<G> {
+    guards: Vec<G>,
+}
+
+impl<G> OrderedGuards<G> {
+    /// Releases every lock, in address order.
+    pub fn unlock_all(self) {
+        drop(self);
+    }
+
+    /// Returns the guards without releasing the locks.
+    pub fn into_inner(self) -> Vec<G> {
+        self.guards
+    }
+}
+
+impl<G> Deref for OrderedGuards<G> {
+    type Target = [G];
+
+    fn deref(&self) -> &[G] {
+        &self.guards
+    }
+}
+
+impl<G> DerefMut for OrderedGuards<G> {
+    fn deref_mut(&mut self) -> &mut [G] {
+        &mut self.guards
+    }
+}
+
+impl<G: fmt::Debug> fmt::Debug for OrderedGuards<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.guards, f)
+    }
+}
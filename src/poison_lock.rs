@@ -0,0 +1,326 @@
+use core::fmt;
+use core::future::Future;
+use core::ops::{Deref, DerefMut};
+use core::panic::Location;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use std::thread;
+
+use crate::{Mutex, MutexGuard};
+
+/// The result of locking a [`PoisonLock`].
+///
+/// A poisoned lock is still locked, and the guard can be taken out of the error.
+pub type LockResult<G> = Result<G, PoisonError<G>>;
+
+/// A mutex that is poisoned when a task panics while holding it.
+///
+/// This works like [`std::sync::Mutex`]'s poisoning: if a guard is dropped while its thread is
+/// panicking, the lock is marked poisoned, and every later lock operation returns a
+/// [`PoisonError`]. Code that relies on poisoning to notice data left in an inconsistent state can
+/// use this instead of a plain [`Mutex`], which never poisons.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::PoisonLock;
+/// use std::panic::{self, AssertUnwindSafe};
+///
+/// let lock = PoisonLock::new(0);
+///
+/// let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+///     let mut guard = lock.try_lock().unwrap().unwrap();
+///     *guard += 1;
+///     panic!("the update was not finished");
+/// }));
+///
+/// assert!(lock.is_poisoned());
+///
+/// // The data can still be reached through the error.
+/// let guard = lock.lock().await.unwrap_err().into_inner();
+/// assert_eq!(*guard, 1);
+/// # })
+/// ```
+pub struct PoisonLock<T: ?Sized> {
+    /// Set when a guard is dropped during a panic.
+    poisoned: AtomicBool,
+
+    mutex: Mutex<T>,
+}
+
+impl<T> PoisonLock<T> {
+    /// Creates a new lock that is not poisoned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PoisonLock;
+    ///
+    /// let lock = PoisonLock::new(0);
+    /// ```
+    pub const fn new(data: T) -> PoisonLock<T> {
+        PoisonLock {
+            poisoned: AtomicBool::new(false),
+            mutex: Mutex::new(data),
+        }
+    }
+
+    /// Consumes the lock, returning the underlying data.
+    ///
+    /// Returns an error holding the data if the lock is poisoned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PoisonLock;
+    ///
+    /// let lock = PoisonLock::new(10);
+    /// assert_eq!(lock.into_inner().unwrap(), 10);
+    /// ```
+    pub fn into_inner(self) -> LockResult<T> {
+        let poisoned = self.is_poisoned();
+        let data = self.mutex.into_inner();
+        if poisoned {
+            Err(PoisonError(data))
+        } else {
+            Ok(data)
+        }
+    }
+}
+
+impl<T: ?Sized> PoisonLock<T> {
+    /// Acquires the lock.
+    ///
+    /// Returns a guard that releases the lock when dropped. If the lock is poisoned, the guard is
+    /// returned inside a [`PoisonError`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::PoisonLock;
+    ///
+    /// let lock = PoisonLock::new(10);
+    /// let guard = lock.lock().await.unwrap();
+    /// assert_eq!(*guard, 10);
+    /// # })
+    /// ```
+    #[track_caller]
+    pub fn lock(&self) -> impl Future<Output = LockResult<PoisonLockGuard<'_, T>>> {
+        let lock = self.mutex.lock_at(Location::caller());
+        async move { self.guard(lock.await) }
+    }
+
+    /// Attempts to acquire the lock.
+    ///
+    /// If the lock could not be acquired at this time, then [`None`] is returned. Otherwise, the
+    /// result is the same as with [`lock()`][`PoisonLock::lock()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PoisonLock;
+    ///
+    /// let lock = PoisonLock::new(10);
+    /// let guard = lock.try_lock().unwrap().unwrap();
+    /// assert!(lock.try_lock().is_none());
+    /// ```
+    #[track_caller]
+    pub fn try_lock(&self) -> Option<LockResult<PoisonLockGuard<'_, T>>> {
+        self.mutex.try_lock().map(|guard| self.guard(guard))
+    }
+
+    /// Returns `true` if a task panicked while holding the lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PoisonLock;
+    ///
+    /// let lock = PoisonLock::new(10);
+    /// assert!(!lock.is_poisoned());
+    /// ```
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Acquire)
+    }
+
+    /// Clears the poisoned state, for example after the data was repaired.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::PoisonLock;
+    /// use std::panic::{self, AssertUnwindSafe};
+    ///
+    /// let lock = PoisonLock::new(0);
+    /// let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+    ///     let _guard = lock.try_lock().unwrap().unwrap();
+    ///     panic!();
+    /// }));
+    ///
+    /// let mut guard = lock.lock().await.unwrap_err().into_inner();
+    /// *guard = 0;
+    /// lock.clear_poison();
+    /// drop(guard);
+    /// assert!(lock.lock().await.is_ok());
+    /// # })
+    /// ```
+    pub fn clear_poison(&self) {
+        self.poisoned.store(false, Ordering::Release);
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Returns an error holding the reference if the lock is poisoned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PoisonLock;
+    ///
+    /// let mut lock = PoisonLock::new(0);
+    /// *lock.get_mut().unwrap() = 10;
+    /// assert_eq!(*lock.try_lock().unwrap().unwrap(), 10);
+    /// ```
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        let poisoned = self.is_poisoned();
+        let data = self.mutex.get_mut();
+        if poisoned {
+            Err(PoisonError(data))
+        } else {
+            Ok(data)
+        }
+    }
+
+    fn guard<'a>(&'a self, guard: MutexGuard<'a, T>) -> LockResult<PoisonLockGuard<'a, T>> {
+        let guard = PoisonLockGuard {
+            lock: self,
+            guard,
+            panicking: thread::panicking(),
+        };
+        if self.is_poisoned() {
+            Err(PoisonError(guard))
+        } else {
+            Ok(guard)
+        }
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for PoisonLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoisonLock")
+            .field("poisoned", &self.is_poisoned())
+            .field("mutex", &&self.mutex)
+            .finish()
+    }
+}
+
+impl<T: Default> Default for PoisonLock<T> {
+    fn default() -> PoisonLock<T> {
+        PoisonLock::new(T::default())
+    }
+}
+
+impl<T> From<T> for PoisonLock<T> {
+    fn from(data: T) -> PoisonLock<T> {
+        PoisonLock::new(data)
+    }
+}
+
+/// A guard that releases a [`PoisonLock`] when dropped, poisoning it during a panic.
+pub struct PoisonLockGuard<'a, T: ?Sized> {
+    lock: &'a PoisonLock<T>,
+    guard: MutexGuard<'a, T>,
+
+    /// Whether the thread was already panicking when the lock was acquired.
+    panicking: bool,
+}
+
+impl<T: ?Sized> Drop for PoisonLockGuard<'_, T> {
+    fn drop(&mut self) {
+        // Poisoned before the mutex is released by the inner guard.
+        if !self.panicking && thread::panicking() {
+            self.lock.poisoned.store(true, Ordering::Release);
+        }
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for PoisonLockGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display + ?Sized> fmt::Display for PoisonLockGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized> Deref for PoisonLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for PoisonLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+/// An error returned when locking a poisoned [`PoisonLock`].
+///
+/// It holds what the lock operation would have returned, so the data can still be used.
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::PoisonLock;
+/// use std::panic::{self, AssertUnwindSafe};
+///
+/// let lock = PoisonLock::new(0);
+/// let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+///     let _guard = lock.try_lock().unwrap().unwrap();
+///     panic!();
+/// }));
+///
+/// let err = lock.try_lock().unwrap().unwrap_err();
+/// assert_eq!(err.to_string(), "poisoned lock: another task failed inside");
+/// ```
+pub struct PoisonError<G>(G);
+
+impl<G> PoisonError<G> {
+    /// Returns what the lock operation would have returned.
+    pub fn into_inner(self) -> G {
+        self.0
+    }
+
+    /// Returns a reference to what the lock operation would have returned.
+    pub fn get_ref(&self) -> &G {
+        &self.0
+    }
+
+    /// Returns a mutable reference to what the lock operation would have returned.
+    pub fn get_mut(&mut self) -> &mut G {
+        &mut self.0
+    }
+}
+
+impl<G> fmt::Debug for PoisonError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoisonError").finish_non_exhaustive()
+    }
+}
+
+impl<G> fmt::Display for PoisonError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("poisoned lock: another task failed inside")
+    }
+}
+
+impl<G> std::error::Error for PoisonError<G> {}
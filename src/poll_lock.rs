@@ -0,0 +1,400 @@
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, Waker};
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+#[cfg(feature = "futures-io")]
+use std::io::{self, IoSlice, IoSliceMut};
+use std::sync::Mutex as StdMutex;
+use std::task::Wake;
+
+#[cfg(feature = "futures-io")]
+use futures_io::{AsyncRead, AsyncSeek, AsyncWrite};
+
+use crate::{Mutex, MutexGuard};
+
+/// A lock that lets several tasks take turns polling one future, stream, or I/O object.
+///
+/// Every call to [`poll_with()`][`PollLock::poll_with()`] locks the inner value for the duration
+/// of a single poll. The inner value is polled with a waker that wakes every task that has polled
+/// it since it last made progress, so each of them gets another turn. This lets multiple tasks
+/// cooperatively drive one connection or stream, where a plain [`Mutex`] would only remember the
+/// waker of whichever task polled last.
+///
+/// # Examples
+///
+/// Two tasks reading from the same stream:
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::PollLock;
+/// use futures_lite::{future, stream, Stream};
+///
+/// let items = PollLock::new(stream::iter(vec![1, 2, 3, 4]));
+/// let next = || future::poll_fn(|cx| items.poll_with(cx, |s, cx| s.poll_next(cx)));
+///
+/// let (a, b) = future::zip(next(), next()).await;
+/// assert_eq!(a.unwrap() + b.unwrap(), 3);
+/// # })
+/// ```
+pub struct PollLock<T> {
+    inner: Mutex<T>,
+
+    /// Tasks waiting for their turn or for the inner value to make progress.
+    wakers: Arc<WakerSet>,
+
+    /// Set when the waiting tasks should be woken once the inner value is unlocked.
+    wake_waiters: AtomicBool,
+}
+
+impl<T> PollLock<T> {
+    /// Puts `inner` behind the lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PollLock;
+    /// use std::future::ready;
+    ///
+    /// let lock = PollLock::new(ready(1));
+    /// ```
+    pub fn new(inner: T) -> PollLock<T> {
+        PollLock {
+            inner: Mutex::new(inner),
+            wakers: Arc::new(WakerSet::default()),
+            wake_waiters: AtomicBool::new(false),
+        }
+    }
+
+    /// Consumes the lock, returning the inner value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PollLock;
+    ///
+    /// let lock = PollLock::new(10);
+    /// assert_eq!(lock.into_inner(), 10);
+    /// ```
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+
+    /// Returns a mutable reference to the inner value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PollLock;
+    ///
+    /// let mut lock = PollLock::new(0);
+    /// *lock.get_mut() = 10;
+    /// assert_eq!(lock.into_inner(), 10);
+    /// ```
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+
+    /// Acquires exclusive access to the inner value for longer than a single poll.
+    ///
+    /// This is useful to write a whole message to a shared connection. Tasks calling
+    /// [`poll_with()`][`PollLock::poll_with()`] in the meantime wait until the guard is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::PollLock;
+    ///
+    /// let lock = PollLock::new(Vec::new());
+    /// lock.lock().await.extend_from_slice(b"hello");
+    /// assert_eq!(lock.into_inner(), b"hello");
+    /// # })
+    /// ```
+    #[track_caller]
+    pub fn lock(&self) -> impl Future<Output = PollLockGuard<'_, T>> + '_ {
+        let lock = self.inner.lock();
+        async move {
+            PollLockGuard {
+                guard: Some(lock.await),
+                lock: self,
+            }
+        }
+    }
+}
+
+impl<T: Unpin> PollLock<T> {
+    /// Polls the inner value once with exclusive access.
+    ///
+    /// `f` is called with the inner value and a context whose waker wakes every task waiting on
+    /// this lock. If another task is polling the inner value at the same time, `f` is not called
+    /// and this returns [`Poll::Pending`]; the current task is woken once it may try again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::PollLock;
+    /// use futures_lite::future;
+    /// use std::future::Future;
+    ///
+    /// let lock = PollLock::new(future::ready(7));
+    /// let n = future::poll_fn(|cx| lock.poll_with(cx, |f, cx| f.poll(cx))).await;
+    /// assert_eq!(n, 7);
+    /// # })
+    /// ```
+    #[track_caller]
+    pub fn poll_with<R>(
+        &self,
+        cx: &mut Context<'_>,
+        f: impl FnOnce(Pin<&mut T>, &mut Context<'_>) -> Poll<R>,
+    ) -> Poll<R> {
+        self.wakers.register(cx.waker());
+
+        let guard = match self.inner.try_lock() {
+            Some(guard) => guard,
+            None => {
+                self.wake_waiters.store(true, Ordering::SeqCst);
+
+                // The holder may have released the lock before seeing the flag.
+                match self.inner.try_lock() {
+                    Some(guard) => guard,
+                    None => return Poll::Pending,
+                }
+            }
+        };
+
+        let mut guard = PollLockGuard {
+            guard: Some(guard),
+            lock: self,
+        };
+        let waker = Waker::from(self.wakers.clone());
+        let res = f(Pin::new(&mut *guard), &mut Context::from_waker(&waker));
+
+        if res.is_ready() {
+            // Others may have relied on this task to poll on their behalf.
+            self.wake_waiters.store(true, Ordering::SeqCst);
+        }
+        res
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PollLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PollLock")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+/// Implements the I/O traits for a shared [`PollLock`], locking the inner I/O object for every
+/// poll.
+///
+/// Several tasks can read from or write to one connection this way. Each poll is exclusive, but
+/// the reads and writes of different tasks may interleave.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::PollLock;
+/// use futures_lite::io::{AsyncReadExt, AsyncWriteExt, Cursor};
+///
+/// let conn = PollLock::new(Cursor::new(Vec::new()));
+/// (&conn).write_all(b"hello").await?;
+/// conn.lock().await.set_position(0);
+///
+/// let mut buf = String::new();
+/// (&conn).read_to_string(&mut buf).await?;
+/// assert_eq!(buf, "hello");
+/// # std::io::Result::Ok(())
+/// # }).unwrap();
+/// ```
+#[cfg(feature = "futures-io")]
+impl<T: AsyncRead + Unpin> AsyncRead for &PollLock<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_with(cx, |io, cx| io.poll_read(cx, buf))
+    }
+
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_with(cx, |io, cx| io.poll_read_vectored(cx, bufs))
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<T: AsyncWrite + Unpin> AsyncWrite for &PollLock<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_with(cx, |io, cx| io.poll_write(cx, buf))
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_with(cx, |io, cx| io.poll_write_vectored(cx, bufs))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_with(cx, |io, cx| io.poll_flush(cx))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_with(cx, |io, cx| io.poll_close(cx))
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<T: AsyncSeek + Unpin> AsyncSeek for &PollLock<T> {
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        pos: io::SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        self.poll_with(cx, |io, cx| io.poll_seek(cx, pos))
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<T: AsyncRead + Unpin> AsyncRead for PollLock<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &*self).poll_read(cx, buf)
+    }
+
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &*self).poll_read_vectored(cx, bufs)
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<T: AsyncWrite + Unpin> AsyncWrite for PollLock<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &*self).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &*self).poll_write_vectored(cx, bufs)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut &*self).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut &*self).poll_close(cx)
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<T: AsyncSeek + Unpin> AsyncSeek for PollLock<T> {
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        pos: io::SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        Pin::new(&mut &*self).poll_seek(cx, pos)
+    }
+}
+
+/// A guard that gives exclusive access to the inner value of a [`PollLock`].
+pub struct PollLockGuard<'a, T> {
+    guard: Option<MutexGuard<'a, T>>,
+    lock: &'a PollLock<T>,
+}
+
+impl<T> Drop for PollLockGuard<'_, T> {
+    fn drop(&mut self) {
+        drop(self.guard.take());
+
+        // Give tasks that found the lock taken another turn.
+        if self.lock.wake_waiters.swap(false, Ordering::SeqCst) {
+            self.lock.wakers.wake_all();
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PollLockGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> core::ops::Deref for PollLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.guard.as_ref().unwrap()
+    }
+}
+
+impl<T> core::ops::DerefMut for PollLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.guard.as_mut().unwrap()
+    }
+}
+
+/// The wakers of tasks that polled a [`PollLock`].
+#[derive(Default)]
+struct WakerSet {
+    wakers: StdMutex<Vec<Waker>>,
+}
+
+impl WakerSet {
+    fn register(&self, waker: &Waker) {
+        let mut wakers = self.wakers.lock().unwrap_or_else(|e| e.into_inner());
+        if !wakers.iter().any(|w| w.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+    }
+
+    fn wake_all(&self) {
+        let wakers = {
+            let mut wakers = self.wakers.lock().unwrap_or_else(|e| e.into_inner());
+            core::mem::take(&mut *wakers)
+        };
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+impl Wake for WakerSet {
+    fn wake(self: Arc<Self>) {
+        self.wake_all();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.wake_all();
+    }
+}
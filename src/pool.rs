@@ -0,0 +1,306 @@
+use core::fmt;
+use core::future::Future;
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+
+use alloc::vec::Vec;
+use std::sync::{Mutex as StdMutex, MutexGuard as StdMutexGuard};
+
+use crate::{Semaphore, SemaphoreGuard};
+
+/// Creates and checks the objects of a [`Pool`].
+pub trait PoolManager {
+    /// The pooled objects.
+    type Object;
+
+    /// The error returned when an object cannot be created.
+    type Error;
+
+    /// Creates a new object.
+    fn create(&self) -> impl Future<Output = Result<Self::Object, Self::Error>>;
+
+    /// Prepares an idle object for reuse, returning `false` if it should be discarded instead.
+    ///
+    /// This is called whenever an idle object is checked out again, for example to reset it or to
+    /// check that a connection is still alive. By default, every object is reused.
+    fn recycle(&self, object: &mut Self::Object) -> impl Future<Output = bool> {
+        let _ = object;
+        async { true }
+    }
+}
+
+/// A pool of reusable objects, such as database connections.
+///
+/// [`get()`][`Pool::get()`] checks out an idle object, or creates a new one if none is idle.
+/// Dropping the guard returns the object to the pool. At most `capacity` objects are checked out
+/// at once, so further calls wait until one is returned.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::{Pool, PoolManager};
+///
+/// struct Buffers;
+///
+/// impl PoolManager for Buffers {
+///     type Object = Vec<u8>;
+///     type Error = std::convert::Infallible;
+///
+///     async fn create(&self) -> Result<Vec<u8>, Self::Error> {
+///         Ok(Vec::with_capacity(1024))
+///     }
+///
+///     async fn recycle(&self, buf: &mut Vec<u8>) -> bool {
+///         buf.clear();
+///         true
+///     }
+/// }
+///
+/// let pool = Pool::new(Buffers, 2);
+///
+/// let mut buf = pool.get().await?;
+/// buf.extend_from_slice(b"hello");
+/// drop(buf);
+///
+/// // The buffer is reused, but cleared.
+/// assert!(pool.get().await?.is_empty());
+/// assert_eq!(pool.idle(), 1);
+/// # Ok::<(), std::convert::Infallible>(())
+/// # }).unwrap();
+/// ```
+pub struct Pool<M: PoolManager> {
+    /// Creates and checks the objects.
+    manager: M,
+
+    /// The maximum number of checked-out objects.
+    capacity: usize,
+
+    /// Limits the number of checked-out objects.
+    permits: Semaphore,
+
+    /// Objects that are not checked out.
+    idle: StdMutex<Vec<M::Object>>,
+}
+
+impl<M: PoolManager> Pool<M> {
+    /// Creates a pool that checks out at most `capacity` objects at once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::{Pool, PoolManager};
+    ///
+    /// struct Numbers;
+    ///
+    /// impl PoolManager for Numbers {
+    ///     type Object = u32;
+    ///     type Error = ();
+    ///
+    ///     async fn create(&self) -> Result<u32, ()> {
+    ///         Ok(0)
+    ///     }
+    /// }
+    ///
+    /// let pool = Pool::new(Numbers, 10);
+    /// ```
+    pub fn new(manager: M, capacity: usize) -> Pool<M> {
+        Pool {
+            manager,
+            capacity,
+            permits: Semaphore::new(capacity),
+            idle: StdMutex::new(Vec::new()),
+        }
+    }
+
+    /// Checks out an object.
+    ///
+    /// Waits while `capacity` objects are checked out. Idle objects are reused if
+    /// [`PoolManager::recycle()`] accepts them, and discarded otherwise. If no idle object is
+    /// accepted, a new one is created, and its error returned if that fails.
+    ///
+    /// If the returned future is dropped while an object is being recycled, that object is
+    /// discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{Pool, PoolManager};
+    ///
+    /// struct Numbers;
+    ///
+    /// impl PoolManager for Numbers {
+    ///     type Object = u32;
+    ///     type Error = ();
+    ///
+    ///     async fn create(&self) -> Result<u32, ()> {
+    ///         Ok(0)
+    ///     }
+    /// }
+    ///
+    /// let pool = Pool::new(Numbers, 1);
+    /// let mut n = pool.get().await?;
+    /// *n += 1;
+    /// drop(n);
+    /// assert_eq!(*pool.get().await?, 1);
+    /// # Ok::<(), ()>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn get(&self) -> Result<PoolGuard<'_, M>, M::Error> {
+        let permit = self.permits.acquire().await;
+
+        loop {
+            let next = self.idle_objects().pop();
+            match next {
+                Some(mut object) => {
+                    if self.manager.recycle(&mut object).await {
+                        return Ok(PoolGuard::new(self, object, permit));
+                    }
+                }
+                None => {
+                    let object = self.manager.create().await?;
+                    return Ok(PoolGuard::new(self, object, permit));
+                }
+            }
+        }
+    }
+
+    /// Returns the number of idle objects.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{Pool, PoolManager};
+    ///
+    /// struct Numbers;
+    ///
+    /// impl PoolManager for Numbers {
+    ///     type Object = u32;
+    ///     type Error = ();
+    ///
+    ///     async fn create(&self) -> Result<u32, ()> {
+    ///         Ok(0)
+    ///     }
+    /// }
+    ///
+    /// let pool = Pool::new(Numbers, 1);
+    /// assert_eq!(pool.idle(), 0);
+    ///
+    /// drop(pool.get().await?);
+    /// assert_eq!(pool.idle(), 1);
+    /// # Ok::<(), ()>(())
+    /// # }).unwrap();
+    /// ```
+    pub fn idle(&self) -> usize {
+        self.idle_objects().len()
+    }
+
+    /// Returns the manager of the pool.
+    pub fn manager(&self) -> &M {
+        &self.manager
+    }
+
+    fn idle_objects(&self) -> StdMutexGuard<'_, Vec<M::Object>> {
+        self.idle.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<M: PoolManager> fmt::Debug for Pool<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pool")
+            .field("capacity", &self.capacity)
+            .field("idle", &self.idle())
+            .finish()
+    }
+}
+
+/// A guard that returns a checked-out object to its [`Pool`] when dropped.
+pub struct PoolGuard<'a, M: PoolManager> {
+    pool: &'a Pool<M>,
+    object: ManuallyDrop<M::Object>,
+
+    // Dropped after the object is returned, so a waiting checkout finds it idle.
+    _permit: SemaphoreGuard<'a>,
+}
+
+impl<'a, M: PoolManager> PoolGuard<'a, M> {
+    fn new(pool: &'a Pool<M>, object: M::Object, permit: SemaphoreGuard<'a>) -> Self {
+        PoolGuard {
+            pool,
+            object: ManuallyDrop::new(object),
+            _permit: permit,
+        }
+    }
+
+    /// Takes the object out of the pool for good.
+    ///
+    /// This frees its place in the pool, so a new object may be created in its stead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{Pool, PoolGuard, PoolManager};
+    ///
+    /// struct Numbers;
+    ///
+    /// impl PoolManager for Numbers {
+    ///     type Object = u32;
+    ///     type Error = ();
+    ///
+    ///     async fn create(&self) -> Result<u32, ()> {
+    ///         Ok(7)
+    ///     }
+    /// }
+    ///
+    /// let pool = Pool::new(Numbers, 1);
+    /// let n = PoolGuard::detach(pool.get().await?);
+    /// assert_eq!(n, 7);
+    /// assert_eq!(pool.idle(), 0);
+    /// # Ok::<(), ()>(())
+    /// # }).unwrap();
+    /// ```
+    pub fn detach(guard: Self) -> M::Object {
+        let mut guard = ManuallyDrop::new(guard);
+
+        // SAFETY: The guard is not dropped, so the object and the permit are taken exactly once.
+        unsafe {
+            let object = ManuallyDrop::take(&mut guard.object);
+            core::ptr::drop_in_place(&mut guard._permit);
+            object
+        }
+    }
+}
+
+impl<M: PoolManager> Drop for PoolGuard<'_, M> {
+    fn drop(&mut self) {
+        // SAFETY: The object is only taken here and in `detach()`, which does not drop the guard.
+        let object = unsafe { ManuallyDrop::take(&mut self.object) };
+        self.pool.idle_objects().push(object);
+    }
+}
+
+impl<M: PoolManager> fmt::Debug for PoolGuard<'_, M>
+where
+    M::Object: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<M: PoolManager> Deref for PoolGuard<'_, M> {
+    type Target = M::Object;
+
+    fn deref(&self) -> &M::Object {
+        &self.object
+    }
+}
+
+impl<M: PoolManager> DerefMut for PoolGuard<'_, M> {
+    fn deref_mut(&mut self) -> &mut M::Object {
+        &mut self.object
+    }
+}
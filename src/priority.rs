@@ -0,0 +1,110 @@
+//! Priority ceilings for soft-real-time executors.
+//!
+//! Under the priority ceiling protocol, every lock is assigned a ceiling: the highest priority of
+//! any task that uses it. A task that holds the lock runs at the ceiling priority, so no task that
+//! could need the lock preempts the holder, and the time a task spends blocked on lower-priority
+//! tasks is bounded by a single critical section.
+//!
+//! Locks are given a ceiling with constructors such as [`Mutex::with_priority_ceiling()`]. This
+//! crate does not schedule anything itself; instead, acquiring such a lock calls the
+//! [`CeilingHooks`] installed with [`set_hooks()`], so the executor can raise the priority of the
+//! current task, and releasing the lock calls them again to restore it.
+//!
+//! Priorities are plain numbers whose meaning is up to the executor.
+//!
+//! [`Mutex::with_priority_ceiling()`]: crate::Mutex::with_priority_ceiling()
+//!
+//! # Examples
+//!
+//! ```
+//! use async_lock::priority::{self, CeilingHooks};
+//! use async_lock::Mutex;
+//! use std::cell::Cell;
+//!
+//! thread_local! {
+//!     static PRIORITY: Cell<u32> = Cell::new(0);
+//! }
+//!
+//! static HOOKS: CeilingHooks = CeilingHooks {
+//!     raise: |ceiling| PRIORITY.with(|p| p.replace(p.get().max(ceiling))),
+//!     restore: |previous| PRIORITY.with(|p| p.set(previous)),
+//! };
+//! priority::set_hooks(&HOOKS);
+//!
+//! let m = Mutex::with_priority_ceiling((), 10);
+//! let guard = m.try_lock().unwrap();
+//! assert_eq!(PRIORITY.with(Cell::get), 10);
+//! drop(guard);
+//! assert_eq!(PRIORITY.with(Cell::get), 0);
+//! # priority::clear_hooks();
+//! ```
+
+use core::fmt;
+
+use crate::sync::atomic::{AtomicPtr, Ordering};
+
+/// Executor callbacks that change the priority of the current task.
+#[derive(Clone, Copy)]
+pub struct CeilingHooks {
+    /// Called when a lock with the given ceiling is acquired.
+    ///
+    /// Returns the priority to restore once the lock is released, usually the task's current
+    /// priority.
+    pub raise: fn(u32) -> u32,
+
+    /// Called with the value returned by `raise` when the lock is released.
+    pub restore: fn(u32),
+}
+
+impl fmt::Debug for CeilingHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CeilingHooks").finish_non_exhaustive()
+    }
+}
+
+/// The installed hooks, or null if there are none.
+static HOOKS: AtomicPtr<CeilingHooks> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Installs process-wide hooks called when locks with a priority ceiling are acquired and
+/// released.
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::priority::{self, CeilingHooks};
+///
+/// static HOOKS: CeilingHooks = CeilingHooks {
+///     raise: |_| 0,
+///     restore: |_| {},
+/// };
+/// priority::set_hooks(&HOOKS);
+/// # priority::clear_hooks();
+/// ```
+pub fn set_hooks(hooks: &'static CeilingHooks) {
+    HOOKS.store(hooks as *const CeilingHooks as *mut _, Ordering::Release);
+}
+
+/// Removes the hooks installed with [`set_hooks()`].
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::priority;
+///
+/// priority::clear_hooks();
+/// ```
+pub fn clear_hooks() {
+    HOOKS.store(core::ptr::null_mut(), Ordering::Release);
+}
+
+fn hooks() -> Option<&'static CeilingHooks> {
+    // SAFETY: Non-null values come from a `&'static` reference in `set_hooks()`.
+    unsafe { HOOKS.load(Ordering::Acquire).as_ref() }
+}
+
+/// Raises the current task to `ceiling`, returning the hooks and the priority to restore.
+#[inline]
+pub(crate) fn raise(ceiling: u32) -> Option<(&'static CeilingHooks, u32)> {
+    let hooks = hooks()?;
+    Some((hooks, (hooks.raise)(ceiling)))
+}
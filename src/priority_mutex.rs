@@ -0,0 +1,266 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::future::Future;
+use core::ops::{Deref, DerefMut};
+
+use crate::{PrioritySemaphore, PrioritySemaphoreGuard};
+
+/// A mutex that hands itself to the highest-priority waiter first.
+///
+/// Lock operations started with [`lock_with_priority()`] are served in order of decreasing
+/// priority, and in the order they started waiting among equal priorities. When the mutex is
+/// released, it is handed directly to the next waiter, so control-plane tasks waiting with a high
+/// priority get it before bulk workers, no matter when they started waiting.
+///
+/// This is a [`PrioritySemaphore`] with a single permit, so the same caveats apply: a steady
+/// stream of high-priority lock operations starves low-priority ones unless the mutex is created
+/// with [`with_aging()`].
+///
+/// [`lock_with_priority()`]: `PriorityMutex::lock_with_priority()`
+/// [`with_aging()`]: `PriorityMutex::with_aging()`
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::PriorityMutex;
+/// use futures_lite::future;
+///
+/// let mutex = PriorityMutex::new(Vec::new());
+/// let guard = mutex.lock_with_priority(0).await;
+///
+/// let mut bulk = Box::pin(async { mutex.lock_with_priority(1).await.push("bulk") });
+/// let mut control = Box::pin(async { mutex.lock_with_priority(10).await.push("control") });
+/// assert!(future::poll_once(&mut bulk).await.is_none());
+/// assert!(future::poll_once(&mut control).await.is_none());
+///
+/// // The control task gets the mutex first, even though it started waiting later.
+/// drop(guard);
+/// assert!(future::poll_once(&mut bulk).await.is_none());
+/// control.await;
+/// bulk.await;
+/// assert_eq!(*mutex.lock_with_priority(0).await, ["control", "bulk"]);
+/// # })
+/// ```
+pub struct PriorityMutex<T: ?Sized> {
+    semaphore: PrioritySemaphore,
+
+    /// The value inside the mutex.
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send + ?Sized> Send for PriorityMutex<T> {}
+unsafe impl<T: Send + ?Sized> Sync for PriorityMutex<T> {}
+
+impl<T> PriorityMutex<T> {
+    /// Creates a new priority mutex.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PriorityMutex;
+    ///
+    /// let mutex = PriorityMutex::new(0);
+    /// ```
+    pub const fn new(data: T) -> PriorityMutex<T> {
+        PriorityMutex {
+            semaphore: PrioritySemaphore::new(1),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Creates a new priority mutex whose waiters gain priority while they wait.
+    ///
+    /// Every time the mutex has been handed to `every` other lock operations while an operation
+    /// waits, its effective priority goes up by one, as with
+    /// [`PrioritySemaphore::with_aging()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PriorityMutex;
+    ///
+    /// let mutex = PriorityMutex::with_aging(0, 4);
+    /// ```
+    pub const fn with_aging(data: T, every: u32) -> PriorityMutex<T> {
+        PriorityMutex {
+            semaphore: PrioritySemaphore::with_aging(1, every),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Consumes the mutex, returning the underlying data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PriorityMutex;
+    ///
+    /// let mutex = PriorityMutex::new(10);
+    /// assert_eq!(mutex.into_inner(), 10);
+    /// ```
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized> PriorityMutex<T> {
+    /// Acquires the mutex with the given priority.
+    ///
+    /// Higher numbers are served first. Returns a guard that releases the mutex when dropped.
+    ///
+    /// The operation joins the queue when it is first polled. If it is dropped after the mutex
+    /// was handed to it, the mutex goes to the next waiter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::PriorityMutex;
+    ///
+    /// let mutex = PriorityMutex::new(10);
+    /// let guard = mutex.lock_with_priority(3).await;
+    /// assert_eq!(*guard, 10);
+    /// # })
+    /// ```
+    pub fn lock_with_priority(
+        &self,
+        priority: u32,
+    ) -> impl Future<Output = PriorityMutexGuard<'_, T>> + '_ {
+        let acquire = self.semaphore.acquire_with_priority(priority);
+        async move {
+            PriorityMutexGuard {
+                mutex: self,
+                _permit: acquire.await,
+            }
+        }
+    }
+
+    /// Attempts to acquire the mutex.
+    ///
+    /// This fails while other lock operations are waiting, because the mutex is handed to them
+    /// first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PriorityMutex;
+    ///
+    /// let mutex = PriorityMutex::new(10);
+    /// let guard = mutex.try_lock().unwrap();
+    /// assert!(mutex.try_lock().is_none());
+    /// ```
+    pub fn try_lock(&self) -> Option<PriorityMutexGuard<'_, T>> {
+        self.semaphore
+            .try_acquire()
+            .map(|permit| PriorityMutexGuard {
+                mutex: self,
+                _permit: permit,
+            })
+    }
+
+    /// Returns the number of lock operations waiting for the mutex.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PriorityMutex;
+    ///
+    /// let mutex = PriorityMutex::new(10);
+    /// assert_eq!(mutex.waiters(), 0);
+    /// ```
+    pub fn waiters(&self) -> usize {
+        self.semaphore.waiters()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the mutex mutably, no actual locking takes place -- the mutable
+    /// borrow statically guarantees the mutex is not already acquired.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PriorityMutex;
+    ///
+    /// let mut mutex = PriorityMutex::new(0);
+    /// *mutex.get_mut() = 10;
+    /// assert_eq!(*mutex.try_lock().unwrap(), 10);
+    /// ```
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for PriorityMutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Locked;
+        impl fmt::Debug for Locked {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("<locked>")
+            }
+        }
+
+        match self.try_lock() {
+            None => f
+                .debug_struct("PriorityMutex")
+                .field("data", &Locked)
+                .finish(),
+            Some(guard) => f
+                .debug_struct("PriorityMutex")
+                .field("data", &&*guard)
+                .finish(),
+        }
+    }
+}
+
+impl<T> From<T> for PriorityMutex<T> {
+    fn from(val: T) -> PriorityMutex<T> {
+        PriorityMutex::new(val)
+    }
+}
+
+impl<T: Default> Default for PriorityMutex<T> {
+    fn default() -> PriorityMutex<T> {
+        PriorityMutex::new(Default::default())
+    }
+}
+
+/// A guard that releases a [`PriorityMutex`] when dropped, handing it to the next waiter.
+pub struct PriorityMutexGuard<'a, T: ?Sized> {
+    mutex: &'a PriorityMutex<T>,
+    _permit: PrioritySemaphoreGuard<'a>,
+}
+
+unsafe impl<T: Send + ?Sized> Send for PriorityMutexGuard<'_, T> {}
+unsafe impl<T: Sync + ?Sized> Sync for PriorityMutexGuard<'_, T> {}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for PriorityMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display + ?Sized> fmt::Display for PriorityMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized> Deref for PriorityMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for PriorityMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
@@ -0,0 +1,339 @@
+use core::cmp::Reverse;
+use core::convert::TryFrom;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use std::sync::{Mutex as StdMutex, MutexGuard as StdMutexGuard};
+
+/// Identifies a waiter: higher priorities first, then in the order they started waiting.
+type Key = (Reverse<u32>, u64);
+
+/// A semaphore that hands freed permits to the highest-priority waiter first.
+///
+/// Operations waiting with [`acquire_with_priority()`] are served in order of decreasing
+/// priority, and in the order they started waiting among equal priorities. A released permit is
+/// handed directly to the next waiter, so new operations cannot take it first, no matter their
+/// priority. This suits resource pools that should serve interactive requests before batch jobs
+/// while they are saturated.
+///
+/// Strict priorities let a steady stream of high-priority operations starve low-priority ones.
+/// Semaphores created with [`with_aging()`] prevent that by raising the priority of operations
+/// the longer they wait.
+///
+/// [`acquire_with_priority()`]: `PrioritySemaphore::acquire_with_priority()`
+/// [`with_aging()`]: `PrioritySemaphore::with_aging()`
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::PrioritySemaphore;
+/// use futures_lite::future;
+///
+/// let pool = PrioritySemaphore::new(1);
+/// let slot = pool.acquire_with_priority(0).await;
+///
+/// let mut batch = Box::pin(pool.acquire_with_priority(1));
+/// let mut interactive = Box::pin(pool.acquire_with_priority(10));
+/// assert!(future::poll_once(&mut batch).await.is_none());
+/// assert!(future::poll_once(&mut interactive).await.is_none());
+///
+/// // The freed slot goes to the interactive request, even though it started waiting later.
+/// drop(slot);
+/// assert!(future::poll_once(&mut batch).await.is_none());
+/// assert!(future::poll_once(&mut interactive).await.is_some());
+/// # })
+/// ```
+pub struct PrioritySemaphore {
+    state: StdMutex<State>,
+
+    /// Waiters gain one priority level per this many permits handed to others, if set.
+    aging: Option<u32>,
+}
+
+struct State {
+    /// Permits that are not held or handed to a waiter.
+    permits: usize,
+
+    /// Waiters that have not received a permit yet.
+    waiting: BTreeMap<Key, Waiter>,
+
+    /// Waiters that received a permit but have not taken it yet.
+    granted: BTreeSet<Key>,
+
+    /// Sequence number of the next waiter.
+    next: u64,
+
+    /// Number of permits handed to waiters so far.
+    handed: u64,
+}
+
+struct Waiter {
+    waker: Option<Waker>,
+
+    /// The value of `State::handed` when this waiter joined the queue.
+    since: u64,
+}
+
+impl PrioritySemaphore {
+    /// Creates a new semaphore with a limit of `n` concurrent operations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PrioritySemaphore;
+    ///
+    /// let s = PrioritySemaphore::new(5);
+    /// ```
+    pub const fn new(n: usize) -> PrioritySemaphore {
+        PrioritySemaphore::with_state(n, None)
+    }
+
+    /// Creates a new semaphore whose waiters gain priority while they wait.
+    ///
+    /// Every time `every` permits have been handed to other operations while an operation waits,
+    /// its effective priority goes up by one. A waiter with priority `p` can therefore be
+    /// overtaken by at most `every` operations of priority `p + 1` before it overtakes them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::PrioritySemaphore;
+    /// use futures_lite::future;
+    ///
+    /// let s = PrioritySemaphore::with_aging(1, 1);
+    /// let guard = s.try_acquire().unwrap();
+    ///
+    /// let mut low = Box::pin(s.acquire_with_priority(0));
+    /// let mut high = Box::pin(s.acquire_with_priority(1));
+    /// assert!(future::poll_once(&mut low).await.is_none());
+    /// assert!(future::poll_once(&mut high).await.is_none());
+    ///
+    /// // The high-priority operation goes first.
+    /// drop(guard);
+    /// let guard = future::poll_once(&mut high).await.unwrap();
+    ///
+    /// // By now, the low-priority operation has aged enough to go before new ones.
+    /// let mut high = Box::pin(s.acquire_with_priority(1));
+    /// assert!(future::poll_once(&mut high).await.is_none());
+    /// drop(guard);
+    /// assert!(future::poll_once(&mut low).await.is_some());
+    /// # })
+    /// ```
+    pub const fn with_aging(n: usize, every: u32) -> PrioritySemaphore {
+        assert!(every > 0, "aging step must be greater than zero");
+        PrioritySemaphore::with_state(n, Some(every))
+    }
+
+    const fn with_state(n: usize, aging: Option<u32>) -> PrioritySemaphore {
+        PrioritySemaphore {
+            state: StdMutex::new(State {
+                permits: n,
+                waiting: BTreeMap::new(),
+                granted: BTreeSet::new(),
+                next: 0,
+                handed: 0,
+            }),
+            aging,
+        }
+    }
+
+    /// Attempts to get a permit without waiting.
+    ///
+    /// This fails while other operations are waiting, because freed permits go to them first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PrioritySemaphore;
+    ///
+    /// let s = PrioritySemaphore::new(1);
+    /// let guard = s.try_acquire().unwrap();
+    /// assert!(s.try_acquire().is_none());
+    /// ```
+    pub fn try_acquire(&self) -> Option<PrioritySemaphoreGuard<'_>> {
+        let mut state = self.state();
+        if state.permits == 0 {
+            return None;
+        }
+        state.permits -= 1;
+        Some(PrioritySemaphoreGuard(self))
+    }
+
+    /// Waits for a permit with the given priority.
+    ///
+    /// Higher numbers are served first. Returns a guard that releases the permit when dropped.
+    ///
+    /// The operation joins the queue when it is first polled. If it is dropped after a permit was
+    /// handed to it, the permit goes to the next waiter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::PrioritySemaphore;
+    ///
+    /// let s = PrioritySemaphore::new(2);
+    /// let guard = s.acquire_with_priority(3).await;
+    /// # })
+    /// ```
+    pub fn acquire_with_priority(
+        &self,
+        priority: u32,
+    ) -> impl Future<Output = PrioritySemaphoreGuard<'_>> + '_ {
+        Acquire {
+            semaphore: self,
+            priority,
+            key: None,
+        }
+    }
+
+    /// Returns the number of operations waiting for a permit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::PrioritySemaphore;
+    ///
+    /// let s = PrioritySemaphore::new(1);
+    /// assert_eq!(s.waiters(), 0);
+    /// ```
+    pub fn waiters(&self) -> usize {
+        self.state().waiting.len()
+    }
+
+    /// Hands a permit to the next waiter, or returns it to the semaphore if there is none.
+    fn release(&self) {
+        let mut state = self.state();
+        let next = match self.aging {
+            None => state.waiting.keys().next().copied(),
+            Some(every) => {
+                let handed = state.handed;
+                let effective = |(&(Reverse(priority), seq), waiter): (&Key, &Waiter)| {
+                    let age = (handed - waiter.since) / u64::from(every);
+                    let boost = u32::try_from(age).unwrap_or(u32::MAX);
+                    (
+                        (priority.saturating_add(boost), Reverse(seq)),
+                        (Reverse(priority), seq),
+                    )
+                };
+                state
+                    .waiting
+                    .iter()
+                    .map(effective)
+                    .max()
+                    .map(|(_, key)| key)
+            }
+        };
+
+        match next {
+            Some(key) => {
+                let waiter = state.waiting.remove(&key).unwrap();
+                state.granted.insert(key);
+                state.handed += 1;
+                drop(state);
+                if let Some(waker) = waiter.waker {
+                    waker.wake();
+                }
+            }
+            None => state.permits += 1,
+        }
+    }
+
+    fn state(&self) -> StdMutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for PrioritySemaphore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state();
+        f.debug_struct("PrioritySemaphore")
+            .field("permits", &state.permits)
+            .field("waiters", &state.waiting.len())
+            .finish()
+    }
+}
+
+/// The future returned by [`PrioritySemaphore::acquire_with_priority()`].
+struct Acquire<'a> {
+    semaphore: &'a PrioritySemaphore,
+    priority: u32,
+
+    /// Set once this operation joined the queue.
+    key: Option<Key>,
+}
+
+impl<'a> Future for Acquire<'a> {
+    type Output = PrioritySemaphoreGuard<'a>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<PrioritySemaphoreGuard<'a>> {
+        let semaphore = self.semaphore;
+        let mut state = semaphore.state();
+
+        match self.key {
+            None => {
+                if state.permits > 0 {
+                    state.permits -= 1;
+                    return Poll::Ready(PrioritySemaphoreGuard(semaphore));
+                }
+
+                let key = (Reverse(self.priority), state.next);
+                state.next += 1;
+                let waiter = Waiter {
+                    waker: Some(cx.waker().clone()),
+                    since: state.handed,
+                };
+                state.waiting.insert(key, waiter);
+                self.key = Some(key);
+                Poll::Pending
+            }
+            Some(key) => {
+                if state.granted.remove(&key) {
+                    self.key = None;
+                    return Poll::Ready(PrioritySemaphoreGuard(semaphore));
+                }
+
+                if let Some(waiter) = state.waiting.get_mut(&key) {
+                    let waker = &mut waiter.waker;
+                    match waker {
+                        Some(w) if w.will_wake(cx.waker()) => {}
+                        _ => *waker = Some(cx.waker().clone()),
+                    }
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for Acquire<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            let mut state = self.semaphore.state();
+            if state.waiting.remove(&key).is_none() && state.granted.remove(&key) {
+                // A permit was already handed to this operation, so pass it on.
+                drop(state);
+                self.semaphore.release();
+            }
+        }
+    }
+}
+
+/// A guard that releases a permit of a [`PrioritySemaphore`].
+#[derive(Debug)]
+pub struct PrioritySemaphoreGuard<'a>(&'a PrioritySemaphore);
+
+impl Drop for PrioritySemaphoreGuard<'_> {
+    fn drop(&mut self) {
+        self.0.release();
+    }
+}
@@ -0,0 +1,247 @@
+use core::convert::TryFrom;
+use core::fmt;
+use core::time::Duration;
+
+use std::sync::{Mutex as StdMutex, MutexGuard as StdMutexGuard};
+
+use crate::clock::Stopwatch;
+use crate::{Mutex, Timer};
+
+/// Limits how often operations run, with a token bucket.
+///
+/// The bucket holds up to `capacity` tokens and starts out full. Every `interval`, one token is
+/// added, and [`acquire()`][`RateLimiter::acquire()`] takes tokens out, waiting on its [`Timer`]
+/// until enough of them have been added. Bursts of up to `capacity` operations can therefore run
+/// at once, while the long-term rate stays at one operation per `interval`.
+///
+/// Operations that wait for tokens are served in the order they started waiting, so a stream
+/// of operations taking one token cannot starve an operation that takes many.
+///
+/// Time is measured with the [`clock`][`crate::clock`] installed when the limiter was created, or
+/// `Instant` if there is none.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
+/// # futures_lite::future::block_on(async {
+/// use async_lock::{AsyncIoTimer, RateLimiter};
+/// use std::time::{Duration, Instant};
+///
+/// // Bursts of up to 5 requests, and 100 requests per second after that.
+/// let limiter = RateLimiter::new(AsyncIoTimer, 5, Duration::from_millis(10));
+///
+/// let start = Instant::now();
+/// for _ in 0..7 {
+///     limiter.acquire(1).await;
+/// }
+/// assert!(start.elapsed() >= Duration::from_millis(20));
+/// # });
+/// ```
+pub struct RateLimiter<T> {
+    /// Sleeps until enough tokens have been added.
+    timer: T,
+
+    /// The maximum number of tokens in the bucket.
+    capacity: usize,
+
+    /// The time it takes to add one token.
+    interval: Duration,
+
+    /// Measures the time since the limiter was created.
+    start: Stopwatch,
+
+    /// The tokens in the bucket.
+    bucket: StdMutex<Bucket>,
+
+    /// Held by the operation that is waiting for tokens, so they are served one at a time.
+    queue: Mutex<()>,
+}
+
+/// The tokens in a [`RateLimiter`].
+struct Bucket {
+    /// The number of tokens at `updated`.
+    tokens: usize,
+
+    /// When the tokens were last counted, in nanoseconds since the limiter was created.
+    updated: u128,
+}
+
+impl<T: Timer> RateLimiter<T> {
+    /// Creates a full bucket of `capacity` tokens, which adds one token every `interval`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
+    /// # {
+    /// use async_lock::{AsyncIoTimer, RateLimiter};
+    /// use std::time::Duration;
+    ///
+    /// let limiter = RateLimiter::new(AsyncIoTimer, 10, Duration::from_secs(1));
+    /// assert_eq!(limiter.available(), 10);
+    /// # }
+    /// ```
+    pub fn new(timer: T, capacity: usize, interval: Duration) -> RateLimiter<T> {
+        assert!(!interval.is_zero(), "`interval` must not be zero");
+
+        RateLimiter {
+            timer,
+            capacity,
+            interval,
+            start: Stopwatch::start(),
+            bucket: StdMutex::new(Bucket {
+                tokens: capacity,
+                updated: 0,
+            }),
+            queue: Mutex::new(()),
+        }
+    }
+
+    /// Takes `n` tokens, waiting until they have been added.
+    ///
+    /// If the returned future is dropped before it completes, no tokens are taken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the capacity, since that many tokens never fit in the bucket.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{AsyncIoTimer, RateLimiter};
+    /// use std::time::Duration;
+    ///
+    /// let limiter = RateLimiter::new(AsyncIoTimer, 3, Duration::from_millis(1));
+    /// limiter.acquire(3).await;
+    ///
+    /// // Waits for three more tokens to be added.
+    /// limiter.acquire(3).await;
+    /// # });
+    /// ```
+    pub async fn acquire(&self, n: usize) {
+        assert!(
+            n <= self.capacity,
+            "acquired more tokens than the `RateLimiter` holds"
+        );
+
+        let _turn = self.queue.lock().await;
+        loop {
+            let wait = {
+                let mut bucket = self.bucket();
+                if bucket.tokens >= n {
+                    bucket.tokens -= n;
+                    return;
+                }
+
+                // The time until the missing tokens have been added.
+                let missing = (n - bucket.tokens) as u128 * self.interval.as_nanos();
+                missing.saturating_sub(self.now().saturating_sub(bucket.updated))
+            };
+            self.timer
+                .sleep(Duration::from_nanos(
+                    u64::try_from(wait).unwrap_or(u64::MAX),
+                ))
+                .await;
+        }
+    }
+
+    /// Attempts to take `n` tokens without waiting.
+    ///
+    /// Returns `false` if there are fewer than `n` tokens in the bucket, or if another operation
+    /// is already waiting for tokens.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
+    /// # {
+    /// use async_lock::{AsyncIoTimer, RateLimiter};
+    /// use std::time::Duration;
+    ///
+    /// let limiter = RateLimiter::new(AsyncIoTimer, 3, Duration::from_secs(1));
+    /// assert!(limiter.try_acquire(2));
+    /// assert!(!limiter.try_acquire(2));
+    /// assert!(limiter.try_acquire(1));
+    /// # }
+    /// ```
+    pub fn try_acquire(&self, n: usize) -> bool {
+        let _turn = match self.queue.try_lock() {
+            Some(turn) => turn,
+            None => return false,
+        };
+
+        let mut bucket = self.bucket();
+        if bucket.tokens >= n {
+            bucket.tokens -= n;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the number of tokens in the bucket.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
+    /// # {
+    /// use async_lock::{AsyncIoTimer, RateLimiter};
+    /// use std::time::Duration;
+    ///
+    /// let limiter = RateLimiter::new(AsyncIoTimer, 3, Duration::from_secs(1));
+    /// assert!(limiter.try_acquire(1));
+    /// assert_eq!(limiter.available(), 2);
+    /// # }
+    /// ```
+    pub fn available(&self) -> usize {
+        self.bucket().tokens
+    }
+
+    /// Returns the maximum number of tokens in the bucket.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the nanoseconds since the limiter was created.
+    fn now(&self) -> u128 {
+        // There always is a clock with `std`.
+        self.start.elapsed().unwrap_or_default().as_nanos()
+    }
+
+    /// Locks the bucket, first adding the tokens for the time since it was last counted.
+    fn bucket(&self) -> StdMutexGuard<'_, Bucket> {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let now = self.now();
+
+        let interval = self.interval.as_nanos();
+        // A clock that went backwards adds no tokens.
+        let added = now.saturating_sub(bucket.updated) / interval;
+        if bucket.tokens as u128 + added >= self.capacity as u128 {
+            bucket.tokens = self.capacity;
+            bucket.updated = now;
+        } else {
+            // Keep the time towards the next token, which is already partly over.
+            bucket.tokens += added as usize;
+            bucket.updated += added * interval;
+        }
+        bucket
+    }
+}
+
+impl<T: Timer> fmt::Debug for RateLimiter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("capacity", &self.capacity)
+            .field("interval", &self.interval)
+            .field("available", &self.available())
+            .finish()
+    }
+}
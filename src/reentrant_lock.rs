@@ -0,0 +1,338 @@
+use core::fmt;
+use core::future::Future;
+use core::ops::Deref;
+use core::panic::Location;
+
+use crate::sync::atomic::{AtomicUsize, Ordering};
+use crate::{Mutex, MutexGuard};
+
+/// The ID of the next [`LockOwner`].
+static NEXT_OWNER: AtomicUsize = AtomicUsize::new(1);
+
+/// Identifies the task holding a [`ReentrantLock`].
+///
+/// Async code has no stable notion of the "current task" that a lock could look up, so each task
+/// that uses a reentrant lock creates an owner and passes it to every lock operation. Lock
+/// operations with the same owner share the lock.
+///
+/// # Examples
+///
+/// ```
+/// use async_lock::{LockOwner, ReentrantLock};
+///
+/// let lock = ReentrantLock::new(0);
+/// let owner = LockOwner::new();
+///
+/// let outer = lock.try_lock(&owner).unwrap();
+/// let inner = lock.try_lock(&owner).unwrap();
+/// assert!(lock.try_lock(&LockOwner::new()).is_none());
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct LockOwner(usize);
+
+impl LockOwner {
+    /// Creates a new owner, distinct from every other owner.
+    pub fn new() -> LockOwner {
+        let id = NEXT_OWNER.fetch_add(1, Ordering::Relaxed);
+
+        // Make sure the IDs never wrap around to zero, which means "not held".
+        if id > isize::MAX as usize {
+            crate::abort();
+        }
+        LockOwner(id)
+    }
+}
+
+impl Default for LockOwner {
+    fn default() -> LockOwner {
+        LockOwner::new()
+    }
+}
+
+/// A lock that the owner holding it can acquire again.
+///
+/// Lock operations with the same [`LockOwner`] do not wait for each other, so callbacks that run
+/// while their task holds the lock can lock it again instead of deadlocking. Lock operations with
+/// other owners wait until every guard of the current owner has been dropped.
+///
+/// Because several guards may exist at once, they only give shared access to the data. Use a
+/// [`Cell`][`core::cell::Cell`] or [`RefCell`][`core::cell::RefCell`] inside the lock to mutate it.
+/// Guards of one owner can also be used from different threads, so the lock can only be shared
+/// between threads if `T` is [`Sync`].
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::{LockOwner, ReentrantLock};
+/// use std::cell::RefCell;
+///
+/// let log = ReentrantLock::new(RefCell::new(Vec::new()));
+/// let owner = LockOwner::new();
+///
+/// async fn record(log: &ReentrantLock<RefCell<Vec<&'static str>>>, owner: &LockOwner) {
+///     log.lock(owner).await.borrow_mut().push("callback");
+/// }
+///
+/// let guard = log.lock(&owner).await;
+/// guard.borrow_mut().push("start");
+/// record(&log, &owner).await;
+/// drop(guard);
+///
+/// assert_eq!(*log.lock(&owner).await.borrow(), ["start", "callback"]);
+/// # })
+/// ```
+pub struct ReentrantLock<T: ?Sized> {
+    /// Held while any guard of the current owner exists.
+    mutex: Mutex<()>,
+
+    /// The ID of the owner holding the lock, or zero.
+    owner: AtomicUsize,
+
+    /// The number of guards of the current owner.
+    count: AtomicUsize,
+
+    /// The value inside the lock.
+    data: T,
+}
+
+impl<T> ReentrantLock<T> {
+    /// Creates a new reentrant lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::ReentrantLock;
+    ///
+    /// let lock = ReentrantLock::new(0);
+    /// ```
+    pub const fn new(data: T) -> ReentrantLock<T> {
+        ReentrantLock {
+            mutex: Mutex::new(()),
+            owner: AtomicUsize::new(0),
+            count: AtomicUsize::new(0),
+            data,
+        }
+    }
+
+    /// Consumes the lock, returning the underlying data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::ReentrantLock;
+    ///
+    /// let lock = ReentrantLock::new(10);
+    /// assert_eq!(lock.into_inner(), 10);
+    /// ```
+    pub fn into_inner(self) -> T {
+        self.data
+    }
+}
+
+impl<T: ?Sized> ReentrantLock<T> {
+    /// Acquires the lock on behalf of `owner`.
+    ///
+    /// If `owner` already holds the lock, this completes immediately. Otherwise it waits until the
+    /// lock is released by its current owner. Returns a guard that releases the lock when it and
+    /// every other guard of `owner` have been dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{LockOwner, ReentrantLock};
+    ///
+    /// let lock = ReentrantLock::new(10);
+    /// let owner = LockOwner::new();
+    ///
+    /// let outer = lock.lock(&owner).await;
+    /// let inner = lock.lock(&owner).await;
+    /// assert_eq!(*inner, 10);
+    /// # })
+    /// ```
+    #[track_caller]
+    pub fn lock(&self, owner: &LockOwner) -> impl Future<Output = ReentrantLockGuard<'_, T>> {
+        let id = owner.0;
+        let location = Location::caller();
+
+        async move {
+            if let Some(guard) = self.reenter(id) {
+                return guard;
+            }
+            let guard = self.mutex.lock_at(location).await;
+            self.enter(guard, id)
+        }
+    }
+
+    /// Attempts to acquire the lock on behalf of `owner`.
+    ///
+    /// If the lock is held by another owner, then [`None`] is returned. Otherwise, a guard is
+    /// returned that releases the lock when it and every other guard of `owner` have been dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::{LockOwner, ReentrantLock};
+    ///
+    /// let lock = ReentrantLock::new(10);
+    /// let (first, second) = (LockOwner::new(), LockOwner::new());
+    ///
+    /// let guard = lock.try_lock(&first).unwrap();
+    /// assert!(lock.try_lock(&first).is_some());
+    /// assert!(lock.try_lock(&second).is_none());
+    /// ```
+    #[track_caller]
+    pub fn try_lock(&self, owner: &LockOwner) -> Option<ReentrantLockGuard<'_, T>> {
+        if let Some(guard) = self.reenter(owner.0) {
+            return Some(guard);
+        }
+        self.mutex
+            .try_lock()
+            .map(|guard| self.enter(guard, owner.0))
+    }
+
+    /// Returns `true` if the lock is held by `owner`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::{LockOwner, ReentrantLock};
+    ///
+    /// let lock = ReentrantLock::new(());
+    /// let owner = LockOwner::new();
+    /// assert!(!lock.is_held_by(&owner));
+    ///
+    /// let guard = lock.try_lock(&owner).unwrap();
+    /// assert!(lock.is_held_by(&owner));
+    /// ```
+    pub fn is_held_by(&self, owner: &LockOwner) -> bool {
+        self.owner.load(Ordering::Acquire) == owner.0
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the lock mutably, no actual locking takes place -- the mutable
+    /// borrow statically guarantees the lock is not held.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::{LockOwner, ReentrantLock};
+    ///
+    /// let mut lock = ReentrantLock::new(0);
+    /// *lock.get_mut() = 10;
+    /// assert_eq!(*lock.try_lock(&LockOwner::new()).unwrap(), 10);
+    /// ```
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.data
+    }
+
+    /// Adds a guard if the owner with ID `id` already holds the lock.
+    ///
+    /// Other threads may drop the last guard of the owner meanwhile, so the count is only raised
+    /// while some guard still keeps the lock held. The lock may also have passed to another owner
+    /// before the count was raised, so the owner is checked again afterwards, and the new guard
+    /// is given back if it changed.
+    fn reenter(&self, id: usize) -> Option<ReentrantLockGuard<'_, T>> {
+        if self.owner.load(Ordering::Acquire) != id {
+            return None;
+        }
+
+        let mut count = self.count.load(Ordering::Relaxed);
+        loop {
+            if count == 0 {
+                return None;
+            }
+
+            // Make sure the number of guards doesn't overflow.
+            if count > isize::MAX as usize {
+                crate::abort();
+            }
+            match self.count.compare_exchange_weak(
+                count,
+                count + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(c) => count = c,
+            }
+        }
+
+        // Whoever owns the lock now, this guard keeps it held, so the owner cannot change anymore.
+        let guard = ReentrantLockGuard { lock: self };
+        if self.owner.load(Ordering::Acquire) != id {
+            // If the other owner dropped its guards in the meantime, this releases the lock.
+            drop(guard);
+            return None;
+        }
+        Some(guard)
+    }
+
+    /// Hands the lock, just acquired with `guard`, to the owner with ID `id`.
+    fn enter(&self, guard: MutexGuard<'_, ()>, id: usize) -> ReentrantLockGuard<'_, T> {
+        // The mutex stays locked until the last guard of this owner is dropped.
+        MutexGuard::into_raw(guard);
+        self.count.store(1, Ordering::Relaxed);
+        self.owner.store(id, Ordering::Release);
+        ReentrantLockGuard { lock: self }
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for ReentrantLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReentrantLock")
+            .field("data", &&self.data)
+            .finish()
+    }
+}
+
+impl<T> From<T> for ReentrantLock<T> {
+    fn from(val: T) -> ReentrantLock<T> {
+        ReentrantLock::new(val)
+    }
+}
+
+impl<T: Default> Default for ReentrantLock<T> {
+    fn default() -> ReentrantLock<T> {
+        ReentrantLock::new(Default::default())
+    }
+}
+
+/// A guard that releases a [`ReentrantLock`] once every guard of its owner has been dropped.
+pub struct ReentrantLockGuard<'a, T: ?Sized> {
+    lock: &'a ReentrantLock<T>,
+}
+
+impl<T: ?Sized> Drop for ReentrantLockGuard<'_, T> {
+    fn drop(&mut self) {
+        if self.lock.count.fetch_sub(1, Ordering::Release) == 1 {
+            self.lock.owner.store(0, Ordering::Relaxed);
+
+            // SAFETY: The mutex was locked by the guard consumed in `enter()`, and only the last
+            // guard of the owner rebuilds it.
+            drop(unsafe { MutexGuard::from_raw(&self.lock.mutex) });
+        }
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for ReentrantLockGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display + ?Sized> fmt::Display for ReentrantLockGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized> Deref for ReentrantLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.lock.data
+    }
+}
@@ -0,0 +1,157 @@
+//! A process-wide list of every lock that has been used.
+
+use std::panic::Location;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+use crate::diagnostics::{HolderInfo, LockSnapshot, WaiterInfo};
+use crate::time::Instant;
+
+/// Live locks, pruned of dropped ones as the list grows.
+static REGISTRY: Mutex<Vec<Weak<Record>>> = Mutex::new(Vec::new());
+
+/// The ID of the next lock operation to start waiting.
+static NEXT_WAITER: AtomicU64 = AtomicU64::new(1);
+
+/// The registry entry of a single lock, shared with the lock itself.
+pub(crate) struct Record {
+    kind: &'static str,
+    id: usize,
+    name: Option<&'static str>,
+
+    /// Number of guards currently holding the lock.
+    holders: AtomicUsize,
+
+    /// The most recent acquisition of the lock, or `None` if it is not held.
+    holder: Mutex<Option<HolderInfo>>,
+
+    /// Lock operations currently waiting, in the order they started waiting.
+    waiters: Mutex<Vec<WaiterInfo>>,
+}
+
+impl Record {
+    pub(crate) fn acquired(&self, location: &'static Location<'static>) {
+        let holder = HolderInfo {
+            label: crate::diagnostics::current_label(),
+            location,
+            since: Instant::now(),
+        };
+
+        let mut current = self.lock_holder();
+        self.holders.fetch_add(1, Ordering::Relaxed);
+        *current = Some(holder);
+    }
+
+    pub(crate) fn released(&self) {
+        let mut current = self.lock_holder();
+        if self.holders.fetch_sub(1, Ordering::Relaxed) == 1 {
+            *current = None;
+        }
+    }
+
+    /// Returns the most recent acquisition of the lock, if it is held.
+    pub(crate) fn holder(&self) -> Option<HolderInfo> {
+        self.lock_holder().clone()
+    }
+
+    fn lock_holder(&self) -> std::sync::MutexGuard<'_, Option<HolderInfo>> {
+        self.holder.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Adds a waiting lock operation and returns its ID.
+    pub(crate) fn wait_started(&self, location: &'static Location<'static>) -> u64 {
+        let id = NEXT_WAITER.fetch_add(1, Ordering::Relaxed);
+        let waiter = WaiterInfo {
+            id,
+            label: crate::diagnostics::current_label(),
+            location,
+            since: Instant::now(),
+        };
+        self.lock_waiters().push(waiter);
+        id
+    }
+
+    pub(crate) fn wait_ended(&self, id: u64) {
+        let mut waiters = self.lock_waiters();
+        if let Some(i) = waiters.iter().position(|w| w.id == id) {
+            waiters.remove(i);
+        }
+    }
+
+    /// Returns the lock operations currently waiting.
+    pub(crate) fn waiters(&self) -> Vec<WaiterInfo> {
+        self.lock_waiters().clone()
+    }
+
+    fn lock_waiters(&self) -> std::sync::MutexGuard<'_, Vec<WaiterInfo>> {
+        self.waiters.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn snapshot(&self) -> LockSnapshot {
+        let holder = self.lock_holder();
+
+        LockSnapshot {
+            kind: self.kind,
+            id: self.id,
+            name: self.name,
+            holders: self.holders.load(Ordering::Relaxed),
+            holder: holder.as_ref().map(|h| h.location),
+            waiters: self.lock_waiters().len(),
+        }
+    }
+}
+
+/// Adds a lock of the given kind and name, first used at address `id`, to the registry.
+pub(crate) fn register(kind: &'static str, id: usize, name: Option<&'static str>) -> Arc<Record> {
+    let record = Arc::new(Record {
+        kind,
+        id,
+        name,
+        holders: AtomicUsize::new(0),
+        holder: Mutex::new(None),
+        waiters: Mutex::new(Vec::new()),
+    });
+
+    let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    if registry.len() == registry.capacity() {
+        registry.retain(|r| r.strong_count() > 0);
+    }
+    registry.push(Arc::downgrade(&record));
+    record
+}
+
+/// Takes a snapshot of every live lock in the registry.
+pub(crate) fn dump() -> Vec<LockSnapshot> {
+    let registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    registry
+        .iter()
+        .filter_map(Weak::upgrade)
+        .map(|r| r.snapshot())
+        .collect()
+}
+
+/// Returns every lock operation that has been waiting for at least `budget`, with its lock.
+pub(crate) fn stuck(budget: std::time::Duration) -> Vec<(LockSnapshot, WaiterInfo)> {
+    let records = {
+        let registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+        registry
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>()
+    };
+
+    let mut stuck = Vec::new();
+    for record in records {
+        let waiters = record.waiters();
+        if waiters.iter().any(|w| w.waited() >= budget) {
+            let lock = record.snapshot();
+            stuck.extend(
+                waiters
+                    .into_iter()
+                    .filter(|w| w.waited() >= budget)
+                    .map(|w| (lock, w)),
+            );
+        }
+    }
+    stuck
+}
@@ -1,13 +1,31 @@
-use std::cell::UnsafeCell;
-use std::fmt;
-use std::mem;
-use std::ops::{Deref, DerefMut};
-use std::process;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::future::Future;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::panic::Location;
+use core::ptr;
+use core::time::Duration;
+
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+#[cfg(feature = "registry")]
+use alloc::vec::Vec;
 
 use event_listener::Event;
-
-use crate::{Mutex, MutexGuard};
+#[cfg(feature = "stream")]
+use futures_core::Stream;
+
+#[cfg(feature = "events")]
+use crate::diagnostics::LockEvents;
+#[cfg(feature = "hooks")]
+use crate::diagnostics::LockMetrics;
+#[cfg(feature = "registry")]
+use crate::diagnostics::{HolderInfo, WaiterInfo};
+use crate::mutex::{RawMutex, RawMutexGuard};
+use crate::sync::atomic::{AtomicUsize, Ordering};
+use crate::timer::{self, Timer};
+use crate::trace;
 
 const WRITER_BIT: usize = 1;
 const ONE_READER: usize = 2;
@@ -40,9 +58,22 @@ const ONE_READER: usize = 2;
 /// assert_eq!(*w, 6);
 /// # })
 /// ```
+///
+/// Like [`Mutex`][`crate::Mutex`], the lock may hold an unsized value that a sized lock is coerced
+/// to:
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::RwLock;
+///
+/// let lock: &RwLock<[u8]> = &RwLock::new([1, 2, 3]);
+/// lock.write().await[0] = 4;
+/// assert_eq!(*lock.read().await, [4, 2, 3]);
+/// # })
+/// ```
 pub struct RwLock<T: ?Sized> {
     /// Acquired by the writer.
-    mutex: Mutex<()>,
+    mutex: RawMutex,
 
     /// Event triggered when the last reader is dropped.
     no_readers: Event,
@@ -50,6 +81,9 @@ pub struct RwLock<T: ?Sized> {
     /// Event triggered when the writer is dropped.
     no_writer: Event,
 
+    /// Instrumentation settings.
+    hooks: trace::Hooks,
+
     /// Current state of the lock.
     ///
     /// The least significant bit (`WRITER_BIT`) is set to 1 when a writer is holding the lock or
@@ -78,9 +112,91 @@ impl<T> RwLock<T> {
     /// ```
     pub const fn new(t: T) -> RwLock<T> {
         RwLock {
-            mutex: Mutex::new(()),
+            mutex: RawMutex::new(),
+            no_readers: Event::new(),
+            no_writer: Event::new(),
+            hooks: trace::Hooks::new(),
+            state: AtomicUsize::new(0),
+            value: UnsafeCell::new(t),
+        }
+    }
+
+    /// Creates a new reader-writer lock with a name.
+    ///
+    /// The name identifies the lock in its [`Debug`][`fmt::Debug`] output, in `tracing` events, in
+    /// slow-acquisition warnings, in the lock registry, and in deadlock reports, instead of its
+    /// address.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::new_named("routes", 0);
+    /// assert_eq!(lock.name(), Some("routes"));
+    /// assert_eq!(format!("{:?}", lock), r#"RwLock { name: "routes", value: 0 }"#);
+    /// ```
+    pub const fn new_named(name: &'static str, t: T) -> RwLock<T> {
+        RwLock {
+            mutex: RawMutex::new(),
+            no_readers: Event::new(),
+            no_writer: Event::new(),
+            hooks: trace::Hooks::named(name),
+            state: AtomicUsize::new(0),
+            value: UnsafeCell::new(t),
+        }
+    }
+
+    /// Creates a new reader-writer lock that reports to its own metrics hooks.
+    ///
+    /// The hooks are used instead of the ones installed with
+    /// [`set_metrics()`][`crate::diagnostics::set_metrics()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::diagnostics::LockMetrics;
+    /// use async_lock::RwLock;
+    /// use std::sync::Arc;
+    ///
+    /// struct Ignore;
+    /// impl LockMetrics for Ignore {}
+    ///
+    /// let lock = RwLock::with_metrics(0, Arc::new(Ignore));
+    /// ```
+    #[cfg(feature = "hooks")]
+    pub fn with_metrics(t: T, metrics: Arc<dyn LockMetrics>) -> RwLock<T> {
+        RwLock {
+            mutex: RawMutex::new(),
+            no_readers: Event::new(),
+            no_writer: Event::new(),
+            hooks: trace::Hooks::with_metrics(metrics),
+            state: AtomicUsize::new(0),
+            value: UnsafeCell::new(t),
+        }
+    }
+
+    /// Creates a new reader-writer lock with a priority ceiling.
+    ///
+    /// Every read or write acquisition raises the acquiring task to `ceiling` through the hooks
+    /// installed with [`priority::set_hooks()`][`crate::priority::set_hooks()`], and releasing it
+    /// restores the previous priority.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::with_priority_ceiling(0, 5);
+    /// assert_eq!(lock.priority_ceiling(), Some(5));
+    /// ```
+    #[cfg(feature = "priority-ceiling")]
+    pub const fn with_priority_ceiling(t: T, ceiling: u32) -> RwLock<T> {
+        RwLock {
+            mutex: RawMutex::new(),
             no_readers: Event::new(),
             no_writer: Event::new(),
+            hooks: trace::Hooks::with_ceiling(ceiling),
             state: AtomicUsize::new(0),
             value: UnsafeCell::new(t),
         }
@@ -121,7 +237,13 @@ impl<T: ?Sized> RwLock<T> {
     /// assert!(lock.try_read().is_some());
     /// # })
     /// ```
+    #[track_caller]
     pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        self.try_read_at(Location::caller())
+    }
+
+    /// Attempts to acquire a read lock on behalf of the code at `location`.
+    fn try_read_at(&self, location: &'static Location<'static>) -> Option<RwLockReadGuard<'_, T>> {
         let mut state = self.state.load(Ordering::Acquire);
 
         loop {
@@ -132,8 +254,8 @@ impl<T: ?Sized> RwLock<T> {
             }
 
             // Make sure the number of readers doesn't overflow.
-            if state > std::isize::MAX as usize {
-                process::abort();
+            if state > isize::MAX as usize {
+                crate::abort();
             }
 
             // Increment the number of readers.
@@ -143,7 +265,10 @@ impl<T: ?Sized> RwLock<T> {
                 Ordering::AcqRel,
                 Ordering::Acquire,
             ) {
-                Ok(_) => return Some(RwLockReadGuard(self)),
+                Ok(_) => {
+                    let held = trace::acquired(self.target("read"), location);
+                    return Some(RwLockReadGuard(self, held));
+                }
                 Err(s) => state = s,
             }
         }
@@ -170,43 +295,161 @@ impl<T: ?Sized> RwLock<T> {
     /// assert!(lock.try_read().is_some());
     /// # })
     /// ```
-    pub async fn read(&self) -> RwLockReadGuard<'_, T> {
-        let mut state = self.state.load(Ordering::Acquire);
+    #[track_caller]
+    pub fn read(&self) -> impl Future<Output = RwLockReadGuard<'_, T>> + '_ {
+        self.read_at(Location::caller())
+    }
 
-        loop {
-            if state & WRITER_BIT == 0 {
-                // Make sure the number of readers doesn't overflow.
-                if state > std::isize::MAX as usize {
-                    process::abort();
-                }
+    /// Acquires a read lock, giving up after `timeout`.
+    ///
+    /// Returns a guard that releases the lock when dropped, or [`None`] if the lock did not become
+    /// available in time. This lets a reader fall back to stale data instead of waiting behind a
+    /// slow writer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{AsyncIoTimer, RwLock};
+    /// use std::time::Duration;
+    ///
+    /// let lock = RwLock::new(1);
+    /// let writer = lock.write().await;
+    /// assert!(lock.read_timeout(AsyncIoTimer, Duration::from_millis(10)).await.is_none());
+    ///
+    /// drop(writer);
+    /// assert!(lock.read_timeout(AsyncIoTimer, Duration::from_millis(10)).await.is_some());
+    /// # });
+    /// ```
+    #[track_caller]
+    pub fn read_timeout<'a>(
+        &'a self,
+        timer: impl Timer + 'a,
+        timeout: Duration,
+    ) -> impl Future<Output = Option<RwLockReadGuard<'a, T>>> + 'a {
+        let location = Location::caller();
+
+        async move {
+            if let Some(guard) = self.try_read_at(location) {
+                return Some(guard);
+            }
+            timer::timeout(self.read_at(location), timer.sleep(timeout)).await
+        }
+    }
 
-                // If nobody is holding a write lock or attempting to acquire it, increment the
-                // number of readers.
-                match self.state.compare_exchange(
-                    state,
-                    state + ONE_READER,
-                    Ordering::AcqRel,
-                    Ordering::Acquire,
-                ) {
-                    Ok(_) => return RwLockReadGuard(self),
-                    Err(s) => state = s,
-                }
-            } else {
-                // Start listening for "no writer" events.
-                let listener = self.no_writer.listen();
+    /// Adds a reader to a lock that is already held for reading.
+    fn add_reader(&self) {
+        // Make sure the number of readers doesn't overflow.
+        if self.state.fetch_add(ONE_READER, Ordering::Relaxed) > isize::MAX as usize {
+            crate::abort();
+        }
+    }
 
-                // Check again if there's a writer.
-                if self.state.load(Ordering::SeqCst) & WRITER_BIT != 0 {
-                    // Wait until the writer is dropped.
-                    listener.await;
-                    // Notify the next reader waiting in line.
-                    self.no_writer.notify(1);
-                }
+    /// Acquires a read lock on behalf of the code at `location`.
+    #[inline]
+    pub(crate) fn read_at(
+        &self,
+        location: &'static Location<'static>,
+    ) -> impl Future<Output = RwLockReadGuard<'_, T>> + '_ {
+        trace::instrument(self.target("read"), "RwLock::read", async move {
+            crate::coop::consume_budget(self).await;
+
+            let mut state = self.state.load(Ordering::Acquire);
+            let mut wait = None;
+
+            loop {
+                if state & WRITER_BIT == 0 {
+                    // Make sure the number of readers doesn't overflow.
+                    if state > isize::MAX as usize {
+                        crate::abort();
+                    }
+
+                    // If nobody is holding a write lock or attempting to acquire it, increment the
+                    // number of readers.
+                    match self.state.compare_exchange(
+                        state,
+                        state + ONE_READER,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    ) {
+                        Ok(_) => {
+                            let held = match wait {
+                                Some(wait) => trace::Wait::acquired(wait),
+                                None => trace::acquired(self.target("read"), location),
+                            };
+                            return RwLockReadGuard(self, held);
+                        }
+                        Err(s) => state = s,
+                    }
+                } else {
+                    // Start listening for "no writer" events.
+                    let listener = self.no_writer.listen();
+
+                    // Check again if there's a writer.
+                    if self.state.load(Ordering::SeqCst) & WRITER_BIT != 0 {
+                        wait.get_or_insert_with(|| trace::wait(self.target("read"), location));
+
+                        // Wait until the writer is dropped.
+                        listener.await;
+                        crate::coop::yielded();
+                        // Notify the next reader waiting in line.
+                        self.no_writer.notify(1);
+                    }
 
-                // Reload the state.
-                state = self.state.load(Ordering::Acquire);
+                    // Reload the state.
+                    state = self.state.load(Ordering::Acquire);
+                }
             }
-        }
+        })
+    }
+
+    /// Acquires a read lock, blocking the current thread until it is available.
+    ///
+    /// Blocked threads wait in the same queues as async lock operations, so a busy pool of blocking
+    /// threads does not starve tasks or vice versa. This method must not be called from async code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::new(1);
+    ///
+    /// let reader = lock.read_blocking();
+    /// assert_eq!(*reader, 1);
+    ///
+    /// assert!(lock.try_read().is_some());
+    /// ```
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    #[track_caller]
+    pub fn read_blocking(&self) -> RwLockReadGuard<'_, T> {
+        crate::blocking::block_on(self.read())
+    }
+
+    /// Acquires a read lock, blocking the current thread for at most `timeout`.
+    ///
+    /// Returns a guard that releases the lock when dropped, or [`None`] if the lock did not become
+    /// available in time. Like [`read_blocking()`][`RwLock::read_blocking()`], the thread waits in
+    /// the same queue as async tasks. This method must not be called from async code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    /// use std::time::Duration;
+    ///
+    /// let lock = RwLock::new(1);
+    /// let writer = lock.write_blocking();
+    /// assert!(lock.try_read_for(Duration::from_millis(10)).is_none());
+    ///
+    /// drop(writer);
+    /// assert_eq!(*lock.try_read_for(Duration::from_millis(10)).unwrap(), 1);
+    /// ```
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    #[track_caller]
+    pub fn try_read_for(&self, timeout: Duration) -> Option<RwLockReadGuard<'_, T>> {
+        crate::blocking::block_on_timeout(self.read(), timeout)
     }
 
     /// Attempts to acquire a read lock with the possiblity to upgrade to a write lock.
@@ -233,15 +476,27 @@ impl<T: ?Sized> RwLock<T> {
     /// *writer = 2;
     /// # })
     /// ```
+    #[track_caller]
     pub fn try_upgradable_read(&self) -> Option<RwLockUpgradableReadGuard<'_, T>> {
+        self.try_upgradable_read_at(Location::caller())
+    }
+
+    /// Attempts to acquire an upgradable read lock on behalf of the code at `location`.
+    fn try_upgradable_read_at(
+        &self,
+        location: &'static Location<'static>,
+    ) -> Option<RwLockUpgradableReadGuard<'_, T>> {
         // First try grabbing the mutex.
-        let lock = self.mutex.try_lock()?;
+        if !self.mutex.try_lock() {
+            return None;
+        }
+        let lock = RawMutexGuard(&self.mutex);
 
         let mut state = self.state.load(Ordering::Acquire);
 
         // Make sure the number of readers doesn't overflow.
-        if state > std::isize::MAX as usize {
-            process::abort();
+        if state > isize::MAX as usize {
+            crate::abort();
         }
 
         // Increment the number of readers.
@@ -253,10 +508,11 @@ impl<T: ?Sized> RwLock<T> {
                 Ordering::Acquire,
             ) {
                 Ok(_) => {
+                    let held = trace::acquired(self.target("upgradable_read"), location);
                     return Some(RwLockUpgradableReadGuard {
-                        reader: RwLockReadGuard(self),
+                        reader: RwLockReadGuard(self, held),
                         reserved: lock,
-                    })
+                    });
                 }
                 Err(s) => state = s,
             }
@@ -289,34 +545,71 @@ impl<T: ?Sized> RwLock<T> {
     /// *writer = 2;
     /// # })
     /// ```
-    pub async fn upgradable_read(&self) -> RwLockUpgradableReadGuard<'_, T> {
-        // First grab the mutex.
-        let lock = self.mutex.lock().await;
+    #[track_caller]
+    pub fn upgradable_read(&self) -> impl Future<Output = RwLockUpgradableReadGuard<'_, T>> + '_ {
+        let location = Location::caller();
+
+        trace::instrument(
+            self.target("upgradable_read"),
+            "RwLock::upgradable_read",
+            async move {
+                if let Some(guard) = self.try_upgradable_read_at(location) {
+                    return guard;
+                }
+                let wait = trace::wait(self.target("upgradable_read"), location);
 
-        let mut state = self.state.load(Ordering::Acquire);
+                // First grab the mutex.
+                self.mutex.lock().await;
+                let lock = RawMutexGuard(&self.mutex);
 
-        // Make sure the number of readers doesn't overflow.
-        if state > std::isize::MAX as usize {
-            process::abort();
-        }
+                let mut state = self.state.load(Ordering::Acquire);
 
-        // Increment the number of readers.
-        loop {
-            match self.state.compare_exchange(
-                state,
-                state + ONE_READER,
-                Ordering::AcqRel,
-                Ordering::Acquire,
-            ) {
-                Ok(_) => {
-                    return RwLockUpgradableReadGuard {
-                        reader: RwLockReadGuard(self),
-                        reserved: lock,
+                // Make sure the number of readers doesn't overflow.
+                if state > isize::MAX as usize {
+                    crate::abort();
+                }
+
+                // Increment the number of readers.
+                loop {
+                    match self.state.compare_exchange(
+                        state,
+                        state + ONE_READER,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    ) {
+                        Ok(_) => {
+                            return RwLockUpgradableReadGuard {
+                                reader: RwLockReadGuard(self, wait.acquired()),
+                                reserved: lock,
+                            }
+                        }
+                        Err(s) => state = s,
                     }
                 }
-                Err(s) => state = s,
-            }
-        }
+            },
+        )
+    }
+
+    /// Acquires a read lock with the option to upgrade it, blocking the current thread until it is
+    /// available.
+    ///
+    /// This is the blocking version of [`upgradable_read()`][`RwLock::upgradable_read()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::{RwLock, RwLockUpgradableReadGuard};
+    ///
+    /// let lock = RwLock::new(1);
+    ///
+    /// let reader = lock.upgradable_read_blocking();
+    /// assert_eq!(*reader, 1);
+    /// assert_eq!(*lock.try_read().unwrap(), 1);
+    /// ```
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    #[track_caller]
+    pub fn upgradable_read_blocking(&self) -> RwLockUpgradableReadGuard<'_, T> {
+        crate::blocking::block_on(self.upgradable_read())
     }
 
     /// Attempts to acquire a write lock.
@@ -337,9 +630,21 @@ impl<T: ?Sized> RwLock<T> {
     /// assert!(lock.try_write().is_none());
     /// # })
     /// ```
+    #[track_caller]
     pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        self.try_write_at(Location::caller())
+    }
+
+    /// Attempts to acquire a write lock on behalf of the code at `location`.
+    fn try_write_at(
+        &self,
+        location: &'static Location<'static>,
+    ) -> Option<RwLockWriteGuard<'_, T>> {
         // First try grabbing the mutex.
-        let lock = self.mutex.try_lock()?;
+        if !self.mutex.try_lock() {
+            return None;
+        }
+        let lock = RawMutexGuard(&self.mutex);
 
         // If there are no readers, grab the write lock.
         if self
@@ -348,7 +653,10 @@ impl<T: ?Sized> RwLock<T> {
             .is_ok()
         {
             Some(RwLockWriteGuard {
-                writer: RwLockWriteGuardInner(self),
+                writer: RwLockWriteGuardInner(
+                    self,
+                    trace::acquired(self.target("write"), location),
+                ),
                 reserved: lock,
             })
         } else {
@@ -372,36 +680,38 @@ impl<T: ?Sized> RwLock<T> {
     /// assert!(lock.try_read().is_none());
     /// # })
     /// ```
-    pub async fn write(&self) -> RwLockWriteGuard<'_, T> {
-        // First grab the mutex.
-        let lock = self.mutex.lock().await;
-
-        // Set `WRITER_BIT` and create a guard that unsets it in case this future is canceled.
-        self.state.fetch_or(WRITER_BIT, Ordering::SeqCst);
-        let guard = RwLockWriteGuard {
-            writer: RwLockWriteGuardInner(self),
-            reserved: lock,
-        };
-
-        // If there are readers, we need to wait for them to finish.
-        while self.state.load(Ordering::SeqCst) != WRITER_BIT {
-            // Start listening for "no readers" events.
-            let listener = self.no_readers.listen();
-
-            // Check again if there are readers.
-            if self.state.load(Ordering::Acquire) != WRITER_BIT {
-                // Wait for the readers to finish.
-                listener.await;
-            }
-        }
+    #[track_caller]
+    pub fn write(&self) -> impl Future<Output = RwLockWriteGuard<'_, T>> + '_ {
+        self.write_at(Location::caller())
+    }
 
-        guard
+    /// Acquires a read lock, runs `f` on the data, and releases the lock again.
+    ///
+    /// The guard can never be held longer than `f` runs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::new(vec![1, 2]);
+    /// assert_eq!(lock.with_read(|v| v.len()).await, 2);
+    /// # })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn with_read<'a, R>(
+        &'a self,
+        f: impl FnOnce(&T) -> R + 'a,
+    ) -> impl Future<Output = R> + 'a {
+        let lock = self.read();
+        async move { f(&*lock.await) }
     }
 
-    /// Returns a mutable reference to the inner value.
+    /// Acquires the write lock, runs `f` on the data, and releases the lock again.
     ///
-    /// Since this call borrows the lock mutably, no actual locking takes place. The mutable borrow
-    /// statically guarantees no locks exist.
+    /// The guard can never be held longer than `f` runs.
     ///
     /// # Examples
     ///
@@ -409,53 +719,754 @@ impl<T: ?Sized> RwLock<T> {
     /// # futures_lite::future::block_on(async {
     /// use async_lock::RwLock;
     ///
-    /// let mut lock = RwLock::new(1);
+    /// let lock = RwLock::new(vec![1, 2]);
+    /// lock.with_write(|v| v.push(3)).await;
+    /// assert_eq!(*lock.read().await, [1, 2, 3]);
+    /// # })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn with_write<'a, R>(
+        &'a self,
+        f: impl FnOnce(&mut T) -> R + 'a,
+    ) -> impl Future<Output = R> + 'a {
+        let lock = self.write();
+        async move { f(&mut *lock.await) }
+    }
+
+    /// Acquires the write lock, replaces the data with `value`, and returns the old data.
     ///
-    /// *lock.get_mut() = 2;
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::new(1);
+    /// assert_eq!(lock.replace(2).await, 1);
     /// assert_eq!(*lock.read().await, 2);
     /// # })
     /// ```
-    pub fn get_mut(&mut self) -> &mut T {
-        unsafe { &mut *self.value.get() }
+    #[inline]
+    #[track_caller]
+    pub fn replace(&self, value: T) -> impl Future<Output = T> + '_
+    where
+        T: Sized,
+    {
+        self.with_write(|data| mem::replace(data, value))
     }
-}
 
-impl<T: fmt::Debug + ?Sized> fmt::Debug for RwLock<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        struct Locked;
-        impl fmt::Debug for Locked {
-            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                f.write_str("<locked>")
-            }
-        }
+    /// Acquires the write lock, takes the data, and leaves [`Default::default()`] in its place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::new(vec![1, 2]);
+    /// assert_eq!(lock.take().await, [1, 2]);
+    /// assert!(lock.read().await.is_empty());
+    /// # })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn take(&self) -> impl Future<Output = T> + '_
+    where
+        T: Default,
+    {
+        self.with_write(mem::take)
+    }
 
-        match self.try_read() {
-            None => f.debug_struct("RwLock").field("value", &Locked).finish(),
-            Some(guard) => f.debug_struct("RwLock").field("value", &&*guard).finish(),
+    /// Acquires the write locks of both locks and swaps their data.
+    ///
+    /// The locks are acquired with [`lock_all()`][`crate::lock_all()`], so two tasks swapping the
+    /// same locks in opposite orders cannot deadlock. Swapping a lock with itself does nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::RwLock;
+    ///
+    /// let (a, b) = (RwLock::new(1), RwLock::new(2));
+    /// a.swap(&b).await;
+    /// assert_eq!((*a.read().await, *b.read().await), (2, 1));
+    /// # })
+    /// ```
+    pub async fn swap(&self, other: &RwLock<T>)
+    where
+        T: Sized,
+    {
+        if ptr::eq(self, other) {
+            return;
         }
+        let (mut a, mut b) = crate::lock_all((self, other)).await;
+        mem::swap(&mut *a, &mut *b);
     }
-}
 
-impl<T> From<T> for RwLock<T> {
-    fn from(val: T) -> RwLock<T> {
-        RwLock::new(val)
+    /// Acquires a write lock, giving up after `timeout`.
+    ///
+    /// Returns a guard that releases the lock when dropped, or [`None`] if the lock did not become
+    /// available in time. A write lock operation that gives up stops blocking new readers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{AsyncIoTimer, RwLock};
+    /// use std::time::Duration;
+    ///
+    /// let lock = RwLock::new(1);
+    /// let reader = lock.read().await;
+    /// assert!(lock.write_timeout(AsyncIoTimer, Duration::from_millis(10)).await.is_none());
+    ///
+    /// // The writer that gave up no longer blocks readers.
+    /// assert!(lock.try_read().is_some());
+    ///
+    /// drop(reader);
+    /// assert!(lock.write_timeout(AsyncIoTimer, Duration::from_millis(10)).await.is_some());
+    /// # });
+    /// ```
+    #[track_caller]
+    pub fn write_timeout<'a>(
+        &'a self,
+        timer: impl Timer + 'a,
+        timeout: Duration,
+    ) -> impl Future<Output = Option<RwLockWriteGuard<'a, T>>> + 'a {
+        let location = Location::caller();
+
+        async move {
+            if let Some(guard) = self.try_write_at(location) {
+                return Some(guard);
+            }
+            timer::timeout(self.write_at(location), timer.sleep(timeout)).await
+        }
     }
-}
 
-impl<T: Default + ?Sized> Default for RwLock<T> {
-    fn default() -> RwLock<T> {
-        RwLock::new(Default::default())
-    }
-}
+    /// Acquires a write lock on behalf of the code at `location`.
+    #[inline]
+    fn write_at(
+        &self,
+        location: &'static Location<'static>,
+    ) -> impl Future<Output = RwLockWriteGuard<'_, T>> + '_ {
+        trace::instrument(self.target("write"), "RwLock::write", async move {
+            if let Some(guard) = self.try_write_at(location) {
+                return guard;
+            }
+            let wait = trace::wait(self.target("write"), location);
 
-/// A guard that releases the read lock when dropped.
-pub struct RwLockReadGuard<'a, T: ?Sized>(&'a RwLock<T>);
+            // First grab the mutex.
+            self.mutex.lock().await;
+            let lock = RawMutexGuard(&self.mutex);
 
-unsafe impl<T: Sync + ?Sized> Send for RwLockReadGuard<'_, T> {}
-unsafe impl<T: Sync + ?Sized> Sync for RwLockReadGuard<'_, T> {}
+            // Set `WRITER_BIT` and create a guard that unsets it in case this future is canceled.
+            self.state.fetch_or(WRITER_BIT, Ordering::SeqCst);
+            let mut guard = RwLockWriteGuard {
+                writer: RwLockWriteGuardInner(self, trace::Held::pending()),
+                reserved: lock,
+            };
+
+            // If there are readers, we need to wait for them to finish.
+            while self.state.load(Ordering::SeqCst) != WRITER_BIT {
+                // Start listening for "no readers" events.
+                let listener = self.no_readers.listen();
+
+                // Check again if there are readers.
+                if self.state.load(Ordering::Acquire) != WRITER_BIT {
+                    // Wait for the readers to finish.
+                    listener.await;
+                    crate::coop::yielded();
+                }
+            }
+
+            guard.writer.1 = wait.acquired();
+            guard
+        })
+    }
+
+    /// Acquires a write lock, blocking the current thread until it is available.
+    ///
+    /// This is the blocking version of [`write()`][`RwLock::write()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::new(1);
+    ///
+    /// let writer = lock.write_blocking();
+    /// assert!(lock.try_read().is_none());
+    /// ```
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    #[track_caller]
+    pub fn write_blocking(&self) -> RwLockWriteGuard<'_, T> {
+        crate::blocking::block_on(self.write())
+    }
+
+    /// Acquires a write lock, blocking the current thread for at most `timeout`.
+    ///
+    /// Returns a guard that releases the lock when dropped, or [`None`] if the lock did not become
+    /// available in time. Like [`write_blocking()`][`RwLock::write_blocking()`], the thread waits
+    /// in the same queue as async tasks. This method must not be called from async code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    /// use std::time::Duration;
+    ///
+    /// let lock = RwLock::new(1);
+    /// let reader = lock.read_blocking();
+    /// assert!(lock.try_write_for(Duration::from_millis(10)).is_none());
+    ///
+    /// drop(reader);
+    /// assert!(lock.try_write_for(Duration::from_millis(10)).is_some());
+    /// ```
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    #[track_caller]
+    pub fn try_write_for(&self, timeout: Duration) -> Option<RwLockWriteGuard<'_, T>> {
+        crate::blocking::block_on_timeout(self.write(), timeout)
+    }
+
+    /// Returns a mutable reference to the inner value.
+    ///
+    /// Since this call borrows the lock mutably, no actual locking takes place. The mutable borrow
+    /// statically guarantees no locks exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::RwLock;
+    ///
+    /// let mut lock = RwLock::new(1);
+    ///
+    /// *lock.get_mut() = 2;
+    /// assert_eq!(*lock.read().await, 2);
+    /// # })
+    /// ```
+    pub fn get_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.value.get() }
+    }
+
+    /// Returns a raw pointer to the underlying data.
+    ///
+    /// This is useful for low-level integrations such as FFI or heap-profiling tools that need the
+    /// address of the data without locking. The pointer is valid for as long as the lock is, but
+    /// dereferencing it is unsafe: it may only be read while the calling code holds a read or
+    /// write lock, and only written through while it holds the write lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::new(10);
+    /// let reader = lock.try_read().unwrap();
+    /// assert_eq!(lock.data_ptr() as *const i32, &*reader as *const i32);
+    /// ```
+    pub fn data_ptr(&self) -> *mut T {
+        self.value.get()
+    }
+
+    /// Returns the number of read locks currently held, including an upgradable read lock.
+    ///
+    /// The value can change right after it was read, so this is only meant for metrics and
+    /// debugging.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::new(());
+    /// let _r1 = lock.try_read().unwrap();
+    /// let _r2 = lock.try_upgradable_read().unwrap();
+    /// assert_eq!(lock.reader_count(), 2);
+    /// ```
+    pub fn reader_count(&self) -> usize {
+        self.state.load(Ordering::Relaxed) / ONE_READER
+    }
+
+    /// Returns the name of this lock, if it was created with [`RwLock::new_named()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::new(0);
+    /// assert_eq!(lock.name(), None);
+    /// ```
+    pub fn name(&self) -> Option<&'static str> {
+        self.hooks.name()
+    }
+
+    /// Returns `true` if the lock is held by readers or a writer.
+    ///
+    /// A writer that is waiting for the last readers to leave also counts, because it already
+    /// keeps new readers out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::new(());
+    /// assert!(!lock.is_locked());
+    ///
+    /// let guard = lock.try_read().unwrap();
+    /// assert!(lock.is_locked());
+    /// ```
+    pub fn is_locked(&self) -> bool {
+        self.state.load(Ordering::Relaxed) != 0
+    }
+
+    /// Returns `true` if a writer holds the lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::new(());
+    /// let guard = lock.try_write().unwrap();
+    /// assert!(lock.is_write_locked());
+    ///
+    /// drop(guard);
+    /// let guard = lock.try_read().unwrap();
+    /// assert!(!lock.is_write_locked());
+    /// ```
+    pub fn is_write_locked(&self) -> bool {
+        self.state.load(Ordering::Relaxed) == WRITER_BIT
+    }
+
+    /// Releases a read lock without a guard.
+    ///
+    /// This is for FFI code that locks and unlocks the lock in separate calls, after the guard was
+    /// consumed with [`mem::forget()`].
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held by a read guard that was consumed without being dropped, and that
+    /// guard may no longer be used, such as through a reference returned by
+    /// [`RwLockReadGuard::leak()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::new(());
+    /// std::mem::forget(lock.try_read().unwrap());
+    /// assert!(lock.try_write().is_none());
+    ///
+    /// unsafe { lock.force_unlock_read() };
+    /// assert!(lock.try_write().is_some());
+    /// ```
+    pub unsafe fn force_unlock_read(&self) {
+        drop(RwLockReadGuard(self, trace::Held::restored()));
+    }
+
+    /// Releases the write lock without a guard.
+    ///
+    /// This is for FFI code that locks and unlocks the lock in separate calls, after the guard was
+    /// consumed with [`mem::forget()`].
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held by a write guard that was consumed without being dropped, and that
+    /// guard may no longer be used, such as through a reference returned by
+    /// [`RwLockWriteGuard::leak()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::new(());
+    /// std::mem::forget(lock.try_write().unwrap());
+    /// assert!(lock.is_write_locked());
+    ///
+    /// unsafe { lock.force_unlock_write() };
+    /// assert!(lock.try_read().is_some());
+    /// ```
+    pub unsafe fn force_unlock_write(&self) {
+        drop(RwLockWriteGuard {
+            writer: RwLockWriteGuardInner(self, trace::Held::restored()),
+            reserved: RawMutexGuard(&self.mutex),
+        });
+    }
+
+    /// Returns the lock operations currently waiting for this lock.
+    ///
+    /// Waiters are listed in the order they started waiting. Each one is identified by an opaque
+    /// ID and carries the location of the call, when it started waiting, and its
+    /// [label][`crate::diagnostics::label()`], if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{diagnostics, RwLock};
+    /// use futures_lite::future;
+    ///
+    /// let lock = RwLock::new(());
+    /// let _guard = lock.try_read().unwrap();
+    ///
+    /// let mut waiter = Box::pin(diagnostics::label("worker", lock.write()));
+    /// assert!(future::poll_once(&mut waiter).await.is_none());
+    ///
+    /// let waiters = lock.waiters();
+    /// assert_eq!(waiters.len(), 1);
+    /// assert_eq!(waiters[0].label(), Some("worker"));
+    /// # })
+    /// ```
+    #[cfg(feature = "registry")]
+    pub fn waiters(&self) -> Vec<WaiterInfo> {
+        self.target("read").waiters()
+    }
+
+    /// Returns where this lock was most recently acquired, if it is held.
+    ///
+    /// While several readers hold the lock, this reports the last one of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::new(());
+    /// assert!(lock.holder_info().is_none());
+    ///
+    /// let writer = lock.try_write().unwrap();
+    /// let holder = lock.holder_info().unwrap();
+    /// assert_eq!(holder.location().line(), line!() - 2);
+    /// ```
+    #[cfg(feature = "registry")]
+    pub fn holder_info(&self) -> Option<HolderInfo> {
+        self.target("read").holder()
+    }
+
+    /// Subscribes to the acquisitions and releases of this lock.
+    ///
+    /// The returned stream yields a [`LockEvent`][`crate::diagnostics::LockEvent`] every time
+    /// the lock is acquired or released from now on, and ends once the lock is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::diagnostics::LockAction;
+    /// use async_lock::RwLock;
+    /// use futures_lite::StreamExt;
+    ///
+    /// let lock = RwLock::new(0);
+    /// let mut events = lock.events();
+    ///
+    /// drop(lock.read().await);
+    /// assert_eq!(events.next().await.unwrap().action(), LockAction::Acquired);
+    /// assert_eq!(events.next().await.unwrap().action(), LockAction::Released);
+    /// # })
+    /// ```
+    #[cfg(feature = "events")]
+    pub fn events(&self) -> LockEvents {
+        self.target("read").events()
+    }
+
+    /// Returns the priority ceiling of this lock, if it has one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::new(0);
+    /// assert_eq!(lock.priority_ceiling(), None);
+    /// ```
+    #[cfg(feature = "priority-ceiling")]
+    pub fn priority_ceiling(&self) -> Option<u32> {
+        self.hooks.ceiling()
+    }
+
+    /// Streams clones of the items in a locked collection, holding the lock only while copying
+    /// them out.
+    ///
+    /// The read lock is acquired once per chunk of up to `chunk_size` items, so long scans do not
+    /// block writers for the whole iteration. Items are read by position: if the collection is
+    /// modified between two chunks, the stream continues at the same index, so items may be
+    /// skipped or seen twice.
+    ///
+    /// The stream is not [`Unpin`], so it has to be pinned before calling methods such as
+    /// `StreamExt::next()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::RwLock;
+    /// use futures_lite::StreamExt;
+    /// use std::pin::pin;
+    ///
+    /// let list = RwLock::new(vec![1, 2, 3]);
+    /// let mut items = pin!(list.stream_items(2));
+    ///
+    /// assert_eq!(items.next().await, Some(1));
+    /// // The lock is free between chunks.
+    /// list.write().await.push(4);
+    /// assert_eq!(items.collect::<Vec<_>>().await, [2, 3, 4]);
+    /// # })
+    /// ```
+    #[cfg(feature = "stream")]
+    #[track_caller]
+    pub fn stream_items<'a, E: Clone + 'a>(
+        &'a self,
+        chunk_size: usize,
+    ) -> impl Stream<Item = E> + 'a
+    where
+        T: AsRef<[E]>,
+    {
+        let location = Location::caller();
+        crate::items::Items::new(move || self.read_at(location), chunk_size)
+    }
+
+    /// Identifies this lock in instrumentation.
+    fn target(&self, mode: &'static str) -> trace::Target<'_> {
+        trace::Target::new("RwLock", mode, self, &self.hooks)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized> RwLock<T> {
+    /// Attempts to acquire an owned read lock.
+    ///
+    /// If a read lock could not be acquired at this time, then [`None`] is returned. Otherwise, an
+    /// owned guard is returned that releases the lock when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    /// use std::sync::Arc;
+    ///
+    /// let lock = Arc::new(RwLock::new(1));
+    ///
+    /// let reader = lock.try_read_arc().unwrap();
+    /// assert_eq!(*reader, 1);
+    /// assert!(lock.try_write_arc().is_none());
+    /// ```
+    #[track_caller]
+    pub fn try_read_arc(self: &Arc<Self>) -> Option<RwLockReadGuardArc<T>> {
+        self.try_read()
+            .map(|guard| RwLockReadGuardArc::new(self.clone(), guard))
+    }
+
+    /// Acquires an owned read lock.
+    ///
+    /// Returns an owned guard that releases the lock when dropped. Unlike the guard returned by
+    /// [`read()`][`RwLock::read()`], it can be moved into spawned tasks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::RwLock;
+    /// use std::sync::Arc;
+    ///
+    /// let lock = Arc::new(RwLock::new(1));
+    ///
+    /// let reader = lock.read_arc().await;
+    /// assert_eq!(*reader, 1);
+    /// assert!(lock.try_read_arc().is_some());
+    /// # })
+    /// ```
+    #[track_caller]
+    pub fn read_arc(self: &Arc<Self>) -> impl Future<Output = RwLockReadGuardArc<T>> + '_ {
+        let location = Location::caller();
+        async move {
+            let guard = self.read_at(location).await;
+            RwLockReadGuardArc::new(self.clone(), guard)
+        }
+    }
+
+    /// Acquires an owned read lock, blocking the current thread until it is available.
+    ///
+    /// This is the blocking version of [`read_arc()`][`RwLock::read_arc()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    /// use std::sync::Arc;
+    ///
+    /// let lock = Arc::new(RwLock::new(1));
+    ///
+    /// let reader = lock.read_arc_blocking();
+    /// assert_eq!(*reader, 1);
+    /// ```
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    #[track_caller]
+    pub fn read_arc_blocking(self: &Arc<Self>) -> RwLockReadGuardArc<T> {
+        crate::blocking::block_on(self.read_arc())
+    }
+
+    /// Attempts to acquire an owned write lock.
+    ///
+    /// If a write lock could not be acquired at this time, then [`None`] is returned. Otherwise,
+    /// an owned guard is returned that releases the lock when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    /// use std::sync::Arc;
+    ///
+    /// let lock = Arc::new(RwLock::new(1));
+    ///
+    /// let writer = lock.try_write_arc().unwrap();
+    /// assert!(lock.try_read_arc().is_none());
+    /// ```
+    #[track_caller]
+    pub fn try_write_arc(self: &Arc<Self>) -> Option<RwLockWriteGuardArc<T>> {
+        self.try_write_at(Location::caller())
+            .map(|guard| RwLockWriteGuardArc::new(self.clone(), guard))
+    }
+
+    /// Acquires an owned write lock.
+    ///
+    /// Returns an owned guard that releases the lock when dropped. Unlike the guard returned by
+    /// [`write()`][`RwLock::write()`], it can be moved into spawned tasks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::RwLock;
+    /// use std::sync::Arc;
+    ///
+    /// let lock = Arc::new(RwLock::new(1));
+    ///
+    /// let mut writer = lock.write_arc().await;
+    /// *writer += 1;
+    /// drop(writer);
+    ///
+    /// assert_eq!(*lock.read().await, 2);
+    /// # })
+    /// ```
+    #[track_caller]
+    pub fn write_arc(self: &Arc<Self>) -> impl Future<Output = RwLockWriteGuardArc<T>> + '_ {
+        let location = Location::caller();
+        async move {
+            let guard = self.write_at(location).await;
+            RwLockWriteGuardArc::new(self.clone(), guard)
+        }
+    }
+
+    /// Acquires an owned write lock, blocking the current thread until it is available.
+    ///
+    /// This is the blocking version of [`write_arc()`][`RwLock::write_arc()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    /// use std::sync::Arc;
+    ///
+    /// let lock = Arc::new(RwLock::new(1));
+    ///
+    /// let writer = lock.write_arc_blocking();
+    /// assert!(lock.try_read().is_none());
+    /// ```
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    #[track_caller]
+    pub fn write_arc_blocking(self: &Arc<Self>) -> RwLockWriteGuardArc<T> {
+        crate::blocking::block_on(self.write_arc())
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for RwLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /// Where the lock was acquired, if known.
+        struct Locked(Option<&'static Location<'static>>);
+        impl fmt::Debug for Locked {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.0 {
+                    Some(location) => write!(f, "<locked at {}>", location),
+                    None => f.write_str("<locked>"),
+                }
+            }
+        }
+
+        #[cfg(feature = "registry")]
+        let holder = self.holder_info().map(|holder| holder.location());
+        #[cfg(not(feature = "registry"))]
+        let holder = None;
+
+        let mut d = f.debug_struct("RwLock");
+        if let Some(name) = self.name() {
+            d.field("name", &name);
+        }
+        match self.try_read() {
+            None => d.field("value", &Locked(holder)).finish(),
+            Some(guard) => d.field("value", &&*guard).finish(),
+        }
+    }
+}
+
+impl<T> From<T> for RwLock<T> {
+    fn from(val: T) -> RwLock<T> {
+        RwLock::new(val)
+    }
+}
+
+impl<T: Default> Default for RwLock<T> {
+    fn default() -> RwLock<T> {
+        RwLock::new(Default::default())
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, T: arbitrary::Arbitrary<'a>> arbitrary::Arbitrary<'a> for RwLock<T> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        T::arbitrary(u).map(RwLock::new)
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        T::size_hint(depth)
+    }
+}
+
+/// Serializes the data, failing if a writer holds or is waiting for the lock.
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize + ?Sized> serde::Serialize for RwLock<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.try_read() {
+            Some(guard) => guard.serialize(serializer),
+            None => Err(serde::ser::Error::custom("the lock is locked for writing")),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for RwLock<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(RwLock::new)
+    }
+}
+
+/// A guard that releases the read lock when dropped.
+pub struct RwLockReadGuard<'a, T: ?Sized>(&'a RwLock<T>, trace::Held);
+
+unsafe impl<T: Sync + ?Sized> Send for RwLockReadGuard<'_, T> {}
+unsafe impl<T: Sync + ?Sized> Sync for RwLockReadGuard<'_, T> {}
+
+impl<T: ?Sized> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        trace::released(self.0.target("read"), self.1);
 
-impl<T: ?Sized> Drop for RwLockReadGuard<'_, T> {
-    fn drop(&mut self) {
         // Decrement the number of readers.
         if self.0.state.fetch_sub(ONE_READER, Ordering::SeqCst) & !WRITER_BIT == ONE_READER {
             // If this was the last reader, trigger the "no readers" event.
@@ -464,6 +1475,129 @@ impl<T: ?Sized> Drop for RwLockReadGuard<'_, T> {
     }
 }
 
+impl<T: ?Sized> Clone for RwLockReadGuard<'_, T> {
+    /// Adds another reader to the read lock held by this guard.
+    ///
+    /// This never waits, even if a writer is waiting for the lock, since the lock is already
+    /// held for reading. The lock is released once every clone has been dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::RwLock;
+    ///
+    /// let lock = RwLock::new(1);
+    /// let reader = lock.try_read().unwrap();
+    /// let copy = reader.clone();
+    /// assert_eq!(lock.reader_count(), 2);
+    ///
+    /// drop(reader);
+    /// assert!(lock.try_write().is_none());
+    /// drop(copy);
+    /// assert!(lock.try_write().is_some());
+    /// ```
+    #[track_caller]
+    fn clone(&self) -> Self {
+        self.0.add_reader();
+        RwLockReadGuard(
+            self.0,
+            trace::acquired(self.0.target("read"), Location::caller()),
+        )
+    }
+}
+
+impl<'a, T: ?Sized> RwLockReadGuard<'a, T> {
+    /// Keeps the read lock held forever, returning a reference to the data that lives as long as
+    /// the lock.
+    ///
+    /// Other readers can still read the data, but every later write lock operation waits forever.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::{RwLock, RwLockReadGuard};
+    ///
+    /// let lock = RwLock::new(1);
+    /// let value = RwLockReadGuard::leak(lock.try_read().unwrap());
+    ///
+    /// assert_eq!(*value, 1);
+    /// assert!(lock.try_read().is_some());
+    /// assert!(lock.try_write().is_none());
+    /// ```
+    pub fn leak(guard: RwLockReadGuard<'a, T>) -> &'a T {
+        let lock = guard.0;
+        mem::forget(guard);
+
+        // SAFETY: The read lock is never released, so no writer can reach the data.
+        unsafe { &*lock.value.get() }
+    }
+
+    /// Makes a guard for a part of the locked data.
+    ///
+    /// The read lock stays held until the returned guard is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{RwLock, RwLockReadGuard};
+    ///
+    /// let lock = RwLock::new((1, "one"));
+    ///
+    /// let name = RwLockReadGuard::map(lock.read().await, |pair| &pair.1);
+    /// assert_eq!(*name, "one");
+    /// assert!(lock.try_write().is_none());
+    /// # })
+    /// ```
+    pub fn map<U: ?Sized>(
+        guard: Self,
+        f: impl FnOnce(&T) -> &U,
+    ) -> MappedRwLockReadGuard<'a, T, U> {
+        // The data stays in place inside the lock while the guard is alive.
+        let value = f(&*guard) as *const U;
+        MappedRwLockReadGuard {
+            _guard: guard,
+            value,
+        }
+    }
+
+    /// Makes a guard for a part of the locked data, if there is one.
+    ///
+    /// Returns the original guard if `f` returns [`None`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{RwLock, RwLockReadGuard};
+    ///
+    /// let lock = RwLock::new(vec![1, 2]);
+    ///
+    /// let first = RwLockReadGuard::try_map(lock.read().await, |v| v.first()).unwrap();
+    /// assert_eq!(*first, 1);
+    /// drop(first);
+    ///
+    /// let guard = RwLockReadGuard::try_map(lock.read().await, |v| v.get(5)).unwrap_err();
+    /// assert_eq!(guard.len(), 2);
+    /// # })
+    /// ```
+    pub fn try_map<U: ?Sized>(
+        guard: Self,
+        f: impl FnOnce(&T) -> Option<&U>,
+    ) -> Result<MappedRwLockReadGuard<'a, T, U>, Self> {
+        match f(&*guard) {
+            Some(value) => {
+                let value = value as *const U;
+                Ok(MappedRwLockReadGuard {
+                    _guard: guard,
+                    value,
+                })
+            }
+            None => Err(guard),
+        }
+    }
+}
+
 impl<T: fmt::Debug + ?Sized> fmt::Debug for RwLockReadGuard<'_, T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Debug::fmt(&**self, f)
@@ -487,17 +1621,18 @@ impl<T: ?Sized> Deref for RwLockReadGuard<'_, T> {
 /// A guard that releases the upgradable read lock when dropped.
 pub struct RwLockUpgradableReadGuard<'a, T: ?Sized> {
     reader: RwLockReadGuard<'a, T>,
-    reserved: MutexGuard<'a, ()>,
+    reserved: RawMutexGuard<'a>,
 }
 
 unsafe impl<T: Send + Sync + ?Sized> Send for RwLockUpgradableReadGuard<'_, T> {}
 unsafe impl<T: Sync + ?Sized> Sync for RwLockUpgradableReadGuard<'_, T> {}
 
 impl<'a, T: ?Sized> RwLockUpgradableReadGuard<'a, T> {
-    /// Converts this guard into a writer guard.
-    fn into_writer(self) -> RwLockWriteGuard<'a, T> {
+    /// Converts this guard into a writer guard that was acquired at `held`.
+    fn into_writer(self, held: trace::Held) -> RwLockWriteGuard<'a, T> {
+        trace::released(self.reader.0.target("upgradable_read"), self.reader.1);
         let writer = RwLockWriteGuard {
-            writer: RwLockWriteGuardInner(self.reader.0),
+            writer: RwLockWriteGuardInner(self.reader.0, held),
             reserved: self.reserved,
         };
         mem::forget(self.reader);
@@ -553,7 +1688,16 @@ impl<'a, T: ?Sized> RwLockUpgradableReadGuard<'a, T> {
     /// let writer = RwLockUpgradableReadGuard::try_upgrade(reader).unwrap();
     /// # })
     /// ```
+    #[track_caller]
     pub fn try_upgrade(guard: Self) -> Result<RwLockWriteGuard<'a, T>, Self> {
+        Self::try_upgrade_at(guard, Location::caller())
+    }
+
+    /// Attempts to upgrade into a write lock on behalf of the code at `location`.
+    fn try_upgrade_at(
+        guard: Self,
+        location: &'static Location<'static>,
+    ) -> Result<RwLockWriteGuard<'a, T>, Self> {
         // If there are no readers, grab the write lock.
         if guard
             .reader
@@ -562,7 +1706,8 @@ impl<'a, T: ?Sized> RwLockUpgradableReadGuard<'a, T> {
             .compare_exchange(ONE_READER, WRITER_BIT, Ordering::AcqRel, Ordering::Acquire)
             .is_ok()
         {
-            Ok(guard.into_writer())
+            let held = trace::acquired(guard.reader.0.target("write"), location);
+            Ok(guard.into_writer(held))
         } else {
             Err(guard)
         }
@@ -585,30 +1730,47 @@ impl<'a, T: ?Sized> RwLockUpgradableReadGuard<'a, T> {
     /// *writer = 2;
     /// # })
     /// ```
-    pub async fn upgrade(guard: Self) -> RwLockWriteGuard<'a, T> {
-        // Set `WRITER_BIT` and decrement the number of readers at the same time.
-        guard
-            .reader
-            .0
-            .state
-            .fetch_sub(ONE_READER - WRITER_BIT, Ordering::SeqCst);
-
-        // Convert into a write guard that unsets `WRITER_BIT` in case this future is canceled.
-        let guard = guard.into_writer();
-
-        // If there are readers, we need to wait for them to finish.
-        while guard.writer.0.state.load(Ordering::SeqCst) != WRITER_BIT {
-            // Start listening for "no readers" events.
-            let listener = guard.writer.0.no_readers.listen();
-
-            // Check again if there are readers.
-            if guard.writer.0.state.load(Ordering::Acquire) != WRITER_BIT {
-                // Wait for the readers to finish.
-                listener.await;
-            }
-        }
+    #[track_caller]
+    pub fn upgrade(guard: Self) -> impl Future<Output = RwLockWriteGuard<'a, T>> + 'a {
+        let location = Location::caller();
+
+        trace::instrument(
+            guard.reader.0.target("write"),
+            "RwLockUpgradableReadGuard::upgrade",
+            async move {
+                let guard = match Self::try_upgrade_at(guard, location) {
+                    Ok(guard) => return guard,
+                    Err(guard) => guard,
+                };
+
+                // Set `WRITER_BIT` and decrement the number of readers at the same time.
+                guard
+                    .reader
+                    .0
+                    .state
+                    .fetch_sub(ONE_READER - WRITER_BIT, Ordering::SeqCst);
+
+                // Convert into a write guard that unsets `WRITER_BIT` in case this future is canceled.
+                let mut guard = guard.into_writer(trace::Held::pending());
+                let wait = trace::wait(guard.writer.0.target("write"), location);
+
+                // If there are readers, we need to wait for them to finish.
+                while guard.writer.0.state.load(Ordering::SeqCst) != WRITER_BIT {
+                    // Start listening for "no readers" events.
+                    let listener = guard.writer.0.no_readers.listen();
+
+                    // Check again if there are readers.
+                    if guard.writer.0.state.load(Ordering::Acquire) != WRITER_BIT {
+                        // Wait for the readers to finish.
+                        listener.await;
+                        crate::coop::yielded();
+                    }
+                }
 
-        guard
+                guard.writer.1 = wait.acquired();
+                guard
+            },
+        )
     }
 }
 
@@ -632,10 +1794,12 @@ impl<T: ?Sized> Deref for RwLockUpgradableReadGuard<'_, T> {
     }
 }
 
-struct RwLockWriteGuardInner<'a, T: ?Sized>(&'a RwLock<T>);
+struct RwLockWriteGuardInner<'a, T: ?Sized>(&'a RwLock<T>, trace::Held);
 
 impl<T: ?Sized> Drop for RwLockWriteGuardInner<'_, T> {
     fn drop(&mut self) {
+        trace::released(self.0.target("write"), self.1);
+
         // Unset `WRITER_BIT`.
         self.0.state.fetch_and(!WRITER_BIT, Ordering::SeqCst);
         // Trigger the "no writer" event.
@@ -646,7 +1810,7 @@ impl<T: ?Sized> Drop for RwLockWriteGuardInner<'_, T> {
 /// A guard that releases the write lock when dropped.
 pub struct RwLockWriteGuard<'a, T: ?Sized> {
     writer: RwLockWriteGuardInner<'a, T>,
-    reserved: MutexGuard<'a, ()>,
+    reserved: RawMutexGuard<'a>,
 }
 
 unsafe impl<T: Send + ?Sized> Send for RwLockWriteGuard<'_, T> {}
@@ -674,7 +1838,11 @@ impl<'a, T: ?Sized> RwLockWriteGuard<'a, T> {
     /// assert!(lock.try_read().is_some());
     /// # })
     /// ```
+    #[track_caller]
     pub fn downgrade(guard: Self) -> RwLockReadGuard<'a, T> {
+        trace::released(guard.writer.0.target("write"), guard.writer.1);
+        let held = trace::acquired(guard.writer.0.target("read"), Location::caller());
+
         // Atomically downgrade state.
         guard
             .writer
@@ -686,7 +1854,7 @@ impl<'a, T: ?Sized> RwLockWriteGuard<'a, T> {
         guard.writer.0.no_writer.notify(1);
 
         // Convert into a read guard and return.
-        let new_guard = RwLockReadGuard(guard.writer.0);
+        let new_guard = RwLockReadGuard(guard.writer.0, held);
         mem::forget(guard.writer); // `RwLockWriteGuardInner::drop()` should not be called!
         new_guard
     }
@@ -715,7 +1883,11 @@ impl<'a, T: ?Sized> RwLockWriteGuard<'a, T> {
     /// assert!(RwLockUpgradableReadGuard::try_upgrade(reader).is_ok())
     /// # })
     /// ```
+    #[track_caller]
     pub fn downgrade_to_upgradable(guard: Self) -> RwLockUpgradableReadGuard<'a, T> {
+        trace::released(guard.writer.0.target("write"), guard.writer.1);
+        let held = trace::acquired(guard.writer.0.target("upgradable_read"), Location::caller());
+
         // Atomically downgrade state.
         guard
             .writer
@@ -725,7 +1897,7 @@ impl<'a, T: ?Sized> RwLockWriteGuard<'a, T> {
 
         // Convert into an upgradable read guard and return.
         let new_guard = RwLockUpgradableReadGuard {
-            reader: RwLockReadGuard(guard.writer.0),
+            reader: RwLockReadGuard(guard.writer.0, held),
             reserved: guard.reserved,
         };
         mem::forget(guard.writer); // `RwLockWriteGuardInner::drop()` should not be called!
@@ -733,6 +1905,101 @@ impl<'a, T: ?Sized> RwLockWriteGuard<'a, T> {
     }
 }
 
+impl<'a, T: ?Sized> RwLockWriteGuard<'a, T> {
+    /// Keeps the write lock held forever, returning a reference to the data that lives as long as
+    /// the lock.
+    ///
+    /// This seals the data after an initialization phase: the returned reference is the only way
+    /// to reach it from now on, and every later lock operation waits forever.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::{RwLock, RwLockWriteGuard};
+    ///
+    /// let lock = RwLock::new(1);
+    /// let value = RwLockWriteGuard::leak(lock.try_write().unwrap());
+    /// *value += 1;
+    ///
+    /// assert_eq!(*value, 2);
+    /// assert!(lock.try_read().is_none());
+    /// ```
+    pub fn leak(guard: RwLockWriteGuard<'a, T>) -> &'a mut T {
+        let lock = guard.writer.0;
+        mem::forget(guard);
+
+        // SAFETY: The write lock is never released, so no other guard can reach the data.
+        unsafe { &mut *lock.value.get() }
+    }
+
+    /// Makes a guard for a part of the locked data.
+    ///
+    /// The write lock stays held until the returned guard is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{RwLock, RwLockWriteGuard};
+    ///
+    /// let lock = RwLock::new((1, String::new()));
+    ///
+    /// let mut name = RwLockWriteGuard::map(lock.write().await, |pair| &mut pair.1);
+    /// name.push_str("one");
+    /// drop(name);
+    ///
+    /// assert_eq!(lock.read().await.1, "one");
+    /// # })
+    /// ```
+    pub fn map<U: ?Sized>(
+        mut guard: Self,
+        f: impl FnOnce(&mut T) -> &mut U,
+    ) -> MappedRwLockWriteGuard<'a, T, U> {
+        // The data stays in place inside the lock while the guard is alive.
+        let value = f(&mut *guard) as *mut U;
+        MappedRwLockWriteGuard {
+            _guard: guard,
+            value,
+        }
+    }
+
+    /// Makes a guard for a part of the locked data, if there is one.
+    ///
+    /// Returns the original guard if `f` returns [`None`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{RwLock, RwLockWriteGuard};
+    ///
+    /// let lock = RwLock::new(Some(1));
+    ///
+    /// let mut n = RwLockWriteGuard::try_map(lock.write().await, |o| o.as_mut()).unwrap();
+    /// *n += 1;
+    /// drop(n);
+    ///
+    /// *lock.write().await = None;
+    /// assert!(RwLockWriteGuard::try_map(lock.write().await, |o| o.as_mut()).is_err());
+    /// # })
+    /// ```
+    pub fn try_map<U: ?Sized>(
+        mut guard: Self,
+        f: impl FnOnce(&mut T) -> Option<&mut U>,
+    ) -> Result<MappedRwLockWriteGuard<'a, T, U>, Self> {
+        match f(&mut *guard) {
+            Some(value) => {
+                let value = value as *mut U;
+                Ok(MappedRwLockWriteGuard {
+                    _guard: guard,
+                    value,
+                })
+            }
+            None => Err(guard),
+        }
+    }
+}
+
 impl<T: fmt::Debug + ?Sized> fmt::Debug for RwLockWriteGuard<'_, T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Debug::fmt(&**self, f)
@@ -758,3 +2025,309 @@ impl<T: ?Sized> DerefMut for RwLockWriteGuard<'_, T> {
         unsafe { &mut *self.writer.0.value.get() }
     }
 }
+
+/// A guard for a part of the data behind a read lock, created by [`RwLockReadGuard::map()`].
+pub struct MappedRwLockReadGuard<'a, T: ?Sized, U: ?Sized> {
+    _guard: RwLockReadGuard<'a, T>,
+    value: *const U,
+}
+
+unsafe impl<T: Sync + ?Sized, U: Sync + ?Sized> Send for MappedRwLockReadGuard<'_, T, U> {}
+unsafe impl<T: Sync + ?Sized, U: Sync + ?Sized> Sync for MappedRwLockReadGuard<'_, T, U> {}
+
+impl<'a, T: ?Sized, U: ?Sized> MappedRwLockReadGuard<'a, T, U> {
+    /// Makes a guard for a smaller part of the locked data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{MappedRwLockReadGuard, RwLock, RwLockReadGuard};
+    ///
+    /// let lock = RwLock::new(((1, 2), 3));
+    /// let inner = RwLockReadGuard::map(lock.read().await, |t| &t.0);
+    /// let second = MappedRwLockReadGuard::map(inner, |t| &t.1);
+    /// assert_eq!(*second, 2);
+    /// # })
+    /// ```
+    pub fn map<V: ?Sized>(
+        guard: Self,
+        f: impl FnOnce(&U) -> &V,
+    ) -> MappedRwLockReadGuard<'a, T, V> {
+        let value = f(unsafe { &*guard.value }) as *const V;
+        MappedRwLockReadGuard {
+            _guard: guard._guard,
+            value,
+        }
+    }
+}
+
+impl<T: ?Sized, U: fmt::Debug + ?Sized> fmt::Debug for MappedRwLockReadGuard<'_, T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized, U: fmt::Display + ?Sized> fmt::Display for MappedRwLockReadGuard<'_, T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized, U: ?Sized> Deref for MappedRwLockReadGuard<'_, T, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        unsafe { &*self.value }
+    }
+}
+
+/// A guard for a part of the data behind a write lock, created by [`RwLockWriteGuard::map()`].
+pub struct MappedRwLockWriteGuard<'a, T: ?Sized, U: ?Sized> {
+    _guard: RwLockWriteGuard<'a, T>,
+    value: *mut U,
+}
+
+unsafe impl<T: Send + ?Sized, U: Send + ?Sized> Send for MappedRwLockWriteGuard<'_, T, U> {}
+unsafe impl<T: Sync + ?Sized, U: Sync + ?Sized> Sync for MappedRwLockWriteGuard<'_, T, U> {}
+
+impl<'a, T: ?Sized, U: ?Sized> MappedRwLockWriteGuard<'a, T, U> {
+    /// Makes a guard for a smaller part of the locked data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{MappedRwLockWriteGuard, RwLock, RwLockWriteGuard};
+    ///
+    /// let lock = RwLock::new(((1, 2), 3));
+    /// let inner = RwLockWriteGuard::map(lock.write().await, |t| &mut t.0);
+    /// let mut second = MappedRwLockWriteGuard::map(inner, |t| &mut t.1);
+    /// *second = 20;
+    /// drop(second);
+    ///
+    /// assert_eq!(*lock.read().await, ((1, 20), 3));
+    /// # })
+    /// ```
+    pub fn map<V: ?Sized>(
+        guard: Self,
+        f: impl FnOnce(&mut U) -> &mut V,
+    ) -> MappedRwLockWriteGuard<'a, T, V> {
+        let value = f(unsafe { &mut *guard.value }) as *mut V;
+        MappedRwLockWriteGuard {
+            _guard: guard._guard,
+            value,
+        }
+    }
+}
+
+impl<T: ?Sized, U: fmt::Debug + ?Sized> fmt::Debug for MappedRwLockWriteGuard<'_, T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized, U: fmt::Display + ?Sized> fmt::Display for MappedRwLockWriteGuard<'_, T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized, U: ?Sized> Deref for MappedRwLockWriteGuard<'_, T, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        unsafe { &*self.value }
+    }
+}
+
+impl<T: ?Sized, U: ?Sized> DerefMut for MappedRwLockWriteGuard<'_, T, U> {
+    fn deref_mut(&mut self) -> &mut U {
+        unsafe { &mut *self.value }
+    }
+}
+
+/// An owned guard that releases the read lock when dropped.
+#[cfg(target_has_atomic = "ptr")]
+pub struct RwLockReadGuardArc<T: ?Sized>(Arc<RwLock<T>>, trace::Held);
+
+#[cfg(target_has_atomic = "ptr")]
+unsafe impl<T: Send + Sync + ?Sized> Send for RwLockReadGuardArc<T> {}
+#[cfg(target_has_atomic = "ptr")]
+unsafe impl<T: Send + Sync + ?Sized> Sync for RwLockReadGuardArc<T> {}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized> RwLockReadGuardArc<T> {
+    /// Takes over the read lock held by `guard`, which must belong to `lock`.
+    fn new(lock: Arc<RwLock<T>>, guard: RwLockReadGuard<'_, T>) -> RwLockReadGuardArc<T> {
+        let held = guard.1;
+        mem::forget(guard);
+        RwLockReadGuardArc(lock, held)
+    }
+
+    /// Returns a reference to the lock a guard came from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{RwLock, RwLockReadGuardArc};
+    /// use std::sync::Arc;
+    ///
+    /// let lock = Arc::new(RwLock::new(10i32));
+    /// let guard = lock.read_arc().await;
+    /// assert!(Arc::ptr_eq(RwLockReadGuardArc::source(&guard), &lock));
+    /// # })
+    /// ```
+    pub fn source(guard: &RwLockReadGuardArc<T>) -> &Arc<RwLock<T>> {
+        &guard.0
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized> Drop for RwLockReadGuardArc<T> {
+    fn drop(&mut self) {
+        // Release the lock the same way as a borrowed guard.
+        drop(RwLockReadGuard(&*self.0, self.1));
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized> Clone for RwLockReadGuardArc<T> {
+    /// Adds another reader to the read lock held by this guard.
+    ///
+    /// This never waits, so the clones can be handed to the subtasks of a fan-out computation
+    /// without each of them queuing for the lock again. The lock is released once every clone has
+    /// been dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::RwLock;
+    /// use std::sync::Arc;
+    ///
+    /// let lock = Arc::new(RwLock::new(vec![1, 2, 3]));
+    /// let reader = lock.read_arc().await;
+    ///
+    /// let sums = (0..3).map(|i| {
+    ///     let reader = reader.clone();
+    ///     async move { reader[i] * 2 }
+    /// });
+    /// let mut total = 0;
+    /// for sum in sums {
+    ///     total += sum.await;
+    /// }
+    /// assert_eq!(total, 12);
+    /// # })
+    /// ```
+    #[track_caller]
+    fn clone(&self) -> Self {
+        self.0.add_reader();
+        RwLockReadGuardArc(
+            self.0.clone(),
+            trace::acquired(self.0.target("read"), Location::caller()),
+        )
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: fmt::Debug + ?Sized> fmt::Debug for RwLockReadGuardArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: fmt::Display + ?Sized> fmt::Display for RwLockReadGuardArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized> Deref for RwLockReadGuardArc<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.0.value.get() }
+    }
+}
+
+/// An owned guard that releases the write lock when dropped.
+#[cfg(target_has_atomic = "ptr")]
+pub struct RwLockWriteGuardArc<T: ?Sized>(Arc<RwLock<T>>, trace::Held);
+
+#[cfg(target_has_atomic = "ptr")]
+unsafe impl<T: Send + Sync + ?Sized> Send for RwLockWriteGuardArc<T> {}
+#[cfg(target_has_atomic = "ptr")]
+unsafe impl<T: Send + Sync + ?Sized> Sync for RwLockWriteGuardArc<T> {}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized> RwLockWriteGuardArc<T> {
+    /// Takes over the write lock held by `guard`, which must belong to `lock`.
+    fn new(lock: Arc<RwLock<T>>, guard: RwLockWriteGuard<'_, T>) -> RwLockWriteGuardArc<T> {
+        let held = guard.writer.1;
+        mem::forget(guard);
+        RwLockWriteGuardArc(lock, held)
+    }
+
+    /// Returns a reference to the lock a guard came from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{RwLock, RwLockWriteGuardArc};
+    /// use std::sync::Arc;
+    ///
+    /// let lock = Arc::new(RwLock::new(10i32));
+    /// let guard = lock.write_arc().await;
+    /// assert!(Arc::ptr_eq(RwLockWriteGuardArc::source(&guard), &lock));
+    /// # })
+    /// ```
+    pub fn source(guard: &RwLockWriteGuardArc<T>) -> &Arc<RwLock<T>> {
+        &guard.0
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized> Drop for RwLockWriteGuardArc<T> {
+    fn drop(&mut self) {
+        // Release the lock the same way as a borrowed guard.
+        drop(RwLockWriteGuard {
+            writer: RwLockWriteGuardInner(&*self.0, self.1),
+            reserved: RawMutexGuard(&self.0.mutex),
+        });
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: fmt::Debug + ?Sized> fmt::Debug for RwLockWriteGuardArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: fmt::Display + ?Sized> fmt::Display for RwLockWriteGuardArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized> Deref for RwLockWriteGuardArc<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.0.value.get() }
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: ?Sized> DerefMut for RwLockWriteGuardArc<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.0.value.get() }
+    }
+}
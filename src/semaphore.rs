@@ -1,13 +1,43 @@
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use core::fmt;
+use core::future::Future;
+use core::mem;
+use core::panic::Location;
+use core::time::Duration;
 
-use event_listener::Event;
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+#[cfg(feature = "registry")]
+use alloc::vec::Vec;
+
+use event_listener::{Event, IntoNotification};
+
+#[cfg(feature = "events")]
+use crate::diagnostics::LockEvents;
+#[cfg(feature = "hooks")]
+use crate::diagnostics::LockMetrics;
+#[cfg(feature = "registry")]
+use crate::diagnostics::WaiterInfo;
+use crate::mutex::{RawMutex, RawMutexGuard};
+use crate::sync::atomic::{AtomicUsize, Ordering};
+use crate::timer::{self, Timer};
+use crate::trace;
+use crate::Flag;
 
 /// A counter for limiting the number of concurrent operations.
 #[derive(Debug)]
 pub struct Semaphore {
     count: AtomicUsize,
     event: Event,
+    hooks: trace::Hooks,
+
+    /// Held by the operation that is collecting permits for [`Semaphore::acquire_many()`].
+    many: RawMutex,
+
+    /// Permits to remove as soon as they are released.
+    debt: AtomicUsize,
+
+    /// Set once the semaphore is closed.
+    closed: Flag,
 }
 
 impl Semaphore {
@@ -24,6 +54,64 @@ impl Semaphore {
         Semaphore {
             count: AtomicUsize::new(n),
             event: Event::new(),
+            hooks: trace::Hooks::new(),
+            many: RawMutex::new(),
+            debt: AtomicUsize::new(0),
+            closed: Flag::new(),
+        }
+    }
+
+    /// Creates a new semaphore that reports to its own metrics hooks.
+    ///
+    /// The hooks are used instead of the ones installed with
+    /// [`set_metrics()`][`crate::diagnostics::set_metrics()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::diagnostics::LockMetrics;
+    /// use async_lock::Semaphore;
+    /// use std::sync::Arc;
+    ///
+    /// struct Ignore;
+    /// impl LockMetrics for Ignore {}
+    ///
+    /// let s = Semaphore::with_metrics(5, Arc::new(Ignore));
+    /// ```
+    #[cfg(feature = "hooks")]
+    pub fn with_metrics(n: usize, metrics: Arc<dyn LockMetrics>) -> Semaphore {
+        Semaphore {
+            count: AtomicUsize::new(n),
+            event: Event::new(),
+            hooks: trace::Hooks::with_metrics(metrics),
+            many: RawMutex::new(),
+            debt: AtomicUsize::new(0),
+            closed: Flag::new(),
+        }
+    }
+
+    /// Creates a new semaphore with a priority ceiling.
+    ///
+    /// Acquiring a permit raises the acquiring task to `ceiling` through the hooks installed with
+    /// [`priority::set_hooks()`][`crate::priority::set_hooks()`], and releasing it restores the
+    /// previous priority.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Semaphore;
+    ///
+    /// let s = Semaphore::with_priority_ceiling(2, 5);
+    /// ```
+    #[cfg(feature = "priority-ceiling")]
+    pub const fn with_priority_ceiling(n: usize, ceiling: u32) -> Semaphore {
+        Semaphore {
+            count: AtomicUsize::new(n),
+            event: Event::new(),
+            hooks: trace::Hooks::with_ceiling(ceiling),
+            many: RawMutex::new(),
+            debt: AtomicUsize::new(0),
+            closed: Flag::new(),
         }
     }
 
@@ -46,11 +134,442 @@ impl Semaphore {
     /// drop(g2);
     /// assert!(s.try_acquire().is_some());
     /// ```
+    #[track_caller]
     pub fn try_acquire(&self) -> Option<SemaphoreGuard<'_>> {
+        self.try_acquire_at(Location::caller())
+    }
+
+    /// Attempts to get a permit on behalf of the code at `location`.
+    fn try_acquire_at(&self, location: &'static Location<'static>) -> Option<SemaphoreGuard<'_>> {
+        if !self.is_closed() && self.try_decrement() {
+            Some(SemaphoreGuard(
+                self,
+                1,
+                trace::acquired(self.target(), location),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Waits for a permit for a concurrent operation.
+    ///
+    /// Returns a guard that releases the permit when dropped.
+    ///
+    /// The returned future is cancel-safe: dropping it before it completes never holds on to a
+    /// permit, and if a released permit already woke it, the wakeup is passed on to the next
+    /// waiting operation.
+    ///
+    /// Once the semaphore is [closed][`Semaphore::close()`], this never completes. Use
+    /// [`acquire_checked()`][`Semaphore::acquire_checked()`] to get an error instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Semaphore;
+    ///
+    /// let s = Semaphore::new(2);
+    /// let guard = s.acquire().await;
+    /// # });
+    /// ```
+    #[track_caller]
+    pub fn acquire(&self) -> impl Future<Output = SemaphoreGuard<'_>> + '_ {
+        let location = Location::caller();
+
+        trace::instrument(self.target(), "Semaphore::acquire", async move {
+            crate::coop::consume_budget(self).await;
+            let held = self.acquire_permit(location).await;
+            SemaphoreGuard(self, 1, held)
+        })
+    }
+
+    /// Waits for a permit, blocking the current thread until one is available.
+    ///
+    /// Blocked threads wait in the same queue as tasks awaiting [`acquire()`][`Semaphore::acquire()`]
+    /// and take turns with them fairly. This method must not be called from async code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Semaphore;
+    ///
+    /// let s = Semaphore::new(2);
+    /// let guard = s.acquire_blocking();
+    /// ```
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    #[track_caller]
+    pub fn acquire_blocking(&self) -> SemaphoreGuard<'_> {
+        crate::blocking::block_on(self.acquire())
+    }
+
+    /// Waits for a permit for a concurrent operation, giving up after `timeout`.
+    ///
+    /// Returns a guard that releases the permit when dropped, or [`None`] if no permit became
+    /// available in time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{AsyncIoTimer, Semaphore};
+    /// use std::time::Duration;
+    ///
+    /// let s = Semaphore::new(1);
+    /// let guard = s.acquire().await;
+    /// assert!(s.acquire_timeout(AsyncIoTimer, Duration::from_millis(10)).await.is_none());
+    ///
+    /// drop(guard);
+    /// assert!(s.acquire_timeout(AsyncIoTimer, Duration::from_millis(10)).await.is_some());
+    /// # });
+    /// ```
+    #[track_caller]
+    pub fn acquire_timeout<'a>(
+        &'a self,
+        timer: impl Timer + 'a,
+        timeout: Duration,
+    ) -> impl Future<Output = Option<SemaphoreGuard<'a>>> + 'a {
+        let location = Location::caller();
+        let acquire = self.acquire();
+
+        async move {
+            if let Some(guard) = self.try_acquire_at(location) {
+                return Some(guard);
+            }
+            timer::timeout(acquire, timer.sleep(timeout)).await
+        }
+    }
+
+    /// Attempts to get `n` permits at once.
+    ///
+    /// If the permits could not be acquired at this time, then [`None`] is returned. Otherwise, a
+    /// guard is returned that releases all `n` permits when dropped. This never takes some of the
+    /// permits only.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Semaphore;
+    ///
+    /// let s = Semaphore::new(3);
+    ///
+    /// let g1 = s.try_acquire_many(2).unwrap();
+    /// assert!(s.try_acquire_many(2).is_none());
+    /// drop(g1);
+    /// assert!(s.try_acquire_many(3).is_some());
+    /// ```
+    #[track_caller]
+    pub fn try_acquire_many(&self, n: usize) -> Option<SemaphoreGuard<'_>> {
+        let location = Location::caller();
+        if !self.is_closed() && self.try_decrement_many(n) {
+            Some(SemaphoreGuard(
+                self,
+                n,
+                trace::acquired(self.target(), location),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Waits for `n` permits for a concurrent operation.
+    ///
+    /// Returns a guard that releases all `n` permits when dropped.
+    ///
+    /// Operations waiting for several permits are served one at a time, in the order they started
+    /// waiting. The first of them collects permits as they are released instead of waiting for
+    /// `n` of them to be free at once. This makes it much harder for single-permit operations to
+    /// starve it, but does not rule it out: [`acquire()`][`Semaphore::acquire()`] and
+    /// [`try_acquire()`][`Semaphore::try_acquire()`] still take a released permit if they get to
+    /// it first. If the returned future is dropped before it completes, the permits collected so
+    /// far are returned to the semaphore.
+    ///
+    /// The permits collected so far are held while waiting, so every other operation has fewer to
+    /// share. Waiting for more permits than the semaphore will ever have therefore not only never
+    /// completes, it eventually holds every permit and blocks all other operations until it is
+    /// dropped. Keep `n` within the number of permits the semaphore was created with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Semaphore;
+    /// use futures_lite::future;
+    ///
+    /// // A memory budget of 100 units.
+    /// let budget = Semaphore::new(100);
+    /// let small = budget.acquire_many(30).await;
+    ///
+    /// let mut large = Box::pin(budget.acquire_many(80));
+    /// assert!(future::poll_once(&mut large).await.is_none());
+    ///
+    /// drop(small);
+    /// assert!(future::poll_once(&mut large).await.is_some());
+    /// # });
+    /// ```
+    #[track_caller]
+    pub fn acquire_many(&self, n: usize) -> impl Future<Output = SemaphoreGuard<'_>> + '_ {
+        let location = Location::caller();
+
+        trace::instrument(self.target(), "Semaphore::acquire_many", async move {
+            crate::coop::consume_budget(self).await;
+            let held = self.acquire_permits(n, location).await;
+            SemaphoreGuard(self, n, held)
+        })
+    }
+
+    /// Takes `n` permits, waiting until all of them were collected.
+    async fn acquire_permits(&self, n: usize, location: &'static Location<'static>) -> trace::Held {
+        match self.acquire_permits_checked(n, location).await {
+            Ok(held) => held,
+            // Unchecked operations cannot report the close, so they are never released.
+            Err(_) => core::future::pending().await,
+        }
+    }
+
+    /// Takes `n` permits, waiting until all of them were collected or the semaphore is closed.
+    async fn acquire_permits_checked(
+        &self,
+        n: usize,
+        location: &'static Location<'static>,
+    ) -> Result<trace::Held, ClosedSemaphoreError> {
+        if self.is_closed() {
+            return Err(ClosedSemaphoreError(()));
+        }
+        match n {
+            0 => return Ok(trace::acquired(self.target(), location)),
+            1 => return self.acquire_permit_checked(location).await,
+            _ => {}
+        }
+        if self.many.try_lock() {
+            let _many = RawMutexGuard(&self.many);
+            if self.try_decrement_many(n) {
+                return Ok(trace::acquired(self.target(), location));
+            }
+            return self
+                .collect_permits(n, trace::wait(self.target(), location))
+                .await;
+        }
+
+        let wait = trace::wait(self.target(), location);
+        timer::timeout(self.many.lock(), self.closed.wait())
+            .await
+            .ok_or(ClosedSemaphoreError(()))?;
+        let _many = RawMutexGuard(&self.many);
+        self.collect_permits(n, wait).await
+    }
+
+    /// Collects `n` permits one release at a time, while holding `many`.
+    ///
+    /// Gives the collected permits back and fails once the semaphore is closed.
+    async fn collect_permits(
+        &self,
+        n: usize,
+        wait: trace::Wait<'_>,
+    ) -> Result<trace::Held, ClosedSemaphoreError> {
+        /// Returns the collected permits if the operation is cancelled.
+        struct Collected<'a>(&'a Semaphore, usize);
+
+        impl Drop for Collected<'_> {
+            fn drop(&mut self) {
+                self.0.add(self.1);
+            }
+        }
+
+        let mut collected = Collected(self, 0);
+        let mut listener = None;
+
+        loop {
+            if self.is_closed() {
+                return Err(ClosedSemaphoreError(()));
+            }
+            collected.1 += self.take_up_to(n - collected.1);
+            if collected.1 == n {
+                mem::forget(collected);
+                return Ok(wait.acquired());
+            }
+
+            match listener.take() {
+                None => listener = Some(self.event.listen()),
+                Some(l) => {
+                    l.await;
+                    crate::coop::yielded();
+                }
+            }
+        }
+    }
+
+    /// Waits for a permit, failing if the semaphore is closed.
+    ///
+    /// Returns a guard that releases the permit when dropped, or an error once
+    /// [`close()`][`Semaphore::close()`] was called, even if this operation was already waiting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Semaphore;
+    /// use futures_lite::future;
+    ///
+    /// let s = Semaphore::new(1);
+    /// let guard = s.acquire_checked().await.unwrap();
+    ///
+    /// let mut waiter = Box::pin(s.acquire_checked());
+    /// assert!(future::poll_once(&mut waiter).await.is_none());
+    ///
+    /// s.close();
+    /// assert!(waiter.await.is_err());
+    /// # });
+    /// ```
+    #[track_caller]
+    pub fn acquire_checked(
+        &self,
+    ) -> impl Future<Output = Result<SemaphoreGuard<'_>, ClosedSemaphoreError>> + '_ {
+        let location = Location::caller();
+
+        trace::instrument(self.target(), "Semaphore::acquire_checked", async move {
+            crate::coop::consume_budget(self).await;
+            let held = self.acquire_permit_checked(location).await?;
+            Ok(SemaphoreGuard(self, 1, held))
+        })
+    }
+
+    /// Waits for `n` permits, failing if the semaphore is closed.
+    ///
+    /// This is the checked version of [`acquire_many()`][`Semaphore::acquire_many()`]. If the
+    /// semaphore is closed while this operation is waiting, the permits it collected so far are
+    /// given back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Semaphore;
+    /// use futures_lite::future;
+    ///
+    /// let s = Semaphore::new(3);
+    /// let guard = s.acquire_many_checked(2).await.unwrap();
+    ///
+    /// let mut waiter = Box::pin(s.acquire_many_checked(2));
+    /// assert!(future::poll_once(&mut waiter).await.is_none());
+    ///
+    /// s.close();
+    /// assert!(waiter.await.is_err());
+    /// # });
+    /// ```
+    #[track_caller]
+    pub fn acquire_many_checked(
+        &self,
+        n: usize,
+    ) -> impl Future<Output = Result<SemaphoreGuard<'_>, ClosedSemaphoreError>> + '_ {
+        let location = Location::caller();
+
+        trace::instrument(
+            self.target(),
+            "Semaphore::acquire_many_checked",
+            async move {
+                crate::coop::consume_budget(self).await;
+                let held = self.acquire_permits_checked(n, location).await?;
+                Ok(SemaphoreGuard(self, n, held))
+            },
+        )
+    }
+
+    /// Closes the semaphore.
+    ///
+    /// A closed semaphore hands out no more permits, whichever method asks for them. The checked
+    /// operations, [`acquire_checked()`][`Semaphore::acquire_checked()`],
+    /// [`acquire_many_checked()`][`Semaphore::acquire_many_checked()`] and
+    /// [`acquire_arc_checked()`][`Semaphore::acquire_arc_checked()`], fail right away if they are
+    /// waiting, and so do new ones. Attempts to acquire permits without waiting return [`None`].
+    ///
+    /// The other operations cannot report an error, so **they are never released**: operations
+    /// that are waiting, and ones started later, wait forever. Tasks that should stop during
+    /// shutdown need to use the checked variants. Permits that are already held can still be
+    /// released.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Semaphore;
+    ///
+    /// let s = Semaphore::new(2);
+    /// s.close();
+    ///
+    /// assert!(s.is_closed());
+    /// assert!(s.try_acquire().is_none());
+    /// ```
+    pub fn close(&self) {
+        if !self.closed.is_set() {
+            self.closed.set();
+            // Wake the operations waiting for a permit, so the checked ones can fail.
+            self.event.notify(usize::MAX);
+        }
+    }
+
+    /// Returns `true` if the semaphore is closed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Semaphore;
+    ///
+    /// let s = Semaphore::new(2);
+    /// assert!(!s.is_closed());
+    /// ```
+    pub fn is_closed(&self) -> bool {
+        self.closed.is_set()
+    }
+
+    /// Takes a permit, waiting until one is available.
+    async fn acquire_permit(&self, location: &'static Location<'static>) -> trace::Held {
+        match self.acquire_permit_checked(location).await {
+            Ok(held) => held,
+            // Unchecked operations cannot report the close, so they are never released.
+            Err(_) => core::future::pending().await,
+        }
+    }
+
+    /// Takes a permit, waiting until one is available or the semaphore is closed.
+    async fn acquire_permit_checked(
+        &self,
+        location: &'static Location<'static>,
+    ) -> Result<trace::Held, ClosedSemaphoreError> {
+        if self.is_closed() {
+            return Err(ClosedSemaphoreError(()));
+        }
+        if self.try_decrement() {
+            return Ok(trace::acquired(self.target(), location));
+        }
+
+        let wait = trace::wait(self.target(), location);
+        let mut listener = None;
+
+        loop {
+            if self.is_closed() {
+                return Err(ClosedSemaphoreError(()));
+            }
+            if self.try_decrement() {
+                return Ok(wait.acquired());
+            }
+
+            match listener.take() {
+                None => listener = Some(self.event.listen()),
+                Some(l) => {
+                    l.await;
+                    crate::coop::yielded();
+                }
+            }
+        }
+    }
+
+    /// Attempts to take a permit without waiting.
+    fn try_decrement(&self) -> bool {
         let mut count = self.count.load(Ordering::Acquire);
         loop {
             if count == 0 {
-                return None;
+                return false;
             }
 
             match self.count.compare_exchange_weak(
@@ -59,42 +578,207 @@ impl Semaphore {
                 Ordering::AcqRel,
                 Ordering::Acquire,
             ) {
-                Ok(_) => return Some(SemaphoreGuard(self)),
+                Ok(_) => return true,
                 Err(c) => count = c,
             }
         }
     }
 
-    /// Waits for a permit for a concurrent operation.
+    /// Attempts to take `n` permits at once without waiting.
+    fn try_decrement_many(&self, n: usize) -> bool {
+        let mut count = self.count.load(Ordering::Acquire);
+        loop {
+            if count < n {
+                return false;
+            }
+
+            match self.count.compare_exchange_weak(
+                count,
+                count - n,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return true,
+                Err(c) => count = c,
+            }
+        }
+    }
+
+    /// Takes as many of the `n` permits as are available, returning how many were taken.
+    fn take_up_to(&self, n: usize) -> usize {
+        let mut count = self.count.load(Ordering::Acquire);
+        loop {
+            let taken = count.min(n);
+            if taken == 0 {
+                return 0;
+            }
+
+            match self.count.compare_exchange_weak(
+                count,
+                count - taken,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return taken,
+                Err(c) => count = c,
+            }
+        }
+    }
+
+    /// Returns `n` permits without instrumentation and wakes as many waiting operations.
     ///
-    /// Returns a guard that releases the permit when dropped.
+    /// Permits still owed to [`Semaphore::remove_permits()`] are removed instead.
+    fn add(&self, mut n: usize) {
+        let mut debt = self.debt.load(Ordering::Acquire);
+        while debt > 0 && n > 0 {
+            let paid = debt.min(n);
+            match self.debt.compare_exchange_weak(
+                debt,
+                debt - paid,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => n -= paid,
+                Err(d) => debt = d,
+            }
+        }
+
+        if n == 0 {
+            return;
+        }
+        self.count.fetch_add(n, Ordering::AcqRel);
+
+        // Every permit wakes its own waiter, even if an earlier one has not run yet. Waiters that
+        // are dropped after being woken pass the notification on to the next one.
+        self.event.notify(n.additional());
+    }
+
+    /// Returns the permits of a guard and wakes waiting operations.
+    fn release(&self, permits: usize, held: trace::Held) {
+        trace::released(self.target(), held);
+        self.add(permits);
+    }
+
+    /// Adds `n` permits to the semaphore, waking operations that wait for them.
+    ///
+    /// Permits that are still to be removed after a call to
+    /// [`remove_permits()`][`Semaphore::remove_permits()`] are offset against the new ones first.
     ///
     /// # Examples
     ///
     /// ```
-    /// # futures_lite::future::block_on(async {
     /// use async_lock::Semaphore;
     ///
-    /// let s = Semaphore::new(2);
-    /// let guard = s.acquire().await;
-    /// # });
+    /// let s = Semaphore::new(1);
+    /// let g1 = s.try_acquire().unwrap();
+    /// assert!(s.try_acquire().is_none());
+    ///
+    /// s.add_permits(1);
+    /// assert!(s.try_acquire().is_some());
     /// ```
-    pub async fn acquire(&self) -> SemaphoreGuard<'_> {
-        let mut listener = None;
+    pub fn add_permits(&self, n: usize) {
+        self.add(n);
+    }
 
-        loop {
-            if let Some(guard) = self.try_acquire() {
-                return guard;
-            }
+    /// Removes `n` permits from the semaphore.
+    ///
+    /// Available permits are removed right away. The rest are removed as they are released by the
+    /// operations holding them, so the number of concurrent operations drops to the new limit
+    /// without waiting for them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Semaphore;
+    ///
+    /// let s = Semaphore::new(3);
+    /// let g1 = s.try_acquire().unwrap();
+    /// let g2 = s.try_acquire().unwrap();
+    ///
+    /// // One permit is removed right away, the other one once it is released.
+    /// s.remove_permits(2);
+    /// assert!(s.try_acquire().is_none());
+    ///
+    /// drop(g1);
+    /// assert!(s.try_acquire().is_none());
+    /// drop(g2);
+    /// assert!(s.try_acquire().is_some());
+    /// ```
+    pub fn remove_permits(&self, n: usize) {
+        let owed = n - self.take_up_to(n);
+        if owed > 0 {
+            self.debt.fetch_add(owed, Ordering::AcqRel);
 
-            match listener.take() {
-                None => listener = Some(self.event.listen()),
-                Some(l) => l.await,
+            // Permits released in the meantime were not offset against the debt yet.
+            let returned = self.take_up_to(owed);
+            if returned > 0 {
+                self.add(returned);
             }
         }
     }
+
+    /// Returns the lock operations currently waiting for this semaphore.
+    ///
+    /// Waiters are listed in the order they started waiting. Each one is identified by an opaque
+    /// ID and carries the location of the call, when it started waiting, and its
+    /// [label][`crate::diagnostics::label()`], if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::{diagnostics, Semaphore};
+    /// use futures_lite::future;
+    ///
+    /// let s = Semaphore::new(1);
+    /// let _guard = s.try_acquire().unwrap();
+    ///
+    /// let mut waiter = Box::pin(diagnostics::label("worker", s.acquire()));
+    /// assert!(future::poll_once(&mut waiter).await.is_none());
+    ///
+    /// let waiters = s.waiters();
+    /// assert_eq!(waiters.len(), 1);
+    /// assert_eq!(waiters[0].label(), Some("worker"));
+    /// # })
+    /// ```
+    #[cfg(feature = "registry")]
+    pub fn waiters(&self) -> Vec<WaiterInfo> {
+        self.target().waiters()
+    }
+
+    /// Subscribes to the acquisitions and releases of this semaphore.
+    ///
+    /// The returned stream yields a [`LockEvent`][`crate::diagnostics::LockEvent`] every time
+    /// a permit is acquired or released from now on, and ends once the semaphore is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::diagnostics::LockAction;
+    /// use async_lock::Semaphore;
+    /// use futures_lite::StreamExt;
+    ///
+    /// let s = Semaphore::new(1);
+    /// let mut events = s.events();
+    ///
+    /// drop(s.acquire().await);
+    /// assert_eq!(events.next().await.unwrap().action(), LockAction::Acquired);
+    /// assert_eq!(events.next().await.unwrap().action(), LockAction::Released);
+    /// # })
+    /// ```
+    #[cfg(feature = "events")]
+    pub fn events(&self) -> LockEvents {
+        self.target().events()
+    }
+
+    /// Identifies this semaphore in instrumentation.
+    fn target(&self) -> trace::Target<'_> {
+        trace::Target::new("Semaphore", "acquire", self, &self.hooks)
+    }
 }
 
+#[cfg(target_has_atomic = "ptr")]
 impl Semaphore {
     /// Attempts to get an owned permit for a concurrent operation.
     ///
@@ -116,22 +800,17 @@ impl Semaphore {
     /// drop(g2);
     /// assert!(s.try_acquire_arc().is_some());
     /// ```
+    #[track_caller]
     pub fn try_acquire_arc(self: &Arc<Self>) -> Option<SemaphoreGuardArc> {
-        let mut count = self.count.load(Ordering::Acquire);
-        loop {
-            if count == 0 {
-                return None;
-            }
-
-            match self.count.compare_exchange_weak(
-                count,
-                count - 1,
-                Ordering::AcqRel,
-                Ordering::Acquire,
-            ) {
-                Ok(_) => return Some(SemaphoreGuardArc(self.clone())),
-                Err(c) => count = c,
-            }
+        let location = Location::caller();
+        if !self.is_closed() && self.try_decrement() {
+            Some(SemaphoreGuardArc(
+                self.clone(),
+                1,
+                trace::acquired(self.target(), location),
+            ))
+        } else {
+            None
         }
     }
 
@@ -150,40 +829,246 @@ impl Semaphore {
     /// let guard = s.acquire_arc().await;
     /// # });
     /// ```
-    pub async fn acquire_arc(self: &Arc<Self>) -> SemaphoreGuardArc {
-        let mut listener = None;
+    #[track_caller]
+    pub fn acquire_arc(self: &Arc<Self>) -> impl Future<Output = SemaphoreGuardArc> + '_ {
+        let location = Location::caller();
 
-        loop {
-            if let Some(guard) = self.try_acquire_arc() {
-                return guard;
-            }
+        trace::instrument(self.target(), "Semaphore::acquire_arc", async move {
+            crate::coop::consume_budget(&**self).await;
+            let held = self.acquire_permit(location).await;
+            SemaphoreGuardArc(self.clone(), 1, held)
+        })
+    }
 
-            match listener.take() {
-                None => listener = Some(self.event.listen()),
-                Some(l) => l.await,
-            }
-        }
+    /// Waits for an owned permit, blocking the current thread until one is available.
+    ///
+    /// This is the blocking version of [`acquire_arc()`][`Semaphore::acquire_arc()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Semaphore;
+    /// use std::sync::Arc;
+    ///
+    /// let s = Arc::new(Semaphore::new(2));
+    /// let guard = s.acquire_arc_blocking();
+    /// ```
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    #[track_caller]
+    pub fn acquire_arc_blocking(self: &Arc<Self>) -> SemaphoreGuardArc {
+        crate::blocking::block_on(self.acquire_arc())
+    }
+
+    /// Waits for an owned permit, failing if the semaphore is closed.
+    ///
+    /// This is the owned version of [`acquire_checked()`][`Semaphore::acquire_checked()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Semaphore;
+    /// use std::sync::Arc;
+    ///
+    /// let s = Arc::new(Semaphore::new(2));
+    /// let guard = s.acquire_arc_checked().await.unwrap();
+    ///
+    /// s.close();
+    /// assert!(s.acquire_arc_checked().await.is_err());
+    /// # });
+    /// ```
+    #[track_caller]
+    pub fn acquire_arc_checked(
+        self: &Arc<Self>,
+    ) -> impl Future<Output = Result<SemaphoreGuardArc, ClosedSemaphoreError>> + '_ {
+        let location = Location::caller();
+
+        trace::instrument(
+            self.target(),
+            "Semaphore::acquire_arc_checked",
+            async move {
+                crate::coop::consume_budget(&**self).await;
+                let held = self.acquire_permit_checked(location).await?;
+                Ok(SemaphoreGuardArc(self.clone(), 1, held))
+            },
+        )
     }
 }
 
-/// A guard that releases the acquired permit.
+/// A guard that releases the acquired permits.
 #[derive(Debug)]
-pub struct SemaphoreGuard<'a>(&'a Semaphore);
+pub struct SemaphoreGuard<'a>(&'a Semaphore, usize, trace::Held);
+
+impl<'a> SemaphoreGuard<'a> {
+    /// Returns the number of permits held by this guard.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Semaphore;
+    ///
+    /// let s = Semaphore::new(5);
+    /// let guard = s.try_acquire_many(3).unwrap();
+    /// assert_eq!(guard.permits(), 3);
+    /// ```
+    pub fn permits(&self) -> usize {
+        self.1
+    }
+
+    /// Drops the guard without releasing its permits.
+    ///
+    /// The permits are removed from the semaphore for good, which is useful for tracking resources
+    /// that are used up.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Semaphore;
+    ///
+    /// let s = Semaphore::new(2);
+    /// s.try_acquire().unwrap().forget();
+    ///
+    /// let guard = s.try_acquire().unwrap();
+    /// assert!(s.try_acquire().is_none());
+    /// ```
+    pub fn forget(mut self) {
+        self.1 = 0;
+    }
+
+    /// Splits `n` permits off into a separate guard.
+    ///
+    /// Both guards release their own permits when dropped. Returns [`None`] and leaves this guard
+    /// unchanged if it holds fewer than `n` permits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Semaphore;
+    ///
+    /// let s = Semaphore::new(4);
+    /// let mut guard = s.try_acquire_many(4).unwrap();
+    ///
+    /// let part = guard.split(1).unwrap();
+    /// assert_eq!(guard.permits(), 3);
+    ///
+    /// drop(part);
+    /// assert!(s.try_acquire().is_some());
+    /// ```
+    pub fn split(&mut self, n: usize) -> Option<SemaphoreGuard<'a>> {
+        if n > self.1 {
+            return None;
+        }
+        self.1 -= n;
+
+        // The acquisition is recorded by this guard, so the new one releases permits only.
+        Some(SemaphoreGuard(self.0, n, trace::Held::pending()))
+    }
+}
 
 impl Drop for SemaphoreGuard<'_> {
     fn drop(&mut self) {
-        self.0.count.fetch_add(1, Ordering::AcqRel);
-        self.0.event.notify(1);
+        self.0.release(self.1, self.2);
     }
 }
 
-/// An owned guard that releases the acquired permit.
+/// An owned guard that releases the acquired permits.
+#[cfg(target_has_atomic = "ptr")]
 #[derive(Debug)]
-pub struct SemaphoreGuardArc(Arc<Semaphore>);
+pub struct SemaphoreGuardArc(Arc<Semaphore>, usize, trace::Held);
+
+#[cfg(target_has_atomic = "ptr")]
+impl SemaphoreGuardArc {
+    /// Returns the number of permits held by this guard.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Semaphore;
+    /// use std::sync::Arc;
+    ///
+    /// let s = Arc::new(Semaphore::new(5));
+    /// let guard = s.try_acquire_arc().unwrap();
+    /// assert_eq!(guard.permits(), 1);
+    /// ```
+    pub fn permits(&self) -> usize {
+        self.1
+    }
 
+    /// Drops the guard without releasing its permits.
+    ///
+    /// This is the owned version of [`SemaphoreGuard::forget()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Semaphore;
+    /// use std::sync::Arc;
+    ///
+    /// let s = Arc::new(Semaphore::new(1));
+    /// s.try_acquire_arc().unwrap().forget();
+    /// assert!(s.try_acquire_arc().is_none());
+    /// ```
+    pub fn forget(mut self) {
+        self.1 = 0;
+    }
+
+    /// Splits `n` permits off into a separate guard.
+    ///
+    /// This is the owned version of [`SemaphoreGuard::split()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Semaphore;
+    /// use std::sync::Arc;
+    ///
+    /// let s = Arc::new(Semaphore::new(1));
+    /// let mut guard = s.try_acquire_arc().unwrap();
+    ///
+    /// let part = guard.split(1).unwrap();
+    /// assert_eq!(guard.permits(), 0);
+    /// assert!(guard.split(1).is_none());
+    /// ```
+    pub fn split(&mut self, n: usize) -> Option<SemaphoreGuardArc> {
+        if n > self.1 {
+            return None;
+        }
+        self.1 -= n;
+
+        // The acquisition is recorded by this guard, so the new one releases permits only.
+        Some(SemaphoreGuardArc(self.0.clone(), n, trace::Held::pending()))
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
 impl Drop for SemaphoreGuardArc {
     fn drop(&mut self) {
-        self.0.count.fetch_add(1, Ordering::AcqRel);
-        self.0.event.notify(1);
+        self.0.release(self.1, self.2);
+    }
+}
+
+/// An error returned when acquiring a permit of a closed [`Semaphore`].
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::Semaphore;
+///
+/// let s = Semaphore::new(1);
+/// s.close();
+/// let err = s.acquire_checked().await.unwrap_err();
+/// assert_eq!(err.to_string(), "semaphore is closed");
+/// # });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClosedSemaphoreError(());
+
+impl fmt::Display for ClosedSemaphoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("semaphore is closed")
     }
 }
+
+#[cfg(feature = "std")]
+impl std::error::Error for ClosedSemaphoreError {}
@@ -0,0 +1,325 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::num::NonZeroUsize;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use std::thread;
+
+use crate::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// The shard of the next thread that reads from a [`ShardedLock`].
+static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+
+std::thread_local! {
+    /// The shard that readers on this thread use.
+    static SHARD: usize = NEXT_SHARD.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A reader-writer lock that spreads its readers over several shards.
+///
+/// Every shard is a separate [`RwLock`] on its own cache line. Readers only lock the shard of the
+/// thread they run on, so readers on different threads do not contend for the same cache line.
+/// Writers lock every shard, which makes writing considerably slower than with a single
+/// [`RwLock`]. This suits read-mostly data such as routing tables that are read on every request.
+///
+/// There is one shard per available CPU.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::ShardedLock;
+/// use std::collections::HashMap;
+///
+/// let routes = ShardedLock::new(HashMap::new());
+/// routes.write().await.insert("/", "index");
+///
+/// assert_eq!(routes.read().await.get("/"), Some(&"index"));
+/// # })
+/// ```
+pub struct ShardedLock<T: ?Sized> {
+    /// The shards, in the order writers lock them.
+    shards: Box<[Shard]>,
+
+    /// The value inside the lock.
+    value: UnsafeCell<T>,
+}
+
+/// A shard, padded to its own cache line.
+#[repr(align(128))]
+struct Shard(RwLock<()>);
+
+unsafe impl<T: Send + ?Sized> Send for ShardedLock<T> {}
+unsafe impl<T: Send + Sync + ?Sized> Sync for ShardedLock<T> {}
+
+impl<T> ShardedLock<T> {
+    /// Creates a new sharded lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::ShardedLock;
+    ///
+    /// let lock = ShardedLock::new(0);
+    /// ```
+    pub fn new(value: T) -> ShardedLock<T> {
+        let count = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        ShardedLock {
+            shards: (0..count).map(|_| Shard(RwLock::new(()))).collect(),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consumes the lock, returning the underlying data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::ShardedLock;
+    ///
+    /// let lock = ShardedLock::new(10);
+    /// assert_eq!(lock.into_inner(), 10);
+    /// ```
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: ?Sized> ShardedLock<T> {
+    /// Acquires a read lock.
+    ///
+    /// This only locks the shard of the current thread, and waits while a writer holds or is
+    /// waiting for it. Returns a guard that releases the lock when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::ShardedLock;
+    ///
+    /// let lock = ShardedLock::new(1);
+    ///
+    /// let reader = lock.read().await;
+    /// assert_eq!(*reader, 1);
+    /// assert!(lock.try_read().is_some());
+    /// # })
+    /// ```
+    pub async fn read(&self) -> ShardedLockReadGuard<'_, T> {
+        let shard = self.shard().read().await;
+        ShardedLockReadGuard {
+            _shard: shard,
+            value: unsafe { &*self.value.get() },
+        }
+    }
+
+    /// Attempts to acquire a read lock.
+    ///
+    /// If a writer holds or is waiting for the shard of the current thread, then [`None`] is
+    /// returned. Otherwise, a guard is returned that releases the lock when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::ShardedLock;
+    ///
+    /// let lock = ShardedLock::new(1);
+    ///
+    /// let reader = lock.try_read().unwrap();
+    /// assert_eq!(*reader, 1);
+    /// assert!(lock.try_write().is_none());
+    /// ```
+    pub fn try_read(&self) -> Option<ShardedLockReadGuard<'_, T>> {
+        let shard = self.shard().try_read()?;
+        Some(ShardedLockReadGuard {
+            _shard: shard,
+            value: unsafe { &*self.value.get() },
+        })
+    }
+
+    /// Acquires the write lock.
+    ///
+    /// This locks every shard in turn. Returns a guard that releases the lock when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::ShardedLock;
+    ///
+    /// let lock = ShardedLock::new(1);
+    ///
+    /// let mut writer = lock.write().await;
+    /// *writer = 2;
+    /// assert!(lock.try_read().is_none());
+    /// # })
+    /// ```
+    pub async fn write(&self) -> ShardedLockWriteGuard<'_, T> {
+        // Every writer locks the shards in the same order, and readers only lock one of them, so
+        // no two lock operations can wait for each other in a cycle.
+        let mut shards = Vec::with_capacity(self.shards.len());
+        for shard in self.shards.iter() {
+            shards.push(shard.0.write().await);
+        }
+        ShardedLockWriteGuard {
+            _shards: shards,
+            lock: self,
+        }
+    }
+
+    /// Attempts to acquire the write lock.
+    ///
+    /// If any shard is locked, then [`None`] is returned. Otherwise, a guard is returned that
+    /// releases the lock when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::ShardedLock;
+    ///
+    /// let lock = ShardedLock::new(1);
+    ///
+    /// let writer = lock.try_write().unwrap();
+    /// assert!(lock.try_read().is_none());
+    /// assert!(lock.try_write().is_none());
+    /// ```
+    pub fn try_write(&self) -> Option<ShardedLockWriteGuard<'_, T>> {
+        // Shards locked before a failure are released on return.
+        let shards = self
+            .shards
+            .iter()
+            .map(|shard| shard.0.try_write())
+            .collect::<Option<Vec<_>>>()?;
+        Some(ShardedLockWriteGuard {
+            _shards: shards,
+            lock: self,
+        })
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the lock mutably, no actual locking takes place -- the mutable
+    /// borrow statically guarantees no locks exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::ShardedLock;
+    ///
+    /// let mut lock = ShardedLock::new(1);
+    ///
+    /// *lock.get_mut() = 2;
+    /// assert_eq!(*lock.read().await, 2);
+    /// # })
+    /// ```
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+
+    /// Returns the shard that readers on the current thread use.
+    fn shard(&self) -> &RwLock<()> {
+        let index = SHARD.with(|shard| *shard) % self.shards.len();
+        &self.shards[index].0
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for ShardedLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Locked;
+        impl fmt::Debug for Locked {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("<locked>")
+            }
+        }
+
+        match self.try_read() {
+            None => f
+                .debug_struct("ShardedLock")
+                .field("value", &Locked)
+                .finish(),
+            Some(guard) => f
+                .debug_struct("ShardedLock")
+                .field("value", &&*guard)
+                .finish(),
+        }
+    }
+}
+
+impl<T> From<T> for ShardedLock<T> {
+    fn from(val: T) -> ShardedLock<T> {
+        ShardedLock::new(val)
+    }
+}
+
+impl<T: Default> Default for ShardedLock<T> {
+    fn default() -> ShardedLock<T> {
+        ShardedLock::new(Default::default())
+    }
+}
+
+/// A guard that releases the read lock when dropped.
+pub struct ShardedLockReadGuard<'a, T: ?Sized> {
+    /// The read lock of the shard.
+    _shard: RwLockReadGuard<'a, ()>,
+
+    /// The value inside the lock.
+    value: &'a T,
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for ShardedLockReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display + ?Sized> fmt::Display for ShardedLockReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized> Deref for ShardedLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+/// A guard that releases the write lock when dropped.
+pub struct ShardedLockWriteGuard<'a, T: ?Sized> {
+    /// The write locks of every shard.
+    _shards: Vec<RwLockWriteGuard<'a, ()>>,
+
+    /// The locked lock.
+    lock: &'a ShardedLock<T>,
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for ShardedLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display + ?Sized> fmt::Display for ShardedLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized> Deref for ShardedLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for ShardedLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
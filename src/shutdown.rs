@@ -0,0 +1,285 @@
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
+use event_listener::Event;
+
+use crate::sync::atomic::{AtomicUsize, Ordering};
+
+/// Set in `Shutdown::state` once shutdown has begun.
+const CLOSED: usize = 1;
+
+/// Added to `Shutdown::state` for every in-flight operation.
+const ONE: usize = 2;
+
+/// Coordinates a graceful shutdown with the operations that are still in flight.
+///
+/// Every operation [registers][`Shutdown::register()`] itself and holds the returned guard until it
+/// is done. Shutdown code calls [`begin()`][`Shutdown::begin()`], which turns away new operations,
+/// and then waits for the in-flight ones with [`wait_idle()`][`Shutdown::wait_idle()`].
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::Shutdown;
+///
+/// let shutdown = Shutdown::new();
+///
+/// let request = shutdown.register().unwrap();
+/// shutdown.begin();
+///
+/// // New requests are turned away.
+/// assert!(shutdown.register().is_none());
+///
+/// drop(request);
+/// shutdown.wait_idle().await;
+/// # })
+/// ```
+#[derive(Debug)]
+pub struct Shutdown {
+    /// The `CLOSED` flag plus `ONE` per in-flight operation.
+    state: AtomicUsize,
+
+    /// Notified when shutdown begins.
+    begun: Event,
+
+    /// Notified when the last in-flight operation finishes after shutdown began.
+    idle: Event,
+}
+
+impl Shutdown {
+    /// Creates a coordinator that accepts operations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Shutdown;
+    ///
+    /// let shutdown = Shutdown::new();
+    /// ```
+    pub const fn new() -> Shutdown {
+        Shutdown {
+            state: AtomicUsize::new(0),
+            begun: Event::new(),
+            idle: Event::new(),
+        }
+    }
+
+    /// Registers an in-flight operation.
+    ///
+    /// Returns a guard that finishes the operation when dropped, or [`None`] if shutdown has
+    /// already begun.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Shutdown;
+    ///
+    /// let shutdown = Shutdown::new();
+    /// let guard = shutdown.register().unwrap();
+    /// assert_eq!(shutdown.in_flight(), 1);
+    /// ```
+    pub fn register(&self) -> Option<ShutdownGuard<'_>> {
+        if self.try_register() {
+            Some(ShutdownGuard(self))
+        } else {
+            None
+        }
+    }
+
+    /// Begins the shutdown.
+    ///
+    /// From now on, [`register()`][`Shutdown::register()`] turns away new operations, and tasks
+    /// waiting in [`wait_begin()`][`Shutdown::wait_begin()`] are woken. Calling this more than
+    /// once has no further effect.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Shutdown;
+    ///
+    /// let shutdown = Shutdown::new();
+    /// shutdown.begin();
+    /// assert!(shutdown.is_shutting_down());
+    /// ```
+    pub fn begin(&self) {
+        let state = self.state.fetch_or(CLOSED, Ordering::AcqRel);
+        if state & CLOSED == 0 {
+            self.begun.notify(usize::MAX);
+            if state == 0 {
+                self.idle.notify(usize::MAX);
+            }
+        }
+    }
+
+    /// Returns `true` if shutdown has begun.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Shutdown;
+    ///
+    /// let shutdown = Shutdown::new();
+    /// assert!(!shutdown.is_shutting_down());
+    /// ```
+    pub fn is_shutting_down(&self) -> bool {
+        self.state.load(Ordering::Acquire) & CLOSED != 0
+    }
+
+    /// Returns the number of operations that are still in flight.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Shutdown;
+    ///
+    /// let shutdown = Shutdown::new();
+    /// assert_eq!(shutdown.in_flight(), 0);
+    /// ```
+    pub fn in_flight(&self) -> usize {
+        self.state.load(Ordering::Acquire) / ONE
+    }
+
+    /// Waits until shutdown has begun.
+    ///
+    /// Long-running operations can use this to stop early.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Shutdown;
+    ///
+    /// let shutdown = Shutdown::new();
+    /// shutdown.begin();
+    /// shutdown.wait_begin().await;
+    /// # })
+    /// ```
+    pub async fn wait_begin(&self) {
+        while !self.is_shutting_down() {
+            let listener = self.begun.listen();
+            if self.is_shutting_down() {
+                break;
+            }
+            listener.await;
+        }
+    }
+
+    /// Waits until shutdown has begun and every in-flight operation has finished.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Shutdown;
+    /// use futures_lite::future;
+    ///
+    /// let shutdown = Shutdown::new();
+    /// let guard = shutdown.register().unwrap();
+    /// shutdown.begin();
+    ///
+    /// let mut idle = Box::pin(shutdown.wait_idle());
+    /// assert!(future::poll_once(&mut idle).await.is_none());
+    ///
+    /// drop(guard);
+    /// idle.await;
+    /// # })
+    /// ```
+    pub async fn wait_idle(&self) {
+        while !self.is_idle() {
+            let listener = self.idle.listen();
+            if self.is_idle() {
+                break;
+            }
+            listener.await;
+        }
+    }
+
+    /// Attempts to count a new operation, returning `false` if shutdown has begun.
+    fn try_register(&self) -> bool {
+        let mut state = self.state.load(Ordering::Acquire);
+        loop {
+            if state & CLOSED != 0 {
+                return false;
+            }
+            if state > usize::MAX / 2 {
+                // In case of potential overflow, abort.
+                crate::abort();
+            }
+
+            match self.state.compare_exchange_weak(
+                state,
+                state + ONE,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return true,
+                Err(s) => state = s,
+            }
+        }
+    }
+
+    /// Finishes an in-flight operation.
+    fn finish(&self) {
+        if self.state.fetch_sub(ONE, Ordering::AcqRel) == CLOSED | ONE {
+            self.idle.notify(usize::MAX);
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.state.load(Ordering::Acquire) == CLOSED
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl Shutdown {
+    /// Registers an in-flight operation with an owned guard.
+    ///
+    /// This is the owned version of [`register()`][`Shutdown::register()`], for operations that
+    /// run in spawned tasks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Shutdown;
+    /// use std::sync::Arc;
+    ///
+    /// let shutdown = Arc::new(Shutdown::new());
+    /// let guard = shutdown.register_arc().unwrap();
+    /// ```
+    pub fn register_arc(self: &Arc<Self>) -> Option<ShutdownGuardArc> {
+        if self.try_register() {
+            Some(ShutdownGuardArc(self.clone()))
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Shutdown {
+        Shutdown::new()
+    }
+}
+
+/// A guard that keeps an operation registered with a [`Shutdown`] in flight.
+#[derive(Debug)]
+pub struct ShutdownGuard<'a>(&'a Shutdown);
+
+impl Drop for ShutdownGuard<'_> {
+    fn drop(&mut self) {
+        self.0.finish();
+    }
+}
+
+/// An owned guard that keeps an operation registered with a [`Shutdown`] in flight.
+#[cfg(target_has_atomic = "ptr")]
+#[derive(Debug)]
+pub struct ShutdownGuardArc(Arc<Shutdown>);
+
+#[cfg(target_has_atomic = "ptr")]
+impl Drop for ShutdownGuardArc {
+    fn drop(&mut self) {
+        self.0.finish();
+    }
+}
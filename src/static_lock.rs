@@ -0,0 +1,377 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::future::Future;
+use core::ops::{Deref, DerefMut};
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use crate::sync::atomic::{AtomicBool, Ordering};
+
+/// An async mutex that never allocates.
+///
+/// Up to `N` blocked lock operations are stored inline inside the lock, so a `StaticLock` can be
+/// placed in a `static` on targets without a heap. When all `N` waiter slots are taken, further
+/// lock operations do not register themselves and instead keep yielding back to the executor
+/// until a slot frees up or the lock becomes available.
+///
+/// Woken waiters are not ordered, so this lock makes no fairness guarantees.
+///
+/// With the `critical-section` feature, the waiter table is guarded by a critical section instead
+/// of a spinlock, so [`try_lock()`][`StaticLock::try_lock()`] may be called and guards may be
+/// dropped from interrupt handlers.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::StaticLock;
+///
+/// static COUNTER: StaticLock<u32, 4> = StaticLock::new(0);
+///
+/// *COUNTER.lock().await += 1;
+/// assert_eq!(*COUNTER.try_lock().unwrap(), 1);
+/// # })
+/// ```
+pub struct StaticLock<T: ?Sized, const N: usize> {
+    /// Set to `true` while the lock is held.
+    locked: AtomicBool,
+
+    /// Set to `true` while the waiter table is being accessed.
+    #[cfg(not(feature = "critical-section"))]
+    busy: AtomicBool,
+
+    /// Inline slots for blocked lock operations.
+    slots: UnsafeCell<[Slot; N]>,
+
+    /// The value inside the lock.
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send + ?Sized, const N: usize> Send for StaticLock<T, N> {}
+unsafe impl<T: Send + ?Sized, const N: usize> Sync for StaticLock<T, N> {}
+
+/// A waiter slot.
+enum Slot {
+    /// Nobody is using this slot.
+    Empty,
+
+    /// A lock operation is waiting to be woken.
+    Waiting(Waker),
+
+    /// A lock operation was woken but has not yet claimed the lock or released the slot.
+    Notified,
+}
+
+impl<T, const N: usize> StaticLock<T, N> {
+    /// Creates a new heap-free lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::StaticLock;
+    ///
+    /// let lock = StaticLock::<_, 8>::new(0);
+    /// ```
+    pub const fn new(data: T) -> StaticLock<T, N> {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const EMPTY: Slot = Slot::Empty;
+
+        StaticLock {
+            locked: AtomicBool::new(false),
+            #[cfg(not(feature = "critical-section"))]
+            busy: AtomicBool::new(false),
+            slots: UnsafeCell::new([EMPTY; N]),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Consumes the lock, returning the underlying data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::StaticLock;
+    ///
+    /// let lock = StaticLock::<_, 8>::new(10);
+    /// assert_eq!(lock.into_inner(), 10);
+    /// ```
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized, const N: usize> StaticLock<T, N> {
+    /// Acquires the lock.
+    ///
+    /// Returns a guard that releases the lock when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::StaticLock;
+    ///
+    /// let lock = StaticLock::<_, 8>::new(10);
+    /// let guard = lock.lock().await;
+    /// assert_eq!(*guard, 10);
+    /// # })
+    /// ```
+    #[inline]
+    pub async fn lock(&self) -> StaticLockGuard<'_, T, N> {
+        crate::coop::consume_budget(self).await;
+        if let Some(guard) = self.try_lock() {
+            return guard;
+        }
+        let guard = Waiter {
+            lock: self,
+            slot: None,
+        }
+        .await;
+        crate::coop::yielded();
+        guard
+    }
+
+    /// Attempts to acquire the lock.
+    ///
+    /// If the lock could not be acquired at this time, then [`None`] is returned. Otherwise, a
+    /// guard is returned that releases the lock when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::StaticLock;
+    ///
+    /// let lock = StaticLock::<_, 8>::new(10);
+    /// if let Some(guard) = lock.try_lock() {
+    ///     assert_eq!(*guard, 10);
+    /// }
+    /// # ;
+    /// ```
+    #[inline]
+    pub fn try_lock(&self) -> Option<StaticLockGuard<'_, T, N>> {
+        if self
+            .locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            Some(StaticLockGuard(self))
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the lock mutably, no actual locking takes place -- the mutable
+    /// borrow statically guarantees the lock is not already acquired.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::StaticLock;
+    ///
+    /// let mut lock = StaticLock::<_, 8>::new(0);
+    /// *lock.get_mut() = 10;
+    /// assert_eq!(*lock.try_lock().unwrap(), 10);
+    /// ```
+    pub fn get_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.data.get() }
+    }
+
+    /// Runs a closure with exclusive access to the waiter table.
+    #[cfg(feature = "critical-section")]
+    fn with_slots<R>(&self, f: impl FnOnce(&mut [Slot; N]) -> R) -> R {
+        critical_section::with(|_| f(unsafe { &mut *self.slots.get() }))
+    }
+
+    /// Runs a closure with exclusive access to the waiter table.
+    #[cfg(not(feature = "critical-section"))]
+    fn with_slots<R>(&self, f: impl FnOnce(&mut [Slot; N]) -> R) -> R {
+        while self
+            .busy
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+
+        let res = f(unsafe { &mut *self.slots.get() });
+        self.busy.store(false, Ordering::Release);
+        res
+    }
+
+    /// Wakes one waiting lock operation, if there is any.
+    fn notify_one(&self) {
+        let waker = self.with_slots(|slots| {
+            for slot in slots.iter_mut() {
+                if let Slot::Waiting(_) = slot {
+                    if let Slot::Waiting(waker) = core::mem::replace(slot, Slot::Notified) {
+                        return Some(waker);
+                    }
+                }
+            }
+            None
+        });
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T: fmt::Debug + ?Sized, const N: usize> fmt::Debug for StaticLock<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Locked;
+        impl fmt::Debug for Locked {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("<locked>")
+            }
+        }
+
+        match self.try_lock() {
+            None => f.debug_struct("StaticLock").field("data", &Locked).finish(),
+            Some(guard) => f
+                .debug_struct("StaticLock")
+                .field("data", &&*guard)
+                .finish(),
+        }
+    }
+}
+
+impl<T, const N: usize> From<T> for StaticLock<T, N> {
+    fn from(val: T) -> StaticLock<T, N> {
+        StaticLock::new(val)
+    }
+}
+
+impl<T: Default, const N: usize> Default for StaticLock<T, N> {
+    fn default() -> StaticLock<T, N> {
+        StaticLock::new(Default::default())
+    }
+}
+
+/// A blocked lock operation, possibly occupying a waiter slot.
+struct Waiter<'a, T: ?Sized, const N: usize> {
+    lock: &'a StaticLock<T, N>,
+    slot: Option<usize>,
+}
+
+impl<'a, T: ?Sized, const N: usize> Future for Waiter<'a, T, N> {
+    type Output = StaticLockGuard<'a, T, N>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let lock = self.lock;
+
+        loop {
+            if let Some(guard) = lock.try_lock() {
+                if let Some(i) = self.slot.take() {
+                    lock.with_slots(|slots| slots[i] = Slot::Empty);
+                }
+                return Poll::Ready(guard);
+            }
+
+            // Register (or refresh) our waker in a slot.
+            let slot = self.slot;
+            let registered = lock.with_slots(|slots| {
+                let i = match slot {
+                    Some(i) => i,
+                    None => slots.iter().position(|s| matches!(s, Slot::Empty))?,
+                };
+                match &slots[i] {
+                    Slot::Waiting(w) if w.will_wake(cx.waker()) => {}
+                    _ => slots[i] = Slot::Waiting(cx.waker().clone()),
+                }
+                Some(i)
+            });
+
+            match registered {
+                Some(i) => self.slot = Some(i),
+                None => {
+                    // The waiter table is full, so yield and try again later.
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+            }
+
+            // Check again in case the lock was released while we were registering.
+            if !lock.locked.load(Ordering::SeqCst) {
+                continue;
+            }
+            return Poll::Pending;
+        }
+    }
+}
+
+impl<T: ?Sized, const N: usize> Drop for Waiter<'_, T, N> {
+    fn drop(&mut self) {
+        if let Some(i) = self.slot.take() {
+            let notified = self.lock.with_slots(|slots| {
+                matches!(
+                    core::mem::replace(&mut slots[i], Slot::Empty),
+                    Slot::Notified
+                )
+            });
+
+            // Pass on a notification that was meant for us.
+            if notified {
+                self.lock.notify_one();
+            }
+        }
+    }
+}
+
+/// A guard that releases a [`StaticLock`] when dropped.
+pub struct StaticLockGuard<'a, T: ?Sized, const N: usize>(&'a StaticLock<T, N>);
+
+unsafe impl<T: Send + ?Sized, const N: usize> Send for StaticLockGuard<'_, T, N> {}
+unsafe impl<T: Sync + ?Sized, const N: usize> Sync for StaticLockGuard<'_, T, N> {}
+
+impl<'a, T: ?Sized, const N: usize> StaticLockGuard<'a, T, N> {
+    /// Returns a reference to the lock a guard came from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::{StaticLock, StaticLockGuard};
+    ///
+    /// let lock = StaticLock::<_, 8>::new(10i32);
+    /// let guard = lock.try_lock().unwrap();
+    /// dbg!(StaticLockGuard::source(&guard));
+    /// ```
+    pub fn source(guard: &StaticLockGuard<'a, T, N>) -> &'a StaticLock<T, N> {
+        guard.0
+    }
+}
+
+impl<T: ?Sized, const N: usize> Drop for StaticLockGuard<'_, T, N> {
+    fn drop(&mut self) {
+        self.0.locked.store(false, Ordering::SeqCst);
+        self.0.notify_one();
+    }
+}
+
+impl<T: fmt::Debug + ?Sized, const N: usize> fmt::Debug for StaticLockGuard<'_, T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display + ?Sized, const N: usize> fmt::Display for StaticLockGuard<'_, T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized, const N: usize> Deref for StaticLockGuard<'_, T, N> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.0.data.get() }
+    }
+}
+
+impl<T: ?Sized, const N: usize> DerefMut for StaticLockGuard<'_, T, N> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.0.data.get() }
+    }
+}
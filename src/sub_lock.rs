@@ -0,0 +1,162 @@
+use core::fmt;
+use core::future::Future;
+use core::ops::{Deref, DerefMut};
+
+use crate::{Mutex, MutexGuard};
+
+/// A view of one part of the data in a [`Mutex`].
+///
+/// Created by [`Mutex::project()`]. Locking a sub-lock locks the whole mutex, but only gives
+/// access to the projected part, so a subsystem can be handed its slice of shared state without
+/// seeing the rest.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::Mutex;
+///
+/// struct State {
+///     cache: Vec<u32>,
+///     stats: u64,
+/// }
+///
+/// let state = Mutex::new(State { cache: Vec::new(), stats: 0 });
+/// let cache = state.project(|s| &mut s.cache);
+///
+/// cache.lock().await.push(1);
+/// assert_eq!(state.lock().await.cache, [1]);
+/// # })
+/// ```
+pub struct SubLock<'a, T: ?Sized, U: ?Sized> {
+    mutex: &'a Mutex<T>,
+    project: fn(&mut T) -> &mut U,
+}
+
+impl<'a, T: ?Sized, U: ?Sized> SubLock<'a, T, U> {
+    pub(crate) fn new(mutex: &'a Mutex<T>, project: fn(&mut T) -> &mut U) -> SubLock<'a, T, U> {
+        SubLock { mutex, project }
+    }
+
+    /// Acquires the parent mutex and returns a guard for the projected part.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Mutex;
+    ///
+    /// let pair = Mutex::new((1, 2));
+    /// let second = pair.project(|p| &mut p.1);
+    ///
+    /// *second.lock().await += 10;
+    /// assert_eq!(*pair.lock().await, (1, 12));
+    /// # })
+    /// ```
+    #[track_caller]
+    pub fn lock(&self) -> impl Future<Output = SubLockGuard<'a, T, U>> {
+        let lock = self.mutex.lock();
+        let project = self.project;
+        async move { SubLockGuard::new(lock.await, project) }
+    }
+
+    /// Attempts to acquire the parent mutex.
+    ///
+    /// If the mutex could not be acquired at this time, then [`None`] is returned. Otherwise, a
+    /// guard for the projected part is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Mutex;
+    ///
+    /// let pair = Mutex::new((1, 2));
+    /// let first = pair.project(|p| &mut p.0);
+    ///
+    /// let guard = pair.try_lock().unwrap();
+    /// assert!(first.try_lock().is_none());
+    /// drop(guard);
+    /// assert_eq!(*first.try_lock().unwrap(), 1);
+    /// ```
+    #[track_caller]
+    pub fn try_lock(&self) -> Option<SubLockGuard<'a, T, U>> {
+        self.mutex
+            .try_lock()
+            .map(|guard| SubLockGuard::new(guard, self.project))
+    }
+
+    /// Returns the parent mutex.
+    pub fn parent(&self) -> &'a Mutex<T> {
+        self.mutex
+    }
+}
+
+impl<T: ?Sized, U: ?Sized> Clone for SubLock<'_, T, U> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized, U: ?Sized> Copy for SubLock<'_, T, U> {}
+
+impl<T: ?Sized, U: fmt::Debug + ?Sized> fmt::Debug for SubLock<'_, T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Locked;
+        impl fmt::Debug for Locked {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("<locked>")
+            }
+        }
+
+        match self.try_lock() {
+            None => f.debug_struct("SubLock").field("data", &Locked).finish(),
+            Some(guard) => f.debug_struct("SubLock").field("data", &&*guard).finish(),
+        }
+    }
+}
+
+/// A guard that releases the parent mutex of a [`SubLock`] when dropped.
+pub struct SubLockGuard<'a, T: ?Sized, U: ?Sized> {
+    _guard: MutexGuard<'a, T>,
+    value: *mut U,
+}
+
+unsafe impl<T: Send + ?Sized, U: Send + ?Sized> Send for SubLockGuard<'_, T, U> {}
+unsafe impl<T: Sync + ?Sized, U: Sync + ?Sized> Sync for SubLockGuard<'_, T, U> {}
+
+impl<'a, T: ?Sized, U: ?Sized> SubLockGuard<'a, T, U> {
+    fn new(mut guard: MutexGuard<'a, T>, project: fn(&mut T) -> &mut U) -> SubLockGuard<'a, T, U> {
+        // The data stays in place inside the mutex while the guard is alive.
+        let value = project(&mut *guard) as *mut U;
+        SubLockGuard {
+            _guard: guard,
+            value,
+        }
+    }
+}
+
+impl<T: ?Sized, U: fmt::Debug + ?Sized> fmt::Debug for SubLockGuard<'_, T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized, U: fmt::Display + ?Sized> fmt::Display for SubLockGuard<'_, T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized, U: ?Sized> Deref for SubLockGuard<'_, T, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        unsafe { &*self.value }
+    }
+}
+
+impl<T: ?Sized, U: ?Sized> DerefMut for SubLockGuard<'_, T, U> {
+    fn deref_mut(&mut self) -> &mut U {
+        unsafe { &mut *self.value }
+    }
+}
@@ -0,0 +1,10 @@
+//! Atomics used by the locks.
+//!
+//! Targets without native compare-and-swap, such as `thumbv6m-none-eabi`, get them from
+//! [`portable-atomic`](https://docs.rs/portable-atomic) with the `portable-atomic` feature.
+
+#[cfg(not(feature = "portable-atomic"))]
+pub(crate) use core::sync::atomic;
+
+#[cfg(feature = "portable-atomic")]
+pub(crate) use portable_atomic as atomic;
@@ -0,0 +1,18 @@
+//! The time source used for fairness and timed operations.
+//!
+//! `std::time::Instant::now()` panics on `wasm32-unknown-unknown`, so browser builds read the clock
+//! through `performance.now()` instead.
+
+/// A point in time, as taken by [`Mutex::lock_deadline()`][`crate::Mutex::lock_deadline()`] and
+/// reported by the diagnostics.
+///
+/// This is `std::time::Instant`, except on `wasm32` targets, where it is `web_time::Instant`.
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant;
+
+/// A point in time, as taken by [`Mutex::lock_deadline()`][`crate::Mutex::lock_deadline()`] and
+/// reported by the diagnostics.
+///
+/// This is `std::time::Instant`, except on `wasm32` targets, where it is `web_time::Instant`.
+#[cfg(target_arch = "wasm32")]
+pub use web_time::Instant;
@@ -0,0 +1,134 @@
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+
+use pin_project_lite::pin_project;
+
+/// A source of timers for operations that give up after a while.
+///
+/// Timed operations in this crate, such as [`Semaphore::acquire_timeout()`], take a `Timer` so
+/// they work on whatever executor the caller uses. Implementations are provided for
+/// [`async-io`](https://docs.rs/async-io) behind the `async-io` feature and for
+/// [`tokio`](https://docs.rs/tokio) behind the `tokio` feature.
+///
+/// [`Semaphore::acquire_timeout()`]: `crate::Semaphore::acquire_timeout()`
+///
+/// # Examples
+///
+/// A timer that never fires:
+///
+/// ```
+/// use async_lock::Timer;
+/// use std::future::{pending, Pending};
+/// use std::time::Duration;
+///
+/// struct Never;
+///
+/// impl Timer for Never {
+///     type Sleep = Pending<()>;
+///
+///     fn sleep(&self, _: Duration) -> Pending<()> {
+///         pending()
+///     }
+/// }
+/// ```
+pub trait Timer {
+    /// The future returned by [`Timer::sleep()`].
+    type Sleep: Future<Output = ()>;
+
+    /// Returns a future that completes once `duration` has elapsed.
+    fn sleep(&self, duration: Duration) -> Self::Sleep;
+}
+
+impl<T: Timer + ?Sized> Timer for &T {
+    type Sleep = T::Sleep;
+
+    fn sleep(&self, duration: Duration) -> T::Sleep {
+        (**self).sleep(duration)
+    }
+}
+
+/// A [`Timer`] backed by [`async_io::Timer`].
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::{AsyncIoTimer, Semaphore};
+/// use std::time::Duration;
+///
+/// let s = Semaphore::new(0);
+/// assert!(s.acquire_timeout(AsyncIoTimer, Duration::from_millis(10)).await.is_none());
+/// # })
+/// ```
+#[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsyncIoTimer;
+
+#[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
+impl Timer for AsyncIoTimer {
+    type Sleep = AsyncIoSleep;
+
+    fn sleep(&self, duration: Duration) -> AsyncIoSleep {
+        AsyncIoSleep(async_io::Timer::after(duration))
+    }
+}
+
+/// The future returned by [`AsyncIoTimer`].
+#[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
+#[derive(Debug)]
+pub struct AsyncIoSleep(async_io::Timer);
+
+#[cfg(all(feature = "async-io", not(target_arch = "wasm32")))]
+impl Future for AsyncIoSleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        Pin::new(&mut self.0).poll(cx).map(|_| ())
+    }
+}
+
+/// A [`Timer`] backed by [`tokio::time::sleep()`].
+///
+/// This timer must be used from within a tokio runtime with the time driver enabled.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioTimer;
+
+#[cfg(feature = "tokio")]
+impl Timer for TokioTimer {
+    type Sleep = tokio::time::Sleep;
+
+    fn sleep(&self, duration: Duration) -> tokio::time::Sleep {
+        tokio::time::sleep(duration)
+    }
+}
+
+/// Runs `future` until it completes or `sleep` does, whichever comes first.
+pub(crate) fn timeout<F: Future, S: Future<Output = ()>>(future: F, sleep: S) -> Timeout<F, S> {
+    Timeout { future, sleep }
+}
+
+pin_project! {
+    /// Future for [`timeout()`].
+    pub(crate) struct Timeout<F, S> {
+        #[pin]
+        future: F,
+        #[pin]
+        sleep: S,
+    }
+}
+
+impl<F: Future, S: Future<Output = ()>> Future for Timeout<F, S> {
+    type Output = Option<F::Output>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        if let Poll::Ready(out) = this.future.poll(cx) {
+            return Poll::Ready(Some(out));
+        }
+        this.sleep.poll(cx).map(|()| None)
+    }
+}
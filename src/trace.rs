@@ -0,0 +1,749 @@
+//! Instrumentation points shared by all primitives.
+//!
+//! Every lock operation is identified by the kind of primitive, the access mode, and the address
+//! of the primitive. Depending on the enabled features, these points emit `tracing` events, log
+//! slow acquisitions, call [`LockMetrics`] hooks, describe locks as resources to `tokio-console`,
+//! keep the lock registry up to date, send events to subscribers, or apply priority ceilings.
+//! Without any such feature, all of these functions compile to nothing.
+//!
+//! [`LockMetrics`]: crate::diagnostics::LockMetrics
+
+use core::fmt;
+use core::future::Future;
+use core::panic::Location;
+
+#[cfg(any(feature = "hooks", feature = "registry"))]
+use alloc::sync::Arc;
+
+#[cfg(all(
+    feature = "std",
+    any(
+        feature = "tracing",
+        feature = "log",
+        feature = "hooks",
+        feature = "events"
+    )
+))]
+use crate::time::Instant;
+
+#[cfg(feature = "events")]
+use crate::diagnostics::{LockAction, LockEvent, LockEvents};
+#[cfg(feature = "hooks")]
+use crate::diagnostics::{LockInfo, LockMetrics};
+#[cfg(feature = "events")]
+use crate::events::Subscribers;
+#[cfg(feature = "registry")]
+use crate::registry::Record;
+
+#[cfg(feature = "priority-ceiling")]
+use crate::sync::atomic::{AtomicPtr, AtomicU32, Ordering};
+
+#[cfg(feature = "console")]
+use core::pin::Pin;
+#[cfg(feature = "console")]
+use core::task::{Context, Poll};
+#[cfg(any(feature = "console", feature = "registry"))]
+use std::sync::OnceLock;
+#[cfg(feature = "console")]
+use tracing::Span;
+
+/// Per-lock instrumentation settings.
+pub(crate) struct Hooks {
+    /// The name of this lock in debugging output.
+    name: Option<&'static str>,
+
+    /// Metrics hooks installed on this lock, overriding the global ones.
+    #[cfg(feature = "hooks")]
+    metrics: Option<Arc<dyn LockMetrics>>,
+
+    /// The `tokio-console` resource span, created on first use.
+    #[cfg(feature = "console")]
+    resource: OnceLock<Span>,
+
+    /// The entry in the lock registry, created on first use.
+    #[cfg(feature = "registry")]
+    record: OnceLock<Arc<Record>>,
+
+    /// Streams subscribed to the events of this lock.
+    #[cfg(feature = "events")]
+    subscribers: Subscribers,
+
+    /// The priority ceiling of this lock.
+    #[cfg(feature = "priority-ceiling")]
+    ceiling: Option<u32>,
+
+    /// The priority to restore, kept here while the guard holding this lock is consumed into a
+    /// raw pointer. Null hooks mean there is nothing to restore.
+    #[cfg(feature = "priority-ceiling")]
+    parked_hooks: AtomicPtr<crate::priority::CeilingHooks>,
+    #[cfg(feature = "priority-ceiling")]
+    parked_priority: AtomicU32,
+}
+
+impl Hooks {
+    /// Uses the global settings.
+    #[inline]
+    pub(crate) const fn new() -> Hooks {
+        Hooks {
+            name: None,
+            #[cfg(feature = "hooks")]
+            metrics: None,
+            #[cfg(feature = "console")]
+            resource: OnceLock::new(),
+            #[cfg(feature = "registry")]
+            record: OnceLock::new(),
+            #[cfg(feature = "events")]
+            subscribers: Subscribers::new(),
+            #[cfg(feature = "priority-ceiling")]
+            ceiling: None,
+            #[cfg(feature = "priority-ceiling")]
+            parked_hooks: AtomicPtr::new(core::ptr::null_mut()),
+            #[cfg(feature = "priority-ceiling")]
+            parked_priority: AtomicU32::new(0),
+        }
+    }
+
+    /// Refers to this lock by `name` in debugging output.
+    pub(crate) const fn named(name: &'static str) -> Hooks {
+        let mut hooks = Hooks::new();
+        hooks.name = Some(name);
+        hooks
+    }
+
+    /// Returns the name of this lock, if it has one.
+    pub(crate) fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    /// Raises holders of this lock to `ceiling`.
+    #[cfg(feature = "priority-ceiling")]
+    pub(crate) const fn with_ceiling(ceiling: u32) -> Hooks {
+        let mut hooks = Hooks::new();
+        hooks.ceiling = Some(ceiling);
+        hooks
+    }
+
+    /// Returns the priority ceiling of this lock.
+    #[cfg(feature = "priority-ceiling")]
+    pub(crate) fn ceiling(&self) -> Option<u32> {
+        self.ceiling
+    }
+
+    /// Keeps the priority a consumed guard would have restored.
+    #[cfg(feature = "priority-ceiling")]
+    fn park(&self, previous: Option<(&'static crate::priority::CeilingHooks, u32)>) {
+        let hooks = match previous {
+            Some((hooks, priority)) => {
+                self.parked_priority.store(priority, Ordering::Relaxed);
+                hooks as *const _ as *mut _
+            }
+            None => core::ptr::null_mut(),
+        };
+        self.parked_hooks.store(hooks, Ordering::Release);
+    }
+
+    /// Takes back the priority kept by [`Hooks::park()`].
+    #[cfg(feature = "priority-ceiling")]
+    fn unpark(&self) -> Option<(&'static crate::priority::CeilingHooks, u32)> {
+        let hooks = self.parked_hooks.load(Ordering::Acquire);
+        self.parked_hooks
+            .store(core::ptr::null_mut(), Ordering::Relaxed);
+        // SAFETY: Non-null values come from a `&'static` reference in `park()`.
+        let hooks = unsafe { hooks.as_ref() }?;
+        Some((hooks, self.parked_priority.load(Ordering::Relaxed)))
+    }
+
+    /// Reports to `metrics` instead of the global hooks.
+    #[cfg(feature = "hooks")]
+    pub(crate) fn with_metrics(metrics: Arc<dyn LockMetrics>) -> Hooks {
+        let mut hooks = Hooks::new();
+        hooks.metrics = Some(metrics);
+        hooks
+    }
+
+    /// Returns the hooks to report to, if any.
+    #[cfg(feature = "hooks")]
+    fn metrics(&self) -> Option<&dyn LockMetrics> {
+        match &self.metrics {
+            Some(metrics) => Some(&**metrics),
+            None => crate::diagnostics::global_metrics(),
+        }
+    }
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks").finish_non_exhaustive()
+    }
+}
+
+/// Identifies a lock in emitted events.
+#[derive(Clone, Copy)]
+#[allow(dead_code)]
+pub(crate) struct Target<'a> {
+    kind: &'static str,
+    mode: &'static str,
+    id: usize,
+    hooks: &'a Hooks,
+}
+
+impl<'a> Target<'a> {
+    #[inline]
+    pub(crate) fn new<T: ?Sized>(
+        kind: &'static str,
+        mode: &'static str,
+        lock: &T,
+        hooks: &'a Hooks,
+    ) -> Target<'a> {
+        Target {
+            kind,
+            mode,
+            id: lock as *const T as *const () as usize,
+            hooks,
+        }
+    }
+
+    /// Describes the lock to metrics hooks.
+    #[cfg(feature = "hooks")]
+    fn info(&self) -> LockInfo {
+        LockInfo {
+            kind: self.kind,
+            mode: self.mode,
+            id: self.id,
+            name: self.hooks.name,
+        }
+    }
+
+    /// Returns the registry entry of the lock, registering it on first use.
+    #[cfg(feature = "registry")]
+    fn record(&self) -> &'a Record {
+        let (kind, id, name) = (self.kind, self.id, self.hooks.name);
+        self.hooks
+            .record
+            .get_or_init(|| crate::registry::register(kind, id, name))
+    }
+
+    /// Refers to the lock in messages.
+    #[cfg(any(feature = "log", feature = "deadlock-detection"))]
+    fn lock(&self) -> LockName {
+        LockName {
+            name: self.hooks.name,
+            id: self.id,
+        }
+    }
+
+    /// Returns the most recent acquisition of the lock, if it is held.
+    #[cfg(feature = "registry")]
+    pub(crate) fn holder(&self) -> Option<crate::diagnostics::HolderInfo> {
+        self.hooks.record.get().and_then(|record| record.holder())
+    }
+
+    /// Returns the lock operations currently waiting for the lock.
+    #[cfg(feature = "registry")]
+    pub(crate) fn waiters(&self) -> alloc::vec::Vec<crate::diagnostics::WaiterInfo> {
+        match self.hooks.record.get() {
+            Some(record) => record.waiters(),
+            None => alloc::vec::Vec::new(),
+        }
+    }
+
+    /// Subscribes to the events of the lock.
+    #[cfg(feature = "events")]
+    pub(crate) fn events(&self) -> LockEvents {
+        self.hooks.subscribers.subscribe()
+    }
+
+    /// Sends an event to the subscribers of the lock.
+    #[cfg(feature = "events")]
+    #[inline]
+    fn publish(&self, action: LockAction, location: Option<&'static Location<'static>>) {
+        self.hooks.subscribers.publish(|| LockEvent {
+            action,
+            kind: self.kind,
+            mode: self.mode,
+            id: self.id,
+            at: Instant::now(),
+            location,
+            label: crate::diagnostics::current_label(),
+        });
+    }
+
+    /// Returns the `tokio-console` resource span of the lock.
+    #[cfg(feature = "console")]
+    fn resource(&self) -> &'a Span {
+        let kind = self.kind;
+        self.hooks.resource.get_or_init(|| {
+            tracing::trace_span!(
+                target: "runtime::resource",
+                "runtime.resource",
+                concrete_type = kind,
+                kind = "Sync",
+            )
+        })
+    }
+
+    /// Reports the lock state after an acquisition or release to `tokio-console`.
+    #[cfg(feature = "console")]
+    fn state_update(&self, acquired: bool) {
+        let resource = self.resource();
+        match self.mode {
+            "read" | "upgradable_read" => tracing::trace!(
+                target: "runtime::resource::state_update",
+                parent: resource,
+                readers = 1u64,
+                readers.op = if acquired { "add" } else { "sub" },
+            ),
+            "acquire" => tracing::trace!(
+                target: "runtime::resource::state_update",
+                parent: resource,
+                permits_acquired = 1u64,
+                permits_acquired.op = if acquired { "add" } else { "sub" },
+            ),
+            _ => tracing::trace!(
+                target: "runtime::resource::state_update",
+                parent: resource,
+                locked = acquired,
+                locked.op = "override",
+            ),
+        }
+    }
+}
+
+/// Refers to a lock by its name, or by its address if it has none.
+#[cfg(any(feature = "log", feature = "registry"))]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct LockName {
+    pub(crate) name: Option<&'static str>,
+    pub(crate) id: usize,
+}
+
+#[cfg(any(feature = "log", feature = "registry"))]
+impl fmt::Display for LockName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name {
+            Some(name) => write!(f, "{:?}", name),
+            None => write!(f, "{:#x}", self.id),
+        }
+    }
+}
+
+/// Remembers when a guard acquired its lock.
+#[derive(Clone, Copy)]
+pub(crate) struct Held {
+    /// Set to `false` for guards that only undo a canceled lock operation.
+    #[cfg(any(
+        feature = "tracing",
+        feature = "hooks",
+        feature = "registry",
+        feature = "events"
+    ))]
+    acquired: bool,
+    #[cfg(feature = "hooks")]
+    since: Instant,
+    /// The priority to restore on release, if the lock has a ceiling.
+    #[cfg(feature = "priority-ceiling")]
+    previous: Option<(&'static crate::priority::CeilingHooks, u32)>,
+    /// The task that acquired the lock, for deadlock detection.
+    #[cfg(feature = "deadlock-detection")]
+    task: Option<u64>,
+}
+
+impl fmt::Debug for Held {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Held").finish_non_exhaustive()
+    }
+}
+
+impl Held {
+    #[inline]
+    #[allow(unused_variables)]
+    fn now(target: &Target<'_>, location: &'static Location<'static>) -> Held {
+        Held {
+            #[cfg(any(
+                feature = "tracing",
+                feature = "hooks",
+                feature = "registry",
+                feature = "events"
+            ))]
+            acquired: true,
+            #[cfg(feature = "hooks")]
+            since: Instant::now(),
+            #[cfg(feature = "priority-ceiling")]
+            previous: target.hooks.ceiling.and_then(crate::priority::raise),
+            #[cfg(feature = "deadlock-detection")]
+            task: crate::deadlock::acquired(target.kind, target.id, location),
+        }
+    }
+
+    /// For a guard rebuilt from a lock that a guard consumed with [`parked()`] acquired.
+    ///
+    /// The hold time of such a guard starts when it is rebuilt. Releasing it restores the priority
+    /// the consumed guard would have restored.
+    #[inline]
+    #[allow(unused_variables)]
+    pub(crate) fn unparked(target: Target<'_>) -> Held {
+        Held {
+            #[cfg(any(
+                feature = "tracing",
+                feature = "hooks",
+                feature = "registry",
+                feature = "events"
+            ))]
+            acquired: true,
+            #[cfg(feature = "hooks")]
+            since: Instant::now(),
+            #[cfg(feature = "priority-ceiling")]
+            previous: target.hooks.unpark(),
+            #[cfg(feature = "deadlock-detection")]
+            task: None,
+        }
+    }
+
+    /// For a guard rebuilt from a lock that some earlier guard already acquired.
+    ///
+    /// The hold time of such a guard starts when it is rebuilt. Its priority ceiling was already
+    /// applied by the earlier guard, so releasing it restores nothing.
+    #[inline]
+    pub(crate) fn restored() -> Held {
+        Held {
+            #[cfg(any(
+                feature = "tracing",
+                feature = "hooks",
+                feature = "registry",
+                feature = "events"
+            ))]
+            acquired: true,
+            #[cfg(feature = "hooks")]
+            since: Instant::now(),
+            #[cfg(feature = "priority-ceiling")]
+            previous: None,
+            #[cfg(feature = "deadlock-detection")]
+            task: None,
+        }
+    }
+
+    /// For a guard created before the lock is actually acquired.
+    ///
+    /// Dropping such a guard does not record a release.
+    #[inline]
+    pub(crate) fn pending() -> Held {
+        Held {
+            #[cfg(any(
+                feature = "tracing",
+                feature = "hooks",
+                feature = "registry",
+                feature = "events"
+            ))]
+            acquired: false,
+            #[cfg(feature = "hooks")]
+            since: Instant::now(),
+            #[cfg(feature = "priority-ceiling")]
+            previous: None,
+            #[cfg(feature = "deadlock-detection")]
+            task: None,
+        }
+    }
+}
+
+/// A lock operation that could not complete immediately.
+#[allow(dead_code)]
+pub(crate) struct Wait<'a> {
+    target: Target<'a>,
+    location: &'static Location<'static>,
+    #[cfg(all(
+        feature = "std",
+        any(feature = "tracing", feature = "log", feature = "hooks")
+    ))]
+    start: Instant,
+    /// The ID of this lock operation in the registry.
+    #[cfg(feature = "registry")]
+    waiter: u64,
+    /// The waiting task, for deadlock detection.
+    #[cfg(feature = "deadlock-detection")]
+    task: Option<u64>,
+}
+
+/// Records that a lock operation called from `location` started waiting.
+#[inline]
+pub(crate) fn wait<'a>(target: Target<'a>, location: &'static Location<'static>) -> Wait<'a> {
+    #[cfg(feature = "tracing")]
+    tracing::trace!(
+        lock = target.kind,
+        mode = target.mode,
+        id = target.id,
+        name = target.hooks.name,
+        "acquire started"
+    );
+
+    #[cfg(feature = "hooks")]
+    if let Some(metrics) = target.hooks.metrics() {
+        metrics.on_contended(&target.info());
+    }
+
+    #[cfg(feature = "registry")]
+    let waiter = target.record().wait_started(location);
+
+    #[cfg(feature = "deadlock-detection")]
+    let task = crate::deadlock::wait_started(target.kind, target.mode, target.lock(), location);
+
+    Wait {
+        target,
+        location,
+        #[cfg(all(
+            feature = "std",
+            any(feature = "tracing", feature = "log", feature = "hooks")
+        ))]
+        start: Instant::now(),
+        #[cfg(feature = "registry")]
+        waiter,
+        #[cfg(feature = "deadlock-detection")]
+        task,
+    }
+}
+
+impl Wait<'_> {
+    /// Records that the waiting lock operation completed.
+    #[inline]
+    pub(crate) fn acquired(self) -> Held {
+        #[cfg(all(
+            feature = "std",
+            any(feature = "tracing", feature = "log", feature = "hooks")
+        ))]
+        let wait = self.start.elapsed();
+
+        #[cfg(all(feature = "tracing", feature = "std"))]
+        tracing::trace!(
+            lock = self.target.kind,
+            mode = self.target.mode,
+            id = self.target.id,
+            name = self.target.hooks.name,
+            wait = ?wait,
+            "acquired"
+        );
+
+        #[cfg(all(feature = "tracing", not(feature = "std")))]
+        tracing::trace!(
+            lock = self.target.kind,
+            mode = self.target.mode,
+            id = self.target.id,
+            name = self.target.hooks.name,
+            "acquired"
+        );
+
+        #[cfg(feature = "log")]
+        if crate::diagnostics::is_slow(wait) {
+            log::warn!(
+                "slow {} {} of {} at {}: waited {:?}",
+                self.target.kind,
+                self.target.mode,
+                self.target.lock(),
+                self.location,
+                wait
+            );
+        }
+
+        #[cfg(feature = "hooks")]
+        if let Some(metrics) = self.target.hooks.metrics() {
+            metrics.on_acquired(&self.target.info(), wait);
+        }
+
+        #[cfg(feature = "console")]
+        self.target.state_update(true);
+
+        #[cfg(feature = "registry")]
+        self.target.record().acquired(self.location);
+
+        #[cfg(feature = "events")]
+        self.target
+            .publish(LockAction::Acquired, Some(self.location));
+
+        Held::now(&self.target, self.location)
+    }
+}
+
+#[cfg(feature = "registry")]
+impl Drop for Wait<'_> {
+    fn drop(&mut self) {
+        // Runs both after `acquired()` and when the lock operation is canceled.
+        self.target.record().wait_ended(self.waiter);
+
+        #[cfg(feature = "deadlock-detection")]
+        crate::deadlock::wait_ended(self.task);
+    }
+}
+
+/// Records a lock operation called from `location` that completed without waiting.
+#[inline]
+#[allow(unused_variables)]
+pub(crate) fn acquired(target: Target<'_>, location: &'static Location<'static>) -> Held {
+    #[cfg(feature = "tracing")]
+    tracing::trace!(
+        lock = target.kind,
+        mode = target.mode,
+        id = target.id,
+        name = target.hooks.name,
+        wait = ?core::time::Duration::from_secs(0),
+        "acquired"
+    );
+
+    #[cfg(feature = "hooks")]
+    if let Some(metrics) = target.hooks.metrics() {
+        metrics.on_acquired(&target.info(), core::time::Duration::from_secs(0));
+    }
+
+    #[cfg(feature = "console")]
+    target.state_update(true);
+
+    #[cfg(feature = "registry")]
+    target.record().acquired(location);
+
+    #[cfg(feature = "events")]
+    target.publish(LockAction::Acquired, Some(location));
+
+    Held::now(&target, location)
+}
+
+/// Records that a lock acquired at `held` was released.
+#[inline]
+#[allow(unused_variables)]
+pub(crate) fn released(target: Target<'_>, held: Held) {
+    #[cfg(any(
+        feature = "tracing",
+        feature = "hooks",
+        feature = "registry",
+        feature = "events"
+    ))]
+    if !held.acquired {
+        return;
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::trace!(
+        lock = target.kind,
+        mode = target.mode,
+        id = target.id,
+        name = target.hooks.name,
+        "released"
+    );
+
+    #[cfg(feature = "hooks")]
+    if let Some(metrics) = target.hooks.metrics() {
+        metrics.on_released(&target.info(), held.since.elapsed());
+    }
+
+    #[cfg(feature = "console")]
+    target.state_update(false);
+
+    #[cfg(feature = "registry")]
+    target.record().released();
+
+    #[cfg(feature = "deadlock-detection")]
+    crate::deadlock::released(target.id, held.task);
+
+    #[cfg(feature = "events")]
+    target.publish(LockAction::Released, None);
+
+    #[cfg(feature = "priority-ceiling")]
+    if let Some((hooks, previous)) = held.previous {
+        (hooks.restore)(previous);
+    }
+}
+
+/// Keeps the priority `held` would restore in the lock, for a guard consumed without being
+/// released. [`Held::unparked()`] takes it back.
+#[inline]
+#[allow(unused_variables)]
+pub(crate) fn parked(target: Target<'_>, held: Held) {
+    #[cfg(feature = "priority-ceiling")]
+    target.hooks.park(held.previous);
+}
+
+/// Records that a released lock was handed directly to a waiting lock operation.
+#[inline]
+#[allow(unused_variables)]
+pub(crate) fn handed_over(target: Target<'_>) {
+    #[cfg(feature = "tracing")]
+    tracing::trace!(
+        lock = target.kind,
+        mode = target.mode,
+        id = target.id,
+        name = target.hooks.name,
+        "handed over"
+    );
+}
+
+/// Wraps a lock operation so `tokio-console` can see which tasks are waiting on the lock.
+///
+/// `source` names the public method, such as `"Mutex::lock"`.
+#[inline]
+#[allow(unused_variables)]
+pub(crate) fn instrument<F: Future>(
+    target: Target<'_>,
+    source: &'static str,
+    future: F,
+) -> Instrumented<F> {
+    #[cfg(feature = "console")]
+    {
+        let async_op = tracing::trace_span!(
+            target: "runtime::resource::async_op",
+            parent: target.resource(),
+            "runtime.resource.async_op",
+            source = source,
+            inherits_child_attrs = false,
+        );
+        let poll = async_op.in_scope(|| {
+            tracing::trace_span!(
+                target: "runtime::resource::async_op::poll",
+                "runtime.resource.async_op.poll",
+            )
+        });
+        AsyncOp {
+            future,
+            async_op,
+            poll,
+            source,
+        }
+    }
+
+    #[cfg(not(feature = "console"))]
+    future
+}
+
+/// Future for [`instrument()`].
+#[cfg(feature = "console")]
+pub(crate) type Instrumented<F> = AsyncOp<F>;
+
+/// Future for [`instrument()`].
+#[cfg(not(feature = "console"))]
+pub(crate) type Instrumented<F> = F;
+
+#[cfg(feature = "console")]
+pin_project_lite::pin_project! {
+    /// Future for [`instrument()`].
+    pub(crate) struct AsyncOp<F> {
+        #[pin]
+        future: F,
+        async_op: Span,
+        poll: Span,
+        source: &'static str,
+    }
+}
+
+#[cfg(feature = "console")]
+impl<F: Future> Future for AsyncOp<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = self.project();
+        let _async_op = this.async_op.enter();
+        let _poll = this.poll.enter();
+
+        let res = this.future.poll(cx);
+        tracing::trace!(
+            target: "runtime::resource::poll_op",
+            op_name = *this.source,
+            is_ready = res.is_ready(),
+        );
+        res
+    }
+}
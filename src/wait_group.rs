@@ -0,0 +1,190 @@
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
+use event_listener::Event;
+
+use crate::sync::atomic::{AtomicUsize, Ordering};
+
+/// Waits for a group of operations to finish.
+///
+/// Every operation takes a guard with [`add()`][`WaitGroup::add()`] and drops it when it is done.
+/// [`wait()`][`WaitGroup::wait()`] completes once no guard is left, which makes this the usual
+/// way to wait for all spawned workers before shutting down.
+///
+/// Unlike [`Shutdown`][`crate::Shutdown`], a wait group never turns operations away, and can be
+/// waited for any number of times.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::WaitGroup;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let wg = Arc::new(WaitGroup::new());
+///
+/// for _ in 0..4 {
+///     let guard = wg.add_arc();
+///     thread::spawn(move || {
+///         // Do some work...
+///         drop(guard);
+///     });
+/// }
+///
+/// wg.wait().await;
+/// assert_eq!(wg.count(), 0);
+/// # })
+/// ```
+#[derive(Debug)]
+pub struct WaitGroup {
+    /// Number of guards alive.
+    count: AtomicUsize,
+
+    /// Notified when the last guard is dropped.
+    done: Event,
+}
+
+impl WaitGroup {
+    /// Creates an empty wait group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::WaitGroup;
+    ///
+    /// let wg = WaitGroup::new();
+    /// ```
+    pub const fn new() -> WaitGroup {
+        WaitGroup {
+            count: AtomicUsize::new(0),
+            done: Event::new(),
+        }
+    }
+
+    /// Adds an operation to the group.
+    ///
+    /// Returns a guard that finishes the operation when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::WaitGroup;
+    ///
+    /// let wg = WaitGroup::new();
+    /// let guard = wg.add();
+    /// assert_eq!(wg.count(), 1);
+    /// ```
+    pub fn add(&self) -> WaitGroupGuard<'_> {
+        self.increment();
+        WaitGroupGuard(self)
+    }
+
+    /// Returns the number of operations that have not finished yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::WaitGroup;
+    ///
+    /// let wg = WaitGroup::new();
+    /// assert_eq!(wg.count(), 0);
+    /// ```
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Acquire)
+    }
+
+    /// Waits until every operation of the group has finished.
+    ///
+    /// Completes right away if the group is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::WaitGroup;
+    /// use futures_lite::future;
+    ///
+    /// let wg = WaitGroup::new();
+    /// let guard = wg.add();
+    ///
+    /// let mut done = Box::pin(wg.wait());
+    /// assert!(future::poll_once(&mut done).await.is_none());
+    ///
+    /// drop(guard);
+    /// done.await;
+    /// # })
+    /// ```
+    pub async fn wait(&self) {
+        while self.count() != 0 {
+            let listener = self.done.listen();
+            if self.count() == 0 {
+                break;
+            }
+            listener.await;
+        }
+    }
+
+    fn increment(&self) {
+        if self.count.fetch_add(1, Ordering::AcqRel) > usize::MAX / 2 {
+            // In case of potential overflow, abort.
+            crate::abort();
+        }
+    }
+
+    fn finish(&self) {
+        if self.count.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.done.notify(usize::MAX);
+        }
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl WaitGroup {
+    /// Adds an operation to the group with an owned guard.
+    ///
+    /// This is the owned version of [`add()`][`WaitGroup::add()`], for operations that run in
+    /// spawned tasks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::WaitGroup;
+    /// use std::sync::Arc;
+    ///
+    /// let wg = Arc::new(WaitGroup::new());
+    /// let guard = wg.add_arc();
+    /// ```
+    pub fn add_arc(self: &Arc<Self>) -> WaitGroupGuardArc {
+        self.increment();
+        WaitGroupGuardArc(self.clone())
+    }
+}
+
+impl Default for WaitGroup {
+    fn default() -> WaitGroup {
+        WaitGroup::new()
+    }
+}
+
+/// A guard that keeps an operation of a [`WaitGroup`] unfinished.
+#[derive(Debug)]
+pub struct WaitGroupGuard<'a>(&'a WaitGroup);
+
+impl Drop for WaitGroupGuard<'_> {
+    fn drop(&mut self) {
+        self.0.finish();
+    }
+}
+
+/// An owned guard that keeps an operation of a [`WaitGroup`] unfinished.
+#[cfg(target_has_atomic = "ptr")]
+#[derive(Debug)]
+pub struct WaitGroupGuardArc(Arc<WaitGroup>);
+
+#[cfg(target_has_atomic = "ptr")]
+impl Drop for WaitGroupGuardArc {
+    fn drop(&mut self) {
+        self.0.finish();
+    }
+}
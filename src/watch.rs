@@ -0,0 +1,337 @@
+use core::fmt;
+use core::mem;
+
+use event_listener::Event;
+
+use crate::sync::atomic::{AtomicUsize, Ordering};
+use crate::{RwLock, RwLockReadGuard};
+
+/// A value that tasks can watch for changes.
+///
+/// Writers update the value under a lock with [`send()`][`Watch::send()`] or
+/// [`send_modify()`][`Watch::send_modify()`], and every update wakes the tasks waiting in
+/// [`Watcher::changed()`]. Watchers then borrow the latest value. A watcher that falls behind
+/// only sees the latest value, not every intermediate one, which makes this a good fit for
+/// propagating configuration or state.
+///
+/// # Examples
+///
+/// ```
+/// # futures_lite::future::block_on(async {
+/// use async_lock::Watch;
+/// use futures_lite::future;
+///
+/// let config = Watch::new("debug");
+/// let mut watcher = config.subscribe();
+///
+/// let mut changed = Box::pin(watcher.changed());
+/// assert!(future::poll_once(&mut changed).await.is_none());
+///
+/// config.send("info").await;
+/// changed.await;
+/// assert_eq!(*watcher.borrow_and_update().await, "info");
+/// # })
+/// ```
+pub struct Watch<T: ?Sized> {
+    /// Incremented by every update, while the value is locked for writing.
+    version: AtomicUsize,
+
+    /// Notified after every update.
+    changed: Event,
+
+    value: RwLock<T>,
+}
+
+impl<T> Watch<T> {
+    /// Creates a new watched value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Watch;
+    ///
+    /// let watch = Watch::new(0);
+    /// ```
+    pub const fn new(value: T) -> Watch<T> {
+        Watch {
+            version: AtomicUsize::new(0),
+            changed: Event::new(),
+            value: RwLock::new(value),
+        }
+    }
+
+    /// Consumes the watch, returning the current value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Watch;
+    ///
+    /// let watch = Watch::new(5);
+    /// assert_eq!(watch.into_inner(), 5);
+    /// ```
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    /// Replaces the value, returning the old one, and wakes every watcher.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Watch;
+    ///
+    /// let watch = Watch::new(1);
+    /// assert_eq!(watch.send(2).await, 1);
+    /// assert_eq!(*watch.borrow().await, 2);
+    /// # })
+    /// ```
+    pub async fn send(&self, value: T) -> T {
+        self.modify(|v| mem::replace(v, value)).await
+    }
+}
+
+impl<T: ?Sized> Watch<T> {
+    /// Updates the value in place and wakes every watcher.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Watch;
+    ///
+    /// let watch = Watch::new(vec![1]);
+    /// watch.send_modify(|v| v.push(2)).await;
+    /// assert_eq!(*watch.borrow().await, [1, 2]);
+    /// # })
+    /// ```
+    pub async fn send_modify(&self, f: impl FnOnce(&mut T)) {
+        self.modify(f).await
+    }
+
+    /// Borrows the current value.
+    ///
+    /// Updates wait until the returned guard is dropped, so it should not be held for long.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Watch;
+    ///
+    /// let watch = Watch::new(1);
+    /// assert_eq!(*watch.borrow().await, 1);
+    /// # })
+    /// ```
+    pub async fn borrow(&self) -> RwLockReadGuard<'_, T> {
+        self.value.read().await
+    }
+
+    /// Creates a watcher that waits for updates made after this call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Watch;
+    ///
+    /// let watch = Watch::new(1);
+    /// let mut watcher = watch.subscribe();
+    /// assert!(!watcher.has_changed());
+    ///
+    /// watch.send(2).await;
+    /// assert!(watcher.has_changed());
+    /// # })
+    /// ```
+    pub fn subscribe(&self) -> Watcher<'_, T> {
+        Watcher {
+            watch: self,
+            seen: self.version(),
+        }
+    }
+
+    /// Returns a mutable reference to the value.
+    ///
+    /// Since this call borrows the watch mutably, no actual locking takes place, and watchers are
+    /// not woken.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_lock::Watch;
+    ///
+    /// let mut watch = Watch::new(1);
+    /// *watch.get_mut() = 2;
+    /// assert_eq!(watch.into_inner(), 2);
+    /// ```
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+
+    /// Updates the value with `f` and wakes every watcher.
+    async fn modify<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut value = self.value.write().await;
+        let result = f(&mut value);
+        self.version.fetch_add(1, Ordering::Release);
+        drop(value);
+
+        self.changed.notify(usize::MAX);
+        result
+    }
+
+    fn version(&self) -> usize {
+        self.version.load(Ordering::Acquire)
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for Watch<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watch")
+            .field("version", &self.version())
+            .field("value", &&self.value)
+            .finish()
+    }
+}
+
+impl<T: Default> Default for Watch<T> {
+    fn default() -> Watch<T> {
+        Watch::new(T::default())
+    }
+}
+
+impl<T> From<T> for Watch<T> {
+    fn from(value: T) -> Watch<T> {
+        Watch::new(value)
+    }
+}
+
+/// Waits for updates to a [`Watch`], created by [`Watch::subscribe()`].
+///
+/// Each watcher remembers the last version of the value it has seen. Cloning a watcher keeps
+/// that version.
+pub struct Watcher<'a, T: ?Sized> {
+    watch: &'a Watch<T>,
+
+    /// The last version this watcher has seen.
+    seen: usize,
+}
+
+impl<'a, T: ?Sized> Watcher<'a, T> {
+    /// Waits until the value has been updated since this watcher last saw it.
+    ///
+    /// Returns right away if an update has not been seen yet. The update is marked as seen, so the
+    /// next call waits for another one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Watch;
+    /// use futures_lite::future;
+    ///
+    /// let watch = Watch::new(1);
+    /// let mut watcher = watch.subscribe();
+    ///
+    /// watch.send(2).await;
+    /// watch.send(3).await;
+    /// watcher.changed().await;
+    /// assert!(future::poll_once(watcher.changed()).await.is_none());
+    /// # })
+    /// ```
+    pub async fn changed(&mut self) {
+        loop {
+            let version = self.watch.version();
+            if version != self.seen {
+                self.seen = version;
+                return;
+            }
+
+            let listener = self.watch.changed.listen();
+            if self.watch.version() != self.seen {
+                continue;
+            }
+            listener.await;
+        }
+    }
+
+    /// Returns `true` if the value has been updated since this watcher last saw it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Watch;
+    ///
+    /// let watch = Watch::new(1);
+    /// let mut watcher = watch.subscribe();
+    /// watch.send_modify(|v| *v += 1).await;
+    /// assert!(watcher.has_changed());
+    ///
+    /// watcher.changed().await;
+    /// assert!(!watcher.has_changed());
+    /// # })
+    /// ```
+    pub fn has_changed(&self) -> bool {
+        self.watch.version() != self.seen
+    }
+
+    /// Borrows the current value, without marking it as seen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Watch;
+    ///
+    /// let watch = Watch::new(1);
+    /// let watcher = watch.subscribe();
+    /// assert_eq!(*watcher.borrow().await, 1);
+    /// # })
+    /// ```
+    pub async fn borrow(&self) -> RwLockReadGuard<'a, T> {
+        self.watch.borrow().await
+    }
+
+    /// Borrows the current value and marks it as seen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures_lite::future::block_on(async {
+    /// use async_lock::Watch;
+    ///
+    /// let watch = Watch::new(1);
+    /// let mut watcher = watch.subscribe();
+    /// watch.send(2).await;
+    ///
+    /// assert_eq!(*watcher.borrow_and_update().await, 2);
+    /// assert!(!watcher.has_changed());
+    /// # })
+    /// ```
+    pub async fn borrow_and_update(&mut self) -> RwLockReadGuard<'a, T> {
+        let value = self.watch.borrow().await;
+        // Updates are made while the value is locked for writing, so this is the version of the
+        // borrowed value.
+        self.seen = self.watch.version();
+        value
+    }
+}
+
+impl<T: ?Sized> Clone for Watcher<'_, T> {
+    fn clone(&self) -> Self {
+        Watcher {
+            watch: self.watch,
+            seen: self.seen,
+        }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Watcher<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watcher")
+            .field("seen", &self.seen)
+            .field("changed", &self.has_changed())
+            .finish()
+    }
+}
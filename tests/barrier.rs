@@ -1,10 +1,11 @@
 use std::sync::Arc;
 use std::thread;
 
-use async_lock::Barrier;
-use futures_lite::future; 
+use async_lock::{Barrier, OneShotBarrier};
+use futures_lite::future;
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn smoke() {
     future::block_on(async move {
         const N: usize = 10;
@@ -47,3 +48,101 @@ fn smoke() {
         }
     });
 }
+
+#[test]
+fn one_shot() {
+    future::block_on(async move {
+        const N: usize = 10;
+
+        let barrier = Arc::new(OneShotBarrier::new(N));
+        let (tx, rx) = async_channel::unbounded();
+
+        for _ in 0..N - 1 {
+            let c = barrier.clone();
+            let tx = tx.clone();
+
+            thread::spawn(move || {
+                future::block_on(async move {
+                    let res = c.wait().await;
+                    tx.send(res.is_leader()).await.unwrap();
+                })
+            });
+        }
+
+        assert!(rx.try_recv().is_err());
+        assert!(!barrier.is_tripped());
+
+        let mut leader_found = barrier.wait().await.is_leader();
+        for _ in 0..N - 1 {
+            if rx.recv().await.unwrap() {
+                assert!(!leader_found);
+                leader_found = true;
+            }
+        }
+        assert!(leader_found);
+
+        // The barrier does not reset.
+        assert!(barrier.is_tripped());
+        assert!(!barrier.wait().await.is_leader());
+    });
+}
+
+#[test]
+fn broken() {
+    future::block_on(async move {
+        const N: usize = 4;
+
+        let barrier = Arc::new(Barrier::new(N));
+        let (tx, rx) = async_channel::unbounded();
+
+        for _ in 0..N - 2 {
+            let c = barrier.clone();
+            let tx = tx.clone();
+
+            thread::spawn(move || {
+                future::block_on(async move {
+                    tx.send(c.wait_checked().await.is_err()).await.unwrap();
+                })
+            });
+        }
+
+        // One participant fails instead of reaching the barrier.
+        assert!(rx.try_recv().is_err());
+        barrier.mark_broken();
+
+        for _ in 0..N - 2 {
+            assert!(rx.recv().await.unwrap());
+        }
+        assert!(barrier.wait_checked().await.is_err());
+
+        // Plain waits still synchronize the whole group.
+        assert!(future::poll_once(Box::pin(barrier.wait())).await.is_none());
+    });
+}
+
+#[test]
+fn broken_by_cancelled_waiter() {
+    future::block_on(async move {
+        let barrier = Barrier::new(2);
+
+        // Cancelling a plain `wait()` leaves the barrier intact.
+        let mut waiter = Box::pin(barrier.wait());
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        drop(waiter);
+        assert!(!barrier.is_broken());
+
+        let barrier = Barrier::new(2);
+        let mut waiter = Box::pin(barrier.wait_checked());
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        drop(waiter);
+        assert!(barrier.is_broken());
+
+        // A waiter that was released before it was cancelled leaves the barrier intact.
+        let barrier = Barrier::new(2);
+        let mut waiter = Box::pin(barrier.wait_checked());
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        assert!(barrier.wait_checked().await.unwrap().is_leader());
+        drop(waiter);
+        assert!(!barrier.is_broken());
+    });
+}
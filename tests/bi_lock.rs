@@ -0,0 +1,108 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Wake, Waker};
+
+use async_lock::BiLock;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+/// Counts how often it was woken.
+#[derive(Default)]
+struct CountWaker(AtomicUsize);
+
+impl Wake for CountWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn smoke() {
+    future::block_on(async {
+        let (mut a, mut b) = BiLock::new(0);
+        *a.lock().await += 1;
+        *b.try_lock().unwrap() += 1;
+
+        let guard = a.lock().await;
+        assert_eq!(format!("{:?}", b), "BiLock { locked: true }");
+        assert!(b.try_lock().is_none());
+        drop(guard);
+        assert_eq!(format!("{:?}", b), "BiLock { locked: false }");
+
+        assert_eq!(a.reunite(b).unwrap(), 2);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn wakes_other_half() {
+    let (mut a, mut b) = BiLock::new(());
+    let counter = Arc::new(CountWaker::default());
+    let waker = Waker::from(counter.clone());
+    let cx = &mut Context::from_waker(&waker);
+
+    let guard = a.try_lock().unwrap();
+
+    // Polling again replaces the registered waker instead of adding another one.
+    assert!(b.poll_lock(cx).is_pending());
+    assert!(b.poll_lock(cx).is_pending());
+    assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+
+    drop(guard);
+    assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+    assert!(b.poll_lock(cx).is_ready());
+
+    // Unlocking without a waiting half wakes nobody.
+    drop(a.try_lock().unwrap());
+    assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn reunite_mismatched() {
+    let (a, b) = BiLock::new(1);
+    let (c, d) = BiLock::new(2);
+
+    let err = a.reunite(d).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "tried to reunite two halves of different `BiLock`s"
+    );
+    let (a, d) = (err.0, err.1);
+    assert!(a.is_pair_of(&b) && c.is_pair_of(&d));
+    assert_eq!(b.reunite(a).unwrap(), 1);
+    assert_eq!(c.reunite(d).unwrap(), 2);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn contention() {
+    const N: usize = 1000;
+
+    let (mut a, mut b) = BiLock::new(0);
+    let t = thread::spawn(move || {
+        future::block_on(async {
+            for _ in 0..N {
+                *b.lock().await += 1;
+            }
+        });
+        b
+    });
+    future::block_on(async {
+        for _ in 0..N {
+            *a.lock().await += 1;
+        }
+    });
+
+    let b = t.join().unwrap();
+    assert_eq!(a.reunite(b).unwrap(), 2 * N);
+}
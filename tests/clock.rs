@@ -0,0 +1,117 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use async_lock::clock::{self, Clock};
+#[cfg(feature = "std")]
+use async_lock::RateLimiter;
+use async_lock::{Mutex, MutexGuard};
+use futures_lite::future;
+
+mod common;
+#[cfg(feature = "std")]
+use common::Never;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+/// A clock showing a set number of microseconds, which counts how often it was read.
+struct MockClock(AtomicU64, AtomicUsize);
+
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        self.1.fetch_add(1, Ordering::SeqCst);
+        Duration::from_micros(self.0.load(Ordering::SeqCst))
+    }
+}
+
+static CLOCK: MockClock = MockClock(AtomicU64::new(0), AtomicUsize::new(0));
+static CLOCK_REF: &dyn Clock = &CLOCK;
+
+/// Lets a woken waiter lose the lock to a new `try_lock()`, which starts measuring how long it
+/// keeps losing.
+async fn lose_race<'a>(m: &'a Mutex<()>, waiter: &mut (impl Future + Unpin)) -> MutexGuard<'a, ()> {
+    let guard = m.try_lock().unwrap();
+    assert!(future::poll_once(&mut *waiter).await.is_none());
+    drop(guard);
+    let guard = m.try_lock().unwrap();
+    assert!(future::poll_once(&mut *waiter).await.is_none());
+    guard
+}
+
+/// Lets a new `try_lock()` take the lock from a woken waiter again after `advance`, and returns
+/// whether it still works afterwards.
+async fn barging_allowed(m: &Mutex<()>, advance: Duration) -> bool {
+    let mut waiter = Box::pin(m.lock());
+    let guard = lose_race(m, &mut waiter).await;
+
+    CLOCK
+        .0
+        .fetch_add(advance.as_micros() as u64, Ordering::SeqCst);
+
+    // Barge in before the woken waiter gets to run.
+    drop(guard);
+    let guard = m.try_lock().unwrap();
+    assert!(future::poll_once(&mut waiter).await.is_none());
+    drop(guard);
+
+    // A starved waiter switches to fair locking, which keeps newcomers out.
+    let allowed = m.try_lock().is_some();
+    drop(waiter.await);
+    allowed
+}
+
+/// Cancels a starved waiter, and returns whether new lock operations can take the lock again.
+async fn cancelled_starved_waiter(m: &Mutex<()>) -> bool {
+    let mut waiter = Box::pin(m.lock());
+    let guard = lose_race(m, &mut waiter).await;
+
+    CLOCK.0.fetch_add(1_000_000, Ordering::SeqCst);
+    drop(guard);
+    let guard = m.try_lock().unwrap();
+    assert!(future::poll_once(&mut waiter).await.is_none());
+    drop(guard);
+    assert!(m.try_lock().is_none());
+
+    drop(waiter);
+    m.try_lock().is_some()
+}
+
+// Both cases live in one test because the clock is process-wide.
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn mock_clock_controls_fairness() {
+    clock::set_clock(&CLOCK_REF);
+
+    future::block_on(async {
+        let m = Mutex::new(());
+
+        // A waiter that gets the lock on its first wakeup never reads the clock.
+        let guard = m.try_lock().unwrap();
+        let mut waiter = Box::pin(m.lock());
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        drop(guard);
+        drop(waiter.await);
+        assert_eq!(CLOCK.1.load(Ordering::SeqCst), 0);
+
+        assert!(barging_allowed(&m, Duration::ZERO).await);
+        assert!(!barging_allowed(&m, Duration::from_secs(1)).await);
+        assert!(cancelled_starved_waiter(&m).await);
+    });
+
+    // A rate limiter survives a clock that goes backwards.
+    #[cfg(feature = "std")]
+    {
+        let limiter = RateLimiter::new(Never, 1, Duration::from_secs(1));
+        CLOCK.0.fetch_add(10_000_000, Ordering::SeqCst);
+        assert!(limiter.try_acquire(1));
+        CLOCK.0.fetch_sub(5_000_000, Ordering::SeqCst);
+        assert_eq!(limiter.available(), 0);
+        assert!(future::block_on(future::poll_once(limiter.acquire(1))).is_none());
+    }
+
+    clock::clear_clock();
+}
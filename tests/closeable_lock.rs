@@ -0,0 +1,76 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use async_lock::CloseableLock;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn close_fails_waiters() {
+    future::block_on(async {
+        let lock = CloseableLock::new(0);
+        let mut guard = lock.lock().await.unwrap();
+
+        let mut waiters = (0..3).map(|_| Box::pin(lock.lock())).collect::<Vec<_>>();
+        for waiter in &mut waiters {
+            assert!(future::poll_once(waiter).await.is_none());
+        }
+
+        lock.close();
+        for waiter in waiters {
+            assert_eq!(waiter.await.unwrap_err().to_string(), "lock is closed");
+        }
+
+        // The held guard still works.
+        *guard += 1;
+        drop(guard);
+
+        assert!(lock.lock().await.is_err());
+        assert!(lock.try_lock().is_none());
+        assert_eq!(lock.into_inner(), 1);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn open() {
+    future::block_on(async {
+        let mut lock = CloseableLock::<i32>::default();
+        *lock.lock().await.unwrap() += 1;
+        *lock.try_lock().unwrap() += 1;
+        *lock.get_mut() += 1;
+        assert!(!lock.is_closed());
+        assert_eq!(
+            format!("{:?}", lock),
+            "CloseableLock { closed: false, mutex: Mutex { data: 3 } }"
+        );
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn close_from_thread() {
+    let lock = Arc::new(CloseableLock::new(()));
+    let guard = lock.try_lock().unwrap();
+
+    let waiters = (0..4)
+        .map(|_| {
+            let lock = lock.clone();
+            thread::spawn(move || future::block_on(lock.lock()).is_err())
+        })
+        .collect::<Vec<_>>();
+
+    lock.close();
+    for waiter in waiters {
+        assert!(waiter.join().unwrap());
+    }
+    drop(guard);
+}
@@ -0,0 +1,51 @@
+//! Timers shared by the integration tests.
+
+// Every test binary compiles this module, but none of them uses all of it.
+#![allow(dead_code)]
+
+use std::future::{pending, ready, Future, Pending, Ready};
+use std::pin::Pin;
+use std::thread;
+use std::time::Duration;
+
+use async_lock::Timer;
+
+/// A timer whose sleeps have already elapsed.
+pub struct Expired;
+
+impl Timer for Expired {
+    type Sleep = Ready<()>;
+
+    fn sleep(&self, _: Duration) -> Ready<()> {
+        ready(())
+    }
+}
+
+/// A timer whose sleeps never elapse.
+pub struct Never;
+
+impl Timer for Never {
+    type Sleep = Pending<()>;
+
+    fn sleep(&self, _: Duration) -> Pending<()> {
+        pending()
+    }
+}
+
+/// A timer that sleeps on a helper thread.
+pub struct ThreadTimer;
+
+impl Timer for ThreadTimer {
+    type Sleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+    fn sleep(&self, duration: Duration) -> Self::Sleep {
+        let (tx, rx) = async_channel::bounded(1);
+        thread::spawn(move || {
+            thread::sleep(duration);
+            let _ = tx.try_send(());
+        });
+        Box::pin(async move {
+            let _ = rx.recv().await;
+        })
+    }
+}
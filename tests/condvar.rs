@@ -0,0 +1,154 @@
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use std::sync::Arc;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use std::thread;
+
+use std::time::Duration;
+
+use async_lock::{Condvar, Mutex};
+use futures_lite::future;
+
+mod common;
+use common::{Expired, Never};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn wait_releases_mutex() {
+    future::block_on(async {
+        let mutex = Mutex::new(0);
+        let condvar = Condvar::new();
+
+        let mut waiter = Box::pin(condvar.wait(mutex.lock().await));
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        assert!(mutex.try_lock().is_some());
+
+        // The notified waiter locks the mutex again before it completes.
+        let guard = mutex.lock().await;
+        condvar.notify_one();
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        drop(guard);
+        assert!(future::poll_once(&mut waiter).await.is_some());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn notify_one_and_all() {
+    future::block_on(async {
+        let mutex = Mutex::new(());
+        let condvar = Condvar::new();
+
+        let mut w1 = Box::pin(condvar.wait(mutex.lock().await));
+        assert!(future::poll_once(&mut w1).await.is_none());
+        let mut w2 = Box::pin(condvar.wait(mutex.lock().await));
+        assert!(future::poll_once(&mut w2).await.is_none());
+        let mut w3 = Box::pin(condvar.wait(mutex.lock().await));
+        assert!(future::poll_once(&mut w3).await.is_none());
+
+        condvar.notify_one();
+        drop(future::poll_once(&mut w1).await.unwrap());
+        assert!(future::poll_once(&mut w2).await.is_none());
+
+        condvar.notify_all();
+        drop(future::poll_once(&mut w2).await.unwrap());
+        drop(future::poll_once(&mut w3).await.unwrap());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn cancelled_waiter_passes_notification_on() {
+    future::block_on(async {
+        let mutex = Mutex::new(());
+        let condvar = Condvar::new();
+
+        let mut w1 = Box::pin(condvar.wait(mutex.lock().await));
+        assert!(future::poll_once(&mut w1).await.is_none());
+        let mut w2 = Box::pin(condvar.wait(mutex.lock().await));
+        assert!(future::poll_once(&mut w2).await.is_none());
+
+        condvar.notify_one();
+        drop(w1);
+        assert!(future::poll_once(&mut w2).await.is_some());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn wait_while() {
+    future::block_on(async {
+        let mutex = Mutex::new(0);
+        let condvar = Condvar::new();
+
+        let mut waiter = Box::pin(condvar.wait_while(mutex.lock().await, |n| *n < 2));
+        assert!(future::poll_once(&mut waiter).await.is_none());
+
+        *mutex.lock().await = 1;
+        condvar.notify_all();
+        assert!(future::poll_once(&mut waiter).await.is_none());
+
+        *mutex.lock().await = 2;
+        condvar.notify_all();
+        assert_eq!(*future::poll_once(&mut waiter).await.unwrap(), 2);
+    });
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+#[test]
+fn producer_consumer() {
+    let state = Arc::new((Mutex::new(Vec::new()), Condvar::new()));
+
+    let producer = thread::spawn({
+        let state = state.clone();
+        move || {
+            for i in 0..100 {
+                let (items, not_empty) = &*state;
+                items.lock_blocking().push(i);
+                not_empty.notify_one();
+            }
+        }
+    });
+
+    future::block_on(async {
+        let (items, not_empty) = &*state;
+        let mut received = 0;
+        while received < 100 {
+            let mut guard = not_empty
+                .wait_while(items.lock().await, |v| v.is_empty())
+                .await;
+            received += guard.drain(..).count();
+        }
+    });
+    producer.join().unwrap();
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn wait_timeout() {
+    future::block_on(async {
+        let mutex = Mutex::new(0);
+        let condvar = Condvar::new();
+
+        let (guard, result) = condvar
+            .wait_timeout(mutex.lock().await, Expired, Duration::from_secs(1))
+            .await;
+        assert!(result.timed_out());
+        drop(guard);
+
+        let mut waiter =
+            Box::pin(condvar.wait_timeout(mutex.lock().await, Never, Duration::from_secs(1)));
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        assert!(mutex.try_lock().is_some());
+
+        condvar.notify_one();
+        let (guard, result) = future::poll_once(&mut waiter).await.unwrap();
+        assert!(!result.timed_out());
+        assert_eq!(*guard, 0);
+    });
+}
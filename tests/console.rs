@@ -0,0 +1,130 @@
+#![cfg(feature = "console")]
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+
+use async_lock::{Mutex, RwLock, Semaphore};
+use futures_lite::future;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// Collects span names and `target field=value` lines for every resource event.
+#[derive(Clone, Default)]
+struct Collector {
+    next_id: Arc<AtomicU64>,
+    lines: Arc<StdMutex<Vec<String>>>,
+}
+
+#[derive(Default)]
+struct Fields(Vec<String>);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.push(format!("{}={:?}", field.name(), value));
+    }
+}
+
+impl Subscriber for Collector {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut fields = Fields::default();
+        span.record(&mut fields);
+        self.lines.lock().unwrap().push(format!(
+            "span {} {}",
+            span.metadata().name(),
+            fields.0.join(" ")
+        ));
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::SeqCst) + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let target = event.metadata().target();
+        if target.starts_with("runtime::resource") {
+            let mut fields = Fields::default();
+            event.record(&mut fields);
+            self.lines
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", target, fields.0.join(" ")));
+        }
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+fn collect(f: impl FnOnce()) -> Vec<String> {
+    let collector = Collector::default();
+    tracing::subscriber::with_default(collector.clone(), f);
+    let lines = collector.lines.lock().unwrap().clone();
+    lines
+}
+
+#[test]
+fn mutex_resource() {
+    let lines = collect(|| {
+        future::block_on(async {
+            let m = Mutex::new(());
+            let guard = m.lock().await;
+            let mut waiter = Box::pin(m.lock());
+            assert!(future::poll_once(waiter.as_mut()).await.is_none());
+            drop(guard);
+            drop(waiter.await);
+        })
+    });
+
+    assert_eq!(
+        lines,
+        [
+            "span runtime.resource concrete_type=\"Mutex\" kind=\"Sync\"",
+            "span runtime.resource.async_op source=\"Mutex::lock\" inherits_child_attrs=false",
+            "span runtime.resource.async_op.poll ",
+            "runtime::resource::state_update locked=true locked.op=\"override\"",
+            "runtime::resource::poll_op op_name=\"Mutex::lock\" is_ready=true",
+            "span runtime.resource.async_op source=\"Mutex::lock\" inherits_child_attrs=false",
+            "span runtime.resource.async_op.poll ",
+            "runtime::resource::poll_op op_name=\"Mutex::lock\" is_ready=false",
+            "runtime::resource::state_update locked=false locked.op=\"override\"",
+            "runtime::resource::state_update locked=true locked.op=\"override\"",
+            "runtime::resource::poll_op op_name=\"Mutex::lock\" is_ready=true",
+            "runtime::resource::state_update locked=false locked.op=\"override\"",
+        ]
+    );
+}
+
+#[test]
+fn rwlock_and_semaphore_resources() {
+    let lines = collect(|| {
+        future::block_on(async {
+            let lock = RwLock::new(());
+            drop(lock.read().await);
+
+            let s = Semaphore::new(1);
+            drop(s.try_acquire());
+        })
+    });
+
+    assert_eq!(
+        lines,
+        [
+            "span runtime.resource concrete_type=\"RwLock\" kind=\"Sync\"",
+            "span runtime.resource.async_op source=\"RwLock::read\" inherits_child_attrs=false",
+            "span runtime.resource.async_op.poll ",
+            "runtime::resource::state_update readers=1 readers.op=\"add\"",
+            "runtime::resource::poll_op op_name=\"RwLock::read\" is_ready=true",
+            "runtime::resource::state_update readers=1 readers.op=\"sub\"",
+            "span runtime.resource concrete_type=\"Semaphore\" kind=\"Sync\"",
+            "runtime::resource::state_update permits_acquired=1 permits_acquired.op=\"add\"",
+            "runtime::resource::state_update permits_acquired=1 permits_acquired.op=\"sub\"",
+        ]
+    );
+}
@@ -0,0 +1,144 @@
+#[cfg(feature = "std")]
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "std")]
+use std::sync::Arc;
+use std::task::{Context, Poll};
+#[cfg(feature = "std")]
+use std::task::{Wake, Waker};
+
+use async_lock::coop::{self, BudgetHook};
+use async_lock::{Mutex, RwLock, Semaphore};
+use futures_lite::future;
+
+static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Keeps tests from changing the process-wide settings under each other.
+static SERIAL: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+static EVERY_OTHER: BudgetHook = every_other;
+
+/// Allows one acquisition, then forces a yield.
+fn every_other(cx: &mut Context<'_>) -> Poll<()> {
+    if CALLS.fetch_add(1, Ordering::SeqCst) & 1 == 0 {
+        Poll::Ready(())
+    } else {
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[test]
+fn budget_forces_yield() {
+    let _serial = SERIAL.lock().unwrap();
+    coop::set_budget(&EVERY_OTHER);
+
+    future::block_on(async {
+        let m = Mutex::new(());
+        assert!(future::poll_once(m.lock()).await.is_some());
+        assert!(future::poll_once(m.lock()).await.is_none());
+        assert!(future::poll_once(m.lock()).await.is_some());
+
+        // A yielded acquisition still completes once polled again.
+        let rw = RwLock::new(());
+        let s = Semaphore::new(1);
+        for _ in 0..4 {
+            drop(rw.read().await);
+            drop(s.acquire().await);
+        }
+    });
+
+    coop::clear_budget();
+    let calls = CALLS.load(Ordering::SeqCst);
+
+    future::block_on(async {
+        let m = Mutex::new(());
+        for _ in 0..4 {
+            assert!(future::poll_once(m.lock()).await.is_some());
+        }
+    });
+    assert_eq!(CALLS.load(Ordering::SeqCst), calls);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn yield_after_repeated_acquisitions() {
+    let _serial = SERIAL.lock().unwrap();
+    coop::set_yield_after(Some(3));
+
+    future::block_on(async {
+        let m = Mutex::new(());
+        for _ in 0..2 {
+            for _ in 0..3 {
+                assert!(future::poll_once(m.lock()).await.is_some());
+            }
+            assert!(future::poll_once(m.lock()).await.is_none());
+        }
+
+        // Switching to another lock starts a new streak.
+        let other = Mutex::new(());
+        for _ in 0..3 {
+            assert!(future::poll_once(m.lock()).await.is_some());
+            assert!(future::poll_once(other.lock()).await.is_some());
+        }
+
+        // A yielded acquisition completes once polled again.
+        for _ in 0..10 {
+            drop(m.lock().await);
+        }
+    });
+
+    coop::set_yield_after(None);
+    future::block_on(async {
+        let m = Mutex::new(());
+        for _ in 0..10 {
+            assert!(future::poll_once(m.lock()).await.is_some());
+        }
+    });
+}
+
+#[cfg(feature = "std")]
+/// A waker standing for a separate task.
+struct Task;
+
+#[cfg(feature = "std")]
+impl Wake for Task {
+    fn wake(self: Arc<Self>) {}
+}
+
+#[cfg(feature = "std")]
+/// Polls `future` once on behalf of the task of `waker`.
+fn poll_as<F: Future>(waker: &Waker, future: F) -> Poll<F::Output> {
+    let future = std::pin::pin!(future);
+    future.poll(&mut Context::from_waker(waker))
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn yield_after_counts_per_task() {
+    let _serial = SERIAL.lock().unwrap();
+    coop::set_yield_after(Some(2));
+
+    let (a, b) = (Waker::from(Arc::new(Task)), Waker::from(Arc::new(Task)));
+    let m = Mutex::new(());
+
+    // Another task acquiring the lock starts a new streak.
+    for _ in 0..3 {
+        assert!(poll_as(&a, m.lock()).is_ready());
+        assert!(poll_as(&a, m.lock()).is_ready());
+        assert!(poll_as(&b, m.lock()).is_ready());
+    }
+
+    // A lock operation that has to wait starts a new streak.
+    assert!(poll_as(&a, m.lock()).is_ready());
+    let guard = m.try_lock().unwrap();
+    let mut waiter = Box::pin(m.lock());
+    assert!(poll_as(&a, waiter.as_mut()).is_pending());
+    drop(guard);
+    assert!(poll_as(&a, waiter.as_mut()).is_ready());
+    assert!(poll_as(&a, m.lock()).is_ready());
+    assert!(poll_as(&a, m.lock()).is_ready());
+    assert!(poll_as(&a, m.lock()).is_pending());
+
+    coop::set_yield_after(None);
+}
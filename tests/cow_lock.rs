@@ -0,0 +1,99 @@
+#![cfg(feature = "std")]
+
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use async_lock::{CowLock, CowLockWriteGuard};
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn snapshots() {
+    future::block_on(async {
+        let lock = CowLock::new(vec![1]);
+        let first = lock.load();
+
+        let mut guard = lock.write().await;
+        guard.push(2);
+        assert!(lock.try_write().is_none());
+        // Readers do not wait for the writer, and see the old value.
+        assert_eq!(*lock.load(), [1]);
+        drop(guard);
+
+        assert_eq!(*first, [1]);
+        assert_eq!(*lock.load(), [1, 2]);
+
+        let mut guard = lock.try_write().unwrap();
+        guard.clear();
+        CowLockWriteGuard::discard(guard);
+        assert_eq!(*lock.load(), [1, 2]);
+
+        lock.store(vec![3]).await;
+        assert_eq!(format!("{:?}", lock), "CowLock { value: [3] }");
+        let value = lock.into_arc();
+        assert_eq!(*value, [3]);
+        assert_eq!(Arc::strong_count(&value), 1);
+        assert_eq!(Arc::strong_count(&first), 1);
+    })
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn frees_old_values() {
+    future::block_on(async {
+        let tracker = Arc::new(());
+        let lock = CowLock::new(tracker.clone());
+        for _ in 0..10 {
+            drop(lock.write().await);
+        }
+        assert_eq!(Arc::strong_count(&tracker), 2);
+        drop(lock);
+        assert_eq!(Arc::strong_count(&tracker), 1);
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn concurrent_readers_and_writers() {
+    let lock = Arc::new(CowLock::new((0u64, 0u64)));
+
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                for _ in 0..10_000 {
+                    let value = lock.load();
+                    // Writers keep both halves equal.
+                    assert_eq!(value.0, value.1);
+                }
+            })
+        })
+        .collect();
+
+    let writers: Vec<_> = (0..2)
+        .map(|_| {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                future::block_on(async {
+                    for _ in 0..1_000 {
+                        let mut guard = lock.write().await;
+                        guard.0 += 1;
+                        guard.1 += 1;
+                    }
+                })
+            })
+        })
+        .collect();
+
+    for t in readers.into_iter().chain(writers) {
+        t.join().unwrap();
+    }
+    assert_eq!(*lock.load(), (2_000, 2_000));
+}
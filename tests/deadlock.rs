@@ -0,0 +1,187 @@
+#![cfg(all(
+    feature = "deadlock-detection",
+    debug_assertions,
+    not(target_arch = "wasm32")
+))]
+
+use std::panic::{self, AssertUnwindSafe};
+
+use async_lock::{diagnostics, Mutex, RwLock, RwLockUpgradableReadGuard};
+use futures_lite::future;
+
+fn panic_message(f: impl FnOnce()) -> String {
+    let err = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_err();
+    match err.downcast::<String>() {
+        Ok(message) => *message,
+        Err(err) => err.downcast_ref::<&str>().unwrap().to_string(),
+    }
+}
+
+#[test]
+fn lock_order_inversion() {
+    let m1 = Mutex::new(());
+    let m2 = Mutex::new(());
+
+    let message = panic_message(|| {
+        future::block_on(async {
+            let mut a = Box::pin(diagnostics::label("a", async {
+                let _g1 = m1.lock().await;
+                future::yield_now().await;
+                let _g2 = m2.lock().await;
+            }));
+            let mut b = Box::pin(diagnostics::label("b", async {
+                let _g2 = m2.lock().await;
+                future::yield_now().await;
+                let _g1 = m1.lock().await;
+            }));
+
+            assert!(future::poll_once(&mut a).await.is_none());
+            assert!(future::poll_once(&mut b).await.is_none());
+            assert!(future::poll_once(&mut a).await.is_none());
+            future::poll_once(&mut b).await;
+        })
+    });
+
+    assert!(message.starts_with("deadlock detected:"), "{}", message);
+    assert!(
+        message.contains("task \"a\" waits for Mutex"),
+        "{}",
+        message
+    );
+    assert!(
+        message.contains("task \"b\" waits for Mutex"),
+        "{}",
+        message
+    );
+    assert!(message.contains("held by task \"a\""), "{}", message);
+    assert!(message.contains("held by task \"b\""), "{}", message);
+
+    // Everything was released while unwinding.
+    future::block_on(diagnostics::label("c", async {
+        let _g1 = m1.lock().await;
+        let _g2 = m2.lock().await;
+    }));
+}
+
+#[test]
+fn relock() {
+    let mutex = Mutex::new(());
+
+    let message = panic_message(|| {
+        future::block_on(diagnostics::label("a", async {
+            let _guard = mutex.lock().await;
+            let _again = mutex.lock().await;
+        }))
+    });
+    assert!(
+        message.contains("task \"a\" waits for Mutex"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn contention_is_not_a_deadlock() {
+    let mutex = Mutex::new(());
+
+    future::block_on(async {
+        let mut a = Box::pin(diagnostics::label("a", async {
+            let _guard = mutex.lock().await;
+            future::yield_now().await;
+        }));
+        let mut b = Box::pin(diagnostics::label("b", async {
+            let _guard = mutex.lock().await;
+        }));
+
+        assert!(future::poll_once(&mut a).await.is_none());
+        assert!(future::poll_once(&mut b).await.is_none());
+        a.await;
+        b.await;
+    });
+}
+
+#[test]
+fn unlabeled_holder() {
+    let mutex = Mutex::new(());
+
+    future::block_on(async {
+        let guard = mutex.lock().await;
+        let mut a = Box::pin(diagnostics::label("a", async {
+            let _guard = mutex.lock().await;
+        }));
+
+        assert!(future::poll_once(&mut a).await.is_none());
+        drop(guard);
+        a.await;
+    });
+}
+
+#[test]
+fn upgrade() {
+    let lock = RwLock::new(0);
+
+    // Waiting for another reader is fine.
+    let other = future::block_on(lock.read());
+    future::block_on(diagnostics::label("a", async {
+        let reader = lock.upgradable_read().await;
+        let mut upgrade = Box::pin(RwLockUpgradableReadGuard::upgrade(reader));
+        assert!(future::poll_once(&mut upgrade).await.is_none());
+        drop(other);
+        upgrade.await;
+    }));
+
+    // Waiting for a reader in the same task is not.
+    let message = panic_message(|| {
+        future::block_on(diagnostics::label("a", async {
+            let reader = lock.upgradable_read().await;
+            let _other = lock.read().await;
+            RwLockUpgradableReadGuard::upgrade(reader).await;
+        }))
+    });
+    assert!(
+        message.contains("task \"a\" waits for RwLock"),
+        "{}",
+        message
+    );
+
+    future::block_on(diagnostics::label("a", async {
+        let reader = lock.upgradable_read().await;
+        *RwLockUpgradableReadGuard::upgrade(reader).await += 1;
+        assert_eq!(*lock.read().await, 1);
+    }));
+}
+
+#[test]
+fn named_locks() {
+    let first = Mutex::new_named("first", ());
+    let second = Mutex::new_named("second", ());
+
+    let message = panic_message(|| {
+        future::block_on(async {
+            let mut a = Box::pin(diagnostics::label("a", async {
+                let _g1 = first.lock().await;
+                future::yield_now().await;
+                let _g2 = second.lock().await;
+            }));
+            let mut b = Box::pin(diagnostics::label("b", async {
+                let _g2 = second.lock().await;
+                let _g1 = first.lock().await;
+            }));
+
+            assert!(future::poll_once(&mut a).await.is_none());
+            assert!(future::poll_once(&mut b).await.is_none());
+            future::poll_once(&mut a).await;
+        })
+    });
+
+    assert!(
+        message.contains("task \"a\" waits for Mutex \"second\" (lock)"),
+        "{}",
+        message
+    );
+    assert!(
+        message.contains("task \"b\" waits for Mutex \"first\" (lock)"),
+        "{}",
+        message
+    );
+}
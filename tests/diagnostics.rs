@@ -0,0 +1,606 @@
+#![cfg(any(
+    feature = "log",
+    feature = "hooks",
+    feature = "registry",
+    feature = "events"
+))]
+
+#[cfg(any(feature = "log", feature = "hooks"))]
+use std::sync::Mutex as StdMutex;
+#[cfg(any(feature = "log", feature = "hooks"))]
+use std::thread;
+#[cfg(any(feature = "log", feature = "hooks"))]
+use std::time::Duration;
+
+use async_lock::{diagnostics, Mutex};
+use futures_lite::future;
+
+#[cfg(feature = "log")]
+use log::{Level, Log, Metadata, Record};
+
+#[cfg(feature = "hooks")]
+use async_lock::diagnostics::{LockInfo, LockMetrics};
+#[cfg(feature = "hooks")]
+use async_lock::{RwLock, Semaphore};
+#[cfg(feature = "hooks")]
+use std::sync::Arc;
+
+/// Collects every warning.
+#[cfg(feature = "log")]
+struct Logger(StdMutex<Vec<String>>);
+
+#[cfg(feature = "log")]
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= Level::Warn
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(feature = "log")]
+static LOGGER: Logger = Logger(StdMutex::new(Vec::new()));
+
+#[cfg(feature = "log")]
+#[test]
+fn slow_acquisition() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Warn);
+
+    let m = Mutex::new(());
+
+    // Nothing is logged without a threshold.
+    let guard = m.try_lock().unwrap();
+    thread::scope(|s| {
+        s.spawn(|| future::block_on(m.lock()));
+        thread::sleep(Duration::from_millis(50));
+        drop(guard);
+    });
+    assert!(LOGGER.0.lock().unwrap().is_empty());
+
+    diagnostics::set_slow_threshold(Some(Duration::from_millis(10)));
+
+    // Fast acquisitions are not logged.
+    drop(future::block_on(m.lock()));
+    assert!(LOGGER.0.lock().unwrap().is_empty());
+
+    let guard = m.try_lock().unwrap();
+    thread::scope(|s| {
+        s.spawn(|| future::block_on(m.lock()));
+        thread::sleep(Duration::from_millis(50));
+        drop(guard);
+    });
+
+    let logs = LOGGER.0.lock().unwrap();
+    assert_eq!(logs.len(), 1);
+    assert!(logs[0].starts_with("slow Mutex lock of 0x"), "{}", logs[0]);
+    assert!(logs[0].contains(file!()), "{}", logs[0]);
+}
+
+/// Records every event as `kind mode event` along with the measured duration.
+#[cfg(feature = "hooks")]
+#[derive(Default)]
+struct Recorder(StdMutex<Vec<(String, Duration)>>);
+
+#[cfg(feature = "hooks")]
+impl Recorder {
+    fn push(&self, lock: &LockInfo, event: &str, d: Duration) {
+        let line = format!("{} {} {}", lock.kind(), lock.mode(), event);
+        self.0.lock().unwrap().push((line, d));
+    }
+
+    fn events(&self) -> Vec<String> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(e, _)| e.clone())
+            .collect()
+    }
+}
+
+#[cfg(feature = "hooks")]
+impl LockMetrics for Recorder {
+    fn on_contended(&self, lock: &LockInfo) {
+        self.push(lock, "contended", Duration::from_secs(0));
+    }
+
+    fn on_acquired(&self, lock: &LockInfo, wait: Duration) {
+        self.push(lock, "acquired", wait);
+    }
+
+    fn on_released(&self, lock: &LockInfo, hold: Duration) {
+        self.push(lock, "released", hold);
+    }
+}
+
+#[cfg(feature = "hooks")]
+#[test]
+fn per_lock_metrics() {
+    let recorder = Arc::new(Recorder::default());
+    let m = Mutex::with_metrics((), recorder.clone());
+
+    let guard = m.try_lock().unwrap();
+    thread::scope(|s| {
+        s.spawn(|| drop(future::block_on(m.lock())));
+        thread::sleep(Duration::from_millis(50));
+        drop(guard);
+    });
+
+    assert_eq!(
+        recorder.events(),
+        [
+            "Mutex lock acquired",
+            "Mutex lock contended",
+            "Mutex lock released",
+            "Mutex lock acquired",
+            "Mutex lock released",
+        ]
+    );
+
+    let times = recorder.0.lock().unwrap();
+    assert!(times[2].1 >= Duration::from_millis(50));
+    assert!(times[3].1 >= Duration::from_millis(10));
+}
+
+#[cfg(feature = "hooks")]
+#[test]
+fn rwlock_and_semaphore_metrics() {
+    let recorder = Arc::new(Recorder::default());
+
+    let lock = RwLock::with_metrics((), recorder.clone());
+    drop(future::block_on(lock.read()));
+    drop(future::block_on(lock.write()));
+
+    // A canceled write does not report a release.
+    let reader = lock.try_read().unwrap();
+    let mut writer = Box::pin(lock.write());
+    assert!(future::block_on(future::poll_once(writer.as_mut())).is_none());
+    drop(writer);
+    drop(reader);
+
+    let s = Semaphore::with_metrics(1, recorder.clone());
+    drop(future::block_on(s.acquire()));
+
+    assert_eq!(
+        recorder.events(),
+        [
+            "RwLock read acquired",
+            "RwLock read released",
+            "RwLock write acquired",
+            "RwLock write released",
+            "RwLock read acquired",
+            "RwLock write contended",
+            "RwLock read released",
+            "Semaphore acquire acquired",
+            "Semaphore acquire released",
+        ]
+    );
+}
+
+#[cfg(feature = "hooks")]
+#[test]
+fn global_metrics() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts acquisitions of the lock at `ID`, ignoring locks used by other tests.
+    struct Global;
+
+    static ID: AtomicUsize = AtomicUsize::new(0);
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    static GLOBAL: &dyn LockMetrics = &Global;
+
+    impl LockMetrics for Global {
+        fn on_acquired(&self, lock: &LockInfo, _: Duration) {
+            if lock.id() == ID.load(Ordering::SeqCst) {
+                COUNT.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    let m = Mutex::new(());
+    ID.store(&m as *const _ as usize, Ordering::SeqCst);
+    diagnostics::set_metrics(&GLOBAL);
+    drop(m.try_lock());
+    diagnostics::clear_metrics();
+    drop(m.try_lock());
+    assert_eq!(COUNT.load(Ordering::SeqCst), 1);
+
+    // Locks with their own hooks do not report globally.
+    let own = Arc::new(Recorder::default());
+    let m = Mutex::with_metrics((), own.clone());
+    ID.store(&m as *const _ as usize, Ordering::SeqCst);
+    diagnostics::set_metrics(&GLOBAL);
+    drop(m.try_lock());
+    diagnostics::clear_metrics();
+    assert_eq!(COUNT.load(Ordering::SeqCst), 1);
+    assert_eq!(own.events(), ["Mutex lock acquired", "Mutex lock released"]);
+}
+
+#[cfg(feature = "hooks")]
+#[test]
+fn histograms() {
+    use async_lock::diagnostics::LockHistograms;
+
+    let histograms = Arc::new(LockHistograms::new());
+    let m = Mutex::with_metrics((), histograms.clone());
+    assert_eq!(histograms.wait().count(), 0);
+    assert_eq!(histograms.wait().mean(), None);
+    assert_eq!(histograms.wait().quantile(0.5), None);
+
+    for _ in 0..9 {
+        drop(m.try_lock().unwrap());
+    }
+    let guard = m.try_lock().unwrap();
+    thread::scope(|s| {
+        s.spawn(|| drop(future::block_on(m.lock())));
+        thread::sleep(Duration::from_millis(50));
+        drop(guard);
+    });
+
+    assert_eq!(histograms.contentions(), 1);
+
+    let wait = histograms.wait();
+    assert_eq!(wait.count(), 11);
+    assert_eq!(wait.buckets().count(), 32);
+    assert_eq!(
+        wait.buckets().next(),
+        Some((Some(Duration::from_micros(1)), 10))
+    );
+    assert_eq!(wait.quantile(0.9), Some(Duration::from_micros(1)));
+    assert!(wait.quantile(1.0).unwrap() > Duration::from_millis(50));
+    assert!(wait.sum() >= Duration::from_millis(50));
+
+    let hold = histograms.hold();
+    assert_eq!(hold.count(), 11);
+    assert!(hold.mean().unwrap() >= Duration::from_millis(50) / 11);
+
+    // The last bucket has no upper bound.
+    let (bound, _) = hold.buckets().last().unwrap();
+    assert_eq!(bound, None);
+
+    histograms.reset();
+    assert_eq!(histograms.contentions(), 0);
+    assert_eq!(histograms.hold().count(), 0);
+    assert_eq!(histograms.hold().sum(), Duration::from_secs(0));
+}
+
+#[cfg(all(feature = "metrics", not(target_arch = "wasm32")))]
+#[test]
+fn metrics_recorder() {
+    use async_lock::diagnostics::MetricsRecorder;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+
+    metrics::with_local_recorder(&recorder, || {
+        let m = Mutex::with_metrics((), Arc::new(MetricsRecorder::named("test")));
+        future::block_on(async {
+            let guard = m.lock().await;
+            let mut waiter = Box::pin(m.lock());
+            assert!(future::poll_once(waiter.as_mut()).await.is_none());
+            drop(guard);
+            drop(waiter.await);
+        });
+    });
+
+    let mut metrics: Vec<_> = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .map(|(key, _, _, value)| {
+            let key = key.key();
+            let labels: Vec<_> = key
+                .labels()
+                .map(|l| format!("{}={}", l.key(), l.value()))
+                .collect();
+            let value = match value {
+                DebugValue::Counter(n) => n as usize,
+                DebugValue::Histogram(v) => v.len(),
+                DebugValue::Gauge(_) => unreachable!(),
+            };
+            (key.name().to_string(), labels.join(","), value)
+        })
+        .collect();
+    metrics.sort();
+
+    let labels = "kind=Mutex,mode=lock,lock=test".to_string();
+    assert_eq!(
+        metrics,
+        [
+            (
+                "async_lock_acquisitions_total".to_string(),
+                labels.clone(),
+                2
+            ),
+            (
+                "async_lock_contentions_total".to_string(),
+                labels.clone(),
+                1
+            ),
+            ("async_lock_hold_seconds".to_string(), labels.clone(), 2),
+            ("async_lock_wait_seconds".to_string(), labels, 2),
+        ]
+    );
+}
+
+#[cfg(feature = "registry")]
+#[test]
+fn dump() {
+    use async_lock::RwLock;
+
+    let find = |id: usize| {
+        diagnostics::dump()
+            .into_iter()
+            .find(|l| l.id() == id)
+            .unwrap()
+    };
+
+    let m = Box::new(Mutex::new(()));
+    let id = &*m as *const _ as usize;
+
+    // Locks are registered when they are first used.
+    assert!(diagnostics::dump().iter().all(|l| l.id() != id));
+    let guard = m.try_lock().unwrap();
+    let line = line!() - 1;
+
+    let lock = find(id);
+    assert_eq!(lock.kind(), "Mutex");
+    assert!(lock.is_held());
+    assert_eq!(lock.holder().unwrap().file(), file!());
+    assert_eq!(lock.holder().unwrap().line(), line);
+    assert_eq!(lock.waiters(), 0);
+
+    let mut waiter = Box::pin(m.lock());
+    assert!(future::block_on(future::poll_once(waiter.as_mut())).is_none());
+    assert_eq!(find(id).waiters(), 1);
+    assert_eq!(
+        find(id).to_string(),
+        format!(
+            "Mutex {:#x}: held at {}:{}:19, 1 waiting",
+            id,
+            file!(),
+            line
+        )
+    );
+
+    // A canceled lock operation no longer counts as waiting.
+    drop(waiter);
+    drop(guard);
+    let lock = find(id);
+    assert!(!lock.is_held());
+    assert_eq!(lock.holder(), None);
+    assert_eq!(lock.waiters(), 0);
+
+    // Dropped locks are removed.
+    drop(m);
+    assert!(diagnostics::dump().iter().all(|l| l.id() != id));
+
+    let rw = RwLock::new(());
+    let id = &rw as *const _ as usize;
+    let r1 = rw.try_read().unwrap();
+    let _r2 = rw.try_read().unwrap();
+    assert_eq!(find(id).holders(), 2);
+    drop(r1);
+    assert_eq!(find(id).holders(), 1);
+}
+
+#[cfg(feature = "registry")]
+#[test]
+fn holder_info() {
+    let m = Mutex::new(1);
+    assert!(m.holder_info().is_none());
+    assert_eq!(format!("{:?}", m), "Mutex { data: 1 }");
+
+    let mut task = Box::pin(diagnostics::label("holder", async { m.lock().await }));
+    let line = line!() - 1;
+    let guard = future::block_on(task.as_mut());
+
+    let holder = m.holder_info().unwrap();
+    assert_eq!(holder.label(), Some("holder"));
+    assert_eq!(holder.location().file(), file!());
+    assert_eq!(holder.location().line(), line);
+    assert_eq!(m.holder_info().unwrap().since(), holder.since());
+    assert!(holder
+        .to_string()
+        .starts_with(&format!("(holder) at {}, held for ", holder.location())));
+    assert_eq!(
+        format!("{:?}", m),
+        format!("Mutex {{ data: <locked at {}> }}", holder.location())
+    );
+
+    drop(guard);
+    assert!(m.holder_info().is_none());
+
+    let lock = async_lock::RwLock::new(());
+    let reader = lock.try_read().unwrap();
+    let line = line!() - 1;
+    assert_eq!(lock.holder_info().unwrap().label(), None);
+    assert_eq!(lock.holder_info().unwrap().location().line(), line);
+    drop(reader);
+    assert!(lock.holder_info().is_none());
+}
+
+#[cfg(feature = "registry")]
+#[test]
+fn waiters() {
+    let m = Mutex::new(());
+    assert!(m.waiters().is_empty());
+
+    let guard = m.try_lock().unwrap();
+    let mut first = Box::pin(m.lock());
+    let line = line!() - 1;
+    let mut second = Box::pin(diagnostics::label("second", m.lock()));
+    assert!(future::block_on(future::poll_once(first.as_mut())).is_none());
+    assert!(future::block_on(future::poll_once(second.as_mut())).is_none());
+
+    let waiters = m.waiters();
+    assert_eq!(waiters.len(), 2);
+    assert_eq!(waiters[0].label(), None);
+    assert_eq!(waiters[0].location().file(), file!());
+    assert_eq!(waiters[0].location().line(), line);
+    assert_eq!(waiters[1].label(), Some("second"));
+    assert_ne!(waiters[0].id(), waiters[1].id());
+    assert!(waiters[0].since() <= waiters[1].since());
+
+    // Canceled and completed lock operations are no longer waiting.
+    drop(first);
+    assert_eq!(m.waiters().len(), 1);
+    assert_eq!(m.waiters()[0].id(), waiters[1].id());
+    drop(guard);
+    drop(future::block_on(second));
+    assert!(m.waiters().is_empty());
+
+    // Labels apply to every lock operation inside the labeled future.
+    let s = async_lock::Semaphore::new(1);
+    let permit = s.try_acquire().unwrap();
+    let mut task = Box::pin(diagnostics::label("task", async {
+        let _a = s.acquire().await;
+        let _b = s.acquire().await;
+    }));
+    assert!(future::block_on(future::poll_once(task.as_mut())).is_none());
+    assert_eq!(s.waiters()[0].label(), Some("task"));
+    drop(permit);
+    assert!(future::block_on(future::poll_once(task.as_mut())).is_none());
+
+    let waiters = s.waiters();
+    assert_eq!(waiters.len(), 1);
+    assert_eq!(waiters[0].label(), Some("task"));
+    assert!(waiters[0].to_string().starts_with(&format!(
+        "#{} (task) at {}, waiting for ",
+        waiters[0].id(),
+        waiters[0].location()
+    )));
+}
+
+#[cfg(feature = "registry")]
+#[test]
+fn watchdog() {
+    use async_lock::Timer;
+    use std::cell::RefCell;
+    use std::time::Duration;
+
+    /// Fires on the next poll.
+    struct Tick;
+
+    impl Timer for Tick {
+        type Sleep = future::YieldNow;
+
+        fn sleep(&self, _: Duration) -> future::YieldNow {
+            future::yield_now()
+        }
+    }
+
+    let m = Mutex::new(());
+    let id = &m as *const _ as usize;
+    let guard = m.try_lock().unwrap();
+    let mut waiter = Box::pin(diagnostics::label("stuck", m.lock()));
+    assert!(future::block_on(future::poll_once(waiter.as_mut())).is_none());
+
+    let reports = RefCell::new(Vec::new());
+    let mut watchdog = Box::pin(diagnostics::watchdog(
+        Tick,
+        Duration::ZERO,
+        Duration::from_secs(1),
+        |stuck| {
+            let ours = stuck.iter().filter(|s| s.lock().id() == id);
+            reports.borrow_mut().extend(ours.cloned());
+        },
+    ));
+    for _ in 0..2 {
+        assert!(future::block_on(future::poll_once(watchdog.as_mut())).is_none());
+    }
+
+    let report = reports.borrow()[0].clone();
+    assert_eq!(report.waiter().label(), Some("stuck"));
+    assert!(report.lock().is_held());
+    assert!(report
+        .to_string()
+        .ends_with(&format!("on {}", report.lock())));
+
+    // Waiters below the budget are not reported.
+    assert!(diagnostics::stuck_waiters(Duration::from_secs(3600))
+        .iter()
+        .all(|s| s.lock().id() != id));
+    drop(guard);
+    drop(future::block_on(waiter));
+    assert!(diagnostics::stuck_waiters(Duration::ZERO)
+        .iter()
+        .all(|s| s.lock().id() != id));
+}
+
+#[cfg(feature = "events")]
+#[test]
+fn events() {
+    use async_lock::diagnostics::LockAction;
+    use futures_lite::StreamExt;
+
+    let m = Mutex::new(0);
+    let mut events = m.events();
+    assert!(events.try_next().is_none());
+
+    let guard = future::block_on(diagnostics::label("worker", m.lock()));
+    let line = line!() - 1;
+    drop(guard);
+    drop(m.try_lock().unwrap());
+
+    let acquired = events.try_next().unwrap();
+    assert_eq!(acquired.action(), LockAction::Acquired);
+    assert_eq!(acquired.kind(), "Mutex");
+    assert_eq!(acquired.mode(), "lock");
+    assert_eq!(acquired.id(), &m as *const _ as usize);
+    assert_eq!(acquired.label(), Some("worker"));
+    assert_eq!(acquired.location().unwrap().line(), line);
+    assert_eq!(
+        acquired.to_string(),
+        format!(
+            "Mutex {:#x} lock acquired at {} (worker)",
+            acquired.id(),
+            acquired.location().unwrap()
+        )
+    );
+
+    let released = events.try_next().unwrap();
+    assert_eq!(released.action(), LockAction::Released);
+    assert_eq!(released.location(), None);
+    assert!(acquired.at() <= released.at());
+
+    let again = events.try_next().unwrap();
+    assert_eq!(again.action(), LockAction::Acquired);
+    assert_eq!(again.label(), None);
+    assert_eq!(events.try_next().unwrap().action(), LockAction::Released);
+
+    // Waiting subscribers are woken, and streams end when the lock is dropped.
+    let mut next = Box::pin(events.next());
+    assert!(future::block_on(future::poll_once(next.as_mut())).is_none());
+    drop(future::block_on(m.lock()));
+    assert!(future::block_on(next).is_some());
+    drop(m);
+    assert!(future::block_on(events.next()).is_some());
+    assert!(future::block_on(events.next()).is_none());
+    assert_eq!(events.missed(), 0);
+}
+
+#[cfg(feature = "events")]
+#[test]
+fn events_overflow() {
+    let s = async_lock::Semaphore::new(1);
+    let events = s.events();
+    for _ in 0..600 {
+        drop(s.try_acquire().unwrap());
+    }
+
+    assert_eq!(events.missed(), 176);
+    assert_eq!(std::iter::from_fn(|| events.try_next()).count(), 1024);
+
+    // Dropped subscribers no longer receive events.
+    drop(events);
+    drop(s.try_acquire().unwrap());
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "embassy-sync")]
+
+use std::sync::Arc;
+use std::thread;
+
+use async_lock::EmbassyRawMutex;
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::channel::Channel;
+use embassy_sync::mutex::Mutex;
+use futures_lite::future;
+
+#[test]
+fn raw_lock() {
+    let raw = EmbassyRawMutex::INIT;
+    assert_eq!(raw.lock(|| 1), 1);
+    assert_eq!(raw.lock(|| 2), 2);
+
+    // Different raw mutexes can be nested.
+    let other = EmbassyRawMutex::new();
+    assert_eq!(raw.lock(|| other.lock(|| 3)), 3);
+}
+
+#[test]
+fn mutex_contention() {
+    let mutex = Arc::new(Mutex::<EmbassyRawMutex, u32>::new(0));
+    let handles: Vec<_> = (0..10)
+        .map(|_| {
+            let mutex = mutex.clone();
+            thread::spawn(move || {
+                future::block_on(async {
+                    for _ in 0..100 {
+                        *mutex.lock().await += 1;
+                    }
+                })
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(*future::block_on(mutex.lock()), 1000);
+}
+
+#[test]
+fn channel() {
+    let channel = Channel::<EmbassyRawMutex, u32, 2>::new();
+    future::block_on(async {
+        channel.send(1).await;
+        assert_eq!(channel.receive().await, 1);
+    });
+}
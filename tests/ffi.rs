@@ -0,0 +1,65 @@
+#![cfg(feature = "ffi")]
+
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use async_lock::{ffi, Mutex};
+use futures_lite::future;
+
+extern "C" fn count(data: *mut c_void) {
+    let counter = unsafe { &*(data as *const AtomicUsize) };
+    counter.fetch_add(1, Ordering::SeqCst);
+}
+
+#[test]
+fn try_lock_and_unlock() {
+    let handle = ffi::async_lock_mutex_new();
+    unsafe {
+        assert!(ffi::async_lock_mutex_try_lock(handle));
+        assert!(!ffi::async_lock_mutex_try_lock(handle));
+
+        let other = ffi::async_lock_mutex_clone(handle);
+        assert!(!ffi::async_lock_mutex_try_lock(other));
+        ffi::async_lock_mutex_destroy(handle);
+
+        ffi::async_lock_mutex_unlock(other);
+        assert!(ffi::async_lock_mutex_try_lock(other));
+        ffi::async_lock_mutex_unlock(other);
+        ffi::async_lock_mutex_destroy(other);
+    }
+}
+
+#[test]
+fn lock_with_callback() {
+    let counter = AtomicUsize::new(0);
+    let data = &counter as *const AtomicUsize as *mut c_void;
+    let mutex = Arc::new(Mutex::new(0));
+    let handle = ffi::mutex_handle(mutex.clone());
+
+    // A free mutex is acquired right away.
+    unsafe { ffi::async_lock_mutex_lock(handle, count, data) };
+    assert_eq!(counter.load(Ordering::SeqCst), 1);
+    assert!(mutex.try_lock().is_none());
+    unsafe { ffi::async_lock_mutex_unlock(handle) };
+
+    // A mutex held by Rust is handed over when the guard is dropped.
+    let guard = mutex.try_lock().unwrap();
+    unsafe { ffi::async_lock_mutex_lock(handle, count, data) };
+    assert_eq!(counter.load(Ordering::SeqCst), 1);
+    drop(guard);
+    while counter.load(Ordering::SeqCst) == 1 {
+        thread::yield_now();
+    }
+
+    // Rust tasks wait for C to unlock.
+    let mut task = Box::pin(mutex.lock());
+    assert!(future::block_on(future::poll_once(task.as_mut())).is_none());
+    unsafe { ffi::async_lock_mutex_unlock(handle) };
+    *future::block_on(task) += 1;
+
+    unsafe { ffi::async_lock_mutex_destroy(handle) };
+    assert_eq!(Arc::strong_count(&mutex), 1);
+    assert_eq!(*mutex.try_lock().unwrap(), 1);
+}
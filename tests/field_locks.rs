@@ -0,0 +1,61 @@
+use async_lock::field_locks;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+field_locks! {
+    /// Plain state.
+    #[derive(Debug, Default, PartialEq)]
+    struct State {
+        counter: Mutex<u32>,
+        names: RwLock<Vec<&'static str>>,
+        pub(crate) flag: Mutex<bool>,
+    }
+
+    #[derive(Debug, Default)]
+    struct Locks;
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn independent_fields() {
+    future::block_on(async {
+        let locks = Locks::from(State {
+            counter: 1,
+            names: vec!["a"],
+            flag: false,
+        });
+
+        let mut counter = locks.counter().lock().await;
+        let names = locks.names().read().await;
+        let names2 = locks.names().read().await;
+        assert!(locks.counter().try_lock().is_none());
+        assert!(locks.names().try_write().is_none());
+        *locks.flag().try_lock().unwrap() = true;
+
+        *counter += names.len() as u32 + names2.len() as u32;
+        drop((counter, names, names2));
+        locks.names().write().await.push("b");
+
+        assert_eq!(
+            locks.into_inner(),
+            State {
+                counter: 3,
+                names: vec!["a", "b"],
+                flag: true,
+            }
+        );
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn companion_attributes() {
+    let locks = Locks::default();
+    assert!(format!("{:?}", locks).starts_with("Locks {"));
+    assert_eq!(locks.into_inner(), State::default());
+}
@@ -0,0 +1,121 @@
+#![cfg(all(feature = "file-lock", not(target_arch = "wasm32")))]
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use async_lock::FileLock;
+use futures_lite::future;
+
+/// A lock file that is removed at the end of the test.
+struct TempPath(PathBuf);
+
+impl TempPath {
+    fn new(name: &str) -> TempPath {
+        let file = format!("async-lock-test-{}-{}.lock", name, std::process::id());
+        TempPath(std::env::temp_dir().join(file))
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn excludes_other_handles() {
+    future::block_on(async {
+        let path = TempPath::new("handles");
+        // Separate handles conflict the same way separate processes do.
+        let a = FileLock::open(&path.0).unwrap();
+        let b = Arc::new(FileLock::open(&path.0).unwrap());
+
+        let guard = a.lock().await.unwrap();
+        assert!(b.try_lock().unwrap().is_none());
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let waiter = {
+            let b = b.clone();
+            thread::spawn(move || {
+                let guard = future::block_on(b.lock()).unwrap();
+                tx.send(()).unwrap();
+                drop(guard);
+            })
+        };
+
+        thread::sleep(Duration::from_millis(50));
+        assert!(rx.try_recv().is_err());
+
+        drop(guard);
+        rx.recv().unwrap();
+        waiter.join().unwrap();
+        assert!(a.try_lock().unwrap().is_some());
+    });
+}
+
+#[test]
+fn excludes_tasks_of_one_handle() {
+    future::block_on(async {
+        let path = TempPath::new("tasks");
+        let lock = FileLock::open(&path.0).unwrap();
+
+        let guard = lock.lock().await.unwrap();
+        assert!(lock.try_lock().unwrap().is_none());
+
+        let mut waiter = Box::pin(lock.lock());
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        drop(guard);
+        assert!(waiter.await.is_ok());
+    });
+}
+
+#[test]
+fn cancelled_lock_releases_file() {
+    future::block_on(async {
+        let path = TempPath::new("cancel");
+        let a = FileLock::open(&path.0).unwrap();
+        let b = FileLock::open(&path.0).unwrap();
+
+        let guard = a.lock().await.unwrap();
+        let mut waiter = Box::pin(b.lock());
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        drop(waiter);
+        drop(guard);
+
+        // The helper thread gives the file lock back once it gets it.
+        let mut tries = 0;
+        while b.try_lock().unwrap().is_none() {
+            tries += 1;
+            assert!(tries < 100);
+            thread::sleep(Duration::from_millis(10));
+        }
+    });
+}
+
+#[test]
+fn cancelled_lock_keeps_excluding() {
+    future::block_on(async {
+        let path = TempPath::new("cancel-relock");
+        let a = FileLock::open(&path.0).unwrap();
+        let b = FileLock::open(&path.0).unwrap();
+
+        let guard = a.lock().await.unwrap();
+        let mut waiter = Box::pin(b.lock());
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        drop(waiter);
+
+        // The helper thread of the cancelled operation still waits on `b`'s file handle.
+        assert!(b.try_lock().unwrap().is_none());
+        drop(guard);
+
+        let guard = b.lock().await.unwrap();
+        // Give a stray unlock from the helper thread time to happen.
+        thread::sleep(Duration::from_millis(50));
+        assert!(a.try_lock().unwrap().is_none());
+        drop(guard);
+        assert!(a.try_lock().unwrap().is_some());
+    });
+}
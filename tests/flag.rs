@@ -0,0 +1,71 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use async_lock::Flag;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn set_and_reset() {
+    future::block_on(async {
+        let flag = Flag::default();
+        assert!(!flag.is_set());
+
+        let mut wait = Box::pin(flag.wait());
+        assert!(future::poll_once(&mut wait).await.is_none());
+
+        flag.set();
+        flag.set();
+        assert!(flag.is_set());
+        assert!(future::poll_once(flag.wait()).await.is_some());
+
+        // A waiter woken by `set()` completes even though the flag was reset since.
+        flag.reset();
+        assert!(!flag.is_set());
+        assert!(future::poll_once(&mut wait).await.is_some());
+
+        assert!(future::poll_once(flag.wait()).await.is_none());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn wakes_all_waiters() {
+    future::block_on(async {
+        let flag = Flag::new();
+        let mut waits = (0..3).map(|_| Box::pin(flag.wait())).collect::<Vec<_>>();
+        for wait in &mut waits {
+            assert!(future::poll_once(wait).await.is_none());
+        }
+
+        flag.set();
+        for wait in waits {
+            wait.await;
+        }
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn set_from_thread() {
+    let flag = Arc::new(Flag::new());
+    let waiters = (0..4)
+        .map(|_| {
+            let flag = flag.clone();
+            thread::spawn(move || future::block_on(flag.wait()))
+        })
+        .collect::<Vec<_>>();
+
+    flag.set();
+    for waiter in waiters {
+        waiter.join().unwrap();
+    }
+}
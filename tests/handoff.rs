@@ -0,0 +1,92 @@
+#![cfg(feature = "std")]
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use async_lock::Handoff;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn fifo() {
+    future::block_on(async {
+        let h = Handoff::new();
+        assert_eq!(h.notify_one(0), Err(0));
+
+        let waiters: Vec<_> = (0..3).map(|_| Box::pin(h.wait())).collect();
+        for i in 0..3 {
+            h.notify_one(i).unwrap();
+        }
+        assert_eq!(h.notify_one(3), Err(3));
+
+        for (i, w) in waiters.into_iter().enumerate() {
+            assert_eq!(w.await, i);
+        }
+    })
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn canceled_waiter() {
+    future::block_on(async {
+        let h = Handoff::new();
+
+        // A waiter canceled before receiving a value does not take one.
+        drop(h.wait());
+        assert_eq!(h.notify_one(1), Err(1));
+
+        // A value handed to a canceled waiter moves on to the next one.
+        let first = h.wait();
+        let second = h.wait();
+        h.notify_one(2).unwrap();
+        drop(first);
+        assert_eq!(second.await, 2);
+    })
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn notify_all() {
+    future::block_on(async {
+        let h = Handoff::new();
+        let a = h.wait();
+        let b = h.wait();
+        h.notify_one("one").unwrap();
+
+        // Only waiters that have not received a value yet get a clone.
+        assert_eq!(h.notify_all("all"), 1);
+        assert_eq!(a.await, "one");
+        assert_eq!(b.await, "all");
+        assert_eq!(h.notify_all("all"), 0);
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn across_threads() {
+    let h = Handoff::new();
+    let n = 10;
+
+    thread::scope(|s| {
+        let workers: Vec<_> = (0..n)
+            .map(|_| {
+                let wait = h.wait();
+                s.spawn(move || future::block_on(wait))
+            })
+            .collect();
+
+        for i in 0..n {
+            h.notify_one(i).unwrap();
+        }
+
+        let mut received: Vec<_> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+        received.sort();
+        assert_eq!(received, (0..n).collect::<Vec<_>>());
+    });
+}
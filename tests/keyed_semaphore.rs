@@ -0,0 +1,118 @@
+#![cfg(feature = "std")]
+
+use async_lock::KeyedSemaphore;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn limits_per_key() {
+    let s = KeyedSemaphore::new(2);
+
+    let a1 = s.try_acquire("a").unwrap();
+    let _a2 = s.try_acquire("a").unwrap();
+    assert!(s.try_acquire("a").is_none());
+    let b1 = s.try_acquire("b").unwrap();
+    assert_eq!((*a1.key(), *b1.key()), ("a", "b"));
+    assert_eq!(s.len(), 2);
+
+    drop(a1);
+    assert!(s.try_acquire("a").is_some());
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn waiters_keep_key() {
+    future::block_on(async {
+        let s = KeyedSemaphore::new(1);
+        let guard = s.acquire(1).await;
+
+        let mut waiter = Box::pin(s.acquire(1));
+        assert!(future::poll_once(&mut waiter).await.is_none());
+
+        drop(guard);
+        assert_eq!(s.len(), 1);
+        let guard = future::poll_once(&mut waiter).await.unwrap();
+        assert!(s.try_acquire(1).is_none());
+
+        drop(guard);
+        drop(waiter);
+        assert!(s.is_empty());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn removes_unused_keys() {
+    future::block_on(async {
+        let s = KeyedSemaphore::new(1);
+        let guard = s.acquire(1).await;
+
+        // Failed and cancelled operations do not leave their keys behind.
+        assert!(s.try_acquire(1).is_none());
+        let mut waiter = Box::pin(s.acquire(2));
+        let _ = future::poll_once(&mut waiter).await;
+        drop(waiter);
+        let mut waiter = Box::pin(s.acquire(1));
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        drop(waiter);
+        assert_eq!(s.len(), 1);
+
+        drop(guard);
+        assert!(s.is_empty());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn shrink() {
+    let s = KeyedSemaphore::new(1);
+    let guards = (0..1000)
+        .map(|i| s.try_acquire(i).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(s.len(), 1000);
+    assert!(s.capacity() >= 1000);
+
+    drop(guards);
+    assert!(s.is_empty());
+
+    s.shrink();
+    assert_eq!(s.capacity(), 0);
+    assert!(s.try_acquire(1).is_some());
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn exclusive() {
+    future::block_on(async {
+        let s = KeyedSemaphore::new(1);
+        let a = s.acquire("a").await;
+        let mut b = Box::pin(s.acquire("a"));
+        assert!(future::poll_once(&mut b).await.is_none());
+
+        let mut exclusive = Box::pin(s.acquire_exclusive());
+        assert!(future::poll_once(&mut exclusive).await.is_none());
+
+        // New operations on any key wait for the exclusive one.
+        let mut c = Box::pin(s.acquire("c"));
+        assert!(future::poll_once(&mut c).await.is_none());
+        assert!(s.try_acquire("d").is_none());
+
+        // The exclusive operation waits for the one that was already waiting.
+        drop(a);
+        assert!(future::poll_once(&mut exclusive).await.is_none());
+        drop(future::poll_once(&mut b).await.unwrap());
+        let guard = future::poll_once(&mut exclusive).await.unwrap();
+        assert!(s.is_empty());
+        assert!(future::poll_once(&mut c).await.is_none());
+
+        drop(guard);
+        assert_eq!(*c.await.key(), "c");
+        assert!(s.try_acquire_exclusive().is_some());
+    });
+}
@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_lock::Lazy;
+use futures_lite::future::{self, Boxed};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn initializes_once() {
+    future::block_on(async {
+        let calls = AtomicUsize::new(0);
+        let lazy = Lazy::new(|| async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            7
+        });
+        assert_eq!(lazy.try_get(), None);
+
+        assert_eq!(*lazy.get().await, 7);
+        assert_eq!(*lazy.get().await, 7);
+        assert_eq!(lazy.try_get(), Some(&7));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn static_lazy() {
+    type Init = fn() -> Boxed<Vec<u32>>;
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    static VALUE: Lazy<Vec<u32>, Init> = Lazy::new(|| {
+        Box::pin(async {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            vec![1, 2, 3]
+        })
+    });
+
+    future::block_on(async {
+        assert_eq!(VALUE.get().await[..], [1, 2, 3]);
+        assert_eq!(VALUE.get().await.len(), 3);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn cancelled_initialization_is_retried() {
+    future::block_on(async {
+        let calls = AtomicUsize::new(0);
+        let lazy = Lazy::new(|| async {
+            if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                future::yield_now().await;
+            }
+            5
+        });
+
+        let mut first = Box::pin(lazy.get());
+        assert!(future::poll_once(&mut first).await.is_none());
+        drop(first);
+
+        assert_eq!(*lazy.get().await, 5);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn prime() {
+    future::block_on(async {
+        let calls = AtomicUsize::new(0);
+        let lazy = Lazy::new(|| async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            3
+        });
+
+        let mut spawned = None;
+        lazy.prime(|init| spawned = Some(init));
+        let mut init = spawned.unwrap();
+        assert_eq!(lazy.try_get(), None);
+
+        // The primed initialization runs in the background, and callers reuse it.
+        init.as_mut().await;
+        assert_eq!(lazy.try_get(), Some(&3));
+        assert_eq!(*lazy.get().await, 3);
+
+        // An initialized value is not primed again.
+        lazy.prime(|_| unreachable!());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    });
+}
@@ -0,0 +1,104 @@
+#![cfg(feature = "std")]
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use async_lock::LeftRight;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn read_write() {
+    future::block_on(async {
+        let lock = LeftRight::new(vec![1]);
+        lock.write(|v| v.push(2)).await;
+        lock.write(|v| v.push(3)).await;
+        assert_eq!(*lock.read(), [1, 2, 3]);
+        assert_eq!(format!("{:?}", lock), "LeftRight { value: [1, 2, 3] }");
+        assert_eq!(lock.into_inner(), [1, 2, 3]);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn writer_waits_for_old_readers() {
+    future::block_on(async {
+        let lock = LeftRight::new(0);
+        let old = lock.read();
+
+        let mut write = Box::pin(lock.write(|n| *n += 1));
+        assert!(future::poll_once(&mut write).await.is_none());
+
+        // New readers already see the change, while the old reader keeps its copy.
+        assert_eq!(*lock.read(), 1);
+        assert_eq!(*old, 0);
+
+        drop(old);
+        future::poll_once(&mut write).await.unwrap();
+        drop(write);
+
+        // The second copy was changed too.
+        lock.write(|_| {}).await;
+        assert_eq!(*lock.read(), 1);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn cancelled_write_completes() {
+    future::block_on(async {
+        let lock = LeftRight::new(0);
+        let old = lock.read();
+
+        let mut write = Box::pin(lock.write(|n| *n += 1));
+        assert!(future::poll_once(&mut write).await.is_none());
+        drop(old);
+        drop(write);
+
+        lock.write(|n| *n += 1).await;
+        assert_eq!(*lock.read(), 2);
+        lock.write(|_| {}).await;
+        assert_eq!(*lock.read(), 2);
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn concurrent() {
+    let lock = Arc::new(LeftRight::new((0u64, 0u64)));
+
+    let readers = (0..4)
+        .map(|_| {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                for _ in 0..10_000 {
+                    let pair = lock.read();
+                    assert_eq!(pair.0, pair.1);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    future::block_on(async {
+        for _ in 0..1_000 {
+            lock.write(|pair| {
+                pair.0 += 1;
+                pair.1 += 1;
+            })
+            .await;
+        }
+    });
+
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    assert_eq!(*lock.read(), (1_000, 1_000));
+}
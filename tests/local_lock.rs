@@ -0,0 +1,116 @@
+use std::cell::Cell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use async_lock::{LocalLock, LocalLockGuardRc};
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn smoke() {
+    future::block_on(async {
+        let lock = LocalLock::new(0);
+        *lock.lock().await += 1;
+        *lock.try_lock().unwrap() += 1;
+
+        let guard = lock.lock().await;
+        assert!(lock.is_locked());
+        assert!(lock.try_lock().is_none());
+        assert_eq!(format!("{:?}", lock), "LocalLock { data: <locked> }");
+        drop(guard);
+        assert_eq!(format!("{:?}", lock), "LocalLock { data: 2 }");
+        assert_eq!(lock.into_inner(), 2);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn waiters_in_order() {
+    future::block_on(async {
+        let lock = Rc::new(LocalLock::new(Vec::new()));
+        let guard = lock.lock().await;
+
+        let mut tasks = (0..3)
+            .map(|i| {
+                let lock = lock.clone();
+                Box::pin(async move { lock.lock().await.push(i) })
+            })
+            .collect::<Vec<_>>();
+        for task in &mut tasks {
+            assert!(future::poll_once(task.as_mut()).await.is_none());
+        }
+
+        drop(guard);
+        for task in tasks.into_iter().rev() {
+            task.await;
+        }
+        assert_eq!(*lock.lock().await, [2, 1, 0]);
+    });
+}
+
+/// Counts how often it was woken.
+struct CountWaker(Cell<usize>);
+
+// The tests only use the waker on one thread.
+unsafe impl Send for CountWaker {}
+unsafe impl Sync for CountWaker {}
+
+impl Wake for CountWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn cancelled_lock_forwards_wakeup() {
+    let lock = LocalLock::new(());
+    let guard = lock.try_lock().unwrap();
+
+    let first_waker = Arc::new(CountWaker(Cell::new(0)));
+    let second_waker = Arc::new(CountWaker(Cell::new(0)));
+    let mut first = lock.lock();
+    let mut second = lock.lock();
+
+    let waker = Waker::from(first_waker.clone());
+    let cx = &mut Context::from_waker(&waker);
+    assert!(Pin::new(&mut first).poll(cx).is_pending());
+    let waker = Waker::from(second_waker.clone());
+    let cx = &mut Context::from_waker(&waker);
+    assert!(Pin::new(&mut second).poll(cx).is_pending());
+
+    // Only the first waiter is woken.
+    drop(guard);
+    assert_eq!(first_waker.0.get(), 1);
+    assert_eq!(second_waker.0.get(), 0);
+
+    // Dropping it passes the wakeup on.
+    drop(first);
+    assert_eq!(second_waker.0.get(), 1);
+    assert!(matches!(Pin::new(&mut second).poll(cx), Poll::Ready(_)));
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn owned_guards() {
+    future::block_on(async {
+        let lock = Rc::new(LocalLock::new(0));
+        let mut guard = lock.lock_rc().await;
+        *guard += 1;
+        assert!(Rc::ptr_eq(LocalLockGuardRc::source(&guard), &lock));
+        assert!(lock.try_lock_rc().is_none());
+        drop(guard);
+
+        *lock.try_lock_rc().unwrap() += 1;
+        assert_eq!(*lock.lock().await, 2);
+    });
+}
@@ -0,0 +1,121 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use async_lock::{lock_all, try_lock_all, Mutex, RwLock, Semaphore};
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn heterogeneous() {
+    future::block_on(async {
+        let m = Mutex::new(1);
+        let rw = RwLock::new("a");
+        let s = Semaphore::new(1);
+
+        let (mut n, mut text, permit) = lock_all((&m, &rw, &s)).await;
+        assert!(m.try_lock().is_none());
+        assert!(rw.try_read().is_none());
+        assert!(s.try_acquire().is_none());
+        *n += 1;
+        *text = "b";
+        drop((n, text, permit));
+
+        assert_eq!(*m.lock().await, 2);
+        assert_eq!(*rw.read().await, "b");
+        assert!(s.try_acquire().is_some());
+    })
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn backs_off() {
+    future::block_on(async {
+        let a = Mutex::new(0);
+        let b = Mutex::new(0);
+
+        let held = b.lock().await;
+        let mut both = Box::pin(lock_all((&a, &b)));
+        assert!(future::poll_once(both.as_mut()).await.is_none());
+
+        // `a` is not held while waiting for `b`.
+        drop(a.try_lock().unwrap());
+        drop(held);
+        let (ga, gb) = both.await;
+        assert!(a.try_lock().is_none());
+        drop((ga, gb));
+        assert!(a.try_lock().is_some() && b.try_lock().is_some());
+    })
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn try_all_or_nothing() {
+    let a = Mutex::new(0);
+    let b = RwLock::new(0);
+    let c = Semaphore::new(1);
+
+    let reader = b.try_read().unwrap();
+    assert!(try_lock_all((&a, &b, &c)).is_none());
+    // Partial acquisitions were rolled back.
+    assert!(a.try_lock().is_some());
+    assert!(c.try_acquire().is_some());
+    drop(reader);
+
+    let (mut ga, gb, gc) = try_lock_all((&a, &b, &c)).unwrap();
+    *ga += 1;
+    assert!(try_lock_all((&c,)).is_none());
+    drop((ga, gb, gc));
+
+    // The same lock twice can never be acquired.
+    assert!(try_lock_all((&a, &a)).is_none());
+    assert_eq!(*a.try_lock().unwrap(), 1);
+}
+
+#[test]
+#[should_panic = "twice"]
+fn duplicate() {
+    let m = Mutex::new(());
+    future::block_on(lock_all((&m, &m)));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn opposite_orders() {
+    let a = Arc::new(Mutex::new(0u32));
+    let b = Arc::new(Mutex::new(0u32));
+
+    let threads: Vec<_> = (0..4)
+        .map(|t| {
+            let (a, b) = (a.clone(), b.clone());
+            thread::spawn(move || {
+                future::block_on(async {
+                    for _ in 0..500 {
+                        if t % 2 == 0 {
+                            let (mut a, mut b) = lock_all((&*a, &*b)).await;
+                            *a += 1;
+                            *b += 1;
+                        } else {
+                            let (mut b, mut a) = lock_all((&*b, &*a)).await;
+                            *a += 1;
+                            *b += 1;
+                        }
+                    }
+                })
+            })
+        })
+        .collect();
+
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(*a.try_lock().unwrap(), 2000);
+    assert_eq!(*b.try_lock().unwrap(), 2000);
+}
@@ -1,11 +1,23 @@
 #[cfg(not(target_arch = "wasm32"))]
 use std::thread;
-#[cfg(not(target_arch = "wasm32"))]
+
+use std::future::{pending, Future};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::time::Duration;
 
-use async_lock::Mutex;
+#[cfg(feature = "std")]
+use async_lock::Instant;
+use async_lock::{
+    FairnessPolicy, MappedMutexGuard, MappedMutexGuardArc, Mutex, MutexGuard, MutexGuardArc,
+};
 use futures_lite::future;
 
+mod common;
+use common::{Expired, Never};
+
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen_test::*;
 
@@ -29,6 +41,167 @@ fn try_lock() {
     *m.try_lock().unwrap() = ();
 }
 
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn named() {
+    let m = Mutex::new_named("state", 1);
+    assert_eq!(m.name(), Some("state"));
+    assert_eq!(format!("{:?}", m), r#"Mutex { name: "state", data: 1 }"#);
+
+    let _guard = m.try_lock().unwrap();
+    assert!(format!("{:?}", m).starts_with(r#"Mutex { name: "state", data: <locked"#));
+
+    assert_eq!(Mutex::new(()).name(), None);
+}
+
+#[cfg(feature = "std")]
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn introspection() {
+    future::block_on(async {
+        let m = Mutex::new(());
+        assert!(!m.is_locked());
+        assert_eq!(m.waiter_count(), 0);
+
+        let guard = m.lock().await;
+        assert!(m.is_locked());
+
+        let mut first = m.lock();
+        let mut second = m.lock();
+        assert!(future::poll_once(&mut first).await.is_none());
+        assert!(future::poll_once(&mut second).await.is_none());
+        assert_eq!(m.waiter_count(), 2);
+
+        drop(second);
+        assert_eq!(m.waiter_count(), 1);
+
+        // A handed over mutex stays locked.
+        MutexGuard::unlock_fair(guard);
+        assert!(m.is_locked());
+        drop(first.await);
+        assert!(!m.is_locked());
+        assert_eq!(m.waiter_count(), 0);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn try_with() {
+    let m = Mutex::new(vec![1]);
+    assert_eq!(m.try_with(|v| v.len()), Some(1));
+
+    let guard = m.try_lock().unwrap();
+    let mut called = false;
+    assert_eq!(m.try_with(|_| called = true), None);
+    assert!(!called);
+    drop(guard);
+
+    m.try_with(|v| v.push(2)).unwrap();
+    // The mutex is released once the closure returns.
+    assert_eq!(*m.try_lock().unwrap(), [1, 2]);
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn try_lock_spin() {
+    let m = Mutex::new(0);
+    *m.try_lock_spin(0).unwrap() += 1;
+    let guard = m.try_lock_spin(10).unwrap();
+    assert!(m.try_lock_spin(0).is_none());
+    assert!(m.try_lock_spin(1000).is_none());
+    drop(guard);
+    assert_eq!(*m.try_lock_spin(1).unwrap(), 1);
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn leak() {
+    let m = Mutex::new(vec![1]);
+    let data = MutexGuard::leak(m.try_lock().unwrap());
+    data.push(2);
+    assert_eq!(data, &[1, 2]);
+    assert!(m.try_lock().is_none());
+    assert!(m.is_locked());
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn force_unlock() {
+    future::block_on(async {
+        let m = Mutex::new(0);
+        MutexGuard::into_raw(m.lock().await);
+
+        let mut lock = Box::pin(m.lock());
+        assert!(future::poll_once(&mut lock).await.is_none());
+
+        unsafe { m.force_unlock() };
+        *lock.await += 1;
+        assert_eq!(*m.try_lock().unwrap(), 1);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn lock_spin() {
+    future::block_on(async {
+        let m = Mutex::new(0);
+        *m.lock_spin(0).await += 1;
+        *m.lock_spin(100).await += 1;
+        assert_eq!(*m.lock().await, 2);
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn lock_spin_contended() {
+    let m = Arc::new(Mutex::new(0));
+    let threads = (0..4)
+        .map(|_| {
+            let m = m.clone();
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    future::block_on(async { *m.lock_spin(100).await += 1 });
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(*m.try_lock().unwrap(), 4000);
+}
+
+#[cfg(feature = "stream")]
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn stream_items() {
+    use futures_lite::StreamExt;
+
+    future::block_on(async {
+        let m = Mutex::new(vec![1, 2, 3, 4, 5]);
+        assert_eq!(
+            m.stream_items(2).collect::<Vec<i32>>().await,
+            [1, 2, 3, 4, 5]
+        );
+        assert_eq!(m.stream_items(0).count().await, 5);
+
+        // The stream waits for the mutex between chunks, and sees later changes.
+        let mut items = Box::pin(m.stream_items(3));
+        assert_eq!(items.next().await, Some(1));
+        let mut guard = m.try_lock().unwrap();
+        assert_eq!(items.next().await, Some(2));
+        assert_eq!(items.next().await, Some(3));
+        assert!(future::poll_once(items.next()).await.is_none());
+        guard.truncate(4);
+        drop(guard);
+        assert_eq!(items.next().await, Some(4));
+        assert_eq!(items.next().await, None);
+
+        let empty = Mutex::new(Vec::<i32>::new());
+        assert_eq!(Box::pin(empty.stream_items(1)).next().await, None);
+    })
+}
+
 #[test]
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
 fn into_inner() {
@@ -44,6 +217,344 @@ fn get_mut() {
     assert_eq!(m.into_inner(), 20);
 }
 
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn unsized_values() {
+    future::block_on(async {
+        let m: Arc<Mutex<dyn Fn() -> i32 + Send>> = Arc::new(Mutex::new(|| 1));
+        assert_eq!((m.lock().await)(), 1);
+        assert_eq!((m.lock_arc().await)(), 1);
+
+        let buf: &mut Mutex<[u8]> = &mut Mutex::new([0; 3]);
+        buf.lock().await[0] = 1;
+        buf.get_mut()[1] = 2;
+        assert_eq!(*buf.try_lock().unwrap(), [1, 2, 0]);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn data_ptr() {
+    let m = Mutex::new(1);
+    let mut guard = m.try_lock().unwrap();
+    unsafe { *m.data_ptr() += 1 };
+    assert_eq!(*guard, 2);
+    assert_eq!(m.data_ptr(), &mut *guard as *mut i32);
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn slow_path() {
+    future::block_on(async {
+        let m = Mutex::new(0i32);
+        let guard = m.lock().await;
+
+        let mut waiter = Box::pin(m.lock());
+        assert!(future::poll_once(waiter.as_mut()).await.is_none());
+
+        // The woken lock operation loses the mutex to a new `try_lock()`, so it starts measuring
+        // how long it has been waiting.
+        drop(guard);
+        let guard = m.try_lock().unwrap();
+        assert!(future::poll_once(waiter.as_mut()).await.is_none());
+
+        drop(guard);
+        *waiter.await += 1;
+        assert_eq!(*m.lock().await, 1);
+    })
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn raw_guards() {
+    use async_lock::{MutexGuard, MutexGuardArc};
+
+    future::block_on(async {
+        let m = Mutex::new(0i32);
+        let raw = MutexGuard::into_raw(m.lock().await);
+
+        // The mutex stays locked while the guard is in raw form.
+        let mut waiter = Box::pin(m.lock());
+        assert!(future::poll_once(waiter.as_mut()).await.is_none());
+
+        let mut guard = unsafe { MutexGuard::from_raw(raw) };
+        *guard += 1;
+        drop(guard);
+        assert_eq!(*waiter.await, 1);
+
+        let m = std::sync::Arc::new(Mutex::new(0i32));
+        let raw = MutexGuardArc::into_raw(m.lock_arc().await);
+        assert!(m.try_lock().is_none());
+        assert_eq!(std::sync::Arc::strong_count(&m), 2);
+
+        drop(unsafe { MutexGuardArc::from_raw(raw) });
+        assert!(m.try_lock().is_some());
+        assert_eq!(std::sync::Arc::strong_count(&m), 1);
+    })
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn with_lock() {
+    future::block_on(async {
+        let m = std::sync::Arc::new(Mutex::new(0i32));
+        let guard = m.lock().await;
+
+        // The closure only runs once the mutex is acquired.
+        let mut add = Box::pin(m.with_lock(|n| *n += 1));
+        let mut double = Box::pin(m.with_lock_arc(|n| {
+            *n *= 2;
+            *n
+        }));
+        assert!(future::poll_once(add.as_mut()).await.is_none());
+        assert!(future::poll_once(double.as_mut()).await.is_none());
+
+        drop(guard);
+        add.await;
+        assert_eq!(double.await, 2);
+        assert!(m.try_lock().is_some());
+    })
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn replace_take_swap() {
+    future::block_on(async {
+        let a = Mutex::new(vec![1]);
+        let b = Mutex::new(vec![2]);
+
+        assert_eq!(a.replace(vec![3]).await, [1]);
+        a.swap(&b).await;
+        a.swap(&a).await;
+        assert_eq!(a.take().await, [2]);
+        assert_eq!(b.take().await, [3]);
+        assert!(a.try_lock().unwrap().is_empty());
+    })
+}
+
+#[cfg(feature = "async-closure")]
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn with_lock_async() {
+    future::block_on(async {
+        let m = Mutex::new(0i32);
+
+        // The mutex stays locked while the closure's future is pending.
+        let mut add = Box::pin(m.with_lock_async(async |n| {
+            *n += 1;
+            future::yield_now().await;
+            *n
+        }));
+        assert!(future::poll_once(add.as_mut()).await.is_none());
+        assert!(m.try_lock().is_none());
+
+        assert_eq!(add.await, 1);
+        assert!(m.try_lock().is_some());
+    })
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn boxed() {
+    use async_lock::{DynLock, RwLock};
+
+    future::block_on(async {
+        let m = Mutex::new(0i32);
+        *m.lock_boxed().await += 1;
+
+        let locks: [&dyn DynLock<i32>; 2] = [&m, &RwLock::new(0)];
+        for lock in locks {
+            let guard = lock.lock_dyn().await;
+            assert!(lock.try_lock_dyn().is_none());
+            drop(guard);
+            *lock.try_lock_dyn().unwrap() += 1;
+        }
+        assert_eq!(*m.lock().await, 2);
+
+        let m = std::sync::Arc::new(m);
+        let lock = m.lock_arc_boxed();
+        drop(m);
+        assert_eq!(*lock.await, 2);
+    })
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn project() {
+    struct State {
+        names: Vec<&'static str>,
+        hits: u32,
+    }
+
+    future::block_on(async {
+        let m = Mutex::new(State {
+            names: Vec::new(),
+            hits: 0,
+        });
+        let names = m.project(|s| &mut s.names);
+        let hits = m.project(|s| &mut s.hits);
+
+        // Sub-locks share the parent's mutual exclusion.
+        let mut guard = names.lock().await;
+        guard.push("a");
+        assert!(m.try_lock().is_none());
+        assert!(hits.try_lock().is_none());
+
+        let mut waiting = Box::pin(hits.lock());
+        assert!(future::poll_once(waiting.as_mut()).await.is_none());
+        drop(guard);
+        *waiting.await += 1;
+
+        let names2 = names;
+        names2.try_lock().unwrap().push("b");
+        assert_eq!(format!("{:?}", names), r#"SubLock { data: ["a", "b"] }"#);
+
+        let state = m.lock().await;
+        assert_eq!(state.names, ["a", "b"]);
+        assert_eq!(state.hits, 1);
+        assert!(std::ptr::eq(hits.parent(), &m));
+    })
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn fifo_fairness() {
+    future::block_on(async {
+        let mutex = Mutex::with_fairness(0, FairnessPolicy::Fifo);
+        let guard = mutex.lock().await;
+
+        let mut waiter = mutex.lock();
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        drop(guard);
+
+        // The queued lock operation is served before newcomers.
+        assert!(mutex.try_lock().is_none());
+        *waiter.await += 1;
+        assert_eq!(*mutex.try_lock().unwrap(), 1);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn barging_fairness() {
+    future::block_on(async {
+        let mutex = Mutex::with_fairness(0, FairnessPolicy::Barging);
+        let guard = mutex.lock().await;
+
+        let mut waiter = mutex.lock();
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        drop(guard);
+
+        // Newcomers can take the mutex before the queued lock operation.
+        *mutex.try_lock().unwrap() += 1;
+        assert_eq!(*waiter.await, 1);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn lock_fair() {
+    future::block_on(async {
+        let mutex = Mutex::with_fairness(0, FairnessPolicy::Barging);
+        let guard = mutex.lock().await;
+
+        let mut waiter = mutex.lock_fair();
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        drop(guard);
+
+        // The fair lock operation is not overtaken even though the mutex allows barging.
+        assert!(mutex.try_lock().is_none());
+        *waiter.await += 1;
+        assert_eq!(*mutex.try_lock().unwrap(), 1);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn unlock_fair() {
+    future::block_on(async {
+        let mutex = Mutex::new(0);
+        let mut guard = mutex.lock().await;
+
+        // Two tasks take turns without one of them taking the mutex back right away.
+        for i in 0..4 {
+            *guard += 1;
+            let mut next = mutex.lock();
+            assert!(future::poll_once(&mut next).await.is_none());
+            MutexGuard::unlock_fair(guard);
+            assert!(mutex.try_lock().is_none());
+            guard = next.await;
+            assert_eq!(*guard, i + 1);
+        }
+
+        // Without waiters, the mutex is released.
+        MutexGuard::unlock_fair(guard);
+        assert_eq!(*mutex.try_lock().unwrap(), 4);
+
+        // A waiter that was already woken by an earlier release is not handed the mutex.
+        let guard = mutex.lock().await;
+        let mut next = mutex.lock();
+        assert!(future::poll_once(&mut next).await.is_none());
+        drop(guard);
+        let guard = mutex.try_lock().unwrap();
+        MutexGuard::unlock_fair(guard);
+        assert!(mutex.try_lock().is_some());
+        assert_eq!(*next.await, 4);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn unlocked() {
+    future::block_on(async {
+        let mutex = Mutex::new(0);
+        let guard = mutex.lock().await;
+
+        let (mut guard, ()) = MutexGuard::unlocked(guard, async {
+            *mutex.try_lock().unwrap() += 1;
+        })
+        .await;
+        assert!(mutex.try_lock().is_none());
+        *guard += 1;
+        drop(guard);
+
+        // Cancelling the operation leaves the mutex unlocked.
+        let guard = mutex.lock().await;
+        let mut unlocked = Box::pin(MutexGuard::unlocked(guard, future::pending::<()>()));
+        assert!(future::poll_once(&mut unlocked).await.is_none());
+        assert!(mutex.try_lock().is_some());
+        drop(unlocked);
+        assert_eq!(*mutex.try_lock().unwrap(), 2);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn cancelled_handover() {
+    future::block_on(async {
+        let mutex = Mutex::new(0);
+        let guard = mutex.lock().await;
+
+        let mut first = mutex.lock();
+        let mut second = mutex.lock();
+        assert!(future::poll_once(&mut first).await.is_none());
+        assert!(future::poll_once(&mut second).await.is_none());
+
+        // The mutex handed to the cancelled operation goes to the next one.
+        MutexGuard::unlock_fair(guard);
+        drop(first);
+        assert!(mutex.try_lock().is_none());
+        let guard = second.await;
+
+        // With nobody left to take it, the mutex is released.
+        let mut third = mutex.lock();
+        assert!(future::poll_once(&mut third).await.is_none());
+        MutexGuard::unlock_fair(guard);
+        drop(third);
+        assert!(mutex.try_lock().is_some());
+    });
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[test]
 fn contention() {
@@ -76,3 +587,326 @@ fn contention() {
         assert_eq!(num_tasks, *lock);
     });
 }
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+#[test]
+fn unlock_fair_contention() {
+    let mutex = Arc::new(Mutex::new(0));
+
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let mutex = mutex.clone();
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    let mut guard = mutex.lock_blocking();
+                    *guard += 1;
+                    MutexGuard::unlock_fair(guard);
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(*mutex.try_lock().unwrap(), 8000);
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+#[test]
+fn blocking_and_async() {
+    let mutex = Arc::new(Mutex::new(0i32));
+    let guard = mutex.lock_blocking();
+    let num_threads = 10;
+
+    // Blocking threads and async tasks wait on the same lock.
+    let handles: Vec<_> = (0..num_threads)
+        .map(|i| {
+            let mutex = mutex.clone();
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    if i % 2 == 0 {
+                        *mutex.lock_blocking() += 1;
+                    } else {
+                        future::block_on(async { *mutex.lock().await += 1 });
+                    }
+                }
+            })
+        })
+        .collect();
+
+    drop(guard);
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(*mutex.lock_arc_blocking(), num_threads * 100);
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+#[test]
+fn try_lock_for() {
+    let mutex = Arc::new(Mutex::new(0));
+    let guard = mutex.lock_blocking();
+    assert!(mutex.try_lock_for(Duration::from_millis(10)).is_none());
+
+    // The timed out operation left the queue, so the mutex is handed to the next waiter.
+    let m = mutex.clone();
+    let handle = thread::spawn(move || *m.try_lock_for(Duration::from_secs(60)).unwrap() += 1);
+    thread::sleep(Duration::from_millis(10));
+    drop(guard);
+    handle.join().unwrap();
+    assert_eq!(*mutex.try_lock().unwrap(), 1);
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn arbitrary() {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    let mut u = Unstructured::new(&[7, 0, 0, 0]);
+    let m = Mutex::<u32>::arbitrary(&mut u).unwrap();
+    assert_eq!(m.into_inner(), 7);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn serde() {
+    let m: Mutex<Vec<u32>> = serde_json::from_str("[1,2]").unwrap();
+    assert_eq!(serde_json::to_string(&m).unwrap(), "[1,2]");
+
+    let _guard = m.try_lock().unwrap();
+    let err = serde_json::to_string(&m).unwrap_err();
+    assert_eq!(err.to_string(), "the mutex is locked");
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn map() {
+    future::block_on(async {
+        let m = Mutex::new((vec![1, 2], (0, "zero")));
+
+        let mut items = MutexGuard::map(m.lock().await, |s| &mut s.0);
+        items.push(3);
+        // Mapped guards keep the mutex locked.
+        assert!(m.try_lock().is_none());
+        drop(items);
+        assert_eq!(m.lock().await.0, [1, 2, 3]);
+
+        let pair = MutexGuard::map(m.lock().await, |s| &mut s.1);
+        let mut name = MappedMutexGuard::map(pair, |p| &mut p.1);
+        *name = "one";
+        assert_eq!(format!("{:?} {}", name, name), "\"one\" one");
+        drop(name);
+        assert!(m.try_lock().is_some());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn try_map() {
+    future::block_on(async {
+        let m = Mutex::new(None::<i32>);
+
+        let mut guard = MutexGuard::try_map(m.lock().await, |o| o.as_mut()).unwrap_err();
+        // The original guard still holds the mutex.
+        assert!(m.try_lock().is_none());
+        guard.replace(1);
+        drop(guard);
+
+        let mut n = MutexGuard::try_map(m.lock().await, |o| o.as_mut()).unwrap();
+        *n += 1;
+        drop(n);
+        assert_eq!(*m.lock().await, Some(2));
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn map_arc() {
+    future::block_on(async {
+        let m = Arc::new(Mutex::new((vec![1, 2], (0, "zero"))));
+
+        let mut items = MutexGuardArc::map(m.lock_arc().await, |s| &mut s.0);
+        items.push(3);
+        assert!(m.try_lock().is_none());
+        drop(items);
+        assert_eq!(m.lock().await.0, [1, 2, 3]);
+
+        let pair = MutexGuardArc::map(m.lock_arc().await, |s| &mut s.1);
+        let mut name = MappedMutexGuardArc::map(pair, |p| &mut p.1);
+        *name = "one";
+        assert_eq!(format!("{:?} {}", name, name), "\"one\" one");
+
+        // The mapped guard keeps the mutex alive.
+        let weak = Arc::downgrade(&m);
+        drop(m);
+        assert!(weak.upgrade().is_some());
+        drop(name);
+        assert!(weak.upgrade().is_none());
+    });
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+#[test]
+fn map_arc_moves_to_thread() {
+    let m = Arc::new(Mutex::new((0, String::new())));
+
+    let mut name = MutexGuardArc::map(m.lock_arc_blocking(), |s| &mut s.1);
+    thread::spawn(move || name.push_str("one")).join().unwrap();
+    assert_eq!(m.lock_blocking().1, "one");
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn lock_timeout() {
+    future::block_on(async {
+        let m = Mutex::new(1);
+
+        // An available mutex is locked even if the timeout has elapsed.
+        let guard = m.lock_timeout(Expired, Duration::ZERO).await.unwrap();
+        assert!(m.lock_timeout(Expired, Duration::ZERO).await.is_none());
+
+        let mut waiter = Box::pin(m.lock_timeout(Never, Duration::from_secs(1)));
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        drop(guard);
+        assert_eq!(*waiter.await.unwrap(), 1);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn timed_out_lock_leaves_queue() {
+    future::block_on(async {
+        let m = Mutex::new(());
+        let guard = m.lock().await;
+
+        let mut timed = Box::pin(m.lock_timeout(Never, Duration::from_secs(1)));
+        assert!(future::poll_once(&mut timed).await.is_none());
+        let mut other = Box::pin(m.lock());
+        assert!(future::poll_once(&mut other).await.is_none());
+
+        // The notification meant for the dropped waiter is passed on.
+        drop(guard);
+        drop(timed);
+        assert!(future::poll_once(&mut other).await.is_some());
+    });
+}
+
+#[cfg(feature = "std")]
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn lock_deadline() {
+    future::block_on(async {
+        let m = Mutex::new(1);
+        let passed = Instant::now();
+
+        let guard = m.lock_deadline(Expired, passed).await.unwrap();
+        assert!(m.lock_deadline(Expired, passed).await.is_none());
+
+        let later = Instant::now() + Duration::from_secs(60);
+        let mut waiter = Box::pin(m.lock_deadline(Never, later));
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        drop(guard);
+        assert_eq!(*waiter.await.unwrap(), 1);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn lock_cancellable() {
+    future::block_on(async {
+        let m = Mutex::new(1);
+        let (stop, stopped) = async_channel::bounded::<()>(1);
+
+        // An available mutex is locked even if cancellation already happened.
+        let guard = m.lock_cancellable(async {}).await.unwrap();
+
+        let mut waiter = Box::pin(m.lock_cancellable(stopped.recv()));
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        drop(stop);
+        assert!(waiter.await.is_err());
+
+        drop(guard);
+        assert_eq!(*m.lock_cancellable(pending::<()>()).await.unwrap(), 1);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn lock_future_is_nameable() {
+    struct Waiting<'a> {
+        lock: async_lock::LockFuture<'a, i32>,
+    }
+
+    fn assert_unpin<T: Unpin>(_: &T) {}
+
+    future::block_on(async {
+        let m = Mutex::new(1);
+        let guard = m.lock().await;
+
+        let mut waiting = Waiting { lock: m.lock() };
+        assert_unpin(&waiting.lock);
+        assert!(future::poll_once(&mut waiting.lock).await.is_none());
+
+        drop(guard);
+        *(&mut waiting.lock).await += 1;
+        assert_eq!(*m.lock().await, 2);
+    });
+}
+
+#[cfg(feature = "stream")]
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn lock_future_is_fused() {
+    use futures_core::future::FusedFuture;
+
+    future::block_on(async {
+        let m = Mutex::new(());
+        let mut lock = m.lock();
+        assert!(!lock.is_terminated());
+
+        drop((&mut lock).await);
+        assert!(lock.is_terminated());
+    });
+}
+
+/// Counts how often it was woken.
+struct CountWaker(AtomicUsize);
+
+impl Wake for CountWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Polls `f` once with a waker that counts its wakeups.
+fn poll_counted<F: Future + Unpin>(f: &mut F) -> (Poll<F::Output>, Arc<CountWaker>) {
+    let count = Arc::new(CountWaker(AtomicUsize::new(0)));
+    let waker = Waker::from(count.clone());
+    (Pin::new(f).poll(&mut Context::from_waker(&waker)), count)
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn cancelled_lock_forwards_wakeup() {
+    let m = Mutex::new(());
+    let guard = m.try_lock().unwrap();
+
+    let mut first = m.lock();
+    let mut second = m.lock();
+    let (poll, first_wakes) = poll_counted(&mut first);
+    assert!(poll.is_pending());
+    let (poll, second_wakes) = poll_counted(&mut second);
+    assert!(poll.is_pending());
+
+    drop(guard);
+    assert_eq!(first_wakes.0.load(Ordering::SeqCst), 1);
+    assert_eq!(second_wakes.0.load(Ordering::SeqCst), 0);
+
+    // The woken lock operation is cancelled, so the wakeup goes to the next one.
+    drop(first);
+    assert_eq!(second_wakes.0.load(Ordering::SeqCst), 1);
+    assert!(poll_counted(&mut second).0.is_ready());
+    assert!(m.try_lock().is_some());
+}
@@ -0,0 +1,154 @@
+#![cfg(feature = "std")]
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use async_lock::Notify;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn stored_permit() {
+    future::block_on(async {
+        let notify = Notify::new();
+        assert!(future::poll_once(notify.notified()).await.is_none());
+
+        notify.notify_one();
+        notify.notify_one();
+        assert!(future::poll_once(notify.notified()).await.is_some());
+        assert!(future::poll_once(notify.notified()).await.is_none());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn notify_one_in_order() {
+    future::block_on(async {
+        let notify = Notify::new();
+        let mut a = Box::pin(notify.notified());
+        let mut b = Box::pin(notify.notified());
+        assert!(future::poll_once(&mut a).await.is_none());
+        assert!(future::poll_once(&mut b).await.is_none());
+
+        notify.notify_one();
+        assert!(future::poll_once(&mut b).await.is_none());
+        assert!(future::poll_once(&mut a).await.is_some());
+
+        notify.notify_one();
+        assert!(future::poll_once(&mut b).await.is_some());
+        assert_eq!(
+            format!("{:?}", notify),
+            "Notify { permit: false, waiters: 0 }"
+        );
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn notify_all() {
+    future::block_on(async {
+        let notify = Notify::new();
+        let mut polled = Box::pin(notify.notified());
+        assert!(future::poll_once(&mut polled).await.is_none());
+        let unpolled = notify.notified();
+
+        notify.notify_all();
+        polled.await;
+        unpolled.await;
+
+        // No permit is stored.
+        assert!(future::poll_once(notify.notified()).await.is_none());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn notify_one_after_notify_all() {
+    future::block_on(async {
+        let notify = Notify::new();
+        let mut a = Box::pin(notify.notified());
+        let mut b = Box::pin(notify.notified());
+        assert!(future::poll_once(&mut a).await.is_none());
+        assert!(future::poll_once(&mut b).await.is_none());
+
+        // The waiters released by `notify_all()` cannot take the later notification.
+        notify.notify_all();
+        notify.notify_one();
+        a.await;
+        b.await;
+        assert!(future::poll_once(notify.notified()).await.is_some());
+
+        // A notification sent before `notify_all()` is kept as well.
+        let mut c = Box::pin(notify.notified());
+        assert!(future::poll_once(&mut c).await.is_none());
+        notify.notify_one();
+        notify.notify_all();
+        c.await;
+        assert!(future::poll_once(notify.notified()).await.is_some());
+        assert!(future::poll_once(notify.notified()).await.is_none());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn dropped_waiter_passes_on() {
+    future::block_on(async {
+        let notify = Notify::new();
+        let mut a = Box::pin(notify.notified());
+        let mut b = Box::pin(notify.notified());
+        assert!(future::poll_once(&mut a).await.is_none());
+        assert!(future::poll_once(&mut b).await.is_none());
+
+        notify.notify_one();
+        drop(a);
+        assert!(future::poll_once(&mut b).await.is_some());
+
+        // Without another waiter, the notification is stored.
+        let mut c = Box::pin(notify.notified());
+        assert!(future::poll_once(&mut c).await.is_none());
+        notify.notify_one();
+        drop(c);
+        assert!(future::poll_once(notify.notified()).await.is_some());
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn queue() {
+    let notify = Arc::new(Notify::new());
+    let items = Arc::new(AtomicUsize::new(0));
+
+    let consumer = thread::spawn({
+        let notify = notify.clone();
+        let items = items.clone();
+        move || {
+            future::block_on(async {
+                let mut taken = 0;
+                while taken < 1000 {
+                    let notified = notify.notified();
+                    let n = items.swap(0, Ordering::AcqRel);
+                    if n == 0 {
+                        notified.await;
+                    }
+                    taken += n;
+                }
+            })
+        }
+    });
+
+    for _ in 0..1000 {
+        items.fetch_add(1, Ordering::AcqRel);
+        notify.notify_one();
+    }
+    consumer.join().unwrap();
+}
@@ -0,0 +1,83 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use async_lock::Once;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn waiters_wait_for_completion() {
+    future::block_on(async {
+        let once = Once::new();
+        let calls = AtomicUsize::new(0);
+
+        let mut first = Box::pin(once.call_once(|| async {
+            future::yield_now().await;
+            calls.fetch_add(1, Ordering::SeqCst);
+        }));
+        let mut second = Box::pin(once.call_once(|| async {
+            calls.fetch_add(1, Ordering::SeqCst);
+        }));
+
+        assert!(future::poll_once(&mut first).await.is_none());
+        assert!(future::poll_once(&mut second).await.is_none());
+        assert!(!once.is_completed());
+
+        first.await;
+        second.await;
+        assert!(once.is_completed());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn cancelled_operation_is_taken_over() {
+    future::block_on(async {
+        let once = Once::new();
+
+        let mut first = Box::pin(once.call_once(future::pending));
+        let mut second = Box::pin(once.call_once(|| async {}));
+        assert!(future::poll_once(&mut first).await.is_none());
+        assert!(future::poll_once(&mut second).await.is_none());
+
+        drop(first);
+        second.await;
+        assert!(once.is_completed());
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn call_once_from_threads() {
+    let once = Arc::new(Once::new());
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let once = once.clone();
+            let calls = calls.clone();
+            thread::spawn(move || {
+                future::block_on(once.call_once(|| async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                }));
+                assert!(once.is_completed());
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,182 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use async_lock::OnceCell;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn get_or_init() {
+    future::block_on(async {
+        let cell = OnceCell::new();
+        assert_eq!(cell.get(), None);
+
+        assert_eq!(*cell.get_or_init(|| async { 1 }).await, 1);
+        assert_eq!(*cell.get_or_init(|| async { 2 }).await, 1);
+        assert_eq!(cell.get(), Some(&1));
+        assert_eq!(cell.into_inner(), Some(1));
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn waiters_use_first_value() {
+    future::block_on(async {
+        let cell = OnceCell::new();
+        let calls = AtomicUsize::new(0);
+
+        let mut first = Box::pin(cell.get_or_init(|| async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            future::yield_now().await;
+            1
+        }));
+        let mut second = Box::pin(cell.get_or_init(|| async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            2
+        }));
+
+        assert!(future::poll_once(&mut first).await.is_none());
+        assert!(future::poll_once(&mut second).await.is_none());
+        assert_eq!(*first.await, 1);
+        assert_eq!(*second.await, 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn cancelled_initialization_is_taken_over() {
+    future::block_on(async {
+        let cell = OnceCell::new();
+
+        let mut first = Box::pin(cell.get_or_init(future::pending));
+        let mut second = Box::pin(cell.get_or_init(|| async { 2 }));
+        assert!(future::poll_once(&mut first).await.is_none());
+        assert!(future::poll_once(&mut second).await.is_none());
+
+        drop(first);
+        assert_eq!(*second.await, 2);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn drops_value() {
+    let counter = std::rc::Rc::new(());
+    let cell = OnceCell::from(counter.clone());
+    assert_eq!(std::rc::Rc::strong_count(&counter), 2);
+    drop(cell);
+    assert_eq!(std::rc::Rc::strong_count(&counter), 1);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn concurrent_init() {
+    let cell = Arc::new(OnceCell::new());
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let threads = (0..8)
+        .map(|_| {
+            let cell = cell.clone();
+            let calls = calls.clone();
+            thread::spawn(move || {
+                future::block_on(async {
+                    *cell
+                        .get_or_init(|| async {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            7
+                        })
+                        .await
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for t in threads {
+        assert_eq!(t.join().unwrap(), 7);
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+#[test]
+fn get_or_init_blocking_waits_for_task() {
+    let cell = Arc::new(OnceCell::new());
+    let (tx, rx) = async_channel::bounded::<()>(1);
+
+    future::block_on(async {
+        let mut init = Box::pin(cell.get_or_init(|| async move {
+            rx.recv().await.unwrap();
+            1
+        }));
+        assert!(future::poll_once(&mut init).await.is_none());
+
+        let blocked = {
+            let cell = cell.clone();
+            thread::spawn(move || *cell.get_or_init_blocking(|| 2))
+        };
+        thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!blocked.is_finished());
+
+        tx.send(()).await.unwrap();
+        assert_eq!(*init.await, 1);
+        assert_eq!(blocked.join().unwrap(), 1);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn failed_initialization_is_retried() {
+    future::block_on(async {
+        let cell = OnceCell::new();
+
+        let mut first = Box::pin(cell.get_or_try_init(|| async {
+            future::yield_now().await;
+            Err("first failed")
+        }));
+        let mut second = Box::pin(cell.get_or_try_init(|| async { Ok::<_, &str>(2) }));
+        assert!(future::poll_once(&mut first).await.is_none());
+        assert!(future::poll_once(&mut second).await.is_none());
+
+        // The error goes to the failing caller only, and the waiting one takes over.
+        assert_eq!(first.await, Err("first failed"));
+        assert_eq!(second.await, Ok(&2));
+        assert_eq!(cell.get(), Some(&2));
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn wait_does_not_initialize() {
+    future::block_on(async {
+        let cell = OnceCell::new();
+
+        let mut passive = Box::pin(cell.wait());
+        assert!(future::poll_once(&mut passive).await.is_none());
+
+        // A failed initialization wakes the next initializer, not the passive waiter.
+        let mut first = Box::pin(cell.get_or_try_init(|| async {
+            future::yield_now().await;
+            Err(())
+        }));
+        let mut second = Box::pin(cell.get_or_try_init(|| async { Ok::<_, ()>(4) }));
+        assert!(future::poll_once(&mut first).await.is_none());
+        assert!(future::poll_once(&mut second).await.is_none());
+        assert!(first.await.is_err());
+        assert!(future::poll_once(&mut passive).await.is_none());
+
+        assert_eq!(second.await, Ok(&4));
+        assert_eq!(*passive.await, 4);
+        assert_eq!(*cell.wait().await, 4);
+    });
+}
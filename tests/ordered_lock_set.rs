@@ -0,0 +1,103 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use std::sync::Arc;
+
+use async_lock::{Mutex, OrderedLockSet, Semaphore};
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn address_order() {
+    future::block_on(async {
+        let locks: Vec<_> = (0..5).map(Mutex::new).collect();
+
+        let mut set = OrderedLockSet::new();
+        for i in [3, 1, 4, 0] {
+            assert!(set.insert(&locks[i]));
+        }
+        assert!(!set.insert(&locks[1]));
+        assert_eq!(set.len(), 4);
+        assert!(set.contains(&&locks[4]));
+        assert!(!set.contains(&&locks[2]));
+
+        let ids: Vec<_> = set.iter().map(|m| *m as *const Mutex<i32>).collect();
+        let mut sorted = ids.clone();
+        sorted.sort();
+        assert_eq!(ids, sorted);
+
+        let mut guards = set.lock_all().await;
+        assert_eq!(guards.len(), 4);
+        assert!(locks[0].try_lock().is_none());
+        assert!(locks[2].try_lock().is_some());
+        for g in guards.iter_mut() {
+            **g *= 10;
+        }
+        guards.unlock_all();
+
+        assert!(set.remove(&&locks[4]).is_some());
+        assert!(set.remove(&&locks[4]).is_none());
+        let values: Vec<_> = set.try_lock_all().unwrap().iter().map(|g| **g).collect();
+        assert_eq!(values, [0, 10, 30]);
+        assert_eq!(*locks[4].lock().await, 40);
+    })
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn owned_locks() {
+    future::block_on(async {
+        let a = Arc::new(Semaphore::new(1));
+        let b = Arc::new(Semaphore::new(1));
+        let set: OrderedLockSet<_> = vec![a.clone(), b.clone()].into_iter().collect();
+
+        let permit = b.try_acquire().unwrap();
+        assert!(set.try_lock_all().is_none());
+        assert!(a.try_acquire().is_some());
+
+        let mut all = Box::pin(set.lock_all());
+        assert!(future::poll_once(all.as_mut()).await.is_none());
+        drop(permit);
+        let guards = all.await.into_inner();
+        drop(set);
+        assert_eq!(guards.len(), 2);
+        assert!(a.try_acquire().is_none());
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn overlapping_sets() {
+    let locks: Arc<Vec<_>> = Arc::new((0..4).map(|_| Mutex::new(0u32)).collect());
+
+    let threads: Vec<_> = (0..4)
+        .map(|t| {
+            let locks = locks.clone();
+            thread::spawn(move || {
+                let mut set = OrderedLockSet::new();
+                set.insert(&locks[t]);
+                set.insert(&locks[(t + 1) % 4]);
+                future::block_on(async {
+                    for _ in 0..500 {
+                        for g in set.lock_all().await.iter_mut() {
+                            **g += 1;
+                        }
+                    }
+                })
+            })
+        })
+        .collect();
+
+    for t in threads {
+        t.join().unwrap();
+    }
+    for m in locks.iter() {
+        assert_eq!(*m.try_lock().unwrap(), 1000);
+    }
+}
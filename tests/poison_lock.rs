@@ -0,0 +1,84 @@
+#![cfg(feature = "std")]
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::panic::{self, AssertUnwindSafe};
+
+use async_lock::PoisonLock;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn smoke() {
+    future::block_on(async {
+        let lock = PoisonLock::new(1);
+        *lock.lock().await.unwrap() += 1;
+        assert!(lock.try_lock().unwrap().is_ok());
+
+        let guard = lock.lock().await.unwrap();
+        assert!(lock.try_lock().is_none());
+        drop(guard);
+
+        assert!(!lock.is_poisoned());
+        assert_eq!(lock.into_inner().unwrap(), 2);
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn panic_poisons() {
+    let lock = PoisonLock::new(vec![1]);
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        future::block_on(async {
+            let mut guard = lock.lock().await.unwrap();
+            guard.push(2);
+            panic!("interrupted");
+        })
+    }));
+    assert!(result.is_err());
+    assert!(lock.is_poisoned());
+
+    // A poisoned lock is still locked and unlocked as usual.
+    future::block_on(async {
+        let err = lock.lock().await.unwrap_err();
+        assert_eq!(*err.get_ref().as_slice(), [1, 2]);
+        let guard = err.into_inner();
+        assert!(lock.try_lock().is_none());
+        drop(guard);
+    });
+
+    lock.clear_poison();
+    assert!(lock.try_lock().unwrap().is_ok());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn panic_without_guard_does_not_poison() {
+    let lock = PoisonLock::new(0);
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        drop(lock.try_lock().unwrap().unwrap());
+        panic!("after the guard was dropped");
+    }));
+    assert!(result.is_err());
+    assert!(!lock.is_poisoned());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn poisoned_into_inner_and_get_mut() {
+    let mut lock = PoisonLock::new(5);
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+        let _guard = lock.try_lock().unwrap().unwrap();
+        panic!();
+    }));
+
+    *lock.get_mut().unwrap_err().into_inner() += 1;
+    assert_eq!(lock.into_inner().unwrap_err().into_inner(), 6);
+}
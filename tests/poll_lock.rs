@@ -0,0 +1,115 @@
+#![cfg(feature = "std")]
+
+use std::pin::Pin;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use async_lock::PollLock;
+use futures_lite::{future, Stream};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+/// Receives the next item from a shared stream.
+async fn next<S: Stream + Unpin>(lock: &PollLock<S>) -> Option<S::Item> {
+    future::poll_fn(|cx| lock.poll_with(cx, |s: Pin<&mut S>, cx| s.poll_next(cx))).await
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn every_poller_is_woken() {
+    future::block_on(async {
+        let (tx, rx) = async_channel::unbounded::<i32>();
+        let lock = PollLock::new(rx);
+
+        // Both tasks wait on the stream, but only the last one registered with the channel.
+        let mut a = Box::pin(next(&lock));
+        let mut b = Box::pin(next(&lock));
+        assert!(future::poll_once(a.as_mut()).await.is_none());
+        assert!(future::poll_once(b.as_mut()).await.is_none());
+
+        tx.send(1).await.unwrap();
+        tx.send(2).await.unwrap();
+        let (a, b) = future::zip(a, b).await;
+        assert_eq!(a.unwrap() + b.unwrap(), 3);
+    })
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn lock_excludes_pollers() {
+    future::block_on(async {
+        let lock = PollLock::new(futures_lite::stream::iter(vec![1]));
+        let guard = lock.lock().await;
+
+        let mut poller = Box::pin(next(&lock));
+        assert!(future::poll_once(poller.as_mut()).await.is_none());
+
+        drop(guard);
+        assert_eq!(poller.await, Some(1));
+        assert_eq!(next(&lock).await, None);
+    })
+}
+
+#[cfg(feature = "futures-io")]
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn io_passthrough() {
+    use futures_lite::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, Cursor, SeekFrom};
+
+    future::block_on(async {
+        let mut conn = PollLock::new(Cursor::new(Vec::new()));
+        (&conn).write_all(b"hello ").await.unwrap();
+        conn.write_all(b"world").await.unwrap();
+        (&conn).flush().await.unwrap();
+
+        // Polls wait while the I/O object is locked.
+        let mut shared = &conn;
+        let guard = conn.lock().await;
+        let mut seek = Box::pin(shared.seek(SeekFrom::Start(6)));
+        assert!(future::poll_once(seek.as_mut()).await.is_none());
+        drop(guard);
+        assert_eq!(seek.await.unwrap(), 6);
+
+        let mut buf = String::new();
+        shared.read_to_string(&mut buf).await.unwrap();
+        assert_eq!(buf, "world");
+        assert_eq!(conn.into_inner().into_inner(), b"hello world");
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn shared_stream() {
+    let (tx, rx) = async_channel::unbounded::<usize>();
+    let lock = Arc::new(PollLock::new(rx));
+    let n = 1000;
+
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                future::block_on(async {
+                    let mut sum = 0;
+                    while let Some(i) = next(&*lock).await {
+                        sum += i;
+                    }
+                    sum
+                })
+            })
+        })
+        .collect();
+
+    for i in 0..n {
+        tx.send_blocking(i).unwrap();
+    }
+    drop(tx);
+
+    let total: usize = workers.into_iter().map(|w| w.join().unwrap()).sum();
+    assert_eq!(total, n * (n - 1) / 2);
+}
@@ -0,0 +1,140 @@
+#![cfg(feature = "std")]
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_lock::{Pool, PoolGuard, PoolManager};
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+/// Numbers its objects, and fails or discards objects on request.
+#[derive(Default)]
+struct Counter {
+    created: AtomicUsize,
+    fail: AtomicUsize,
+    discard: AtomicUsize,
+}
+
+impl PoolManager for Counter {
+    type Object = usize;
+    type Error = &'static str;
+
+    async fn create(&self) -> Result<usize, &'static str> {
+        if self.fail.load(Ordering::SeqCst) == 1 {
+            return Err("failed");
+        }
+        Ok(self.created.fetch_add(1, Ordering::SeqCst))
+    }
+
+    async fn recycle(&self, object: &mut usize) -> bool {
+        *object != self.discard.load(Ordering::SeqCst)
+    }
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn reuses_objects() {
+    future::block_on(async {
+        let pool = Pool::new(Counter::default(), 2);
+        pool.manager().discard.store(usize::MAX, Ordering::SeqCst);
+
+        let a = pool.get().await.unwrap();
+        let b = pool.get().await.unwrap();
+        assert_eq!((*a, *b), (0, 1));
+        drop(a);
+        assert_eq!(pool.idle(), 1);
+        assert_eq!(format!("{:?}", pool), "Pool { capacity: 2, idle: 1 }");
+
+        assert_eq!(*pool.get().await.unwrap(), 0);
+        assert_eq!(pool.manager().created.load(Ordering::SeqCst), 2);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn waits_for_capacity() {
+    future::block_on(async {
+        let pool = Pool::new(Counter::default(), 1);
+        pool.manager().discard.store(usize::MAX, Ordering::SeqCst);
+        let a = pool.get().await.unwrap();
+
+        let mut get = Box::pin(pool.get());
+        assert!(future::poll_once(&mut get).await.is_none());
+
+        drop(a);
+        assert_eq!(*future::poll_once(&mut get).await.unwrap().unwrap(), 0);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn discards_rejected_objects() {
+    future::block_on(async {
+        let pool = Pool::new(Counter::default(), 2);
+        let (a, b) = (pool.get().await.unwrap(), pool.get().await.unwrap());
+        drop(b);
+        drop(a);
+        assert_eq!(pool.idle(), 2);
+
+        // Object 0 is rejected, so object 1 is used instead.
+        let c = pool.get().await.unwrap();
+        assert_eq!(*c, 1);
+        assert_eq!(pool.idle(), 0);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn errors_and_detach() {
+    future::block_on(async {
+        let pool = Pool::new(Counter::default(), 1);
+        pool.manager().fail.store(1, Ordering::SeqCst);
+        assert_eq!(pool.get().await.unwrap_err(), "failed");
+
+        // The failed checkout gave its place back.
+        pool.manager().fail.store(0, Ordering::SeqCst);
+        let n = PoolGuard::detach(pool.get().await.unwrap());
+        assert_eq!(n, 0);
+        assert_eq!(pool.idle(), 0);
+        assert_eq!(*pool.get().await.unwrap(), 1);
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn contention() {
+    const THREADS: usize = 8;
+
+    let pool = Arc::new(Pool::new(Counter::default(), 2));
+    pool.manager().discard.store(usize::MAX, Ordering::SeqCst);
+    let in_use = Arc::new(AtomicUsize::new(0));
+
+    let threads = (0..THREADS)
+        .map(|_| {
+            let (pool, in_use) = (pool.clone(), in_use.clone());
+            thread::spawn(move || {
+                future::block_on(async {
+                    for _ in 0..100 {
+                        let _object = pool.get().await.unwrap();
+                        assert!(in_use.fetch_add(1, Ordering::SeqCst) < 2);
+                        in_use.fetch_sub(1, Ordering::SeqCst);
+                    }
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert!(pool.manager().created.load(Ordering::SeqCst) <= 2);
+}
@@ -0,0 +1,126 @@
+#![cfg(feature = "priority-ceiling")]
+
+use std::cell::{Cell, RefCell};
+
+use async_lock::priority::{self, CeilingHooks};
+use async_lock::{Mutex, MutexGuard, RwLock, Semaphore};
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+/// Serializes the tests, which install process-wide hooks.
+static SERIAL: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+std::thread_local! {
+    static PRIORITY: Cell<u32> = const { Cell::new(1) };
+    static CALLS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+static HOOKS: CeilingHooks = CeilingHooks {
+    raise: |ceiling| {
+        CALLS.with(|c| c.borrow_mut().push(format!("raise {}", ceiling)));
+        PRIORITY.with(|p| p.replace(p.get().max(ceiling)))
+    },
+    restore: |previous| {
+        CALLS.with(|c| c.borrow_mut().push(format!("restore {}", previous)));
+        PRIORITY.with(|p| p.set(previous))
+    },
+};
+
+fn priority() -> u32 {
+    PRIORITY.with(Cell::get)
+}
+
+fn calls() -> Vec<String> {
+    CALLS.with(|c| c.borrow_mut().drain(..).collect())
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn ceilings() {
+    let _serial = SERIAL.lock().unwrap();
+    priority::set_hooks(&HOOKS);
+
+    future::block_on(async {
+        let m = Mutex::with_priority_ceiling(0, 5);
+        let rw = RwLock::with_priority_ceiling(0, 7);
+        let s = Semaphore::with_priority_ceiling(2, 3);
+        let plain = Mutex::new(0);
+
+        let g = m.lock().await;
+        assert_eq!(priority(), 5);
+
+        // Nested locks restore priorities in reverse order.
+        let r1 = rw.read().await;
+        let r2 = rw.read().await;
+        assert_eq!(priority(), 7);
+        drop(r2);
+        drop(r1);
+        assert_eq!(priority(), 5);
+
+        let p = s.acquire().await;
+        assert_eq!(priority(), 5);
+        drop(p);
+        drop(g);
+        assert_eq!(priority(), 1);
+
+        drop(plain.lock().await);
+        // A canceled acquisition does not touch the priority.
+        let w = rw.write().await;
+        drop(rw.try_read());
+        drop(w);
+    });
+
+    assert_eq!(
+        calls(),
+        [
+            "raise 5",
+            "raise 7",
+            "raise 7",
+            "restore 7",
+            "restore 5",
+            "raise 3",
+            "restore 5",
+            "restore 1",
+            "raise 7",
+            "restore 1",
+        ]
+    );
+
+    priority::clear_hooks();
+    drop(Mutex::with_priority_ceiling((), 9).try_lock());
+    assert!(calls().is_empty());
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn raw_guards_restore_priority() {
+    let _serial = SERIAL.lock().unwrap();
+    priority::set_hooks(&HOOKS);
+
+    let m = Mutex::with_priority_ceiling(0, 5);
+    let raw = MutexGuard::into_raw(m.try_lock().unwrap());
+    assert_eq!(priority(), 5);
+    drop(unsafe { MutexGuard::from_raw(raw) });
+    assert_eq!(priority(), 1);
+
+    MutexGuard::into_raw(m.try_lock().unwrap());
+    unsafe { m.force_unlock() };
+    assert_eq!(priority(), 1);
+
+    // A forgotten guard left nothing to restore.
+    std::mem::forget(m.try_lock().unwrap());
+    unsafe { m.force_unlock() };
+    assert_eq!(priority(), 5);
+
+    assert_eq!(
+        calls(),
+        ["raise 5", "restore 1", "raise 5", "restore 1", "raise 5"]
+    );
+    PRIORITY.with(|p| p.set(1));
+    priority::clear_hooks();
+}
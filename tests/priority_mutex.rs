@@ -0,0 +1,62 @@
+#![cfg(feature = "std")]
+
+use async_lock::PriorityMutex;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn highest_priority_first() {
+    future::block_on(async {
+        let mutex = PriorityMutex::new(Vec::new());
+        let guard = mutex.try_lock().unwrap();
+
+        let mut low = Box::pin(mutex.lock_with_priority(1));
+        let mut high = Box::pin(mutex.lock_with_priority(5));
+        assert!(future::poll_once(&mut low).await.is_none());
+        assert!(future::poll_once(&mut high).await.is_none());
+        assert_eq!(mutex.waiters(), 2);
+
+        // The mutex is handed over, so nobody can barge in.
+        drop(guard);
+        assert!(mutex.try_lock().is_none());
+        assert!(future::poll_once(&mut low).await.is_none());
+        future::poll_once(&mut high).await.unwrap().push(5);
+        low.await.push(1);
+
+        assert_eq!(*mutex.try_lock().unwrap(), [5, 1]);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn cancelled_waiter_passes_mutex_on() {
+    future::block_on(async {
+        let mutex = PriorityMutex::new(0);
+        let guard = mutex.try_lock().unwrap();
+
+        let mut high = Box::pin(mutex.lock_with_priority(5));
+        let mut low = Box::pin(mutex.lock_with_priority(1));
+        assert!(future::poll_once(&mut high).await.is_none());
+        assert!(future::poll_once(&mut low).await.is_none());
+
+        drop(guard);
+        drop(high);
+        *low.await += 1;
+        assert_eq!(*mutex.try_lock().unwrap(), 1);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn debug() {
+    let mutex = PriorityMutex::new(1);
+    assert_eq!(format!("{:?}", mutex), "PriorityMutex { data: 1 }");
+    let _guard = mutex.try_lock().unwrap();
+    assert_eq!(format!("{:?}", mutex), "PriorityMutex { data: <locked> }");
+}
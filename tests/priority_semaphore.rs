@@ -0,0 +1,121 @@
+#![cfg(feature = "std")]
+
+use async_lock::PrioritySemaphore;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn highest_priority_first() {
+    future::block_on(async {
+        let s = PrioritySemaphore::new(1);
+        let guard = s.try_acquire().unwrap();
+
+        let mut low = Box::pin(s.acquire_with_priority(1));
+        let mut high = Box::pin(s.acquire_with_priority(5));
+        let mut high_later = Box::pin(s.acquire_with_priority(5));
+        assert!(future::poll_once(&mut low).await.is_none());
+        assert!(future::poll_once(&mut high).await.is_none());
+        assert!(future::poll_once(&mut high_later).await.is_none());
+        assert_eq!(s.waiters(), 3);
+
+        // Freed permits are handed over, so nobody can barge in.
+        drop(guard);
+        assert!(s.try_acquire().is_none());
+        assert!(future::poll_once(&mut low).await.is_none());
+        assert!(future::poll_once(&mut high_later).await.is_none());
+        let guard = future::poll_once(&mut high).await.unwrap();
+
+        drop(guard);
+        let guard = future::poll_once(&mut high_later).await.unwrap();
+        drop(guard);
+        let guard = future::poll_once(&mut low).await.unwrap();
+        drop(guard);
+
+        assert_eq!(s.waiters(), 0);
+        assert!(s.try_acquire().is_some());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn cancelled_waiter_passes_permit_on() {
+    future::block_on(async {
+        let s = PrioritySemaphore::new(1);
+        let guard = s.try_acquire().unwrap();
+
+        let mut first = Box::pin(s.acquire_with_priority(2));
+        let mut second = Box::pin(s.acquire_with_priority(1));
+        assert!(future::poll_once(&mut first).await.is_none());
+        assert!(future::poll_once(&mut second).await.is_none());
+
+        // The permit is handed to `first`, which is cancelled before taking it.
+        drop(guard);
+        drop(first);
+        assert!(future::poll_once(&mut second).await.is_some());
+        assert!(s.try_acquire().is_some());
+
+        // Cancelling a waiter that was never granted a permit just leaves the queue.
+        let guard = s.try_acquire().unwrap();
+        let mut waiter = Box::pin(s.acquire_with_priority(0));
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        drop(waiter);
+        assert_eq!(s.waiters(), 0);
+        drop(guard);
+        assert!(s.try_acquire().is_some());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn aging() {
+    future::block_on(async {
+        let s = PrioritySemaphore::with_aging(1, 3);
+        let mut guard = s.try_acquire().unwrap();
+
+        let mut low = Box::pin(s.acquire_with_priority(0));
+        assert!(future::poll_once(&mut low).await.is_none());
+
+        // A steady stream of higher-priority operations only delays it by a bounded amount.
+        let mut overtaken = 0;
+        loop {
+            let mut high = Box::pin(s.acquire_with_priority(1));
+            assert!(future::poll_once(&mut high).await.is_none());
+            drop(guard);
+
+            if let Some(g) = future::poll_once(&mut low).await {
+                drop(high);
+                drop(g);
+                break;
+            }
+            guard = future::poll_once(&mut high).await.unwrap();
+            overtaken += 1;
+        }
+        assert_eq!(overtaken, 3);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn no_aging_starves() {
+    future::block_on(async {
+        let s = PrioritySemaphore::new(1);
+        let mut guard = s.try_acquire().unwrap();
+
+        let mut low = Box::pin(s.acquire_with_priority(0));
+        assert!(future::poll_once(&mut low).await.is_none());
+
+        for _ in 0..10 {
+            let mut high = Box::pin(s.acquire_with_priority(1));
+            assert!(future::poll_once(&mut high).await.is_none());
+            drop(guard);
+            assert!(future::poll_once(&mut low).await.is_none());
+            guard = future::poll_once(&mut high).await.unwrap();
+        }
+    });
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "std")]
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+use std::time::Duration;
+
+use async_lock::RateLimiter;
+use futures_lite::future;
+
+mod common;
+use common::Never;
+#[cfg(not(target_arch = "wasm32"))]
+use common::ThreadTimer;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn starts_full() {
+    let limiter = RateLimiter::new(Never, 3, Duration::from_secs(3600));
+    assert_eq!(limiter.capacity(), 3);
+    assert_eq!(limiter.available(), 3);
+
+    assert!(limiter.try_acquire(2));
+    assert!(!limiter.try_acquire(2));
+    assert!(limiter.try_acquire(1));
+    assert_eq!(limiter.available(), 0);
+    assert!(!limiter.try_acquire(1));
+    assert!(limiter.try_acquire(0));
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn acquire_waits_for_tokens() {
+    future::block_on(async {
+        let limiter = RateLimiter::new(Never, 2, Duration::from_secs(3600));
+        limiter.acquire(2).await;
+
+        let mut acquire = Box::pin(limiter.acquire(1));
+        assert!(future::poll_once(&mut acquire).await.is_none());
+
+        // The waiting operation is served first.
+        assert!(!limiter.try_acquire(0));
+        drop(acquire);
+        assert!(limiter.try_acquire(0));
+        assert_eq!(limiter.available(), 0);
+    });
+}
+
+#[test]
+#[should_panic = "acquired more tokens than the `RateLimiter` holds"]
+fn acquire_more_than_capacity() {
+    let limiter = RateLimiter::new(Never, 2, Duration::from_secs(1));
+    future::block_on(limiter.acquire(3));
+}
+
+#[test]
+#[should_panic = "`interval` must not be zero"]
+fn zero_interval() {
+    RateLimiter::new(Never, 1, Duration::ZERO);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn refills_over_time() {
+    let interval = Duration::from_millis(20);
+    let limiter = RateLimiter::new(ThreadTimer, 2, interval);
+
+    future::block_on(async {
+        limiter.acquire(2).await;
+
+        let start = std::time::Instant::now();
+        limiter.acquire(2).await;
+        assert!(start.elapsed() >= interval * 2);
+    });
+
+    thread::sleep(interval * 5);
+    assert_eq!(limiter.available(), 2);
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn debug() {
+    let limiter = RateLimiter::new(Never, 2, Duration::from_secs(1));
+    assert_eq!(
+        format!("{:?}", limiter),
+        "RateLimiter { capacity: 2, interval: 1s, available: 2 }"
+    );
+}
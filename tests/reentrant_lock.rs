@@ -0,0 +1,131 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use std::cell::Cell;
+
+use async_lock::{LockOwner, ReentrantLock};
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn reentry() {
+    future::block_on(async {
+        let lock = ReentrantLock::new(Cell::new(0));
+        let owner = LockOwner::new();
+
+        let outer = lock.lock(&owner).await;
+        let inner = lock.lock(&owner).await;
+        inner.set(inner.get() + 1);
+        assert_eq!(outer.get(), 1);
+        assert!(lock.is_held_by(&owner));
+
+        // Other owners wait until every guard of the owner is dropped.
+        let other = LockOwner::new();
+        let mut waiter = Box::pin(lock.lock(&other));
+        assert!(future::poll_once(waiter.as_mut()).await.is_none());
+        drop(outer);
+        assert!(future::poll_once(waiter.as_mut()).await.is_none());
+        drop(inner);
+        assert!(!lock.is_held_by(&owner));
+
+        let guard = waiter.await;
+        assert!(lock.is_held_by(&other));
+        assert!(lock.try_lock(&owner).is_none());
+        drop(guard);
+        assert!(lock.try_lock(&owner).is_some());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn callback() {
+    fn visit(lock: &ReentrantLock<Cell<u32>>, owner: &LockOwner, depth: u32) {
+        let guard = lock.try_lock(owner).unwrap();
+        guard.set(guard.get() + 1);
+        if depth > 0 {
+            visit(lock, owner, depth - 1);
+        }
+    }
+
+    let lock = ReentrantLock::new(Cell::new(0));
+    visit(&lock, &LockOwner::new(), 9);
+    assert_eq!(lock.into_inner().get(), 10);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn contention() {
+    let lock = Arc::new(ReentrantLock::new(std::sync::Mutex::new(0)));
+
+    let handles = (0..4)
+        .map(|_| {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                future::block_on(async {
+                    let owner = LockOwner::new();
+                    for _ in 0..100 {
+                        let outer = lock.lock(&owner).await;
+                        let inner = lock.lock(&owner).await;
+                        *inner.lock().unwrap() += 1;
+                        drop(outer);
+                        *inner.lock().unwrap() += 1;
+                    }
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(
+        *lock.try_lock(&LockOwner::new()).unwrap().lock().unwrap(),
+        800
+    );
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn owner_shared_between_threads() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Two owners, each used from two threads, that must never hold the lock at the same time.
+    let lock = Arc::new(ReentrantLock::new(()));
+    let holding = Arc::new([AtomicUsize::new(0), AtomicUsize::new(0)]);
+    let owners = [Arc::new(LockOwner::new()), Arc::new(LockOwner::new())];
+
+    let handles = (0..4)
+        .map(|i| {
+            let lock = lock.clone();
+            let holding = holding.clone();
+            let owner = owners[i % 2].clone();
+            thread::spawn(move || {
+                for _ in 0..2_000_000 {
+                    if let Some(guard) = lock.try_lock(&owner) {
+                        holding[i % 2].fetch_add(1, Ordering::SeqCst);
+                        for _ in 0..10 {
+                            assert_eq!(holding[1 - i % 2].load(Ordering::SeqCst), 0);
+                        }
+                        holding[i % 2].fetch_sub(1, Ordering::SeqCst);
+                        drop(guard);
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let owner = LockOwner::new();
+    drop(lock.try_lock(&owner).unwrap());
+    assert!(lock.try_lock(&LockOwner::new()).is_some());
+}
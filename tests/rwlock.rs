@@ -7,10 +7,15 @@ use futures_lite::prelude::*;
 use std::future::Future;
 #[cfg(not(target_arch = "wasm32"))]
 use std::thread;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use std::time::Duration;
 
 use futures_lite::future;
 
-use async_lock::{RwLock, RwLockUpgradableReadGuard};
+use async_lock::{RwLock, RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard};
+
+mod common;
+use common::Expired;
 
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen_test::*;
@@ -41,6 +46,27 @@ fn smoke() {
     });
 }
 
+#[cfg(feature = "stream")]
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn stream_items() {
+    use futures_lite::StreamExt;
+
+    future::block_on(async {
+        let lock = RwLock::new([1, 2, 3]);
+        let mut items = Box::pin(lock.stream_items(1));
+        assert_eq!(items.next().await, Some(1));
+
+        // Readers do not block each other, but writers can get in between items.
+        let reader = lock.read().await;
+        assert_eq!(items.next().await, Some(2));
+        drop(reader);
+        lock.write().await[2] = 30;
+        assert_eq!(items.next().await, Some(30));
+        assert_eq!(items.next().await, None);
+    })
+}
+
 #[test]
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
 fn try_write() {
@@ -90,8 +116,26 @@ fn get_mut() {
     assert_eq!(lock.into_inner(), 20);
 }
 
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn unsized_values() {
+    future::block_on(async {
+        let lock: Arc<RwLock<dyn Fn() -> i32 + Send + Sync>> = Arc::new(RwLock::new(|| 1));
+        assert_eq!((lock.read().await)(), 1);
+        assert_eq!((lock.read_arc().await)(), 1);
+        assert_eq!((lock.write_arc().await)(), 1);
+
+        let buf: &RwLock<[u8]> = &RwLock::new([0; 3]);
+        buf.write().await[0] = 1;
+        let upgradable = buf.upgradable_read().await;
+        RwLockUpgradableReadGuard::upgrade(upgradable).await[1] = 2;
+        assert_eq!(*buf.try_read().unwrap(), [1, 2, 0]);
+    });
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[test]
+#[allow(unused_must_use)]
 fn contention() {
     const N: u32 = 10;
     const M: usize = 1000;
@@ -126,6 +170,7 @@ fn contention() {
 
 #[cfg(not(target_arch = "wasm32"))]
 #[test]
+#[allow(unused_must_use)]
 fn writer_and_readers() {
     let lock = Arc::new(RwLock::new(0i32));
     let (tx, rx) = async_channel::unbounded();
@@ -170,6 +215,178 @@ fn writer_and_readers() {
     });
 }
 
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+#[test]
+fn blocking_and_async() {
+    let lock = Arc::new(RwLock::new(0i32));
+    let reader = lock.upgradable_read_blocking();
+
+    // A blocking writer and async readers wait on the same lock.
+    let writer = thread::spawn({
+        let lock = lock.clone();
+        move || {
+            for _ in 0..100 {
+                *lock.write_blocking() += 1;
+            }
+        }
+    });
+    let readers = spawn({
+        let lock = lock.clone();
+        async move {
+            for _ in 0..100 {
+                assert!(*lock.read().await >= 0);
+            }
+        }
+    });
+
+    drop(reader);
+    future::block_on(readers);
+    writer.join().unwrap();
+    assert_eq!(*lock.read_blocking(), 100);
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn timeouts() {
+    future::block_on(async {
+        let lock = RwLock::new(0);
+        let zero = std::time::Duration::ZERO;
+
+        let writer = lock.write().await;
+        assert!(lock.read_timeout(Expired, zero).await.is_none());
+        assert!(lock.write_timeout(Expired, zero).await.is_none());
+        drop(writer);
+
+        // An available lock is acquired even if the timeout has elapsed.
+        let reader = lock.read_timeout(Expired, zero).await.unwrap();
+        assert!(lock.write_timeout(Expired, zero).await.is_none());
+
+        // The writer that gave up does not block readers.
+        assert!(lock.try_read().is_some());
+        drop(reader);
+        *lock.write_timeout(Expired, zero).await.unwrap() += 1;
+        assert_eq!(*lock.try_read().unwrap(), 1);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn data_ptr() {
+    let lock = RwLock::new(1);
+    let mut writer = lock.try_write().unwrap();
+    unsafe { *lock.data_ptr() += 1 };
+    assert_eq!(*writer, 2);
+    assert_eq!(lock.data_ptr(), &mut *writer as *mut i32);
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn leak() {
+    let lock = RwLock::new(1);
+    let value = RwLockReadGuard::leak(lock.try_read().unwrap());
+    assert_eq!(*value, 1);
+    assert_eq!(*lock.try_read().unwrap(), 1);
+    assert!(lock.try_write().is_none());
+
+    let lock = RwLock::new(1);
+    let value = RwLockWriteGuard::leak(lock.try_write().unwrap());
+    *value = 2;
+    assert!(lock.try_read().is_none());
+    assert!(lock.is_write_locked());
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn force_unlock() {
+    future::block_on(async {
+        let lock = RwLock::new(0);
+        std::mem::forget(lock.read().await);
+        std::mem::forget(lock.read().await);
+
+        let mut write = Box::pin(lock.write());
+        assert!(future::poll_once(&mut write).await.is_none());
+
+        unsafe { lock.force_unlock_read() };
+        assert!(future::poll_once(&mut write).await.is_none());
+        unsafe { lock.force_unlock_read() };
+        std::mem::forget(write.await);
+
+        assert!(lock.try_read().is_none());
+        unsafe { lock.force_unlock_write() };
+        assert_eq!(*lock.try_read().unwrap(), 0);
+    });
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+#[test]
+fn blocking_timeout_and_arc() {
+    let lock = Arc::new(RwLock::new(0));
+    let writer = lock.write_arc_blocking();
+    assert!(lock.try_read_for(Duration::from_millis(10)).is_none());
+    assert!(lock.try_write_for(Duration::from_millis(10)).is_none());
+
+    // A blocked thread takes the lock once the writer releases it.
+    let blocked = thread::spawn({
+        let lock = lock.clone();
+        move || *lock.try_write_for(Duration::from_secs(10)).unwrap() += 1
+    });
+    thread::sleep(Duration::from_millis(20));
+    drop(writer);
+    blocked.join().unwrap();
+
+    let reader = lock.read_arc_blocking();
+    assert_eq!(*reader, 1);
+    assert!(lock.try_read_for(Duration::from_millis(10)).is_some());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn serde() {
+    let lock: RwLock<Vec<u32>> = serde_json::from_str("[1,2]").unwrap();
+    let reader = lock.try_read().unwrap();
+    assert_eq!(serde_json::to_string(&lock).unwrap(), "[1,2]");
+
+    drop(reader);
+    let _writer = lock.try_write().unwrap();
+    let err = serde_json::to_string(&lock).unwrap_err();
+    assert_eq!(err.to_string(), "the lock is locked for writing");
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn replace_take_swap() {
+    future::block_on(async {
+        let a = RwLock::new(vec![1]);
+        let b = RwLock::new(vec![2]);
+
+        assert_eq!(a.replace(vec![3]).await, [1]);
+        a.swap(&b).await;
+        a.swap(&a).await;
+        assert_eq!(a.take().await, [2]);
+        assert_eq!(b.take().await, [3]);
+        assert!(a.try_read().unwrap().is_empty());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn with_read_and_write() {
+    future::block_on(async {
+        let lock = RwLock::new(vec![1]);
+        let reader = lock.read().await;
+
+        // Readers run alongside the reader, the writer waits for it.
+        assert_eq!(lock.with_read(|v| v.len()).await, 1);
+        let mut push = Box::pin(lock.with_write(|v| v.push(2)));
+        assert!(future::poll_once(push.as_mut()).await.is_none());
+
+        drop(reader);
+        push.await;
+        assert_eq!(*lock.try_read().unwrap(), [1, 2]);
+    });
+}
+
 #[test]
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
 fn upgrade() {
@@ -261,3 +478,136 @@ fn upgradable_with_concurrent_writer() {
         assert_eq!(2, *read_guard);
     });
 }
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn map() {
+    future::block_on(async {
+        let lock = RwLock::new((vec![1, 2], None::<i32>));
+
+        let items = RwLockReadGuard::map(lock.read().await, |s| &s.0[..]);
+        assert_eq!(*items, [1, 2]);
+        // Mapped guards keep the lock held.
+        assert!(lock.try_write().is_none());
+        assert!(lock.try_read().is_some());
+        drop(items);
+
+        let mut items = RwLockWriteGuard::map(lock.write().await, |s| &mut s.0);
+        items.push(3);
+        assert!(lock.try_read().is_none());
+        drop(items);
+        assert_eq!(lock.read().await.0, [1, 2, 3]);
+
+        let mut guard =
+            RwLockWriteGuard::try_map(lock.write().await, |s| s.1.as_mut()).unwrap_err();
+        guard.1.replace(7);
+        drop(guard);
+        let mut n = RwLockWriteGuard::try_map(lock.write().await, |s| s.1.as_mut()).unwrap();
+        *n += 1;
+        drop(n);
+
+        let n = RwLockReadGuard::try_map(lock.read().await, |s| s.1.as_ref()).unwrap();
+        assert_eq!(format!("{:?} {}", n, n), "8 8");
+        drop(n);
+        assert!(lock.try_write().is_some());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn introspection() {
+    future::block_on(async {
+        let lock = RwLock::new(());
+        assert_eq!(lock.reader_count(), 0);
+        assert!(!lock.is_locked());
+        assert!(!lock.is_write_locked());
+
+        let r1 = lock.read().await;
+        let r2 = lock.upgradable_read().await;
+        assert_eq!(lock.reader_count(), 2);
+        assert!(lock.is_locked());
+        assert!(!lock.is_write_locked());
+
+        // A writer waiting for readers keeps new readers out, but does not hold the lock yet.
+        let mut upgrade = Box::pin(RwLockUpgradableReadGuard::upgrade(r2));
+        assert!(future::poll_once(&mut upgrade).await.is_none());
+        assert_eq!(lock.reader_count(), 1);
+        assert!(lock.try_read().is_none());
+        assert!(!lock.is_write_locked());
+
+        drop(r1);
+        let guard = future::poll_once(&mut upgrade).await.unwrap();
+        assert_eq!(lock.reader_count(), 0);
+        assert!(lock.is_write_locked());
+
+        drop(guard);
+        assert!(!lock.is_locked());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn arc_guards() {
+    future::block_on(async {
+        let lock = Arc::new(RwLock::new(0));
+
+        let r1 = lock.read_arc().await;
+        let r2 = lock.try_read_arc().unwrap();
+        assert!(lock.try_write_arc().is_none());
+        drop((r1, r2));
+
+        let mut writer = lock.write_arc().await;
+        *writer += 1;
+        assert!(lock.try_read_arc().is_none());
+        drop(writer);
+
+        assert_eq!(*lock.try_read_arc().unwrap(), 1);
+        assert!(!lock.is_locked());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn clone_read_guards() {
+    future::block_on(async {
+        let lock = Arc::new(RwLock::new(0));
+
+        let reader = lock.read().await;
+        let mut write = Box::pin(lock.write());
+        assert!(future::poll_once(&mut write).await.is_none());
+
+        // Cloning does not wait behind the writer.
+        let copy = reader.clone();
+        assert_eq!(lock.reader_count(), 2);
+        drop(reader);
+        assert!(future::poll_once(&mut write).await.is_none());
+        drop(copy);
+        drop(write.await);
+
+        let reader = lock.read_arc().await;
+        let copies = vec![reader.clone(), reader.clone()];
+        drop(reader);
+        assert!(lock.try_write().is_none());
+        drop(copies);
+        assert!(!lock.is_locked());
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn arc_guards_move_to_threads() {
+    future::block_on(async {
+        let lock = Arc::new(RwLock::new(0));
+
+        let mut writer = lock.write_arc().await;
+        let done = spawn(async move {
+            *writer += 1;
+        });
+        done.await;
+
+        let reader = lock.read_arc().await;
+        let value = spawn(async move { *reader }).await;
+        assert_eq!(value, 1);
+        assert!(lock.try_write().is_some());
+    });
+}
@@ -1,9 +1,17 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{mpsc, Arc};
+use std::task::{Context, Poll, Wake, Waker};
 use std::thread;
+use std::time::Duration;
 
 use async_lock::Semaphore;
 use futures_lite::future;
 
+mod common;
+use common::ThreadTimer;
+
 #[test]
 fn try_acquire() {
     let s = Semaphore::new(2);
@@ -81,3 +89,337 @@ fn multi_resource() {
         rx1.recv().unwrap();
     });
 }
+
+#[test]
+fn acquire_timeout() {
+    future::block_on(async {
+        let s = Semaphore::new(1);
+        let g = s.acquire().await;
+        assert!(s
+            .acquire_timeout(ThreadTimer, Duration::from_millis(10))
+            .await
+            .is_none());
+
+        drop(g);
+        assert!(s
+            .acquire_timeout(ThreadTimer, Duration::from_secs(10))
+            .await
+            .is_some());
+    });
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+#[test]
+fn blocking_and_async() {
+    let s = Arc::new(Semaphore::new(1));
+    let g = s.acquire_arc_blocking();
+
+    let threads: Vec<_> = (0..4)
+        .map(|i| {
+            let s = s.clone();
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    if i % 2 == 0 {
+                        drop(s.acquire_blocking());
+                    } else {
+                        future::block_on(async { drop(s.acquire().await) });
+                    }
+                }
+            })
+        })
+        .collect();
+
+    drop(g);
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert!(s.try_acquire().is_some());
+}
+
+/// Counts how often it was woken.
+struct CountWaker(AtomicUsize);
+
+impl Wake for CountWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Polls `f` once with a waker that counts its wakeups.
+fn poll_counted<F: Future + Unpin>(f: &mut F) -> (Poll<F::Output>, Arc<CountWaker>) {
+    let count = Arc::new(CountWaker(AtomicUsize::new(0)));
+    let waker = Waker::from(count.clone());
+    (Pin::new(f).poll(&mut Context::from_waker(&waker)), count)
+}
+
+#[test]
+fn cancelled_acquire_forwards_wakeup() {
+    let s = Semaphore::new(1);
+    let guard = s.try_acquire().unwrap();
+
+    let mut first = Box::pin(s.acquire());
+    let mut second = Box::pin(s.acquire());
+    let (poll, first_wakes) = poll_counted(&mut first);
+    assert!(poll.is_pending());
+    let (poll, second_wakes) = poll_counted(&mut second);
+    assert!(poll.is_pending());
+
+    drop(guard);
+    assert_eq!(first_wakes.0.load(Ordering::SeqCst), 1);
+    assert_eq!(second_wakes.0.load(Ordering::SeqCst), 0);
+
+    // The woken operation is cancelled, so the wakeup goes to the next one.
+    drop(first);
+    assert_eq!(second_wakes.0.load(Ordering::SeqCst), 1);
+    assert!(poll_counted(&mut second).0.is_ready());
+
+    // No permit was leaked.
+    assert!(s.try_acquire().is_some());
+}
+
+#[test]
+fn cancelled_acquire_keeps_permits() {
+    let s = Semaphore::new(2);
+    let g1 = s.try_acquire().unwrap();
+    let g2 = s.try_acquire().unwrap();
+
+    for _ in 0..10 {
+        let mut waiter = Box::pin(s.acquire());
+        assert!(poll_counted(&mut waiter).0.is_pending());
+        drop(waiter);
+    }
+
+    drop(g1);
+    drop(g2);
+    let _g1 = s.try_acquire().unwrap();
+    let _g2 = s.try_acquire().unwrap();
+    assert!(s.try_acquire().is_none());
+}
+
+#[test]
+fn releases_wake_one_waiter_each() {
+    let s = Semaphore::new(2);
+    let g1 = s.try_acquire().unwrap();
+    let g2 = s.try_acquire().unwrap();
+
+    let mut first = Box::pin(s.acquire());
+    let mut second = Box::pin(s.acquire());
+    let (poll, first_wakes) = poll_counted(&mut first);
+    assert!(poll.is_pending());
+    let (poll, second_wakes) = poll_counted(&mut second);
+    assert!(poll.is_pending());
+
+    // Two permits are released before either waiter runs, so both must be woken.
+    drop(g1);
+    drop(g2);
+    assert_eq!(first_wakes.0.load(Ordering::SeqCst), 1);
+    assert_eq!(second_wakes.0.load(Ordering::SeqCst), 1);
+    assert!(poll_counted(&mut first).0.is_ready());
+    assert!(poll_counted(&mut second).0.is_ready());
+}
+
+#[test]
+fn cancelled_timeout_keeps_permits() {
+    future::block_on(async {
+        let s = Semaphore::new(1);
+        let guard = s.acquire().await;
+
+        let timeout = s.acquire_timeout(ThreadTimer, Duration::from_millis(10));
+        assert!(timeout.await.is_none());
+
+        drop(guard);
+        assert!(s.try_acquire().is_some());
+    });
+}
+
+#[test]
+fn acquire_many() {
+    future::block_on(async {
+        let s = Semaphore::new(4);
+        let g1 = s.acquire_many(3).await;
+        assert!(s.try_acquire_many(2).is_none());
+        assert!(s.try_acquire().is_some());
+
+        let mut g2 = Box::pin(s.acquire_many(4));
+        assert!(future::poll_once(&mut g2).await.is_none());
+        drop(g1);
+        let g2 = future::poll_once(&mut g2).await.unwrap();
+        assert!(s.try_acquire().is_none());
+        drop(g2);
+
+        assert!(s.try_acquire_many(4).is_some());
+        assert!(s.try_acquire_many(5).is_none());
+        assert!(s.try_acquire_many(0).is_some());
+    });
+}
+
+#[test]
+fn acquire_many_not_starved() {
+    future::block_on(async {
+        let s = Semaphore::new(2);
+        let small = s.acquire().await;
+
+        // The large operation keeps the permit it collected, so small operations cannot take
+        // both permits in turns.
+        let mut large = Box::pin(s.acquire_many(2));
+        assert!(future::poll_once(&mut large).await.is_none());
+        assert!(s.try_acquire().is_none());
+
+        drop(small);
+        let small = s.try_acquire().unwrap();
+        assert!(future::poll_once(&mut large).await.is_none());
+        assert!(s.try_acquire().is_none());
+
+        drop(small);
+        assert!(future::poll_once(&mut large).await.is_some());
+    });
+}
+
+#[test]
+fn cancelled_acquire_many_returns_permits() {
+    future::block_on(async {
+        let s = Semaphore::new(3);
+        let g = s.acquire_many(2).await;
+
+        let mut large = Box::pin(s.acquire_many(3));
+        assert!(future::poll_once(&mut large).await.is_none());
+        assert!(s.try_acquire().is_none());
+        drop(large);
+
+        assert!(s.try_acquire().is_some());
+        drop(g);
+        assert!(s.try_acquire_many(3).is_some());
+    });
+}
+
+#[test]
+fn resize() {
+    future::block_on(async {
+        let s = Semaphore::new(1);
+        let g1 = s.acquire().await;
+
+        let mut waiter = Box::pin(s.acquire());
+        assert!(future::poll_once(&mut waiter).await.is_none());
+        s.add_permits(1);
+        let g2 = future::poll_once(&mut waiter).await.unwrap();
+
+        // Both permits are held, so they are removed once released.
+        s.remove_permits(2);
+        drop(g1);
+        drop(g2);
+        assert!(s.try_acquire().is_none());
+
+        // New permits are not offset against a debt that was already paid.
+        s.add_permits(2);
+        assert!(s.try_acquire_many(2).is_some());
+    });
+}
+
+#[test]
+fn add_permits_pays_debt_first() {
+    let s = Semaphore::new(1);
+    let g = s.try_acquire().unwrap();
+    s.remove_permits(1);
+
+    s.add_permits(1);
+    assert!(s.try_acquire().is_none());
+    drop(g);
+    assert!(s.try_acquire().is_some());
+}
+
+#[test]
+fn close() {
+    future::block_on(async {
+        let s = Semaphore::new(1);
+        let guard = s.acquire_checked().await.unwrap();
+
+        let mut w1 = Box::pin(s.acquire_checked());
+        let mut w2 = Box::pin(s.acquire_checked());
+        assert!(future::poll_once(&mut w1).await.is_none());
+        assert!(future::poll_once(&mut w2).await.is_none());
+
+        s.close();
+        assert!(s.is_closed());
+        assert!(w1.await.is_err());
+        assert!(w2.await.is_err());
+
+        // Releasing a held permit does not reopen the semaphore.
+        drop(guard);
+        assert!(s.try_acquire().is_none());
+        assert!(s.try_acquire_many(1).is_none());
+        assert!(s.acquire_checked().await.is_err());
+    });
+}
+
+#[test]
+fn close_checked_many_and_arc() {
+    future::block_on(async {
+        let s = Arc::new(Semaphore::new(3));
+        let guard = s.acquire_many_checked(2).await.unwrap();
+
+        // The first waiter collects the free permit, the second one waits for it to finish.
+        let mut w1 = Box::pin(s.acquire_many_checked(3));
+        let mut w2 = Box::pin(s.acquire_many_checked(2));
+        let mut w3 = Box::pin(s.acquire_arc_checked());
+        assert!(future::poll_once(&mut w1).await.is_none());
+        assert!(future::poll_once(&mut w2).await.is_none());
+        assert!(future::poll_once(&mut w3).await.is_none());
+
+        s.close();
+        assert!(w1.await.is_err());
+        assert!(w2.await.is_err());
+        assert!(w3.await.is_err());
+        assert!(s.acquire_arc_checked().await.is_err());
+
+        // The permit collected by the first waiter was given back.
+        drop(guard);
+        assert!(format!("{:?}", s).starts_with("Semaphore { count: 3,"));
+    });
+}
+
+#[test]
+fn close_never_releases_unchecked_operations() {
+    future::block_on(async {
+        let s = Arc::new(Semaphore::new(2));
+        let guard = s.acquire_many(2).await;
+
+        let mut one = Box::pin(s.acquire());
+        let mut many = Box::pin(s.acquire_many(2));
+        let mut arc = Box::pin(s.acquire_arc());
+        assert!(future::poll_once(&mut one).await.is_none());
+        assert!(future::poll_once(&mut many).await.is_none());
+        assert!(future::poll_once(&mut arc).await.is_none());
+
+        s.close();
+        drop(guard);
+        assert!(future::poll_once(&mut one).await.is_none());
+        assert!(future::poll_once(&mut many).await.is_none());
+        assert!(future::poll_once(&mut arc).await.is_none());
+
+        // Free permits are not handed out either, just like with `try_acquire()`.
+        assert!(s.try_acquire().is_none());
+        assert!(future::poll_once(Box::pin(s.acquire())).await.is_none());
+        assert!(future::poll_once(Box::pin(s.acquire_many(2)))
+            .await
+            .is_none());
+    });
+}
+
+#[test]
+fn forget_and_split() {
+    let s = Semaphore::new(5);
+
+    let mut guard = s.try_acquire_many(4).unwrap();
+    let part = guard.split(3).unwrap();
+    assert_eq!(guard.permits(), 1);
+    assert!(guard.split(2).is_none());
+    assert_eq!(guard.permits(), 1);
+
+    // The split guards release their permits independently.
+    drop(part);
+    assert!(s.try_acquire_many(4).is_some());
+
+    guard.forget();
+    assert!(s.try_acquire_many(5).is_none());
+    assert!(s.try_acquire_many(4).is_some());
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "std")]
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use async_lock::ShardedLock;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn smoke() {
+    future::block_on(async {
+        let lock = ShardedLock::new(0);
+        *lock.write().await += 1;
+        *lock.try_write().unwrap() += 1;
+
+        let r1 = lock.read().await;
+        let r2 = lock.try_read().unwrap();
+        assert_eq!((*r1, *r2), (2, 2));
+        assert!(lock.try_write().is_none());
+        assert_eq!(format!("{:?}", lock), "ShardedLock { value: 2 }");
+        drop((r1, r2));
+
+        let w = lock.write().await;
+        assert!(lock.try_read().is_none());
+        assert_eq!(format!("{:?}", lock), "ShardedLock { value: <locked> }");
+        drop(w);
+        assert_eq!(lock.into_inner(), 2);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn writer_waits_for_readers() {
+    future::block_on(async {
+        let lock = ShardedLock::new(0);
+        let reader = lock.read().await;
+
+        let mut write = Box::pin(lock.write());
+        assert!(future::poll_once(&mut write).await.is_none());
+
+        drop(reader);
+        *future::poll_once(&mut write).await.unwrap() += 1;
+        assert_eq!(*lock.read().await, 1);
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn readers_on_other_threads() {
+    const THREADS: usize = 8;
+    const N: usize = 100;
+
+    let lock = Arc::new(ShardedLock::new(0));
+    let threads = (0..THREADS)
+        .map(|_| {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                future::block_on(async {
+                    for _ in 0..N {
+                        let before = *lock.read().await;
+                        *lock.write().await += 1;
+                        assert!(*lock.read().await > before);
+                    }
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(*future::block_on(lock.read()), THREADS * N);
+}
@@ -0,0 +1,80 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use async_lock::Shutdown;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn lifecycle() {
+    future::block_on(async {
+        let shutdown = Shutdown::new();
+        let a = shutdown.register().unwrap();
+        let b = shutdown.register().unwrap();
+        assert_eq!(shutdown.in_flight(), 2);
+
+        // Not idle before shutdown has begun, even without operations.
+        let mut idle = Box::pin(shutdown.wait_idle());
+        let mut begun = Box::pin(shutdown.wait_begin());
+        assert!(future::poll_once(&mut idle).await.is_none());
+        assert!(future::poll_once(&mut begun).await.is_none());
+
+        shutdown.begin();
+        shutdown.begin();
+        assert!(future::poll_once(&mut begun).await.is_some());
+        assert!(shutdown.register().is_none());
+        assert_eq!(shutdown.in_flight(), 2);
+
+        drop(a);
+        assert!(future::poll_once(&mut idle).await.is_none());
+        drop(b);
+        idle.await;
+        assert_eq!(shutdown.in_flight(), 0);
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn begin_when_idle() {
+    future::block_on(async {
+        let shutdown = Shutdown::new();
+        drop(shutdown.register().unwrap());
+        shutdown.begin();
+        shutdown.wait_idle().await;
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn threads() {
+    let shutdown = Arc::new(Shutdown::new());
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    let workers = (0..4)
+        .map(|_| {
+            let guard = shutdown.register_arc().unwrap();
+            let tx = tx.clone();
+            thread::spawn(move || {
+                thread::sleep(std::time::Duration::from_millis(10));
+                tx.send(()).unwrap();
+                drop(guard);
+            })
+        })
+        .collect::<Vec<_>>();
+
+    shutdown.begin();
+    future::block_on(shutdown.wait_idle());
+    assert_eq!(rx.try_iter().count(), 4);
+
+    for worker in workers {
+        worker.join().unwrap();
+    }
+}
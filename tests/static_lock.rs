@@ -0,0 +1,107 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use async_lock::StaticLock;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+static GLOBAL: StaticLock<u32, 2> = StaticLock::new(0);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn smoke() {
+    future::block_on(async {
+        let m = StaticLock::<(), 4>::new(());
+        drop(m.lock().await);
+        drop(m.lock().await);
+    })
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn try_lock() {
+    let m = StaticLock::<_, 4>::new(());
+    let guard = m.try_lock().unwrap();
+    assert!(m.try_lock().is_none());
+    drop(guard);
+    assert!(m.try_lock().is_some());
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn in_static() {
+    future::block_on(async {
+        *GLOBAL.lock().await += 1;
+        assert!(*GLOBAL.lock().await >= 1);
+    })
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn cancelled_waiter_frees_slot() {
+    let m = StaticLock::<_, 1>::new(0);
+    let guard = m.try_lock().unwrap();
+
+    // The first waiter takes the only slot, then gets cancelled.
+    let mut waiter = Box::pin(m.lock());
+    assert!(future::block_on(future::poll_once(waiter.as_mut())).is_none());
+    drop(waiter);
+
+    // A new waiter can register once the slot is free.
+    let mut waiter = Box::pin(m.lock());
+    assert!(future::block_on(future::poll_once(waiter.as_mut())).is_none());
+    drop(guard);
+    assert!(future::block_on(future::poll_once(waiter.as_mut())).is_some());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn contention_exceeding_slots() {
+    future::block_on(async {
+        let (tx, rx) = async_channel::unbounded();
+
+        let lock = Arc::new(StaticLock::<_, 2>::new(0i32));
+        let num_tasks = 50;
+
+        for _ in 0..num_tasks {
+            let tx = tx.clone();
+            let lock = lock.clone();
+
+            thread::spawn(move || {
+                future::block_on(async move {
+                    let mut guard = lock.lock().await;
+                    *guard += 1;
+                    tx.send(()).await.unwrap();
+                    drop(guard);
+                })
+            });
+        }
+
+        for _ in 0..num_tasks {
+            rx.recv().await.unwrap();
+        }
+
+        assert_eq!(*lock.lock().await, num_tasks);
+    });
+}
+
+#[cfg(feature = "critical-section")]
+#[test]
+fn release_from_interrupt() {
+    let m = StaticLock::<_, 1>::new(0);
+    let guard = m.try_lock().unwrap();
+
+    let mut waiter = Box::pin(m.lock());
+    assert!(future::block_on(future::poll_once(waiter.as_mut())).is_none());
+
+    // Simulate an interrupt handler releasing the lock inside a critical section.
+    critical_section::with(|_| drop(guard));
+    assert!(future::block_on(future::poll_once(waiter.as_mut())).is_some());
+}
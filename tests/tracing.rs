@@ -0,0 +1,184 @@
+#![cfg(feature = "tracing")]
+
+use std::sync::{Arc, Mutex as StdMutex};
+
+use async_lock::{Mutex, MutexGuard, RwLock, Semaphore};
+use futures_lite::future;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// Collects `lock mode message` lines for every event.
+#[derive(Clone, Default)]
+struct Collector(Arc<StdMutex<Vec<String>>>);
+
+#[derive(Default)]
+struct Fields {
+    lock: String,
+    mode: String,
+    name: Option<String>,
+    message: String,
+    has_wait: bool,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "lock" => self.lock = value.to_string(),
+            "mode" => self.mode = value.to_string(),
+            "name" => self.name = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        match field.name() {
+            "message" => self.message = format!("{:?}", value),
+            "wait" => self.has_wait = true,
+            _ => {}
+        }
+    }
+}
+
+impl Subscriber for Collector {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        // Skip `tokio-console` resource events.
+        if event.metadata().target().starts_with("runtime::") {
+            return;
+        }
+
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        if fields.message == "acquired" {
+            assert!(fields.has_wait);
+        }
+        let lock = match fields.name {
+            Some(name) => format!("{} {:?}", fields.lock, name),
+            None => fields.lock,
+        };
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("{} {} {}", lock, fields.mode, fields.message));
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+fn collect(f: impl FnOnce()) -> Vec<String> {
+    let collector = Collector::default();
+    tracing::subscriber::with_default(collector.clone(), f);
+    let events = collector.0.lock().unwrap().clone();
+    events
+}
+
+#[test]
+fn mutex_events() {
+    let events = collect(|| {
+        future::block_on(async {
+            let m = Mutex::new(());
+            let guard = m.lock().await;
+            let mut waiter = Box::pin(m.lock());
+            assert!(future::poll_once(waiter.as_mut()).await.is_none());
+            drop(guard);
+            drop(waiter.await);
+        })
+    });
+
+    assert_eq!(
+        events,
+        [
+            "Mutex lock acquired",
+            "Mutex lock acquire started",
+            "Mutex lock released",
+            "Mutex lock acquired",
+            "Mutex lock released",
+        ]
+    );
+}
+
+#[test]
+fn fair_handoff_events() {
+    let events = collect(|| {
+        future::block_on(async {
+            let m = Mutex::new(());
+            let guard = m.lock().await;
+            let mut waiter = Box::pin(m.lock());
+            assert!(future::poll_once(waiter.as_mut()).await.is_none());
+            MutexGuard::unlock_fair(guard);
+            MutexGuard::unlock_fair(waiter.await);
+        })
+    });
+
+    assert_eq!(
+        events,
+        [
+            "Mutex lock acquired",
+            "Mutex lock acquire started",
+            "Mutex lock released",
+            "Mutex lock handed over",
+            "Mutex lock acquired",
+            "Mutex lock released",
+        ]
+    );
+}
+
+#[test]
+fn rwlock_and_semaphore_events() {
+    let events = collect(|| {
+        future::block_on(async {
+            let lock = RwLock::new(());
+            drop(lock.read().await);
+            drop(lock.try_write().unwrap());
+
+            let s = Semaphore::new(1);
+            drop(s.acquire().await);
+        })
+    });
+
+    assert_eq!(
+        events,
+        [
+            "RwLock read acquired",
+            "RwLock read released",
+            "RwLock write acquired",
+            "RwLock write released",
+            "Semaphore acquire acquired",
+            "Semaphore acquire released",
+        ]
+    );
+}
+
+#[test]
+fn named_lock_events() {
+    let events = collect(|| {
+        let m = Mutex::new_named("state", ());
+        drop(m.try_lock());
+        let lock = RwLock::new_named("routes", ());
+        drop(lock.try_read());
+    });
+
+    assert_eq!(
+        events,
+        [
+            "Mutex \"state\" lock acquired",
+            "Mutex \"state\" lock released",
+            "RwLock \"routes\" read acquired",
+            "RwLock \"routes\" read released",
+        ]
+    );
+}
@@ -0,0 +1,68 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use async_lock::WaitGroup;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn waits_for_guards() {
+    future::block_on(async {
+        let wg = WaitGroup::new();
+        wg.wait().await;
+
+        let g1 = wg.add();
+        let g2 = wg.add();
+        assert_eq!(wg.count(), 2);
+
+        let mut done = Box::pin(wg.wait());
+        assert!(future::poll_once(&mut done).await.is_none());
+        drop(g1);
+        assert!(future::poll_once(&mut done).await.is_none());
+        drop(g2);
+        assert!(future::poll_once(&mut done).await.is_some());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn reusable() {
+    future::block_on(async {
+        let wg = WaitGroup::new();
+        drop(wg.add());
+        wg.wait().await;
+
+        let guard = wg.add();
+        let mut done = Box::pin(wg.wait());
+        assert!(future::poll_once(&mut done).await.is_none());
+        drop(guard);
+        done.await;
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn fan_in() {
+    let wg = Arc::new(WaitGroup::new());
+    let finished = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+    for _ in 0..8 {
+        let guard = wg.add_arc();
+        let finished = finished.clone();
+        thread::spawn(move || {
+            finished.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            drop(guard);
+        });
+    }
+
+    future::block_on(wg.wait());
+    assert_eq!(finished.load(std::sync::atomic::Ordering::SeqCst), 8);
+}
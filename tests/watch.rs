@@ -0,0 +1,100 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use async_lock::Watch;
+use futures_lite::future;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn changed() {
+    future::block_on(async {
+        let watch = Watch::new(0);
+        let mut watcher = watch.subscribe();
+        assert!(future::poll_once(watcher.changed()).await.is_none());
+
+        let mut changed = Box::pin(watcher.changed());
+        assert!(future::poll_once(&mut changed).await.is_none());
+        watch.send_modify(|v| *v += 1).await;
+        changed.await;
+        assert!(!watcher.has_changed());
+        assert_eq!(*watcher.borrow().await, 1);
+
+        // Watchers see the latest value, not every update.
+        assert_eq!(watch.send(2).await, 1);
+        assert_eq!(watch.send(3).await, 2);
+        assert!(watcher.has_changed());
+        assert_eq!(*watcher.borrow_and_update().await, 3);
+        assert!(!watcher.has_changed());
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn wakes_all_watchers() {
+    future::block_on(async {
+        let watch = Watch::<i32>::default();
+        let watcher = watch.subscribe();
+        let mut watchers = [watcher.clone(), watcher.clone(), watcher];
+
+        let mut changes = watchers
+            .iter_mut()
+            .map(|w| Box::pin(w.changed()))
+            .collect::<Vec<_>>();
+        for changed in &mut changes {
+            assert!(future::poll_once(changed).await.is_none());
+        }
+
+        watch.send(1).await;
+        for changed in changes {
+            changed.await;
+        }
+
+        // A watcher created after an update does not see it.
+        assert!(!watch.subscribe().has_changed());
+        assert_eq!(
+            format!("{:?}", watch.subscribe()),
+            "Watcher { seen: 1, changed: false }"
+        );
+    });
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn get_mut_and_debug() {
+    let mut watch = Watch::from(1);
+    *watch.get_mut() = 2;
+    assert_eq!(
+        format!("{:?}", watch),
+        "Watch { version: 0, value: RwLock { value: 2 } }"
+    );
+    assert_eq!(watch.into_inner(), 2);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn propagate_across_threads() {
+    let watch = Watch::new(0);
+
+    thread::scope(|s| {
+        for _ in 0..4 {
+            let mut watcher = watch.subscribe();
+            s.spawn(move || {
+                future::block_on(async {
+                    while *watcher.borrow_and_update().await < 100 {
+                        watcher.changed().await;
+                    }
+                })
+            });
+        }
+
+        for i in 1..=100 {
+            future::block_on(watch.send(i));
+        }
+    });
+}